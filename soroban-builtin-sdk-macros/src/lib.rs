@@ -1,10 +1,14 @@
 mod derive_fn;
 mod derive_type;
+mod spec;
 
 extern crate proc_macro;
 
 use crate::derive_fn::derive_contract_function_set;
 use crate::derive_type::{derive_type_enum, derive_type_struct};
+use crate::spec::{
+    derive_error_spec, derive_fn_spec, derive_type_spec_enum, derive_type_spec_struct,
+};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
@@ -27,8 +31,16 @@ pub fn derive_contract_type(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let ident = &input.ident;
     let derived = match &input.data {
-        syn::Data::Struct(s) => derive_type_struct(ident, s),
-        syn::Data::Enum(e) => derive_type_enum(ident, e),
+        syn::Data::Struct(s) => {
+            let conversions = derive_type_struct(ident, s);
+            let spec = derive_type_spec_struct(ident, &input.attrs, s);
+            quote! { #conversions #spec }
+        }
+        syn::Data::Enum(e) => {
+            let conversions = derive_type_enum(ident, e);
+            let spec = derive_type_spec_enum(ident, &input.attrs, e);
+            quote! { #conversions #spec }
+        }
         syn::Data::Union(u) => Error::new(
             u.union_token.span(),
             "unions are unsupported as contract types",
@@ -51,10 +63,29 @@ pub fn contractimpl(_metadata: TokenStream, input: TokenStream) -> TokenStream {
     let ty = &imp.self_ty;
     let all_methods: Vec<_> = get_methods(&imp).collect();
 
-    let cfs = derive_contract_function_set(ty, all_methods.into_iter());
+    let cfs = derive_contract_function_set(ty, all_methods.iter().copied());
+    let spec = derive_fn_spec(ty, all_methods.into_iter());
     quote! {
         #imp
         #cfs
+        #spec
+    }
+    .into()
+}
+
+/// Generates the `ScSpecUdtErrorEnumV0` spec entry for an enum of contract
+/// error codes. Every variant must have an explicit integer value.
+#[proc_macro_attribute]
+pub fn contracterror(_metadata: TokenStream, input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let spec = match &input.data {
+        syn::Data::Enum(e) => derive_error_spec(ident, &input.attrs, e),
+        _ => Error::new(ident.span(), "contract errors must be enums").to_compile_error(),
+    };
+    quote! {
+        #input
+        #spec
     }
     .into()
 }
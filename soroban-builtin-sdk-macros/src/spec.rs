@@ -0,0 +1,394 @@
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use stellar_xdr::curr::{
+    Limits, ScSpecEntry, ScSpecFunctionInputV0, ScSpecFunctionV0, ScSpecTypeBytesN, ScSpecTypeDef,
+    ScSpecTypeOption, ScSpecTypeUdt, ScSpecTypeVec, ScSpecUdtErrorEnumCaseV0, ScSpecUdtErrorEnumV0,
+    ScSpecUdtStructFieldV0, ScSpecUdtStructV0, ScSpecUdtUnionCaseTupleV0, ScSpecUdtUnionCaseV0,
+    ScSpecUdtUnionCaseVoidV0, ScSpecUdtUnionV0, ScSymbol, StringM, WriteXdr,
+};
+use syn::{
+    spanned::Spanned, Attribute, DataEnum, DataStruct, Error, Expr, ExprLit, FnArg,
+    GenericArgument, Ident, Lit, Meta, MetaNameValue, Pat, PathArguments, ReturnType, Type,
+};
+
+// Builtin contracts import `base_types::Vec` under these names to avoid
+// shadowing `std::vec::Vec`. It is untyped, so its elements are `Val`s.
+const UNTYPED_VEC_NAMES: &[&str] = &["Vec", "HostVec", "ContractTypeVec"];
+
+fn string_m<const MAX: u32>(s: &str, span: &impl Spanned) -> Result<StringM<MAX>, Error> {
+    s.try_into()
+        .map_err(|_| Error::new(span.span(), format!("`{}` is longer than {} bytes", s, MAX)))
+}
+
+fn too_many(span: &impl Spanned) -> Error {
+    Error::new(span.span(), "too many entries for a contract spec")
+}
+
+// Joins the `///` doc comments in `attrs`, truncated to the spec's limit.
+fn docs(attrs: &[Attribute]) -> StringM<1024> {
+    let mut doc = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(MetaNameValue {
+                path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }),
+                ..
+            }) if path.is_ident("doc") => Some(s.value().trim().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut len = doc.len().min(1024);
+    while !doc.is_char_boundary(len) {
+        len -= 1;
+    }
+    doc.truncate(len);
+    doc.try_into().unwrap_or_default()
+}
+
+fn single_generic_arg(args: &PathArguments) -> Option<&GenericArgument> {
+    match args {
+        PathArguments::AngleBracketed(a) if a.args.len() == 1 => a.args.first(),
+        _ => None,
+    }
+}
+
+fn spec_type(ty: &Type) -> Result<ScSpecTypeDef, Error> {
+    let unsupported = || Error::new(ty.span(), "unsupported type in contract spec");
+    let path = match ty {
+        Type::Tuple(t) if t.elems.is_empty() => return Ok(ScSpecTypeDef::Void),
+        Type::Reference(r) => return spec_type(&r.elem),
+        Type::Path(p) if p.qself.is_none() => &p.path,
+        _ => return Err(unsupported()),
+    };
+    let segment = path.segments.last().ok_or_else(unsupported)?;
+    let name = segment.ident.to_string();
+    if let PathArguments::None = segment.arguments {
+        return Ok(match name.as_str() {
+            "bool" => ScSpecTypeDef::Bool,
+            "u32" => ScSpecTypeDef::U32,
+            "i32" => ScSpecTypeDef::I32,
+            "u64" => ScSpecTypeDef::U64,
+            "i64" => ScSpecTypeDef::I64,
+            "u128" => ScSpecTypeDef::U128,
+            "i128" => ScSpecTypeDef::I128,
+            "Val" => ScSpecTypeDef::Val,
+            "Error" => ScSpecTypeDef::Error,
+            "Symbol" => ScSpecTypeDef::Symbol,
+            "Address" | "AddressObject" => ScSpecTypeDef::Address,
+            "String" | "StringObject" => ScSpecTypeDef::String,
+            "BytesObject" => ScSpecTypeDef::Bytes,
+            n if UNTYPED_VEC_NAMES.contains(&n) => ScSpecTypeDef::Vec(Box::new(ScSpecTypeVec {
+                element_type: Box::new(ScSpecTypeDef::Val),
+            })),
+            _ => ScSpecTypeDef::Udt(ScSpecTypeUdt {
+                name: string_m(&name, ty)?,
+            }),
+        });
+    }
+    match (name.as_str(), single_generic_arg(&segment.arguments)) {
+        (
+            "BytesN",
+            Some(GenericArgument::Const(Expr::Lit(ExprLit {
+                lit: Lit::Int(n), ..
+            }))),
+        ) => Ok(ScSpecTypeDef::BytesN(ScSpecTypeBytesN {
+            n: n.base10_parse()?,
+        })),
+        ("Option", Some(GenericArgument::Type(t))) => {
+            Ok(ScSpecTypeDef::Option(Box::new(ScSpecTypeOption {
+                value_type: Box::new(spec_type(t)?),
+            })))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+// The spec output of a function returning `Result<T, HostError>` is `T`.
+fn output_type(output: &ReturnType) -> Option<&Type> {
+    let ty = match output {
+        ReturnType::Default => return None,
+        ReturnType::Type(_, ty) => ty.as_ref(),
+    };
+    Some(match ty {
+        Type::Path(p) => match p.path.segments.last() {
+            Some(s) if s.ident == "Result" => match &s.arguments {
+                PathArguments::AngleBracketed(a) => match a.args.first() {
+                    Some(GenericArgument::Type(t)) => t,
+                    _ => ty,
+                },
+                _ => ty,
+            },
+            _ => ty,
+        },
+        _ => ty,
+    })
+}
+
+fn spec_output(output: &ReturnType) -> Result<Vec<ScSpecTypeDef>, Error> {
+    let Some(ty) = output_type(output) else {
+        return Ok(vec![]);
+    };
+    match spec_type(ty)? {
+        ScSpecTypeDef::Void => Ok(vec![]),
+        t => Ok(vec![t]),
+    }
+}
+
+// Adds to `udts` the user-defined types `ty` is described by, looking
+// through references and options.
+fn collect_udts(ty: &Type, udts: &mut Vec<Type>) {
+    if let Type::Reference(r) = ty {
+        return collect_udts(&r.elem, udts);
+    }
+    if let Type::Path(p) = ty {
+        if let Some(s) = p.path.segments.last() {
+            if let (true, Some(GenericArgument::Type(t))) =
+                (s.ident == "Option", single_generic_arg(&s.arguments))
+            {
+                return collect_udts(t, udts);
+            }
+        }
+    }
+    if let Ok(ScSpecTypeDef::Udt(_)) = spec_type(ty) {
+        let key = quote!(#ty).to_string();
+        if !udts.iter().any(|u| quote!(#u).to_string() == key) {
+            udts.push(ty.clone());
+        }
+    }
+}
+
+fn spec_xdr_lit(entry: ScSpecEntry) -> Literal {
+    // Limits::none here is okay since the entries are built from our own
+    // contract definitions.
+    Literal::byte_string(&entry.to_xdr(Limits::none()).unwrap())
+}
+
+fn to_compile_errors(errors: Vec<Error>) -> TokenStream2 {
+    let compile_errors = errors.iter().map(Error::to_compile_error);
+    quote! { #(#compile_errors)* }
+}
+
+fn type_spec_const<'a>(
+    ident: &Ident,
+    entry: Result<ScSpecEntry, Error>,
+    field_types: impl Iterator<Item = &'a Type>,
+) -> TokenStream2 {
+    let mut udts = vec![];
+    for ty in field_types {
+        collect_udts(ty, &mut udts);
+    }
+    match entry {
+        Ok(entry) => {
+            let lit = spec_xdr_lit(entry);
+            quote! {
+                impl #ident {
+                    /// The XDR-encoded `ScSpecEntry` describing this type.
+                    #[allow(dead_code)]
+                    pub(crate) const SPEC_XDR: &'static [u8] = #lit;
+
+                    /// Adds the spec entries of this type and of the types it
+                    /// is made of to `specs`, unless they're already in it.
+                    #[allow(dead_code)]
+                    pub(crate) fn collect_spec_xdr(specs: &mut ::std::vec::Vec<&'static [u8]>) {
+                        if specs.contains(&Self::SPEC_XDR) {
+                            return;
+                        }
+                        specs.push(Self::SPEC_XDR);
+                        #(<#udts>::collect_spec_xdr(specs);)*
+                    }
+                }
+            }
+        }
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+pub fn derive_fn_spec<'a>(
+    ty: &Type,
+    methods: impl Iterator<Item = &'a syn::ImplItemFn>,
+) -> TokenStream2 {
+    let mut errors = Vec::<Error>::new();
+    let mut lits = Vec::new();
+    let mut udts = Vec::new();
+    for m in methods {
+        for a in m.sig.inputs.iter().skip(1) {
+            if let FnArg::Typed(t) = a {
+                collect_udts(&t.ty, &mut udts);
+            }
+        }
+        if let Some(ty) = output_type(&m.sig.output) {
+            collect_udts(ty, &mut udts);
+        }
+        // The first argument is always the host.
+        let inputs = m
+            .sig
+            .inputs
+            .iter()
+            .skip(1)
+            .map(|a| match a {
+                FnArg::Typed(t) => {
+                    let name = match t.pat.as_ref() {
+                        Pat::Ident(i) => i.ident.to_string(),
+                        _ => return Err(Error::new(t.pat.span(), "invalid argument name")),
+                    };
+                    Ok(ScSpecFunctionInputV0 {
+                        doc: StringM::default(),
+                        name: string_m(&name, t)?,
+                        type_: spec_type(&t.ty)?,
+                    })
+                }
+                _ => Err(Error::new(a.span(), "invalid argument type")),
+            })
+            .collect::<Result<Vec<_>, _>>();
+        let entry = inputs.and_then(|inputs| {
+            Ok(ScSpecEntry::FunctionV0(ScSpecFunctionV0 {
+                doc: docs(&m.attrs),
+                name: ScSymbol(string_m(&m.sig.ident.to_string(), &m.sig.ident)?),
+                inputs: inputs.try_into().map_err(|_| too_many(&m.sig.inputs))?,
+                outputs: spec_output(&m.sig.output)?
+                    .try_into()
+                    .map_err(|_| too_many(&m.sig.output))?,
+            }))
+        });
+        match entry {
+            Ok(entry) => lits.push(spec_xdr_lit(entry)),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if !errors.is_empty() {
+        return to_compile_errors(errors);
+    }
+    quote! {
+        impl #ty {
+            /// The XDR-encoded `ScSpecEntry`s describing the contract's
+            /// functions, in declaration order.
+            #[allow(dead_code)]
+            pub(crate) const FN_SPECS_XDR: &'static [&'static [u8]] = &[#(#lits),*];
+
+            /// The XDR-encoded `ScSpecEntry`s describing the user-defined
+            /// types in the contract's function signatures and the types
+            /// they are made of.
+            #[allow(dead_code)]
+            pub(crate) fn type_specs_xdr() -> ::std::vec::Vec<&'static [u8]> {
+                let mut specs = ::std::vec::Vec::new();
+                #(<#udts>::collect_spec_xdr(&mut specs);)*
+                specs
+            }
+        }
+    }
+}
+
+pub fn derive_type_spec_struct(
+    ident: &Ident,
+    attrs: &[Attribute],
+    data: &DataStruct,
+) -> TokenStream2 {
+    let entry = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let name = match &f.ident {
+                Some(ident) => ident.to_string(),
+                None => i.to_string(),
+            };
+            Ok(ScSpecUdtStructFieldV0 {
+                doc: docs(&f.attrs),
+                name: string_m(&name, f)?,
+                type_: spec_type(&f.ty)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()
+        .and_then(|fields| {
+            Ok(ScSpecEntry::UdtStructV0(ScSpecUdtStructV0 {
+                doc: docs(attrs),
+                lib: StringM::default(),
+                name: string_m(&ident.to_string(), ident)?,
+                fields: fields.try_into().map_err(|_| too_many(&data.fields))?,
+            }))
+        });
+    type_spec_const(ident, entry, data.fields.iter().map(|f| &f.ty))
+}
+
+pub fn derive_type_spec_enum(ident: &Ident, attrs: &[Attribute], data: &DataEnum) -> TokenStream2 {
+    let entry = data
+        .variants
+        .iter()
+        .map(|v| {
+            let doc = docs(&v.attrs);
+            let name = string_m(&v.ident.to_string(), &v.ident)?;
+            if v.fields.is_empty() {
+                Ok(ScSpecUdtUnionCaseV0::VoidV0(ScSpecUdtUnionCaseVoidV0 {
+                    doc,
+                    name,
+                }))
+            } else {
+                let types = v
+                    .fields
+                    .iter()
+                    .map(|f| spec_type(&f.ty))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(ScSpecUdtUnionCaseV0::TupleV0(ScSpecUdtUnionCaseTupleV0 {
+                    doc,
+                    name,
+                    type_: types.try_into().map_err(|_| too_many(&v.fields))?,
+                }))
+            }
+        })
+        .collect::<Result<Vec<_>, Error>>()
+        .and_then(|cases| {
+            Ok(ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 {
+                doc: docs(attrs),
+                lib: StringM::default(),
+                name: string_m(&ident.to_string(), ident)?,
+                cases: cases.try_into().map_err(|_| too_many(&data.variants))?,
+            }))
+        });
+    let field_types = data
+        .variants
+        .iter()
+        .flat_map(|v| v.fields.iter().map(|f| &f.ty));
+    type_spec_const(ident, entry, field_types)
+}
+
+pub fn derive_error_spec(ident: &Ident, attrs: &[Attribute], data: &DataEnum) -> TokenStream2 {
+    let entry = data
+        .variants
+        .iter()
+        .map(|v| {
+            let value = match &v.discriminant {
+                Some((
+                    _,
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(n), ..
+                    }),
+                )) => n.base10_parse()?,
+                _ => {
+                    return Err(Error::new(
+                        v.span(),
+                        "contract error variants must have an explicit integer value",
+                    ))
+                }
+            };
+            Ok(ScSpecUdtErrorEnumCaseV0 {
+                doc: docs(&v.attrs),
+                name: string_m(&v.ident.to_string(), &v.ident)?,
+                value,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()
+        .and_then(|cases| {
+            Ok(ScSpecEntry::UdtErrorEnumV0(ScSpecUdtErrorEnumV0 {
+                doc: docs(attrs),
+                lib: StringM::default(),
+                name: string_m(&ident.to_string(), ident)?,
+                cases: cases.try_into().map_err(|_| too_many(&data.variants))?,
+            }))
+        });
+    type_spec_const(ident, entry, std::iter::empty())
+}
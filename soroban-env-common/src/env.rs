@@ -4,9 +4,9 @@ use crate::Object;
 
 use super::Symbol;
 use super::{
-    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I256Object, I256Val,
-    I64Object, MapObject, StorageType, StringObject, SymbolObject, TimepointObject, U128Object,
-    U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
+    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I128Val, I256Object,
+    I256Val, I64Object, MapObject, StorageType, StringObject, SymbolObject, TimepointObject,
+    U128Object, U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
 };
 use crate::xdr::{ScErrorCode, ScErrorType};
 
@@ -284,6 +284,7 @@ impl_checkedenvarg_for_val_or_wrapper!(U128Object);
 impl_checkedenvarg_for_val_or_wrapper!(U256Object);
 
 impl_checkedenvarg_for_val_or_wrapper!(U64Val);
+impl_checkedenvarg_for_val_or_wrapper!(I128Val);
 impl_checkedenvarg_for_val_or_wrapper!(U256Val);
 impl_checkedenvarg_for_val_or_wrapper!(I256Val);
 
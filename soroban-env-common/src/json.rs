@@ -0,0 +1,427 @@
+//! A human-readable JSON representation of [ScVal]s.
+//!
+//! Unlike the generic XDR-JSON provided by the `serde` feature, this
+//! representation is specific to Soroban values and meant to be read (and
+//! written) by people: every value is a single-key object naming its type, and
+//!
+//!   - addresses are rendered as strkeys (`G...` and `C...`),
+//!   - 64-bit and wider integers as decimal strings, so they survive JSON
+//!     parsers that use doubles,
+//!   - bytes as lowercase hex,
+//!   - maps as arrays of `[key, value]` pairs, in map order.
+//!
+//! For example `ScVal::I128` 5 is `{"i128":"5"}`, and a map from the symbol
+//! `a` to `true` is `{"map":[[{"symbol":"a"},{"bool":true}]]}`.
+//!
+//! Conversion in both directions is lossless, so this is suitable for test
+//! fixtures. Use [to_json] and [from_json], or the serde-compatible wrapper
+//! [ScValJson].
+
+use crate::{
+    num::{i256_from_pieces, i256_into_pieces, u256_from_pieces, u256_into_pieces},
+    xdr::{
+        AccountId, ContractExecutable, Duration, Hash, Int128Parts, Int256Parts, PublicKey,
+        ScAddress, ScBytes, ScContractInstance, ScError, ScErrorCode, ScErrorType, ScMap,
+        ScMapEntry, ScNonceKey, ScString, ScSymbol, ScVal, ScVec, TimePoint, UInt128Parts,
+        UInt256Parts, Uint256,
+    },
+    Error, I256, U256,
+};
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+const INVALID_JSON: Error =
+    Error::from_type_and_code(ScErrorType::Value, ScErrorCode::InvalidInput);
+
+/// A serde-compatible wrapper around an [ScVal] that (de)serializes it using
+/// the representation described in the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScValJson(pub ScVal);
+
+impl serde::Serialize for ScValJson {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error as _;
+        to_json(&self.0)
+            .map_err(|e| S::Error::custom(format!("{:?}", e)))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ScValJson {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+        let value = Value::deserialize(deserializer)?;
+        from_json(&value)
+            .map(ScValJson)
+            .map_err(|e| D::Error::custom(format!("{:?}", e)))
+    }
+}
+
+fn tagged(tag: &str, value: Value) -> Value {
+    let mut map = Map::new();
+    map.insert(tag.to_string(), value);
+    Value::Object(map)
+}
+
+fn account_strkey(account: &AccountId) -> String {
+    let AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(key))) = account;
+    stellar_strkey::ed25519::PublicKey(*key).to_string()
+}
+
+fn address_to_json(address: &ScAddress) -> Result<Value, Error> {
+    let strkey = match address {
+        ScAddress::Account(account) => account_strkey(account),
+        ScAddress::Contract(Hash(id)) => stellar_strkey::Contract(*id).to_string(),
+        #[allow(unreachable_patterns)]
+        _ => return Err(INVALID_JSON),
+    };
+    Ok(Value::String(strkey))
+}
+
+fn error_to_json(error: &ScError) -> Value {
+    let (ty, code) = match error {
+        ScError::Contract(code) => ("Contract", json!(code)),
+        ScError::WasmVm(code) => ("WasmVm", json!(code.name())),
+        ScError::Context(code) => ("Context", json!(code.name())),
+        ScError::Storage(code) => ("Storage", json!(code.name())),
+        ScError::Object(code) => ("Object", json!(code.name())),
+        ScError::Crypto(code) => ("Crypto", json!(code.name())),
+        ScError::Events(code) => ("Events", json!(code.name())),
+        ScError::Budget(code) => ("Budget", json!(code.name())),
+        ScError::Value(code) => ("Value", json!(code.name())),
+        ScError::Auth(code) => ("Auth", json!(code.name())),
+    };
+    json!({ "type": ty, "code": code })
+}
+
+fn map_to_json(map: &ScMap) -> Result<Value, Error> {
+    map.0
+        .iter()
+        .map(|ScMapEntry { key, val }| Ok(json!([to_json(key)?, to_json(val)?])))
+        .collect::<Result<Vec<_>, Error>>()
+        .map(Value::Array)
+}
+
+/// Converts an [ScVal] to its human-readable JSON representation.
+pub fn to_json(val: &ScVal) -> Result<Value, Error> {
+    let value = match val {
+        ScVal::Bool(b) => tagged("bool", json!(b)),
+        ScVal::Void => tagged("void", Value::Null),
+        ScVal::Error(e) => tagged("error", error_to_json(e)),
+        ScVal::U32(u) => tagged("u32", json!(u)),
+        ScVal::I32(i) => tagged("i32", json!(i)),
+        ScVal::U64(u) => tagged("u64", json!(u.to_string())),
+        ScVal::I64(i) => tagged("i64", json!(i.to_string())),
+        ScVal::Timepoint(TimePoint(t)) => tagged("timepoint", json!(t.to_string())),
+        ScVal::Duration(Duration(d)) => tagged("duration", json!(d.to_string())),
+        ScVal::U128(UInt128Parts { hi, lo }) => {
+            let u = ((*hi as u128) << 64) | (*lo as u128);
+            tagged("u128", json!(u.to_string()))
+        }
+        ScVal::I128(Int128Parts { hi, lo }) => {
+            let i = ((*hi as i128) << 64) | (*lo as i128);
+            tagged("i128", json!(i.to_string()))
+        }
+        ScVal::U256(UInt256Parts {
+            hi_hi,
+            hi_lo,
+            lo_hi,
+            lo_lo,
+        }) => {
+            let u = u256_from_pieces(*hi_hi, *hi_lo, *lo_hi, *lo_lo);
+            tagged("u256", json!(u.to_string()))
+        }
+        ScVal::I256(Int256Parts {
+            hi_hi,
+            hi_lo,
+            lo_hi,
+            lo_lo,
+        }) => {
+            let i = i256_from_pieces(*hi_hi, *hi_lo, *lo_hi, *lo_lo);
+            tagged("i256", json!(i.to_string()))
+        }
+        ScVal::Bytes(ScBytes(b)) => tagged("bytes", json!(hex::encode(b.as_slice()))),
+        ScVal::String(ScString(s)) => tagged(
+            "string",
+            json!(s.to_utf8_string().map_err(|_| INVALID_JSON)?),
+        ),
+        ScVal::Symbol(ScSymbol(s)) => tagged(
+            "symbol",
+            json!(s.to_utf8_string().map_err(|_| INVALID_JSON)?),
+        ),
+        ScVal::Vec(None) => tagged("vec", Value::Null),
+        ScVal::Vec(Some(ScVec(v))) => tagged(
+            "vec",
+            Value::Array(v.iter().map(to_json).collect::<Result<_, _>>()?),
+        ),
+        ScVal::Map(None) => tagged("map", Value::Null),
+        ScVal::Map(Some(m)) => tagged("map", map_to_json(m)?),
+        ScVal::Address(a) => tagged("address", address_to_json(a)?),
+        ScVal::LedgerKeyContractInstance => tagged("ledger_key_contract_instance", Value::Null),
+        ScVal::LedgerKeyNonce(ScNonceKey { nonce }) => {
+            tagged("ledger_key_nonce", json!(nonce.to_string()))
+        }
+        ScVal::ContractInstance(ScContractInstance {
+            executable,
+            storage,
+        }) => {
+            let executable = match executable {
+                ContractExecutable::Wasm(Hash(h)) => json!({ "wasm": hex::encode(h) }),
+                ContractExecutable::StellarAsset => json!("stellar_asset"),
+            };
+            let storage = match storage {
+                None => Value::Null,
+                Some(m) => map_to_json(m)?,
+            };
+            tagged(
+                "contract_instance",
+                json!({ "executable": executable, "storage": storage }),
+            )
+        }
+    };
+    Ok(value)
+}
+
+fn as_str(value: &Value) -> Result<&str, Error> {
+    value.as_str().ok_or(INVALID_JSON)
+}
+
+fn parse_str<T: FromStr>(value: &Value) -> Result<T, Error> {
+    as_str(value)?.parse().map_err(|_| INVALID_JSON)
+}
+
+fn as_array(value: &Value) -> Result<&Vec<Value>, Error> {
+    value.as_array().ok_or(INVALID_JSON)
+}
+
+fn parse_hash(value: &Value) -> Result<Hash, Error> {
+    let bytes = hex::decode(as_str(value)?).map_err(|_| INVALID_JSON)?;
+    Ok(Hash(bytes.try_into().map_err(|_| INVALID_JSON)?))
+}
+
+fn address_from_json(value: &Value) -> Result<ScAddress, Error> {
+    use stellar_strkey::Strkey;
+    match Strkey::from_string(as_str(value)?).map_err(|_| INVALID_JSON)? {
+        Strkey::PublicKeyEd25519(stellar_strkey::ed25519::PublicKey(key)) => Ok(
+            ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(key)))),
+        ),
+        Strkey::Contract(stellar_strkey::Contract(id)) => Ok(ScAddress::Contract(Hash(id))),
+        _ => Err(INVALID_JSON),
+    }
+}
+
+fn error_from_json(value: &Value) -> Result<ScError, Error> {
+    let ty = as_str(value.get("type").ok_or(INVALID_JSON)?)?;
+    let code = value.get("code").ok_or(INVALID_JSON)?;
+    if ty == "Contract" {
+        let code = code.as_u64().ok_or(INVALID_JSON)?;
+        return Ok(ScError::Contract(
+            code.try_into().map_err(|_| INVALID_JSON)?,
+        ));
+    }
+    let ty = ScErrorType::variants()
+        .into_iter()
+        .find(|t| t.name() == ty)
+        .ok_or(INVALID_JSON)?;
+    let code = as_str(code)?;
+    let code = ScErrorCode::variants()
+        .into_iter()
+        .find(|c| c.name() == code)
+        .ok_or(INVALID_JSON)?;
+    Error::from_type_and_code(ty, code)
+        .try_into()
+        .map_err(|_| INVALID_JSON)
+}
+
+fn map_from_json(value: &Value) -> Result<ScMap, Error> {
+    let entries = as_array(value)?
+        .iter()
+        .map(|entry| match as_array(entry)?.as_slice() {
+            [key, val] => Ok(ScMapEntry {
+                key: from_json(key)?,
+                val: from_json(val)?,
+            }),
+            _ => Err(INVALID_JSON),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(ScMap(entries.try_into()?))
+}
+
+/// Converts the human-readable JSON representation of an [ScVal] back to an
+/// [ScVal]. Fails with `(Value, InvalidInput)` on any malformed input.
+pub fn from_json(value: &Value) -> Result<ScVal, Error> {
+    let obj = value.as_object().ok_or(INVALID_JSON)?;
+    if obj.len() != 1 {
+        return Err(INVALID_JSON);
+    }
+    let (tag, v) = obj.iter().next().ok_or(INVALID_JSON)?;
+    let val = match tag.as_str() {
+        "bool" => ScVal::Bool(v.as_bool().ok_or(INVALID_JSON)?),
+        "void" if v.is_null() => ScVal::Void,
+        "error" => ScVal::Error(error_from_json(v)?),
+        "u32" => ScVal::U32(
+            v.as_u64()
+                .ok_or(INVALID_JSON)?
+                .try_into()
+                .map_err(|_| INVALID_JSON)?,
+        ),
+        "i32" => ScVal::I32(
+            v.as_i64()
+                .ok_or(INVALID_JSON)?
+                .try_into()
+                .map_err(|_| INVALID_JSON)?,
+        ),
+        "u64" => ScVal::U64(parse_str(v)?),
+        "i64" => ScVal::I64(parse_str(v)?),
+        "timepoint" => ScVal::Timepoint(TimePoint(parse_str(v)?)),
+        "duration" => ScVal::Duration(Duration(parse_str(v)?)),
+        "u128" => {
+            let u: u128 = parse_str(v)?;
+            ScVal::U128(UInt128Parts {
+                hi: (u >> 64) as u64,
+                lo: u as u64,
+            })
+        }
+        "i128" => {
+            let i: i128 = parse_str(v)?;
+            ScVal::I128(Int128Parts {
+                hi: (i >> 64) as i64,
+                lo: i as u64,
+            })
+        }
+        "u256" => {
+            let u: U256 = parse_str(v)?;
+            let (hi_hi, hi_lo, lo_hi, lo_lo) = u256_into_pieces(u);
+            ScVal::U256(UInt256Parts {
+                hi_hi,
+                hi_lo,
+                lo_hi,
+                lo_lo,
+            })
+        }
+        "i256" => {
+            let i: I256 = parse_str(v)?;
+            let (hi_hi, hi_lo, lo_hi, lo_lo) = i256_into_pieces(i);
+            ScVal::I256(Int256Parts {
+                hi_hi,
+                hi_lo,
+                lo_hi,
+                lo_lo,
+            })
+        }
+        "bytes" => {
+            let bytes = hex::decode(as_str(v)?).map_err(|_| INVALID_JSON)?;
+            ScVal::Bytes(ScBytes(bytes.try_into()?))
+        }
+        "string" => ScVal::String(ScString(as_str(v)?.try_into()?)),
+        "symbol" => ScVal::Symbol(ScSymbol(as_str(v)?.try_into()?)),
+        "vec" if v.is_null() => ScVal::Vec(None),
+        "vec" => {
+            let vals = as_array(v)?
+                .iter()
+                .map(from_json)
+                .collect::<Result<Vec<_>, Error>>()?;
+            ScVal::Vec(Some(ScVec(vals.try_into()?)))
+        }
+        "map" if v.is_null() => ScVal::Map(None),
+        "map" => ScVal::Map(Some(map_from_json(v)?)),
+        "address" => ScVal::Address(address_from_json(v)?),
+        "ledger_key_contract_instance" if v.is_null() => ScVal::LedgerKeyContractInstance,
+        "ledger_key_nonce" => ScVal::LedgerKeyNonce(ScNonceKey {
+            nonce: parse_str(v)?,
+        }),
+        "contract_instance" => {
+            let executable = v.get("executable").ok_or(INVALID_JSON)?;
+            let executable = if executable.as_str() == Some("stellar_asset") {
+                ContractExecutable::StellarAsset
+            } else {
+                ContractExecutable::Wasm(parse_hash(executable.get("wasm").ok_or(INVALID_JSON)?)?)
+            };
+            let storage = match v.get("storage").ok_or(INVALID_JSON)? {
+                Value::Null => None,
+                m => Some(map_from_json(m)?),
+            };
+            ScVal::ContractInstance(ScContractInstance {
+                executable,
+                storage,
+            })
+        }
+        _ => return Err(INVALID_JSON),
+    };
+    Ok(val)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(val: ScVal, expected: Value) {
+        let json = to_json(&val).unwrap();
+        assert_eq!(json, expected);
+        assert_eq!(from_json(&json).unwrap(), val);
+        let s = serde_json::to_string(&ScValJson(val.clone())).unwrap();
+        assert_eq!(serde_json::from_str::<ScValJson>(&s).unwrap().0, val);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        round_trip(ScVal::Bool(true), json!({"bool": true}));
+        round_trip(ScVal::Void, json!({"void": null}));
+        round_trip(ScVal::U64(u64::MAX), json!({"u64": "18446744073709551615"}));
+        round_trip(
+            ScVal::I128(Int128Parts { hi: -1, lo: 0 }),
+            json!({"i128": "-18446744073709551616"}),
+        );
+        round_trip(
+            ScVal::U256(UInt256Parts {
+                hi_hi: 0,
+                hi_lo: 0,
+                lo_hi: 1,
+                lo_lo: 0,
+            }),
+            json!({"u256": "18446744073709551616"}),
+        );
+        round_trip(
+            ScVal::Bytes(ScBytes(vec![0xde, 0xad].try_into().unwrap())),
+            json!({"bytes": "dead"}),
+        );
+        round_trip(
+            ScVal::Error(ScError::Contract(7)),
+            json!({"error": {"type": "Contract", "code": 7}}),
+        );
+        round_trip(
+            ScVal::Error(ScError::Auth(ScErrorCode::InvalidAction)),
+            json!({"error": {"type": "Auth", "code": "InvalidAction"}}),
+        );
+        round_trip(
+            ScVal::Address(ScAddress::Contract(Hash([0; 32]))),
+            json!({"address": "CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4"}),
+        );
+        round_trip(
+            ScVal::Map(Some(ScMap(
+                vec![ScMapEntry {
+                    key: ScVal::Symbol(ScSymbol("a".try_into().unwrap())),
+                    val: ScVal::String(ScString("a".try_into().unwrap())),
+                }]
+                .try_into()
+                .unwrap(),
+            ))),
+            json!({"map": [[{"symbol": "a"}, {"string": "a"}]]}),
+        );
+    }
+
+    #[test]
+    fn malformed_json_is_rejected() {
+        for bad in [
+            json!(true),
+            json!({"bool": true, "void": null}),
+            json!({"u32": -1}),
+            json!({"u64": 5}),
+            json!({"symbol": "a_symbol_that_is_much_too_long_to_be_valid"}),
+            json!({"address": "GNOTAKEY"}),
+            json!({"map": [[{"void": null}]]}),
+        ] {
+            assert_eq!(from_json(&bad), Err(INVALID_JSON));
+        }
+    }
+}
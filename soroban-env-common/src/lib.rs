@@ -65,6 +65,8 @@ mod compare;
 mod convert;
 mod env;
 mod error;
+#[cfg(feature = "json")]
+pub mod json;
 
 // mod hash contains "shallow" impls of Hash for `Val` wrappers that are risky
 // to expose in general since they do not "look through" the env to their
@@ -84,10 +86,16 @@ mod tuple;
 mod val;
 mod vmcaller_env;
 
+#[cfg(all(feature = "std", feature = "testutils"))]
+mod mock_env;
+#[cfg(all(feature = "std", feature = "testutils"))]
+pub use mock_env::{MockArg, MockCall, MockEnv, MockEvent, MockLedgerInfo};
+
 // We have some modules that we don't re-export everything
 // from because only specific users are likely to use them.
 pub mod meta;
 pub mod num;
+pub mod stubs;
 pub use num::{
     DurationObject, I128Object, I256Object, I64Object, TimepointObject, U128Object, U256Object,
     U64Object,
@@ -0,0 +1,1479 @@
+//! A lightweight, in-memory implementation of [Env] for unit-testing code
+//! that is generic over `Env` without setting up a full host.
+//!
+//! [MockEnv] stores every object as an [ScVal] in a flat table and does no
+//! metering, auth or contract execution. It supports:
+//!
+//!   - Objects: vecs, maps, bytes, strings, symbols, addresses and all the
+//!     number types, with the same conversions and error codes as the host
+//!     for the common cases.
+//!   - Contract data storage, which can be populated and inspected from the
+//!     test with [MockEnv::set_storage_entry] and [MockEnv::storage_entry].
+//!   - Contract events and logs, and ledger info from [MockLedgerInfo].
+//!   - `require_auth` and `require_auth_for_args`, which always succeed.
+//!
+//! Every host function call is recorded as a [MockCall] along with its
+//! arguments, see [MockEnv::calls]. Host functions that need a real host
+//! (calls to other contracts, crypto, deployment, linear memory access and so
+//! on) are recorded and then fail with `(Context, InvalidAction)`.
+//!
+//! Ordering of object values (for `obj_cmp` and map keys) follows the derived
+//! ordering of [ScVal], which agrees with the host for values of the same type.
+
+use crate::{
+    call_macro_with_all_host_functions, meta,
+    object::ScValObjRef,
+    xdr::{
+        Duration, Int128Parts, Int256Parts, Limits, ReadXdr, ScAddress, ScBytes, ScErrorCode,
+        ScErrorType, ScMap, ScMapEntry, ScString, ScSymbol, ScVal, ScVec, TimePoint, UInt128Parts,
+        UInt256Parts, WriteXdr,
+    },
+    AddressObject, Bool, BytesObject, Convert, DurationObject, Env, EnvBase, Error, I128Object,
+    I128Val, I256Object, I256Val, I64Object, MapObject, Object, ScValObject, StorageType,
+    StringObject, Symbol, SymbolObject, SymbolSmall, Tag, TimepointObject, TryFromVal, U128Object,
+    U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void, I256, U256,
+};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+/// An argument to a host function recorded by [MockEnv].
+#[derive(Debug, Clone, Copy)]
+pub enum MockArg {
+    Val(Val),
+    U64(u64),
+    I64(i64),
+    StorageType(StorageType),
+}
+
+// `Val`s are compared by payload, which for objects means by handle.
+impl PartialEq for MockArg {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (MockArg::Val(a), MockArg::Val(b)) => a.shallow_eq(b),
+            (MockArg::U64(a), MockArg::U64(b)) => a == b,
+            (MockArg::I64(a), MockArg::I64(b)) => a == b,
+            (MockArg::StorageType(a), MockArg::StorageType(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for MockArg {}
+
+macro_rules! impl_mock_arg_from_val_type {
+    ($($T:ident),*) => {
+        $(
+            impl From<$T> for MockArg {
+                fn from(v: $T) -> Self {
+                    MockArg::Val(v.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_mock_arg_from_val_type!(
+    Val,
+    Bool,
+    Void,
+    Error,
+    U32Val,
+    U64Val,
+    I128Val,
+    U256Val,
+    I256Val,
+    Symbol,
+    AddressObject,
+    BytesObject,
+    DurationObject,
+    I128Object,
+    I256Object,
+    I64Object,
+    MapObject,
+    StringObject,
+    SymbolObject,
+    TimepointObject,
+    U128Object,
+    U256Object,
+    U64Object,
+    VecObject
+);
+
+impl From<u64> for MockArg {
+    fn from(v: u64) -> Self {
+        MockArg::U64(v)
+    }
+}
+
+impl From<i64> for MockArg {
+    fn from(v: i64) -> Self {
+        MockArg::I64(v)
+    }
+}
+
+impl From<StorageType> for MockArg {
+    fn from(v: StorageType) -> Self {
+        MockArg::StorageType(v)
+    }
+}
+
+/// A host function call recorded by [MockEnv].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// The name of the `Env` method called, e.g. `"vec_push_back"`.
+    pub name: &'static str,
+    pub args: Vec<MockArg>,
+}
+
+/// A contract event emitted through [MockEnv].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockEvent {
+    pub topics: Vec<ScVal>,
+    pub data: ScVal,
+}
+
+/// The ledger state returned by the ledger-info host functions of [MockEnv].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockLedgerInfo {
+    pub protocol_version: u32,
+    pub sequence_number: u32,
+    pub timestamp: u64,
+    pub network_id: [u8; 32],
+    pub max_live_until_ledger: u32,
+}
+
+impl Default for MockLedgerInfo {
+    fn default() -> Self {
+        Self {
+            protocol_version: meta::INTERFACE_VERSION.protocol,
+            sequence_number: 0,
+            timestamp: 0,
+            network_id: [0; 32],
+            max_live_until_ledger: u32::MAX,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    objects: Vec<ScVal>,
+    // Keyed by the storage type's discriminant, since `StorageType` isn't
+    // `Ord`.
+    storage: BTreeMap<(u64, ScVal), ScVal>,
+    calls: Vec<MockCall>,
+    events: Vec<MockEvent>,
+    logs: Vec<(String, Vec<ScVal>)>,
+    ledger: MockLedgerInfo,
+    current_contract: Option<ScAddress>,
+}
+
+/// An in-memory, unmetered [Env]. Clones share the same state, like clones
+/// of the host.
+#[derive(Clone, Default)]
+pub struct MockEnv(Rc<RefCell<MockState>>);
+
+fn err(type_: ScErrorType, code: ScErrorCode) -> Error {
+    Error::from_type_and_code(type_, code)
+}
+
+fn unsupported() -> Error {
+    err(ScErrorType::Context, ScErrorCode::InvalidAction)
+}
+
+fn index_bounds() -> Error {
+    err(ScErrorType::Object, ScErrorCode::IndexBounds)
+}
+
+fn unexpected_type() -> Error {
+    err(ScErrorType::Object, ScErrorCode::UnexpectedType)
+}
+
+fn arith_domain() -> Error {
+    err(ScErrorType::Object, ScErrorCode::ArithDomain)
+}
+
+fn object_tag(v: &ScVal) -> Option<Tag> {
+    Some(match v {
+        ScVal::U64(_) => Tag::U64Object,
+        ScVal::I64(_) => Tag::I64Object,
+        ScVal::Timepoint(_) => Tag::TimepointObject,
+        ScVal::Duration(_) => Tag::DurationObject,
+        ScVal::U128(_) => Tag::U128Object,
+        ScVal::I128(_) => Tag::I128Object,
+        ScVal::U256(_) => Tag::U256Object,
+        ScVal::I256(_) => Tag::I256Object,
+        ScVal::Bytes(_) => Tag::BytesObject,
+        ScVal::String(_) => Tag::StringObject,
+        ScVal::Symbol(_) => Tag::SymbolObject,
+        ScVal::Vec(_) => Tag::VecObject,
+        ScVal::Map(_) => Tag::MapObject,
+        ScVal::Address(_) => Tag::AddressObject,
+        _ => return None,
+    })
+}
+
+fn index(i: U32Val, len: usize) -> Result<usize, Error> {
+    let i = u32::from(i) as usize;
+    if i < len {
+        Ok(i)
+    } else {
+        Err(index_bounds())
+    }
+}
+
+fn range(start: U32Val, end: U32Val, len: usize) -> Result<core::ops::Range<usize>, Error> {
+    let (start, end) = (u32::from(start) as usize, u32::from(end) as usize);
+    if start <= end && end <= len {
+        Ok(start..end)
+    } else {
+        Err(index_bounds())
+    }
+}
+
+fn u32_len(len: usize) -> Result<U32Val, Error> {
+    u32::try_from(len)
+        .map(U32Val::from)
+        .map_err(|_| err(ScErrorType::Object, ScErrorCode::ExceededLimit))
+}
+
+impl MockEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every host function call made so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.0.borrow().calls.clone()
+    }
+
+    /// Returns the arguments of every call made so far to the host function
+    /// `name`.
+    pub fn calls_to(&self, name: &str) -> Vec<Vec<MockArg>> {
+        self.0
+            .borrow()
+            .calls
+            .iter()
+            .filter(|c| c.name == name)
+            .map(|c| c.args.clone())
+            .collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.0.borrow_mut().calls.clear()
+    }
+
+    pub fn events(&self) -> Vec<MockEvent> {
+        self.0.borrow().events.clone()
+    }
+
+    /// Returns the messages and values logged with [EnvBase::log_from_slice].
+    pub fn logs(&self) -> Vec<(String, Vec<ScVal>)> {
+        self.0.borrow().logs.clone()
+    }
+
+    pub fn set_ledger_info(&self, ledger: MockLedgerInfo) {
+        self.0.borrow_mut().ledger = ledger;
+    }
+
+    /// Sets the address returned by `get_current_contract_address`. Until it
+    /// is set that host function fails.
+    pub fn set_current_contract_address(&self, address: ScAddress) {
+        self.0.borrow_mut().current_contract = Some(address);
+    }
+
+    pub fn set_storage_entry(&self, storage_type: StorageType, key: ScVal, val: ScVal) {
+        self.0
+            .borrow_mut()
+            .storage
+            .insert((storage_type as u64, key), val);
+    }
+
+    pub fn storage_entry(&self, storage_type: StorageType, key: &ScVal) -> Option<ScVal> {
+        self.0
+            .borrow()
+            .storage
+            .get(&(storage_type as u64, key.clone()))
+            .cloned()
+    }
+
+    pub fn remove_storage_entry(&self, storage_type: StorageType, key: &ScVal) -> Option<ScVal> {
+        self.0
+            .borrow_mut()
+            .storage
+            .remove(&(storage_type as u64, key.clone()))
+    }
+
+    /// Converts `val` to an [ScVal], looking through object handles.
+    pub fn to_scval(&self, val: impl Into<Val>) -> Result<ScVal, Error> {
+        ScVal::try_from_val(self, &val.into())
+    }
+
+    /// Converts `val` to a [Val], creating objects as needed.
+    pub fn from_scval(&self, val: &ScVal) -> Result<Val, Error> {
+        Val::try_from_val(self, val)
+    }
+
+    fn record_call(&self, name: &'static str, args: Vec<MockArg>) {
+        self.0.borrow_mut().calls.push(MockCall { name, args });
+    }
+
+    // Always adds a new object, even for values that have a small
+    // representation, as the `obj_from_*` host functions do.
+    fn add_object<T: TryFrom<Object>>(&self, v: ScVal) -> Result<T, Error> {
+        let tag = object_tag(&v).ok_or_else(unexpected_type)?;
+        let mut state = self.0.borrow_mut();
+        let handle = u32::try_from(state.objects.len())
+            .map_err(|_| err(ScErrorType::Object, ScErrorCode::ExceededLimit))?;
+        state.objects.push(v);
+        T::try_from(Object::from_handle_and_tag(handle, tag)).map_err(|_| unexpected_type())
+    }
+
+    fn get_object(&self, obj: impl Into<Object>) -> Result<ScVal, Error> {
+        let obj: Object = obj.into();
+        let state = self.0.borrow();
+        let v = state
+            .objects
+            .get(obj.get_handle() as usize)
+            .ok_or_else(|| err(ScErrorType::Object, ScErrorCode::MissingValue))?;
+        if object_tag(v) != Some(obj.to_val().get_tag()) {
+            return Err(unexpected_type());
+        }
+        Ok(v.clone())
+    }
+
+    fn vec_items(&self, v: VecObject) -> Result<Vec<ScVal>, Error> {
+        match self.get_object(v)? {
+            ScVal::Vec(Some(ScVec(items))) => Ok(items.into()),
+            ScVal::Vec(None) => Ok(vec![]),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn new_vec(&self, items: Vec<ScVal>) -> Result<VecObject, Error> {
+        self.add_object(ScVal::Vec(Some(ScVec(items.try_into()?))))
+    }
+
+    fn map_entries(&self, m: MapObject) -> Result<Vec<ScMapEntry>, Error> {
+        match self.get_object(m)? {
+            ScVal::Map(Some(ScMap(entries))) => Ok(entries.into()),
+            ScVal::Map(None) => Ok(vec![]),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn new_map(&self, entries: Vec<ScMapEntry>) -> Result<MapObject, Error> {
+        self.add_object(ScVal::Map(Some(ScMap(entries.try_into()?))))
+    }
+
+    fn bytes_vec(&self, b: BytesObject) -> Result<Vec<u8>, Error> {
+        match self.get_object(b)? {
+            ScVal::Bytes(ScBytes(bytes)) => Ok(bytes.into()),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn new_bytes(&self, bytes: Vec<u8>) -> Result<BytesObject, Error> {
+        self.add_object(ScVal::Bytes(ScBytes(bytes.try_into()?)))
+    }
+
+    fn byte(&self, u: U32Val) -> Result<u8, Error> {
+        u8::try_from(u32::from(u)).map_err(|_| err(ScErrorType::Value, ScErrorCode::InvalidInput))
+    }
+
+    fn u256(&self, v: U256Val) -> Result<U256, Error> {
+        U256::try_from_val(self, &v.to_val())
+    }
+
+    fn i256(&self, v: I256Val) -> Result<I256, Error> {
+        I256::try_from_val(self, &v.to_val())
+    }
+
+    fn u256_op(
+        &self,
+        lhs: U256Val,
+        rhs: U256Val,
+        op: impl FnOnce(U256, U256) -> Option<U256>,
+    ) -> Result<U256Val, Error> {
+        let res = op(self.u256(lhs)?, self.u256(rhs)?).ok_or_else(arith_domain)?;
+        U256Val::try_from_val(self, &res)
+    }
+
+    fn i256_op(
+        &self,
+        lhs: I256Val,
+        rhs: I256Val,
+        op: impl FnOnce(I256, I256) -> Option<I256>,
+    ) -> Result<I256Val, Error> {
+        let res = op(self.i256(lhs)?, self.i256(rhs)?).ok_or_else(arith_domain)?;
+        I256Val::try_from_val(self, &res)
+    }
+
+    fn storage_key(&self, k: Val, t: StorageType) -> Result<(u64, ScVal), Error> {
+        Ok((t as u64, self.to_scval(k)?))
+    }
+
+    fn check_protocol(&self, ok: impl FnOnce(u32) -> bool) -> Result<(), Error> {
+        if ok(self.0.borrow().ledger.protocol_version) {
+            Ok(())
+        } else {
+            Err(err(ScErrorType::Context, ScErrorCode::IndexBounds))
+        }
+    }
+}
+
+impl Convert<Object, ScValObject> for MockEnv {
+    type Error = Error;
+
+    fn convert(&self, obj: Object) -> Result<ScValObject, Error> {
+        Ok(unsafe { ScValObject::unchecked_from_val(self.get_object(obj)?) })
+    }
+}
+
+impl<'a> Convert<ScValObjRef<'a>, Object> for MockEnv {
+    type Error = Error;
+
+    fn convert(&self, v: ScValObjRef<'a>) -> Result<Object, Error> {
+        self.add_object(v.as_ref().clone())
+    }
+}
+
+impl EnvBase for MockEnv {
+    type Error = Error;
+
+    fn error_from_error_val(&self, e: Error) -> Self::Error {
+        e
+    }
+
+    fn escalate_error_to_panic(&self, e: Self::Error) -> ! {
+        panic!("MockEnv error: {e:?}")
+    }
+
+    fn check_same_env(&self, other: &Self) -> Result<(), Self::Error> {
+        if Rc::ptr_eq(&self.0, &other.0) {
+            Ok(())
+        } else {
+            Err(err(ScErrorType::Context, ScErrorCode::InternalError))
+        }
+    }
+
+    fn bytes_copy_from_slice(
+        &self,
+        b: BytesObject,
+        b_pos: U32Val,
+        slice: &[u8],
+    ) -> Result<BytesObject, Self::Error> {
+        let mut bytes = self.bytes_vec(b)?;
+        let pos = u32::from(b_pos) as usize;
+        let end = pos.checked_add(slice.len()).ok_or_else(index_bounds)?;
+        if end > bytes.len() {
+            bytes.resize(end, 0);
+        }
+        bytes[pos..end].copy_from_slice(slice);
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_copy_to_slice(
+        &self,
+        b: BytesObject,
+        b_pos: U32Val,
+        slice: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let bytes = self.bytes_vec(b)?;
+        copy_to_slice(&bytes, b_pos, slice)
+    }
+
+    fn string_copy_to_slice(
+        &self,
+        b: StringObject,
+        b_pos: U32Val,
+        slice: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        match self.get_object(b)? {
+            ScVal::String(ScString(s)) => copy_to_slice(s.as_slice(), b_pos, slice),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn symbol_copy_to_slice(
+        &self,
+        b: SymbolObject,
+        b_pos: U32Val,
+        mem: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        match self.get_object(b)? {
+            ScVal::Symbol(ScSymbol(s)) => copy_to_slice(s.as_slice(), b_pos, mem),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn bytes_new_from_slice(&self, slice: &[u8]) -> Result<BytesObject, Self::Error> {
+        self.new_bytes(slice.to_vec())
+    }
+
+    fn string_new_from_slice(&self, slice: &[u8]) -> Result<StringObject, Self::Error> {
+        self.add_object(ScVal::String(ScString(slice.try_into()?)))
+    }
+
+    fn symbol_new_from_slice(&self, slice: &[u8]) -> Result<SymbolObject, Self::Error> {
+        for b in slice {
+            SymbolSmall::validate_byte(*b)?;
+        }
+        self.add_object(ScVal::Symbol(ScSymbol(slice.try_into()?)))
+    }
+
+    fn map_new_from_slices(&self, keys: &[&str], vals: &[Val]) -> Result<MapObject, Self::Error> {
+        if keys.len() != vals.len() {
+            return Err(err(ScErrorType::Object, ScErrorCode::UnexpectedSize));
+        }
+        let mut entries = Vec::with_capacity(keys.len());
+        for (k, v) in keys.iter().zip(vals.iter()) {
+            let key = self.to_scval(Symbol::try_from_val(self, k)?)?;
+            if let Some(prev) = entries.last() {
+                let prev: &ScMapEntry = prev;
+                if prev.key >= key {
+                    return Err(err(ScErrorType::Object, ScErrorCode::InvalidInput));
+                }
+            }
+            entries.push(ScMapEntry {
+                key,
+                val: self.to_scval(*v)?,
+            });
+        }
+        self.new_map(entries)
+    }
+
+    fn map_unpack_to_slice(
+        &self,
+        map: MapObject,
+        keys: &[&str],
+        vals: &mut [Val],
+    ) -> Result<Void, Self::Error> {
+        let entries = self.map_entries(map)?;
+        if keys.len() != vals.len() || entries.len() != vals.len() {
+            return Err(err(ScErrorType::Object, ScErrorCode::UnexpectedSize));
+        }
+        for ((k, entry), out) in keys.iter().zip(entries.iter()).zip(vals.iter_mut()) {
+            if self.to_scval(Symbol::try_from_val(self, k)?)? != entry.key {
+                return Err(err(ScErrorType::Object, ScErrorCode::InvalidInput));
+            }
+            *out = self.from_scval(&entry.val)?;
+        }
+        Ok(Void::from(()))
+    }
+
+    fn vec_new_from_slice(&self, vals: &[Val]) -> Result<VecObject, Self::Error> {
+        let items = vals
+            .iter()
+            .map(|v| self.to_scval(*v))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.new_vec(items)
+    }
+
+    fn vec_unpack_to_slice(&self, vec: VecObject, vals: &mut [Val]) -> Result<Void, Self::Error> {
+        let items = self.vec_items(vec)?;
+        if items.len() != vals.len() {
+            return Err(err(ScErrorType::Object, ScErrorCode::UnexpectedSize));
+        }
+        for (item, out) in items.iter().zip(vals.iter_mut()) {
+            *out = self.from_scval(item)?;
+        }
+        Ok(Void::from(()))
+    }
+
+    fn symbol_index_in_strs(&self, key: Symbol, strs: &[&str]) -> Result<U32Val, Self::Error> {
+        let key = self.to_scval(key)?;
+        for (i, s) in strs.iter().enumerate() {
+            if self.to_scval(Symbol::try_from_val(self, s)?)? == key {
+                return u32_len(i);
+            }
+        }
+        Err(err(ScErrorType::Value, ScErrorCode::InvalidInput))
+    }
+
+    fn log_from_slice(&self, msg: &str, vals: &[Val]) -> Result<Void, Self::Error> {
+        let vals = vals
+            .iter()
+            .map(|v| self.to_scval(*v))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.0.borrow_mut().logs.push((msg.to_string(), vals));
+        Ok(Void::from(()))
+    }
+
+    fn check_protocol_version_lower_bound(&self, lower_bound: u32) -> Result<(), Self::Error> {
+        self.check_protocol(|v| v >= lower_bound)
+    }
+
+    fn check_protocol_version_upper_bound(&self, upper_bound: u32) -> Result<(), Self::Error> {
+        self.check_protocol(|v| v <= upper_bound)
+    }
+}
+
+fn copy_to_slice(src: &[u8], pos: U32Val, dst: &mut [u8]) -> Result<(), Error> {
+    let pos = u32::from(pos) as usize;
+    let end = pos.checked_add(dst.len()).ok_or_else(index_bounds)?;
+    let src = src.get(pos..end).ok_or_else(index_bounds)?;
+    dst.copy_from_slice(src);
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// X-macro use: default (unsupported) mock host functions
+///////////////////////////////////////////////////////////////////////////////
+//
+// The mock's host function implementations live in a private trait whose
+// default methods, generated for every host function, fail. `MockEnv`
+// overrides the ones it supports, and the `Env` impl below records each call
+// before forwarding it to this trait.
+macro_rules! mock_default_function_helper {
+    {fn $fn_id:ident($($arg:ident:$type:ty),*) -> $ret:ty}
+    =>
+    {
+        #[allow(unused_variables, clippy::too_many_arguments)]
+        fn $fn_id(&self, $($arg:$type),*) -> Result<$ret, Error> {
+            Err(unsupported())
+        }
+    };
+}
+
+macro_rules! generate_mock_host_functions_trait {
+    {
+        $(
+            $(#[$mod_attr:meta])*
+            mod $mod_id:ident $mod_str:literal
+            {
+                $(
+                    $(#[$fn_attr:meta])*
+                    { $fn_str:literal, $($min_proto:literal)?, $($max_proto:literal)?, fn $fn_id:ident $args:tt -> $ret:ty }
+                )*
+            }
+        )*
+    }
+    =>
+    {
+        trait MockHostFunctions {
+            $(
+                $(
+                    mock_default_function_helper!{fn $fn_id $args -> $ret}
+                )*
+            )*
+        }
+    };
+}
+
+call_macro_with_all_host_functions! { generate_mock_host_functions_trait }
+
+///////////////////////////////////////////////////////////////////////////////
+/// X-macro use: impl Env for MockEnv
+///////////////////////////////////////////////////////////////////////////////
+macro_rules! mock_env_function_helper {
+    {fn $fn_id:ident($($arg:ident:$type:ty),*) -> $ret:ty}
+    =>
+    {
+        fn $fn_id(&self, $($arg:$type),*) -> Result<$ret, Self::Error> {
+            self.record_call(core::stringify!($fn_id), vec![$(MockArg::from($arg)),*]);
+            <Self as MockHostFunctions>::$fn_id(self, $($arg),*)
+        }
+    };
+}
+
+macro_rules! impl_env_for_mock_env {
+    {
+        $(
+            $(#[$mod_attr:meta])*
+            mod $mod_id:ident $mod_str:literal
+            {
+                $(
+                    $(#[$fn_attr:meta])*
+                    { $fn_str:literal, $($min_proto:literal)?, $($max_proto:literal)?, fn $fn_id:ident $args:tt -> $ret:ty }
+                )*
+            }
+        )*
+    }
+    =>
+    {
+        impl Env for MockEnv {
+            $(
+                $(
+                    mock_env_function_helper!{fn $fn_id $args -> $ret}
+                )*
+            )*
+        }
+    };
+}
+
+call_macro_with_all_host_functions! { impl_env_for_mock_env }
+
+impl MockHostFunctions for MockEnv {
+    // context
+
+    fn obj_cmp(&self, a: Val, b: Val) -> Result<i64, Error> {
+        Ok(match self.to_scval(a)?.cmp(&self.to_scval(b)?) {
+            core::cmp::Ordering::Less => -1,
+            core::cmp::Ordering::Equal => 0,
+            core::cmp::Ordering::Greater => 1,
+        })
+    }
+
+    fn contract_event(&self, topics: VecObject, data: Val) -> Result<Void, Error> {
+        let event = MockEvent {
+            topics: self.vec_items(topics)?,
+            data: self.to_scval(data)?,
+        };
+        self.0.borrow_mut().events.push(event);
+        Ok(Void::from(()))
+    }
+
+    fn get_ledger_version(&self) -> Result<U32Val, Error> {
+        Ok(self.0.borrow().ledger.protocol_version.into())
+    }
+
+    fn get_ledger_sequence(&self) -> Result<U32Val, Error> {
+        Ok(self.0.borrow().ledger.sequence_number.into())
+    }
+
+    fn get_ledger_timestamp(&self) -> Result<U64Val, Error> {
+        let timestamp = self.0.borrow().ledger.timestamp;
+        U64Val::try_from_val(self, &timestamp)
+    }
+
+    fn fail_with_error(&self, error: Error) -> Result<Void, Error> {
+        Err(error)
+    }
+
+    fn get_ledger_network_id(&self) -> Result<BytesObject, Error> {
+        let network_id = self.0.borrow().ledger.network_id;
+        self.new_bytes(network_id.to_vec())
+    }
+
+    fn get_current_contract_address(&self) -> Result<AddressObject, Error> {
+        let address = self.0.borrow().current_contract.clone();
+        self.add_object(ScVal::Address(address.ok_or_else(unsupported)?))
+    }
+
+    fn get_max_live_until_ledger(&self) -> Result<U32Val, Error> {
+        Ok(self.0.borrow().ledger.max_live_until_ledger.into())
+    }
+
+    // int
+
+    fn obj_from_u64(&self, v: u64) -> Result<U64Object, Error> {
+        self.add_object(ScVal::U64(v))
+    }
+
+    fn obj_to_u64(&self, obj: U64Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U64(v) => Ok(v),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_from_i64(&self, v: i64) -> Result<I64Object, Error> {
+        self.add_object(ScVal::I64(v))
+    }
+
+    fn obj_to_i64(&self, obj: I64Object) -> Result<i64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I64(v) => Ok(v),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_from_u128_pieces(&self, hi: u64, lo: u64) -> Result<U128Object, Error> {
+        self.add_object(ScVal::U128(UInt128Parts { hi, lo }))
+    }
+
+    fn obj_to_u128_lo64(&self, obj: U128Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U128(parts) => Ok(parts.lo),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_u128_hi64(&self, obj: U128Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U128(parts) => Ok(parts.hi),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_from_i128_pieces(&self, hi: i64, lo: u64) -> Result<I128Object, Error> {
+        self.add_object(ScVal::I128(Int128Parts { hi, lo }))
+    }
+
+    fn obj_to_i128_lo64(&self, obj: I128Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I128(parts) => Ok(parts.lo),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_i128_hi64(&self, obj: I128Object) -> Result<i64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I128(parts) => Ok(parts.hi),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_from_u256_pieces(
+        &self,
+        hi_hi: u64,
+        hi_lo: u64,
+        lo_hi: u64,
+        lo_lo: u64,
+    ) -> Result<U256Object, Error> {
+        self.add_object(ScVal::U256(UInt256Parts {
+            hi_hi,
+            hi_lo,
+            lo_hi,
+            lo_lo,
+        }))
+    }
+
+    fn u256_val_from_be_bytes(&self, bytes: BytesObject) -> Result<U256Val, Error> {
+        let bytes: [u8; 32] = self
+            .bytes_vec(bytes)?
+            .try_into()
+            .map_err(|_| err(ScErrorType::Object, ScErrorCode::UnexpectedSize))?;
+        U256Val::try_from_val(self, &U256::from_be_bytes(bytes))
+    }
+
+    fn u256_val_to_be_bytes(&self, val: U256Val) -> Result<BytesObject, Error> {
+        self.new_bytes(self.u256(val)?.to_be_bytes().to_vec())
+    }
+
+    fn obj_to_u256_hi_hi(&self, obj: U256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U256(parts) => Ok(parts.hi_hi),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_u256_hi_lo(&self, obj: U256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U256(parts) => Ok(parts.hi_lo),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_u256_lo_hi(&self, obj: U256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U256(parts) => Ok(parts.lo_hi),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_u256_lo_lo(&self, obj: U256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::U256(parts) => Ok(parts.lo_lo),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_from_i256_pieces(
+        &self,
+        hi_hi: i64,
+        hi_lo: u64,
+        lo_hi: u64,
+        lo_lo: u64,
+    ) -> Result<I256Object, Error> {
+        self.add_object(ScVal::I256(Int256Parts {
+            hi_hi,
+            hi_lo,
+            lo_hi,
+            lo_lo,
+        }))
+    }
+
+    fn i256_val_from_be_bytes(&self, bytes: BytesObject) -> Result<I256Val, Error> {
+        let bytes: [u8; 32] = self
+            .bytes_vec(bytes)?
+            .try_into()
+            .map_err(|_| err(ScErrorType::Object, ScErrorCode::UnexpectedSize))?;
+        I256Val::try_from_val(self, &I256::from_be_bytes(bytes))
+    }
+
+    fn i256_val_to_be_bytes(&self, val: I256Val) -> Result<BytesObject, Error> {
+        self.new_bytes(self.i256(val)?.to_be_bytes().to_vec())
+    }
+
+    fn obj_to_i256_hi_hi(&self, obj: I256Object) -> Result<i64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I256(parts) => Ok(parts.hi_hi),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_i256_hi_lo(&self, obj: I256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I256(parts) => Ok(parts.hi_lo),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_i256_lo_hi(&self, obj: I256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I256(parts) => Ok(parts.lo_hi),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn obj_to_i256_lo_lo(&self, obj: I256Object) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::I256(parts) => Ok(parts.lo_lo),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn u256_add(&self, lhs: U256Val, rhs: U256Val) -> Result<U256Val, Error> {
+        self.u256_op(lhs, rhs, |a, b| a.checked_add(b))
+    }
+
+    fn u256_sub(&self, lhs: U256Val, rhs: U256Val) -> Result<U256Val, Error> {
+        self.u256_op(lhs, rhs, |a, b| a.checked_sub(b))
+    }
+
+    fn u256_mul(&self, lhs: U256Val, rhs: U256Val) -> Result<U256Val, Error> {
+        self.u256_op(lhs, rhs, |a, b| a.checked_mul(b))
+    }
+
+    fn u256_div(&self, lhs: U256Val, rhs: U256Val) -> Result<U256Val, Error> {
+        self.u256_op(lhs, rhs, |a, b| a.checked_div(b))
+    }
+
+    fn u256_rem_euclid(&self, lhs: U256Val, rhs: U256Val) -> Result<U256Val, Error> {
+        self.u256_op(lhs, rhs, |a, b| a.checked_rem_euclid(b))
+    }
+
+    fn u256_pow(&self, lhs: U256Val, rhs: U32Val) -> Result<U256Val, Error> {
+        let res = self
+            .u256(lhs)?
+            .checked_pow(rhs.into())
+            .ok_or_else(arith_domain)?;
+        U256Val::try_from_val(self, &res)
+    }
+
+    fn u256_shl(&self, lhs: U256Val, rhs: U32Val) -> Result<U256Val, Error> {
+        let res = self
+            .u256(lhs)?
+            .checked_shl(rhs.into())
+            .ok_or_else(arith_domain)?;
+        U256Val::try_from_val(self, &res)
+    }
+
+    fn u256_shr(&self, lhs: U256Val, rhs: U32Val) -> Result<U256Val, Error> {
+        let res = self
+            .u256(lhs)?
+            .checked_shr(rhs.into())
+            .ok_or_else(arith_domain)?;
+        U256Val::try_from_val(self, &res)
+    }
+
+    fn i256_add(&self, lhs: I256Val, rhs: I256Val) -> Result<I256Val, Error> {
+        self.i256_op(lhs, rhs, |a, b| a.checked_add(b))
+    }
+
+    fn i256_sub(&self, lhs: I256Val, rhs: I256Val) -> Result<I256Val, Error> {
+        self.i256_op(lhs, rhs, |a, b| a.checked_sub(b))
+    }
+
+    fn i256_mul(&self, lhs: I256Val, rhs: I256Val) -> Result<I256Val, Error> {
+        self.i256_op(lhs, rhs, |a, b| a.checked_mul(b))
+    }
+
+    fn i256_div(&self, lhs: I256Val, rhs: I256Val) -> Result<I256Val, Error> {
+        self.i256_op(lhs, rhs, |a, b| a.checked_div(b))
+    }
+
+    fn i256_rem_euclid(&self, lhs: I256Val, rhs: I256Val) -> Result<I256Val, Error> {
+        self.i256_op(lhs, rhs, |a, b| a.checked_rem_euclid(b))
+    }
+
+    fn i256_pow(&self, lhs: I256Val, rhs: U32Val) -> Result<I256Val, Error> {
+        let res = self
+            .i256(lhs)?
+            .checked_pow(rhs.into())
+            .ok_or_else(arith_domain)?;
+        I256Val::try_from_val(self, &res)
+    }
+
+    fn i256_shl(&self, lhs: I256Val, rhs: U32Val) -> Result<I256Val, Error> {
+        let res = self
+            .i256(lhs)?
+            .checked_shl(rhs.into())
+            .ok_or_else(arith_domain)?;
+        I256Val::try_from_val(self, &res)
+    }
+
+    fn i256_shr(&self, lhs: I256Val, rhs: U32Val) -> Result<I256Val, Error> {
+        let res = self
+            .i256(lhs)?
+            .checked_shr(rhs.into())
+            .ok_or_else(arith_domain)?;
+        I256Val::try_from_val(self, &res)
+    }
+
+    fn timepoint_obj_from_u64(&self, v: u64) -> Result<TimepointObject, Error> {
+        self.add_object(ScVal::Timepoint(TimePoint(v)))
+    }
+
+    fn timepoint_obj_to_u64(&self, obj: TimepointObject) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::Timepoint(TimePoint(v)) => Ok(v),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn duration_obj_from_u64(&self, v: u64) -> Result<DurationObject, Error> {
+        self.add_object(ScVal::Duration(Duration(v)))
+    }
+
+    fn duration_obj_to_u64(&self, obj: DurationObject) -> Result<u64, Error> {
+        match self.get_object(obj)? {
+            ScVal::Duration(Duration(v)) => Ok(v),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    // map
+
+    fn map_new(&self) -> Result<MapObject, Error> {
+        self.new_map(vec![])
+    }
+
+    fn map_put(&self, m: MapObject, k: Val, v: Val) -> Result<MapObject, Error> {
+        let mut entries = self.map_entries(m)?;
+        let entry = ScMapEntry {
+            key: self.to_scval(k)?,
+            val: self.to_scval(v)?,
+        };
+        match entries.binary_search_by(|e| e.key.cmp(&entry.key)) {
+            Ok(i) => entries[i] = entry,
+            Err(i) => entries.insert(i, entry),
+        }
+        self.new_map(entries)
+    }
+
+    fn map_get(&self, m: MapObject, k: Val) -> Result<Val, Error> {
+        let entries = self.map_entries(m)?;
+        let key = self.to_scval(k)?;
+        match entries.binary_search_by(|e| e.key.cmp(&key)) {
+            Ok(i) => self.from_scval(&entries[i].val),
+            Err(_) => Err(err(ScErrorType::Object, ScErrorCode::MissingValue)),
+        }
+    }
+
+    fn map_del(&self, m: MapObject, k: Val) -> Result<MapObject, Error> {
+        let mut entries = self.map_entries(m)?;
+        let key = self.to_scval(k)?;
+        match entries.binary_search_by(|e| e.key.cmp(&key)) {
+            Ok(i) => {
+                entries.remove(i);
+                self.new_map(entries)
+            }
+            Err(_) => Err(err(ScErrorType::Object, ScErrorCode::MissingValue)),
+        }
+    }
+
+    fn map_len(&self, m: MapObject) -> Result<U32Val, Error> {
+        u32_len(self.map_entries(m)?.len())
+    }
+
+    fn map_has(&self, m: MapObject, k: Val) -> Result<Bool, Error> {
+        let entries = self.map_entries(m)?;
+        let key = self.to_scval(k)?;
+        Ok(entries.binary_search_by(|e| e.key.cmp(&key)).is_ok().into())
+    }
+
+    fn map_key_by_pos(&self, m: MapObject, i: U32Val) -> Result<Val, Error> {
+        let entries = self.map_entries(m)?;
+        self.from_scval(&entries[index(i, entries.len())?].key)
+    }
+
+    fn map_val_by_pos(&self, m: MapObject, i: U32Val) -> Result<Val, Error> {
+        let entries = self.map_entries(m)?;
+        self.from_scval(&entries[index(i, entries.len())?].val)
+    }
+
+    fn map_keys(&self, m: MapObject) -> Result<VecObject, Error> {
+        let entries = self.map_entries(m)?;
+        self.new_vec(entries.into_iter().map(|e| e.key).collect())
+    }
+
+    fn map_values(&self, m: MapObject) -> Result<VecObject, Error> {
+        let entries = self.map_entries(m)?;
+        self.new_vec(entries.into_iter().map(|e| e.val).collect())
+    }
+
+    // vec
+
+    fn vec_new(&self) -> Result<VecObject, Error> {
+        self.new_vec(vec![])
+    }
+
+    fn vec_put(&self, v: VecObject, i: U32Val, x: Val) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        let i = index(i, items.len())?;
+        items[i] = self.to_scval(x)?;
+        self.new_vec(items)
+    }
+
+    fn vec_get(&self, v: VecObject, i: U32Val) -> Result<Val, Error> {
+        let items = self.vec_items(v)?;
+        self.from_scval(&items[index(i, items.len())?])
+    }
+
+    fn vec_del(&self, v: VecObject, i: U32Val) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        items.remove(index(i, items.len())?);
+        self.new_vec(items)
+    }
+
+    fn vec_len(&self, v: VecObject) -> Result<U32Val, Error> {
+        u32_len(self.vec_items(v)?.len())
+    }
+
+    fn vec_push_front(&self, v: VecObject, x: Val) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        items.insert(0, self.to_scval(x)?);
+        self.new_vec(items)
+    }
+
+    fn vec_pop_front(&self, v: VecObject) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        if items.is_empty() {
+            return Err(index_bounds());
+        }
+        items.remove(0);
+        self.new_vec(items)
+    }
+
+    fn vec_push_back(&self, v: VecObject, x: Val) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        items.push(self.to_scval(x)?);
+        self.new_vec(items)
+    }
+
+    fn vec_pop_back(&self, v: VecObject) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        items.pop().ok_or_else(index_bounds)?;
+        self.new_vec(items)
+    }
+
+    fn vec_front(&self, v: VecObject) -> Result<Val, Error> {
+        let items = self.vec_items(v)?;
+        self.from_scval(items.first().ok_or_else(index_bounds)?)
+    }
+
+    fn vec_back(&self, v: VecObject) -> Result<Val, Error> {
+        let items = self.vec_items(v)?;
+        self.from_scval(items.last().ok_or_else(index_bounds)?)
+    }
+
+    fn vec_insert(&self, v: VecObject, i: U32Val, x: Val) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v)?;
+        let i = u32::from(i) as usize;
+        if i > items.len() {
+            return Err(index_bounds());
+        }
+        items.insert(i, self.to_scval(x)?);
+        self.new_vec(items)
+    }
+
+    fn vec_append(&self, v1: VecObject, v2: VecObject) -> Result<VecObject, Error> {
+        let mut items = self.vec_items(v1)?;
+        items.extend(self.vec_items(v2)?);
+        self.new_vec(items)
+    }
+
+    fn vec_slice(&self, v: VecObject, start: U32Val, end: U32Val) -> Result<VecObject, Error> {
+        let items = self.vec_items(v)?;
+        let range = range(start, end, items.len())?;
+        self.new_vec(items[range].to_vec())
+    }
+
+    fn vec_first_index_of(&self, v: VecObject, x: Val) -> Result<Val, Error> {
+        let x = self.to_scval(x)?;
+        Ok(match self.vec_items(v)?.iter().position(|i| *i == x) {
+            Some(i) => u32_len(i)?.into(),
+            None => Val::VOID.into(),
+        })
+    }
+
+    fn vec_last_index_of(&self, v: VecObject, x: Val) -> Result<Val, Error> {
+        let x = self.to_scval(x)?;
+        Ok(match self.vec_items(v)?.iter().rposition(|i| *i == x) {
+            Some(i) => u32_len(i)?.into(),
+            None => Val::VOID.into(),
+        })
+    }
+
+    fn vec_binary_search(&self, v: VecObject, x: Val) -> Result<u64, Error> {
+        let x = self.to_scval(x)?;
+        Ok(match self.vec_items(v)?.binary_search(&x) {
+            Ok(i) => u64::from(u32::from(u32_len(i)?)) | (1_u64 << u32::BITS),
+            Err(i) => u64::from(u32::from(u32_len(i)?)),
+        })
+    }
+
+    // ledger
+
+    fn put_contract_data(&self, k: Val, v: Val, t: StorageType) -> Result<Void, Error> {
+        let key = self.storage_key(k, t)?;
+        let val = self.to_scval(v)?;
+        self.0.borrow_mut().storage.insert(key, val);
+        Ok(Void::from(()))
+    }
+
+    fn has_contract_data(&self, k: Val, t: StorageType) -> Result<Bool, Error> {
+        let key = self.storage_key(k, t)?;
+        Ok(self.0.borrow().storage.contains_key(&key).into())
+    }
+
+    fn get_contract_data(&self, k: Val, t: StorageType) -> Result<Val, Error> {
+        let key = self.storage_key(k, t)?;
+        let val = self.0.borrow().storage.get(&key).cloned();
+        self.from_scval(&val.ok_or_else(|| err(ScErrorType::Storage, ScErrorCode::MissingValue))?)
+    }
+
+    fn del_contract_data(&self, k: Val, t: StorageType) -> Result<Void, Error> {
+        let key = self.storage_key(k, t)?;
+        self.0.borrow_mut().storage.remove(&key);
+        Ok(Void::from(()))
+    }
+
+    // TTLs aren't modelled, so extending them only checks the entry exists.
+    fn extend_contract_data_ttl(
+        &self,
+        k: Val,
+        t: StorageType,
+        _threshold: U32Val,
+        _extend_to: U32Val,
+    ) -> Result<Void, Error> {
+        let key = self.storage_key(k, t)?;
+        if !self.0.borrow().storage.contains_key(&key) {
+            return Err(err(ScErrorType::Storage, ScErrorCode::MissingValue));
+        }
+        Ok(Void::from(()))
+    }
+
+    fn extend_current_contract_instance_and_code_ttl(
+        &self,
+        _threshold: U32Val,
+        _extend_to: U32Val,
+    ) -> Result<Void, Error> {
+        Ok(Void::from(()))
+    }
+
+    // buf
+
+    fn serialize_to_bytes(&self, v: Val) -> Result<BytesObject, Error> {
+        self.new_bytes(self.to_scval(v)?.to_xdr(Limits::none())?)
+    }
+
+    fn deserialize_from_bytes(&self, b: BytesObject) -> Result<Val, Error> {
+        self.from_scval(&ScVal::from_xdr(self.bytes_vec(b)?, Limits::none())?)
+    }
+
+    fn bytes_new(&self) -> Result<BytesObject, Error> {
+        self.new_bytes(vec![])
+    }
+
+    fn bytes_put(&self, b: BytesObject, i: U32Val, u: U32Val) -> Result<BytesObject, Error> {
+        let mut bytes = self.bytes_vec(b)?;
+        let i = index(i, bytes.len())?;
+        bytes[i] = self.byte(u)?;
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_get(&self, b: BytesObject, i: U32Val) -> Result<U32Val, Error> {
+        let bytes = self.bytes_vec(b)?;
+        Ok(u32::from(bytes[index(i, bytes.len())?]).into())
+    }
+
+    fn bytes_del(&self, b: BytesObject, i: U32Val) -> Result<BytesObject, Error> {
+        let mut bytes = self.bytes_vec(b)?;
+        bytes.remove(index(i, bytes.len())?);
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_len(&self, b: BytesObject) -> Result<U32Val, Error> {
+        u32_len(self.bytes_vec(b)?.len())
+    }
+
+    fn bytes_push(&self, b: BytesObject, u: U32Val) -> Result<BytesObject, Error> {
+        let mut bytes = self.bytes_vec(b)?;
+        bytes.push(self.byte(u)?);
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_pop(&self, b: BytesObject) -> Result<BytesObject, Error> {
+        let mut bytes = self.bytes_vec(b)?;
+        bytes.pop().ok_or_else(index_bounds)?;
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_front(&self, b: BytesObject) -> Result<U32Val, Error> {
+        let bytes = self.bytes_vec(b)?;
+        Ok(u32::from(*bytes.first().ok_or_else(index_bounds)?).into())
+    }
+
+    fn bytes_back(&self, b: BytesObject) -> Result<U32Val, Error> {
+        let bytes = self.bytes_vec(b)?;
+        Ok(u32::from(*bytes.last().ok_or_else(index_bounds)?).into())
+    }
+
+    fn bytes_insert(&self, b: BytesObject, i: U32Val, u: U32Val) -> Result<BytesObject, Error> {
+        let mut bytes = self.bytes_vec(b)?;
+        let i = u32::from(i) as usize;
+        if i > bytes.len() {
+            return Err(index_bounds());
+        }
+        bytes.insert(i, self.byte(u)?);
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_append(&self, b1: BytesObject, b2: BytesObject) -> Result<BytesObject, Error> {
+        let mut bytes = self.bytes_vec(b1)?;
+        bytes.extend(self.bytes_vec(b2)?);
+        self.new_bytes(bytes)
+    }
+
+    fn bytes_slice(
+        &self,
+        b: BytesObject,
+        start: U32Val,
+        end: U32Val,
+    ) -> Result<BytesObject, Error> {
+        let bytes = self.bytes_vec(b)?;
+        let range = range(start, end, bytes.len())?;
+        self.new_bytes(bytes[range].to_vec())
+    }
+
+    fn string_len(&self, s: StringObject) -> Result<U32Val, Error> {
+        match self.get_object(s)? {
+            ScVal::String(ScString(s)) => u32_len(s.len()),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    fn symbol_len(&self, s: SymbolObject) -> Result<U32Val, Error> {
+        match self.get_object(s)? {
+            ScVal::Symbol(ScSymbol(s)) => u32_len(s.len()),
+            _ => Err(unexpected_type()),
+        }
+    }
+
+    // address
+
+    fn require_auth_for_args(
+        &self,
+        address: AddressObject,
+        args: VecObject,
+    ) -> Result<Void, Error> {
+        self.get_object(address)?;
+        self.vec_items(args)?;
+        Ok(Void::from(()))
+    }
+
+    fn require_auth(&self, address: AddressObject) -> Result<Void, Error> {
+        self.get_object(address)?;
+        Ok(Void::from(()))
+    }
+
+    // test
+
+    fn dummy0(&self) -> Result<Val, Error> {
+        Ok(Val::VOID.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{unsupported, MockArg, MockEnv};
+    use crate::{
+        xdr::{Hash, ScAddress, ScBytes, ScSymbol, ScVal, ScVec},
+        AddressObject, Env, StorageType, Symbol, TryIntoVal, U32Val, Val, VecObject,
+    };
+
+    #[test]
+    fn objects_round_trip_through_scval() {
+        let env = MockEnv::new();
+        let v = ScVal::Vec(Some(ScVec(
+            vec![
+                ScVal::U64(u64::MAX),
+                ScVal::Symbol(ScSymbol("a_rather_long_symbol".try_into().unwrap())),
+                ScVal::Bytes(ScBytes(vec![1, 2, 3].try_into().unwrap())),
+            ]
+            .try_into()
+            .unwrap(),
+        )));
+        let val = env.from_scval(&v).unwrap();
+        assert_eq!(env.to_scval(val).unwrap(), v);
+
+        let vec: VecObject = val.try_into().unwrap();
+        let vec = env.vec_push_back(vec, U32Val::from(7).into()).unwrap();
+        assert_eq!(u32::from(env.vec_len(vec).unwrap()), 4);
+        let last: u32 = env.vec_back(vec).unwrap().try_into_val(&env).unwrap();
+        assert_eq!(last, 7);
+        let big: u64 = env
+            .vec_get(vec, U32Val::from(0))
+            .unwrap()
+            .try_into_val(&env)
+            .unwrap();
+        assert_eq!(big, u64::MAX);
+    }
+
+    #[test]
+    fn maps_are_sorted_and_storage_is_configurable() {
+        let env = MockEnv::new();
+        let m = env.map_new().unwrap();
+        let m = env
+            .map_put(m, U32Val::from(2).into(), Val::from_bool(true).into())
+            .unwrap();
+        let m = env
+            .map_put(m, U32Val::from(1).into(), Val::from_bool(false).into())
+            .unwrap();
+        let first: u32 = env
+            .map_key_by_pos(m, U32Val::from(0))
+            .unwrap()
+            .try_into_val(&env)
+            .unwrap();
+        assert_eq!(first, 1);
+
+        env.set_storage_entry(StorageType::Persistent, ScVal::U32(1), ScVal::U32(10));
+        let k: Val = U32Val::from(1).into();
+        assert!(bool::from(
+            env.has_contract_data(k, StorageType::Persistent).unwrap()
+        ));
+        assert!(!bool::from(
+            env.has_contract_data(k, StorageType::Temporary).unwrap()
+        ));
+        env.put_contract_data(k, m.into(), StorageType::Temporary)
+            .unwrap();
+        assert!(matches!(
+            env.storage_entry(StorageType::Temporary, &ScVal::U32(1)),
+            Some(ScVal::Map(_))
+        ));
+    }
+
+    #[test]
+    fn calls_are_recorded_and_unsupported_calls_fail() {
+        let env = MockEnv::new();
+        let v = env.vec_new().unwrap();
+        let x: Val = U32Val::from(5).into();
+        env.vec_push_back(v, x).unwrap();
+        let address: AddressObject = env
+            .from_scval(&ScVal::Address(ScAddress::Contract(Hash([0; 32]))))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let e = env.call(address, Symbol::try_from_small_str("f").unwrap(), v);
+        assert_eq!(e.err(), Some(unsupported()));
+
+        assert_eq!(
+            env.calls_to("vec_push_back"),
+            vec![vec![MockArg::from(v), MockArg::from(x)]]
+        );
+        let names: Vec<_> = env.calls().iter().map(|c| c.name).collect();
+        assert_eq!(names, ["vec_new", "vec_push_back", "call"]);
+    }
+}
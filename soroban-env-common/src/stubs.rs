@@ -0,0 +1,40 @@
+//! This module contains the host interface described by `env.json` rendered
+//! for contracts written in languages other than Rust:
+//!
+//!   - [C_HEADER] is a C/C++ header (also usable from Zig via `@cImport`)
+//!     declaring every host function with its `Val`-encoded signature, its
+//!     Wasm import module and name, and `#if SOROBAN_PROTOCOL` guards around
+//!     functions only available in some protocols.
+//!
+//!   - [WAT_IMPORTS] is a list of WAT `import` declarations for every host
+//!     function, annotated with the same protocol ranges.
+//!
+//! Both are generated at build time from the same `env.json` as the [Env](crate::Env)
+//! trait, so they can't drift from the interface the host actually provides.
+//! Tooling can write them out to files as needed.
+
+soroban_env_macros::generate_host_interface_stubs!("env.json");
+
+#[cfg(test)]
+mod test {
+    use super::{C_HEADER, WAT_IMPORTS};
+
+    #[test]
+    fn stubs_declare_host_functions() {
+        assert!(C_HEADER.starts_with("/* Generated from env.json"));
+        assert!(C_HEADER.contains(
+            "SOROBAN_IMPORT(\"x\", \"_\") SorobanVoid soroban_log_from_linear_memory(\
+             SorobanU32Val msg_pos, SorobanU32Val msg_len, SorobanU32Val vals_pos, \
+             SorobanU32Val vals_len);"
+        ));
+        assert!(C_HEADER.contains("SorobanU32Val soroban_get_ledger_version(void);"));
+        assert!(WAT_IMPORTS.contains(
+            "(import \"x\" \"_\" (func $log_from_linear_memory (param i64 i64 i64 i64) (result i64)))"
+        ));
+        // Every declaration in the header has a matching import in the WAT.
+        assert_eq!(
+            C_HEADER.matches("SOROBAN_IMPORT(\"").count(),
+            WAT_IMPORTS.matches("(import ").count()
+        );
+    }
+}
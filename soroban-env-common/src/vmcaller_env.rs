@@ -3,9 +3,9 @@
 use crate::xdr::{ScErrorCode, ScErrorType};
 
 use super::{
-    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I256Object, I256Val,
-    I64Object, MapObject, StorageType, StringObject, SymbolObject, TimepointObject, U128Object,
-    U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
+    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I128Val, I256Object,
+    I256Val, I64Object, MapObject, StorageType, StringObject, SymbolObject, TimepointObject,
+    U128Object, U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
 };
 use crate::call_macro_with_all_host_functions;
 use crate::{CheckedEnvArg, EnvBase, Symbol};
@@ -85,6 +85,8 @@ macro_rules! host_function_helper {
     =>
     {
         $(#[$attr])*
+        // With `vmcaller`, 6-argument host functions exceed clippy's limit.
+        #[allow(clippy::too_many_arguments)]
         fn $fn_id(&self, vmcaller: &mut VmCaller<Self::VmUserState>, $($arg:$type),*) -> Result<$ret, Self::Error>;
     };
 }
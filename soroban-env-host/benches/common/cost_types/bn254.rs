@@ -0,0 +1,202 @@
+use crate::common::HostCostMeasurement;
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use rand::{rngs::StdRng, RngCore};
+use soroban_env_host::{
+    cost_runner::{
+        Bn254DecodeFpRun, Bn254DecodeFpSample, Bn254EncodeFpRun, Bn254EncodeFpSample,
+        Bn254FrAddRun, Bn254FrAddSubMulSample, Bn254FrFromU256Run, Bn254FrFromU256Sample,
+        Bn254FrMulRun, Bn254FrPowRun, Bn254FrPowSample, Bn254FrSubRun, Bn254FrToU256Run,
+        Bn254FrToU256Sample, Bn254G1AddRun, Bn254G1AddSample, Bn254G1CheckPointOnCurveRun,
+        Bn254G1CheckPointOnCurveSample, Bn254G1MsmRun, Bn254G1MsmSample, Bn254G1MulRun,
+        Bn254G1MulSample, Bn254G1ProjectiveToAffineRun, Bn254G1ProjectiveToAffineSample,
+        Bn254G2CheckPointInSubgroupRun, Bn254G2CheckPointInSubgroupSample,
+        Bn254G2CheckPointOnCurveRun, Bn254G2CheckPointOnCurveSample, Bn254PairingRun,
+        Bn254PairingSample,
+    },
+    xdr::ContractCostType::*,
+    Host, TryIntoVal, U256Val, U256,
+};
+
+pub(crate) struct Bn254EncodeFpMeasure;
+impl HostCostMeasurement for Bn254EncodeFpMeasure {
+    type Runner = Bn254EncodeFpRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254EncodeFpSample {
+        Bn254EncodeFpSample(Fq::rand(rng), vec![0; 32])
+    }
+}
+
+pub(crate) struct Bn254DecodeFpMeasure;
+impl HostCostMeasurement for Bn254DecodeFpMeasure {
+    type Runner = Bn254DecodeFpRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254DecodeFpSample {
+        Bn254DecodeFpSample(Fq::rand(rng).into_bigint().to_bytes_be(), "test")
+    }
+}
+
+pub(crate) struct Bn254G1CheckPointOnCurveMeasure;
+impl HostCostMeasurement for Bn254G1CheckPointOnCurveMeasure {
+    type Runner = Bn254G1CheckPointOnCurveRun;
+
+    fn new_random_case(
+        _host: &Host,
+        rng: &mut StdRng,
+        _input: u64,
+    ) -> Bn254G1CheckPointOnCurveSample {
+        Bn254G1CheckPointOnCurveSample(
+            G1Affine::new_unchecked(Fq::rand(rng), Fq::rand(rng)),
+            Bn254G1CheckPointOnCurve,
+        )
+    }
+}
+
+pub(crate) struct Bn254G2CheckPointOnCurveMeasure;
+impl HostCostMeasurement for Bn254G2CheckPointOnCurveMeasure {
+    type Runner = Bn254G2CheckPointOnCurveRun;
+
+    fn new_random_case(
+        _host: &Host,
+        rng: &mut StdRng,
+        _input: u64,
+    ) -> Bn254G2CheckPointOnCurveSample {
+        Bn254G2CheckPointOnCurveSample(
+            G2Affine::new_unchecked(Fq2::rand(rng), Fq2::rand(rng)),
+            Bn254G2CheckPointOnCurve,
+        )
+    }
+}
+
+pub(crate) struct Bn254G2CheckPointInSubgroupMeasure;
+impl HostCostMeasurement for Bn254G2CheckPointInSubgroupMeasure {
+    type Runner = Bn254G2CheckPointInSubgroupRun;
+
+    fn new_random_case(
+        _host: &Host,
+        rng: &mut StdRng,
+        _input: u64,
+    ) -> Bn254G2CheckPointInSubgroupSample {
+        Bn254G2CheckPointInSubgroupSample(G2Affine::rand(rng), Bn254G2CheckPointInSubgroup)
+    }
+}
+
+pub(crate) struct Bn254G1ProjectiveToAffineMeasure;
+impl HostCostMeasurement for Bn254G1ProjectiveToAffineMeasure {
+    type Runner = Bn254G1ProjectiveToAffineRun;
+
+    fn new_random_case(
+        _host: &Host,
+        rng: &mut StdRng,
+        _input: u64,
+    ) -> Bn254G1ProjectiveToAffineSample {
+        // G1 has cofactor 1, so a random projective point is already
+        // normalized. A sum isn't, which makes the conversion do its inversion.
+        Bn254G1ProjectiveToAffineSample(G1Affine::rand(rng) + G1Affine::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254G1AddMeasure;
+impl HostCostMeasurement for Bn254G1AddMeasure {
+    type Runner = Bn254G1AddRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254G1AddSample {
+        Bn254G1AddSample(G1Affine::rand(rng), G1Affine::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254G1MulMeasure;
+impl HostCostMeasurement for Bn254G1MulMeasure {
+    type Runner = Bn254G1MulRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254G1MulSample {
+        Bn254G1MulSample(G1Affine::rand(rng), Fr::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254G1MsmMeasure;
+impl HostCostMeasurement for Bn254G1MsmMeasure {
+    type Runner = Bn254G1MsmRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> Bn254G1MsmSample {
+        Bn254G1MsmSample(
+            (0..input).map(|_| G1Affine::rand(rng)).collect(),
+            (0..input).map(|_| Fr::rand(rng)).collect(),
+        )
+    }
+}
+
+pub(crate) struct Bn254PairingMeasure;
+impl HostCostMeasurement for Bn254PairingMeasure {
+    type Runner = Bn254PairingRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> Bn254PairingSample {
+        Bn254PairingSample(
+            (0..input).map(|_| G1Affine::rand(rng)).collect(),
+            (0..input).map(|_| G2Affine::rand(rng)).collect(),
+        )
+    }
+}
+
+pub(crate) struct Bn254FrFromU256Measure;
+impl HostCostMeasurement for Bn254FrFromU256Measure {
+    type Runner = Bn254FrFromU256Run;
+
+    fn new_random_case(host: &Host, rng: &mut StdRng, _input: u64) -> Bn254FrFromU256Sample {
+        let mut buf = [0; 32];
+        rng.fill_bytes(&mut buf);
+        let val: U256Val = U256::from_be_bytes(buf).try_into_val(host).unwrap();
+        Bn254FrFromU256Sample(val)
+    }
+}
+
+pub(crate) struct Bn254FrToU256Measure;
+impl HostCostMeasurement for Bn254FrToU256Measure {
+    type Runner = Bn254FrToU256Run;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254FrToU256Sample {
+        Bn254FrToU256Sample(Fr::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254FrAddMeasure;
+impl HostCostMeasurement for Bn254FrAddMeasure {
+    type Runner = Bn254FrAddRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254FrAddSubMulSample {
+        Bn254FrAddSubMulSample(Fr::rand(rng), Fr::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254FrSubMeasure;
+impl HostCostMeasurement for Bn254FrSubMeasure {
+    type Runner = Bn254FrSubRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254FrAddSubMulSample {
+        Bn254FrAddSubMulSample(Fr::rand(rng), Fr::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254FrMulMeasure;
+impl HostCostMeasurement for Bn254FrMulMeasure {
+    type Runner = Bn254FrMulRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> Bn254FrAddSubMulSample {
+        Bn254FrAddSubMulSample(Fr::rand(rng), Fr::rand(rng))
+    }
+}
+
+pub(crate) struct Bn254FrPowMeasure;
+impl HostCostMeasurement for Bn254FrPowMeasure {
+    type Runner = Bn254FrPowRun;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> Bn254FrPowSample {
+        assert!(input <= 64);
+        let rhs = if input == 64 {
+            u64::MAX
+        } else {
+            (1 << input) - 1
+        };
+        Bn254FrPowSample(Fr::rand(rng), rhs)
+    }
+}
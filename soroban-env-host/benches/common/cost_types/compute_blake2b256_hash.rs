@@ -0,0 +1,17 @@
+use crate::common::HostCostMeasurement;
+use rand::rngs::StdRng;
+use soroban_env_host::{cost_runner::ComputeBlake2b256HashRun, Host};
+
+// This measures the costs of performing a blake2b256 hash on a variable-sized
+// byte buffer. The input value is the size of the buffer. It should be
+// linear time.
+pub(crate) struct ComputeBlake2b256HashMeasure;
+
+impl HostCostMeasurement for ComputeBlake2b256HashMeasure {
+    type Runner = ComputeBlake2b256HashRun;
+
+    fn new_random_case(_host: &Host, _rng: &mut StdRng, input: u64) -> Vec<u8> {
+        let size = Self::INPUT_BASE_SIZE + input * Self::STEP_SIZE;
+        (0..size).map(|n| n as u8).collect()
+    }
+}
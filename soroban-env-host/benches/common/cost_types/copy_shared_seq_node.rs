@@ -0,0 +1,26 @@
+use crate::common::HostCostMeasurement;
+use rand::rngs::StdRng;
+use soroban_env_host::{
+    budget::AsBudget, cost_runner::CopySharedSeqNodeRun, Host, MeteredVector, U32Val, Val,
+};
+
+// This measures the cost of copying a node of a shared vector, by setting an
+// element of a vector that fits in a single leaf. The input value is the
+// number of elements, and the charge is by the size of the copied leaf. It
+// should be linear time.
+pub(crate) struct CopySharedSeqNodeMeasure;
+
+impl HostCostMeasurement for CopySharedSeqNodeMeasure {
+    type Runner = CopySharedSeqNodeRun;
+
+    const STEP_SIZE: u64 = 1;
+
+    fn new_random_case(host: &Host, _rng: &mut StdRng, input: u64) -> MeteredVector<Val> {
+        let len = Self::INPUT_BASE_SIZE + input * Self::STEP_SIZE;
+        let vals: Vec<Val> = (0..len).map(|i| U32Val::from(i as u32).to_val()).collect();
+        MeteredVector::from_vec(vals)
+            .unwrap()
+            .to_shared(host.as_budget())
+            .unwrap()
+    }
+}
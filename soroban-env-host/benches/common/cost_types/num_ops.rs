@@ -2,8 +2,10 @@ use crate::common::{util, HostCostMeasurement};
 use rand::{rngs::StdRng, RngCore};
 use soroban_env_common::{Env, EnvBase};
 use soroban_env_host::{
-    cost_runner::{Int256AddSubRun, Int256DivRun, Int256MulRun, Int256PowRun, Int256ShiftRun},
-    Host, I256Val, U32Val, I256,
+    cost_runner::{
+        Int256AddSubRun, Int256DivRun, Int256MulDivRun, Int256MulRun, Int256PowRun, Int256ShiftRun,
+    },
+    Host, I256Val, U256Val, U32Val, I256, U256,
 };
 
 // These are best guesses.
@@ -91,3 +93,31 @@ macro_rules! impl_int256_measure_rhs_u32 {
 }
 impl_int256_measure_rhs_u32!(Int256PowMeasure, Int256PowRun, worst_case_pow_pair);
 impl_int256_measure_rhs_u32!(Int256ShiftMeasure, Int256ShiftRun, worst_case_shift_pair);
+
+// This measures the cost of `u256_mul_div` on random inputs whose product
+// doesn't fit in 256 bits, which takes the long division path. `c` has its
+// top bit set and `a` doesn't, so that the quotient never overflows. It
+// should be constant time.
+pub(crate) struct Int256MulDivMeasure;
+
+impl HostCostMeasurement for Int256MulDivMeasure {
+    type Runner = Int256MulDivRun;
+
+    fn new_random_case(host: &Host, rng: &mut StdRng, _input: u64) -> (U256Val, U256Val, U256Val) {
+        let mut random_u256 = || {
+            let mut bytes = [0; 32];
+            rng.fill_bytes(bytes.as_mut_slice());
+            U256::from_be_bytes(bytes)
+        };
+        let a = (random_u256() >> 1) | (U256::ONE << 254);
+        let b = random_u256() | (U256::ONE << 255);
+        let c = random_u256() | (U256::ONE << 255);
+        let val = |x: U256| {
+            let bytes = host
+                .bytes_new_from_slice(x.to_be_bytes().as_slice())
+                .unwrap();
+            host.u256_val_from_be_bytes(bytes).unwrap()
+        };
+        (val(a), val(b), val(c))
+    }
+}
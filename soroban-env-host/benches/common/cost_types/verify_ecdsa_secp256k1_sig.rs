@@ -4,17 +4,21 @@ use elliptic_curve::scalar::IsHigh;
 use k256::ecdsa::{Signature, SigningKey};
 use rand::{rngs::StdRng, RngCore};
 use soroban_env_host::{
-    cost_runner::{EcdsaSecp256k1VerifyRun, EcdsaSecp256k1VerifySample},
+    cost_runner::{VerifyEcdsaSecp256k1SigRun, VerifyEcdsaSecp256k1SigSample},
     xdr::Hash,
     Host,
 };
 
-pub(crate) struct EcdsaSecp256k1VerifyMeasure {}
+pub(crate) struct VerifyEcdsaSecp256k1SigMeasure {}
 
-impl HostCostMeasurement for EcdsaSecp256k1VerifyMeasure {
-    type Runner = EcdsaSecp256k1VerifyRun;
+impl HostCostMeasurement for VerifyEcdsaSecp256k1SigMeasure {
+    type Runner = VerifyEcdsaSecp256k1SigRun;
 
-    fn new_random_case(_host: &Host, rng: &mut StdRng, _input: u64) -> EcdsaSecp256k1VerifySample {
+    fn new_random_case(
+        _host: &Host,
+        rng: &mut StdRng,
+        _input: u64,
+    ) -> VerifyEcdsaSecp256k1SigSample {
         let mut key_bytes = [0u8; 32];
         rng.fill_bytes(&mut key_bytes);
         let signer = SigningKey::from_bytes(&key_bytes.into()).unwrap();
@@ -25,7 +29,7 @@ impl HostCostMeasurement for EcdsaSecp256k1VerifyMeasure {
         if bool::from(sig.s().is_high()) {
             sig = sig.normalize_s().unwrap()
         }
-        EcdsaSecp256k1VerifySample {
+        VerifyEcdsaSecp256k1SigSample {
             pub_key: signer.verifying_key().clone(),
             msg_hash: Hash::from(msg_hash),
             sig,
@@ -0,0 +1,38 @@
+use crate::common::HostCostMeasurement;
+use ed25519_dalek::{Signer, SigningKey};
+use rand::rngs::StdRng;
+use soroban_env_host::{
+    cost_runner::{Ed25519BatchVerifyRun, Ed25519BatchVerifySample},
+    Host,
+};
+
+// This measures the cost of verifying a batch of Ed25519 signatures of short,
+// fixed-length messages. The input value is the number of signatures in the
+// batch. It should cost linear CPU in the number of signatures, with the same
+// slope as `VerifyEd25519SigMeasure`.
+pub(crate) struct Ed25519BatchVerifyMeasure;
+
+const MSG_LEN: usize = 64;
+
+impl HostCostMeasurement for Ed25519BatchVerifyMeasure {
+    type Runner = Ed25519BatchVerifyRun;
+
+    const STEP_SIZE: u64 = 4;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> Ed25519BatchVerifySample {
+        let n = 1 + input * Self::STEP_SIZE;
+        let mut sample = Ed25519BatchVerifySample {
+            keys: vec![],
+            msgs: vec![],
+            sigs: vec![],
+        };
+        for i in 0..n {
+            let signingkey = SigningKey::generate(rng);
+            let msg: Vec<u8> = (0..MSG_LEN).map(|x| (x as u64 + i) as u8).collect();
+            sample.sigs.push(signingkey.sign(msg.as_slice()));
+            sample.keys.push(signingkey.verifying_key());
+            sample.msgs.push(msg);
+        }
+        sample
+    }
+}
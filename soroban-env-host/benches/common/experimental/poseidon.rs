@@ -0,0 +1,60 @@
+use crate::common::HostCostMeasurement;
+use ark_bls12_381::Fr;
+use ark_ff::UniformRand;
+use rand::rngs::StdRng;
+use soroban_env_host::{
+    cost_runner::{
+        Poseidon2PermutationRun, Poseidon2PermutationSample, PoseidonPermutationRun,
+        PoseidonPermutationSample,
+    },
+    Host,
+};
+
+// The cost of a permutation is dominated by the number of rounds, and the
+// values of the parameters don't matter, so random ones are used for a
+// state of size 3 with the usual 8 full rounds. The input is the number of
+// partial rounds.
+const T: usize = 3;
+const ROUNDS_F: u32 = 8;
+
+fn random_vec(rng: &mut StdRng, len: usize) -> Vec<Fr> {
+    (0..len).map(|_| Fr::rand(rng)).collect()
+}
+
+pub(crate) struct PoseidonPermutationMeasure;
+impl HostCostMeasurement for PoseidonPermutationMeasure {
+    type Runner = PoseidonPermutationRun;
+
+    const STEP_SIZE: u64 = 8;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> PoseidonPermutationSample {
+        let rounds_p = input as u32;
+        PoseidonPermutationSample {
+            input: random_vec(rng, T),
+            d: 5,
+            rounds_f: ROUNDS_F,
+            rounds_p,
+            mds: random_vec(rng, T * T),
+            round_constants: random_vec(rng, (ROUNDS_F + rounds_p) as usize * T),
+        }
+    }
+}
+
+pub(crate) struct Poseidon2PermutationMeasure;
+impl HostCostMeasurement for Poseidon2PermutationMeasure {
+    type Runner = Poseidon2PermutationRun;
+
+    const STEP_SIZE: u64 = 8;
+
+    fn new_random_case(_host: &Host, rng: &mut StdRng, input: u64) -> Poseidon2PermutationSample {
+        let rounds_p = input as u32;
+        Poseidon2PermutationSample {
+            input: random_vec(rng, T),
+            d: 5,
+            rounds_f: ROUNDS_F,
+            rounds_p,
+            mat_internal_diag_m_1: random_vec(rng, T),
+            round_constants: random_vec(rng, (ROUNDS_F + rounds_p) as usize * T),
+        }
+    }
+}
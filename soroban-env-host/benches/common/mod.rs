@@ -56,7 +56,6 @@ pub(crate) fn for_each_experimental_cost_measurement<B: Benchmark>(
     call_bench::<B, Ed25519ScalarMulMeasure>(&mut params)?;
     call_bench::<B, VerifyEd25519SigMeasure>(&mut params)?;
     call_bench::<B, ReadXdrByteArrayMeasure>(&mut params)?;
-    call_bench::<B, EcdsaSecp256k1VerifyMeasure>(&mut params)?;
     call_bench::<B, EcdsaSecp256r1RecoverMeasure>(&mut params)?;
     call_bench::<B, Sec1DecodePointCompressedMeasure>(&mut params)?;
     call_bench::<B, DecodeSecp256r1SigMeasure>(&mut params)?;
@@ -71,6 +70,9 @@ pub(crate) fn for_each_experimental_cost_measurement<B: Benchmark>(
     call_bench::<B, Bls12381G2CheckPointOnCurveMeasure>(&mut params)?;
     call_bench::<B, Bls12381G2CheckPointInSubgroupMeasure>(&mut params)?;
     call_bench::<B, Bls12381G2ComputeYFromXMeasure>(&mut params)?;
+    call_bench::<B, PoseidonPermutationMeasure>(&mut params)?;
+    call_bench::<B, Poseidon2PermutationMeasure>(&mut params)?;
+    call_bench::<B, Ed25519BatchVerifyMeasure>(&mut params)?;
 
     Ok(params)
 }
@@ -155,6 +157,31 @@ pub(crate) fn for_each_host_cost_measurement<B: Benchmark>(
     call_bench::<B, Bls12381FrPowMeasure>(&mut params)?;
     call_bench::<B, Bls12381FrInvMeasure>(&mut params)?;
 
+    // P23 cost types
+    call_bench::<B, CopySharedSeqNodeMeasure>(&mut params)?;
+    call_bench::<B, Bn254EncodeFpMeasure>(&mut params)?;
+    call_bench::<B, Bn254DecodeFpMeasure>(&mut params)?;
+    call_bench::<B, Bn254G1CheckPointOnCurveMeasure>(&mut params)?;
+    call_bench::<B, Bn254G2CheckPointOnCurveMeasure>(&mut params)?;
+    call_bench::<B, Bn254G2CheckPointInSubgroupMeasure>(&mut params)?;
+    call_bench::<B, Bn254G1ProjectiveToAffineMeasure>(&mut params)?;
+    call_bench::<B, Bn254G1AddMeasure>(&mut params)?;
+    call_bench::<B, Bn254G1MulMeasure>(&mut params)?;
+    call_bench::<B, Bn254G1MsmMeasure>(&mut params)?;
+    call_bench::<B, Bn254PairingMeasure>(&mut params)?;
+    call_bench::<B, Bn254FrFromU256Measure>(&mut params)?;
+    call_bench::<B, Bn254FrToU256Measure>(&mut params)?;
+    call_bench::<B, Bn254FrAddMeasure>(&mut params)?;
+    call_bench::<B, Bn254FrSubMeasure>(&mut params)?;
+    call_bench::<B, Bn254FrMulMeasure>(&mut params)?;
+    call_bench::<B, Bn254FrPowMeasure>(&mut params)?;
+    call_bench::<B, ComputeSha512HashMeasure>(&mut params)?;
+    call_bench::<B, ComputeBlake2b256HashMeasure>(&mut params)?;
+    call_bench::<B, ComputeBlake3HashMeasure>(&mut params)?;
+    call_bench::<B, ComputeRipemd160HashMeasure>(&mut params)?;
+    call_bench::<B, VerifyEcdsaSecp256k1SigMeasure>(&mut params)?;
+    call_bench::<B, Int256MulDivMeasure>(&mut params)?;
+
     // These three mem ones are derived analytically, we do not calibrate them typically
     if std::env::var("INCLUDE_ANALYTICAL_COSTTYPES").is_ok() {
         call_bench::<B, MemAllocMeasure>(&mut params)?;
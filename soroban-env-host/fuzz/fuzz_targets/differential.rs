@@ -0,0 +1,187 @@
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use arbitrary::{Arbitrary, Unstructured};
+use ed25519_dalek::SigningKey;
+use libfuzzer_sys::fuzz_target;
+use soroban_env_host::{
+    e2e_testutils::{
+        account_entry, default_ledger_info, get_account_id, get_wasm_hash, ledger_entry, wasm_entry,
+    },
+    testutils::{check_recording_matches_enforcing, DifferentialInvocation},
+    xdr::{
+        AccountId, ContractDataDurability, ContractDataEntry, ContractExecutable, ExtensionPoint,
+        Hash, HostFunction, InvokeContractArgs, LedgerEntry, LedgerEntryData, PublicKey, ScAddress,
+        ScContractInstance, ScSymbol, ScVal, Uint256,
+    },
+    StorageType,
+};
+use soroban_synth_wasm::{ContractArg, TypedContract};
+
+// This target generates the same kind of contracts as `typed_contract`, but
+// instead of running them on a fuzz host it runs them end to end the way an
+// embedder would: simulated in recording mode, then applied in enforcing mode
+// with the simulated footprint, auth and instructions. Any divergence between
+// the two runs is a failure.
+
+#[derive(Arbitrary, Debug)]
+struct Harness {
+    // Each data key is either temporary or persistent (instance storage lives
+    // in the contract instance entry, so it isn't a separate ledger entry), is
+    // optionally present in the ledger with the given value, and is either
+    // read-only or read-write for the generated contract.
+    data_keys: BTreeMap<u8, (bool, Option<u32>, bool)>,
+    byte_literals: Vec<Vec<u8>>,
+    n_signers: u8,
+    prng_seed: [u8; 32],
+}
+
+const TEST_FN_NAME: &'static str = "test";
+
+fn contract_address(n: u8) -> ScAddress {
+    ScAddress::Contract(Hash([n; 32]))
+}
+
+fn contract_data_entry(
+    contract: &ScAddress,
+    key: ScVal,
+    durability: ContractDataDurability,
+    val: ScVal,
+) -> LedgerEntry {
+    ledger_entry(LedgerEntryData::ContractData(ContractDataEntry {
+        ext: ExtensionPoint::V0,
+        contract: contract.clone(),
+        key,
+        durability,
+        val,
+    }))
+}
+
+fn contract_instance_entry(contract: &ScAddress, wasm: &[u8]) -> LedgerEntry {
+    contract_data_entry(
+        contract,
+        ScVal::LedgerKeyContractInstance,
+        ContractDataDurability::Persistent,
+        ScVal::ContractInstance(ScContractInstance {
+            executable: ContractExecutable::Wasm(Hash(get_wasm_hash(wasm))),
+            storage: None,
+        }),
+    )
+}
+
+fn account_id(key: &SigningKey) -> AccountId {
+    AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+        key.verifying_key().to_bytes(),
+    )))
+}
+
+fn run(u: &mut Unstructured<'_>) -> arbitrary::Result<()> {
+    let harness: Harness = u.arbitrary()?;
+
+    let data_keys = harness
+        .data_keys
+        .iter()
+        .map(|(k, v)| (ScVal::U32(*k as u32), v.clone()))
+        .take(5)
+        .collect::<BTreeMap<_, _>>();
+    let n_signers = 1 + (harness.n_signers % 3) as usize;
+    let n_byte_literals = harness.byte_literals.len().min(3);
+
+    let mut args_a: Vec<ContractArg> = data_keys
+        .values()
+        .map(|(temporary, _, read_only)| ContractArg::DataKey {
+            storage_type: if *temporary {
+                StorageType::Temporary
+            } else {
+                StorageType::Persistent
+            },
+            read_only: *read_only,
+        })
+        .collect();
+    args_a.push(ContractArg::Callee { arity: 1 });
+    args_a.extend(std::iter::repeat(ContractArg::Signer).take(n_signers));
+    args_a.extend(std::iter::repeat(ContractArg::Bytes).take(n_byte_literals));
+    let args_b = [ContractArg::Val];
+
+    let contract_a = TypedContract::generate(u, &args_a)?;
+    let wasm_a = contract_a.as_single_function_wasm_module(TEST_FN_NAME);
+    let wasm_b = TypedContract::generate(u, &args_b)?.as_single_function_wasm_module(TEST_FN_NAME);
+
+    let ledger_info = default_ledger_info();
+    let seq = ledger_info.sequence_number;
+    let (address_a, address_b) = (contract_address(1), contract_address(2));
+    let signers: Vec<SigningKey> = (0..n_signers)
+        .map(|i| SigningKey::from_bytes(&[i as u8 + 1; 32]))
+        .collect();
+    let source_account = get_account_id([123; 32]);
+
+    let mut ledger_entries = vec![
+        (wasm_entry(&wasm_a), Some(seq + 10_000)),
+        (wasm_entry(&wasm_b), Some(seq + 10_000)),
+        (
+            contract_instance_entry(&address_a, &wasm_a),
+            Some(seq + 10_000),
+        ),
+        (
+            contract_instance_entry(&address_b, &wasm_b),
+            Some(seq + 10_000),
+        ),
+        (account_entry(&source_account), None),
+    ];
+    for signer in signers.iter() {
+        ledger_entries.push((account_entry(&account_id(signer)), None));
+    }
+    // The generated contract assumes the keys it reads before writing exist;
+    // if they didn't, both runs would fail on the first read and there would
+    // be nothing to compare. Such keys default to 0.
+    let read_before_written = contract_a.data_keys_read_before_written();
+    for ((key, (temporary, initial, _)), required) in data_keys.iter().zip(read_before_written) {
+        let initial = initial.or(required.then_some(0));
+        if let Some(initial) = initial {
+            let durability = if *temporary {
+                ContractDataDurability::Temporary
+            } else {
+                ContractDataDurability::Persistent
+            };
+            ledger_entries.push((
+                contract_data_entry(&address_a, key.clone(), durability, ScVal::U32(initial)),
+                Some(seq + 100),
+            ));
+        }
+    }
+
+    let mut args: Vec<ScVal> = data_keys.keys().cloned().collect();
+    args.push(ScVal::Address(address_b));
+    args.push(ScVal::Symbol(ScSymbol(TEST_FN_NAME.try_into().unwrap())));
+    for signer in signers.iter() {
+        args.push(ScVal::Address(ScAddress::Account(account_id(signer))));
+    }
+    for bytes in harness.byte_literals.iter().take(n_byte_literals) {
+        args.push(ScVal::Bytes(bytes.clone().try_into().unwrap()));
+    }
+    let host_fn = HostFunction::InvokeContract(InvokeContractArgs {
+        contract_address: address_a,
+        function_name: ScSymbol(TEST_FN_NAME.try_into().unwrap()),
+        args: args.try_into().unwrap(),
+    });
+
+    let invocation = DifferentialInvocation {
+        host_fn,
+        source_account,
+        ledger_info,
+        ledger_entries,
+        signers,
+        prng_seed: harness.prng_seed,
+    };
+    // Failures outside the invoked function (e.g. running out of budget while
+    // building the recorded footprint) leave nothing to replay.
+    if let Ok(outcome) = check_recording_matches_enforcing(&invocation) {
+        outcome.assert_no_divergence();
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = run(&mut Unstructured::new(data));
+});
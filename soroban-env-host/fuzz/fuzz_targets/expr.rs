@@ -11,6 +11,7 @@ use soroban_env_host::{
     },
     Host, StorageType,
 };
+use soroban_env_host_fuzz::dump_wat_on_panic;
 use soroban_synth_wasm::{Emit, Expr};
 
 // We augment the `Expr` we generate with other parameters we'd like the fuzzer to explore.
@@ -59,7 +60,7 @@ fuzz_target!(|test: TestCase| {
         .map(|(k, v)| (ScVal::U32(*k as u32), v.clone()))
         .take(5)
         .collect::<BTreeMap<_, _>>();
-    let mut args_a: Vec<ScVal> = data_keys.keys().cloned().collect();
+    let args_a: Vec<ScVal> = data_keys.keys().cloned().collect();
     let mut arg_tys_a: Vec<&'static str> = args_a.iter().map(|_| "U32Val").collect();
     arg_tys_a.push("AddressObject"); // contract B
     arg_tys_a.push("Symbol"); // test function name
@@ -82,11 +83,32 @@ fuzz_target!(|test: TestCase| {
         .contract_b_expr
         .0
         .as_single_function_wasm_module(TEST_FN_NAME, &arg_tys_b);
-    let (host, contracts, signers) = Host::new_recording_fuzz_host(
-        &[wasm_a.as_slice(), wasm_b.as_slice()],
-        &data_keys,
-        n_signers,
-    );
+    // When a test case panics, we write annotated WAT for both contracts next
+    // to the crash artifact.
+    dump_wat_on_panic("expr", &[wasm_a.as_slice(), wasm_b.as_slice()], || {
+        run_test_case(
+            &test,
+            &data_keys,
+            args_a,
+            n_signers,
+            n_byte_literals,
+            &wasm_a,
+            &wasm_b,
+        )
+    });
+});
+
+fn run_test_case(
+    test: &TestCase,
+    data_keys: &BTreeMap<ScVal, (StorageType, bool)>,
+    mut args_a: Vec<ScVal>,
+    n_signers: usize,
+    n_byte_literals: usize,
+    wasm_a: &[u8],
+    wasm_b: &[u8],
+) {
+    let (host, contracts, signers) =
+        Host::new_recording_fuzz_host(&[wasm_a, wasm_b], data_keys, n_signers);
 
     let contract_address_a = host.scaddress_from_address(contracts[0]).unwrap();
 
@@ -122,7 +144,7 @@ fuzz_target!(|test: TestCase| {
     let _ = host.invoke_function(hf.clone());
 
     // Second pass: enforcing (with synthesized content as needed).
-    host.switch_fuzz_host_to_enforcing(&data_keys, &signers);
+    host.switch_fuzz_host_to_enforcing(data_keys, &signers);
     test.install_budget(&host);
     let res = host.invoke_function(hf);
 
@@ -135,4 +157,4 @@ fuzz_target!(|test: TestCase| {
             panic!("got internal error: {:?}", hosterror)
         }
     }
-});
+}
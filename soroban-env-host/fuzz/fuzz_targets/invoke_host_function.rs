@@ -0,0 +1,293 @@
+#![no_main]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use soroban_env_host::{
+    budget::Budget,
+    e2e_invoke::{invoke_host_function, InvokeHostFunctionResult},
+    xdr::{
+        AccountId, Hash, HostFunction, LedgerEntry, LedgerEntryData, LedgerFootprint, LedgerKey,
+        LedgerKeyAccount, LedgerKeyContractCode, LedgerKeyContractData, LedgerKeyTrustLine, Limits,
+        ReadXdr, ScErrorCode, ScErrorType, SorobanAuthorizationEntry, SorobanResources, TtlEntry,
+        WriteXdr,
+    },
+    Host, HostError, LedgerInfo,
+};
+
+// This target fuzzes `e2e_invoke::invoke_host_function`, the entry point
+// embedders call with untrusted XDR. Inputs are built from `Arbitrary` XDR
+// types and then adjusted to satisfy the invariants the embedder guarantees
+// (every ledger entry is in the footprint, contract entries have live TTLs,
+// and so on), since violating those is reported as an internal error by
+// design. Optionally one byte of one encoded input is then corrupted, to
+// exercise the metered decoders on malformed XDR.
+//
+// For every input we check that:
+//
+//   - nothing panics,
+//   - the budget is never exceeded without a budget error being reported,
+//   - internal errors only occur when the input was corrupted, and
+//   - on success, the ledger changes cover exactly the footprint, with
+//     read-only entries unmodified and TTLs never decreasing.
+
+#[derive(Arbitrary, Debug)]
+struct TestCase {
+    host_fn: HostFunction,
+    resources: SorobanResources,
+    source_account: AccountId,
+    auth_entries: Vec<SorobanAuthorizationEntry>,
+    ledger_entries: Vec<(LedgerEntry, u32)>,
+    sequence_number: u32,
+    timestamp: u64,
+    network_id: [u8; 32],
+    prng_seed: [u8; 32],
+    cpu_budget: u32,
+    mem_budget: u32,
+    corruption: Option<(u8, u16, u8)>,
+}
+
+fn ledger_key(entry: &LedgerEntry) -> Option<LedgerKey> {
+    match &entry.data {
+        LedgerEntryData::Account(a) => Some(LedgerKey::Account(LedgerKeyAccount {
+            account_id: a.account_id.clone(),
+        })),
+        LedgerEntryData::Trustline(tl) => Some(LedgerKey::Trustline(LedgerKeyTrustLine {
+            account_id: tl.account_id.clone(),
+            asset: tl.asset.clone(),
+        })),
+        LedgerEntryData::ContractData(cd) => Some(LedgerKey::ContractData(LedgerKeyContractData {
+            contract: cd.contract.clone(),
+            key: cd.key.clone(),
+            durability: cd.durability,
+        })),
+        LedgerEntryData::ContractCode(code) => {
+            Some(LedgerKey::ContractCode(LedgerKeyContractCode {
+                hash: code.hash.clone(),
+            }))
+        }
+        _ => None,
+    }
+}
+
+fn is_supported_key(key: &LedgerKey) -> bool {
+    matches!(
+        key,
+        LedgerKey::Account(_)
+            | LedgerKey::Trustline(_)
+            | LedgerKey::ContractData(_)
+            | LedgerKey::ContractCode(_)
+    )
+}
+
+fn has_ttl(key: &LedgerKey) -> bool {
+    matches!(key, LedgerKey::ContractData(_) | LedgerKey::ContractCode(_))
+}
+
+struct Encoded {
+    host_fn: Vec<u8>,
+    resources: Vec<u8>,
+    source_account: Vec<u8>,
+    auth_entries: Vec<Vec<u8>>,
+    ledger_entries: Vec<Vec<u8>>,
+    ttl_entries: Vec<Vec<u8>>,
+}
+
+impl Encoded {
+    fn corrupt(&mut self, (which, pos, byte): (u8, u16, u8)) {
+        let mut bufs: Vec<&mut Vec<u8>> = vec![
+            &mut self.host_fn,
+            &mut self.resources,
+            &mut self.source_account,
+        ];
+        bufs.extend(self.auth_entries.iter_mut());
+        bufs.extend(self.ledger_entries.iter_mut());
+        let n = bufs.len();
+        let buf = &mut bufs[which as usize % n];
+        if !buf.is_empty() {
+            let i = pos as usize % buf.len();
+            buf[i] ^= byte;
+        }
+    }
+}
+
+impl TestCase {
+    // Brings the footprint and ledger entries into the shape the embedder
+    // guarantees, returning the footprint and the (key, entry, live-until)
+    // triples.
+    fn normalize(&self) -> (LedgerFootprint, Vec<(LedgerKey, LedgerEntry, Option<u32>)>) {
+        let mut read_write: BTreeSet<LedgerKey> = self
+            .resources
+            .footprint
+            .read_write
+            .iter()
+            .filter(|k| is_supported_key(k))
+            .cloned()
+            .collect();
+        let mut entries = BTreeMap::new();
+        for (entry, ttl_offset) in self.ledger_entries.iter() {
+            if let Some(key) = ledger_key(entry) {
+                let live_until = has_ttl(&key)
+                    .then(|| self.sequence_number.saturating_add(*ttl_offset % 1_000_000));
+                entries
+                    .entry(key)
+                    .or_insert_with(|| (entry.clone(), live_until));
+            }
+        }
+        let read_only: BTreeSet<LedgerKey> = self
+            .resources
+            .footprint
+            .read_only
+            .iter()
+            .filter(|k| is_supported_key(k) && !read_write.contains(*k))
+            .cloned()
+            .collect();
+        for key in entries.keys() {
+            if !read_only.contains(key) {
+                read_write.insert(key.clone());
+            }
+        }
+        let footprint = LedgerFootprint {
+            read_only: read_only
+                .into_iter()
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+            read_write: read_write
+                .into_iter()
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        };
+        let entries = entries
+            .into_iter()
+            .map(|(k, (e, live_until))| (k, e, live_until))
+            .collect();
+        (footprint, entries)
+    }
+}
+
+fn to_xdr(v: &impl WriteXdr) -> Vec<u8> {
+    v.to_xdr(Limits::none()).unwrap()
+}
+
+fn is_budget_error(e: &HostError) -> bool {
+    e.error.is_type(ScErrorType::Budget) && e.error.is_code(ScErrorCode::ExceededLimit)
+}
+
+fn is_internal_error(e: &HostError) -> bool {
+    e.error.is_code(ScErrorCode::InternalError) && !e.error.is_type(ScErrorType::Contract)
+}
+
+fn check_ledger_changes(footprint: &LedgerFootprint, res: &InvokeHostFunctionResult) {
+    if res.encoded_invoke_result.is_err() {
+        assert!(res.ledger_changes.is_empty());
+        assert!(res.encoded_contract_events.is_empty());
+        return;
+    }
+    let read_only: BTreeSet<&LedgerKey> = footprint.read_only.iter().collect();
+    let read_write: BTreeSet<&LedgerKey> = footprint.read_write.iter().collect();
+    assert_eq!(
+        res.ledger_changes.len(),
+        read_only.len() + read_write.len(),
+        "ledger changes don't cover the footprint"
+    );
+    let mut seen = BTreeSet::new();
+    for change in res.ledger_changes.iter() {
+        let key = LedgerKey::from_xdr(&change.encoded_key, Limits::none()).unwrap();
+        assert_eq!(change.read_only, read_only.contains(&key));
+        assert!(change.read_only || read_write.contains(&key));
+        if change.read_only {
+            assert!(change.encoded_new_value.is_none());
+        }
+        if let Some(new_value) = &change.encoded_new_value {
+            let entry = LedgerEntry::from_xdr(new_value, Limits::none()).unwrap();
+            assert_eq!(ledger_key(&entry).as_ref(), Some(&key));
+        }
+        if let Some(ttl) = &change.ttl_change {
+            assert!(ttl.new_live_until_ledger >= ttl.old_live_until_ledger);
+        }
+        assert!(seen.insert(key), "duplicate ledger change");
+    }
+}
+
+fuzz_target!(|test: TestCase| {
+    let (footprint, entries) = test.normalize();
+    let mut resources = test.resources.clone();
+    resources.footprint = footprint.clone();
+
+    let mut encoded = Encoded {
+        host_fn: to_xdr(&test.host_fn),
+        resources: to_xdr(&resources),
+        source_account: to_xdr(&test.source_account),
+        auth_entries: test.auth_entries.iter().map(to_xdr).collect(),
+        ledger_entries: entries.iter().map(|(_, e, _)| to_xdr(e)).collect(),
+        ttl_entries: entries
+            .iter()
+            .map(|(k, _, live_until)| match live_until {
+                // The host doesn't check the key hash, only the live-until
+                // ledger.
+                Some(live_until_ledger_seq) => to_xdr(&TtlEntry {
+                    key_hash: Hash([0; 32]),
+                    live_until_ledger_seq: *live_until_ledger_seq,
+                }),
+                None => {
+                    debug_assert!(!has_ttl(k));
+                    vec![]
+                }
+            })
+            .collect(),
+    };
+    if let Some(corruption) = test.corruption {
+        encoded.corrupt(corruption);
+    }
+
+    let ledger_info = LedgerInfo {
+        protocol_version: Host::current_test_protocol(),
+        sequence_number: test.sequence_number,
+        timestamp: test.timestamp,
+        network_id: test.network_id,
+        base_reserve: 1,
+        min_temp_entry_ttl: 16,
+        min_persistent_entry_ttl: 4096,
+        max_entry_ttl: 6_312_000,
+    };
+
+    let cpu_limit = test.cpu_budget as u64 & 0x0fff_ffff | 0x000f_ffff;
+    let mem_limit = test.mem_budget as u64 & 0x0ff_ffff | 0x000f_ffff;
+    let budget = Budget::default();
+    budget.reset_limits(cpu_limit, mem_limit).unwrap();
+
+    let mut diagnostic_events = vec![];
+    let res = invoke_host_function(
+        &budget,
+        true,
+        encoded.host_fn,
+        encoded.resources,
+        encoded.source_account,
+        encoded.auth_entries.into_iter(),
+        ledger_info,
+        encoded.ledger_entries.into_iter(),
+        encoded.ttl_entries.into_iter(),
+        test.prng_seed.to_vec(),
+        &mut diagnostic_events,
+    );
+
+    let err = match &res {
+        Ok(res) => res.encoded_invoke_result.as_ref().err(),
+        Err(e) => Some(e),
+    };
+    if !err.is_some_and(is_budget_error) {
+        assert!(budget.get_cpu_insns_consumed().unwrap() <= cpu_limit);
+        assert!(budget.get_mem_bytes_consumed().unwrap() <= mem_limit);
+    }
+    if test.corruption.is_none() {
+        if let Some(e) = err.filter(|e| is_internal_error(e)) {
+            panic!("got internal error: {:?}", e)
+        }
+        if let Ok(res) = &res {
+            check_ledger_changes(&footprint, res);
+        }
+    }
+});
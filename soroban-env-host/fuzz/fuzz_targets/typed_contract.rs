@@ -0,0 +1,173 @@
+#![no_main]
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use arbitrary::{Arbitrary, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use soroban_env_host::{
+    xdr::{
+        AccountId, HostFunction, InvokeContractArgs, PublicKey, ScAddress, ScErrorCode,
+        ScErrorType, ScSymbol, ScVal, Uint256,
+    },
+    Host, StorageType,
+};
+use soroban_env_host_fuzz::dump_wat_on_panic;
+use soroban_synth_wasm::{ContractArg, TypedContract};
+
+// Unlike the `expr` target, this target generates contracts with
+// `TypedContract`, which only emits well-typed host calls on storage keys,
+// signers and callees that the harness actually provides, so that most runs
+// get past argument decoding and into storage, auth and crypto logic.
+
+#[derive(Arbitrary, Debug)]
+struct Harness {
+    cpu_budget: u32,
+    mem_budget: u32,
+    data_keys: BTreeMap<u8, (StorageType, bool)>,
+    byte_literals: Vec<Vec<u8>>,
+    n_signers: u8,
+}
+
+const TEST_FN_NAME: &'static str = "test";
+
+// Set `SOROBAN_FUZZ_STATS=<n>` to print, every n runs, how often each host
+// function was reached (in the enforcing pass) so far.
+thread_local! {
+    static STATS: RefCell<(u64, BTreeMap<&'static str, u64>)> = RefCell::new(Default::default());
+}
+
+fn record_stats(counts: &BTreeMap<&'static str, u64>) {
+    let Some(every) = std::env::var("SOROBAN_FUZZ_STATS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+    else {
+        return;
+    };
+    STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        stats.0 += 1;
+        for (name, n) in counts {
+            *stats.1.entry(*name).or_insert(0) += n;
+        }
+        if every != 0 && stats.0 % every == 0 {
+            eprintln!("host functions reached after {} runs:", stats.0);
+            for (name, n) in stats.1.iter() {
+                eprintln!("  {name:32} {n}");
+            }
+        }
+    });
+}
+
+fn run(u: &mut Unstructured<'_>) -> arbitrary::Result<()> {
+    let harness: Harness = u.arbitrary()?;
+
+    let data_keys = harness
+        .data_keys
+        .iter()
+        .map(|(k, v)| (ScVal::U32(*k as u32), v.clone()))
+        .take(5)
+        .collect::<BTreeMap<_, _>>();
+    let n_signers = 3 + (harness.n_signers % 4) as usize;
+    let n_byte_literals = harness.byte_literals.len().min(3);
+
+    // Contract A gets the data keys, contract B to call, the signers and the
+    // byte literals; contract B takes a single value.
+    let mut args_a: Vec<ContractArg> = data_keys
+        .values()
+        .map(|(storage_type, read_only)| ContractArg::DataKey {
+            storage_type: *storage_type,
+            read_only: *read_only,
+        })
+        .collect();
+    args_a.push(ContractArg::Callee { arity: 1 });
+    args_a.extend(std::iter::repeat(ContractArg::Signer).take(n_signers));
+    args_a.extend(std::iter::repeat(ContractArg::Bytes).take(n_byte_literals));
+    let args_b = [ContractArg::Val];
+
+    let contract_a = TypedContract::generate(u, &args_a)?;
+    let contract_b = TypedContract::generate(u, &args_b)?;
+    let wasm_a = contract_a.as_single_function_wasm_module(TEST_FN_NAME);
+    let wasm_b = contract_b.as_single_function_wasm_module(TEST_FN_NAME);
+
+    dump_wat_on_panic(
+        "typed_contract",
+        &[wasm_a.as_slice(), wasm_b.as_slice()],
+        || {
+            run_contracts(
+                &harness,
+                &data_keys,
+                n_signers,
+                n_byte_literals,
+                &wasm_a,
+                &wasm_b,
+            )
+        },
+    );
+    Ok(())
+}
+
+fn run_contracts(
+    harness: &Harness,
+    data_keys: &BTreeMap<ScVal, (StorageType, bool)>,
+    n_signers: usize,
+    n_byte_literals: usize,
+    wasm_a: &[u8],
+    wasm_b: &[u8],
+) {
+    let (host, contracts, signers) =
+        Host::new_recording_fuzz_host(&[wasm_a, wasm_b], data_keys, n_signers);
+
+    let mut scargs_a: Vec<ScVal> = data_keys.keys().cloned().collect();
+    scargs_a.push(ScVal::Address(
+        host.scaddress_from_address(contracts[1]).unwrap(),
+    ));
+    scargs_a.push(ScVal::Symbol(ScSymbol(TEST_FN_NAME.try_into().unwrap())));
+    for signer in signers.iter() {
+        let account = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            signer.verifying_key().to_bytes(),
+        )));
+        scargs_a.push(ScVal::Address(ScAddress::Account(account)));
+    }
+    for bytes in harness.byte_literals.iter().take(n_byte_literals) {
+        scargs_a.push(ScVal::Bytes(bytes.clone().try_into().unwrap()));
+    }
+
+    let hf = HostFunction::InvokeContract(InvokeContractArgs {
+        contract_address: host.scaddress_from_address(contracts[0]).unwrap(),
+        function_name: ScSymbol(TEST_FN_NAME.try_into().unwrap()),
+        args: scargs_a.try_into().unwrap(),
+    });
+
+    let install_budget = |host: &Host| {
+        host.with_budget(|budget| {
+            budget.reset_limits(
+                harness.cpu_budget as u64 & 0x0fff_ffff | 0x000f_ffff,
+                harness.mem_budget as u64 & 0x0ff_ffff | 0x000f_ffff,
+            )
+        })
+        .unwrap();
+    };
+
+    // First pass: recording.
+    install_budget(&host);
+    let _ = host.invoke_function(hf.clone());
+
+    // Second pass: enforcing, counting the host functions reached.
+    host.switch_fuzz_host_to_enforcing(data_keys, &signers);
+    install_budget(&host);
+    let counts = host.count_host_function_calls().unwrap();
+    let res = host.invoke_function(hf);
+    record_stats(&counts.borrow());
+
+    if let Err(hosterror) = res {
+        if hosterror.error.is_code(ScErrorCode::InternalError)
+            && !hosterror.error.is_type(ScErrorType::Contract)
+        {
+            panic!("got internal error: {:?}", hosterror)
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = run(&mut Unstructured::new(data));
+});
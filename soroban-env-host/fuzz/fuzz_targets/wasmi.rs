@@ -1,6 +1,7 @@
 #![no_main]
 use arbitrary::Arbitrary;
 use libfuzzer_sys::fuzz_target;
+use soroban_env_host_fuzz::dump_wat_on_panic;
 use wasmi::{core::ValueType, Engine, Extern, Linker, Module, Store, StoreLimitsBuilder, Value};
 
 #[derive(Debug, Arbitrary)]
@@ -67,6 +68,10 @@ fuzz_target!(|cfg_module: wasm_smith::ConfiguredModule<WasmiConfig>| {
     let mut smith_module = cfg_module.module;
     smith_module.ensure_termination(1000);
     let wasm = smith_module.to_bytes();
+    dump_wat_on_panic("wasmi", &[wasm.as_slice()], || run_module(&wasm));
+});
+
+fn run_module(wasm: &[u8]) {
     let engine = Engine::default();
     let linker = Linker::new(&engine);
     let limiter = StoreLimitsBuilder::new()
@@ -74,7 +79,7 @@ fuzz_target!(|cfg_module: wasm_smith::ConfiguredModule<WasmiConfig>| {
         .build();
     let mut store = Store::new(&engine, limiter);
     store.limiter(|lim| lim);
-    let module = Module::new(store.engine(), wasm.as_slice()).unwrap();
+    let module = Module::new(store.engine(), wasm).unwrap();
     let Ok(preinstance) = linker.instantiate(&mut store, &module) else {
         return;
     };
@@ -104,4 +109,4 @@ fuzz_target!(|cfg_module: wasm_smith::ConfiguredModule<WasmiConfig>| {
             .unwrap();
         let _ = wasm_fn.call(&mut store, &args, &mut out);
     }
-});
+}
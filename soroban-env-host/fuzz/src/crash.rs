@@ -0,0 +1,71 @@
+use std::{
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    panic,
+    path::PathBuf,
+    sync::Once,
+};
+
+use soroban_synth_wasm::annotated_wat;
+
+/// Writes annotated WAT for each of `wasms` next to the crash artifact
+/// libfuzzer is about to write for `target`, so triage doesn't start by
+/// disassembling them by hand. The directory can be overridden with
+/// `SOROBAN_FUZZ_WAT_DIR`.
+pub fn dump_wat_for_crash(target: &str, wasms: &[&[u8]]) {
+    let dir = std::env::var_os("SOROBAN_FUZZ_WAT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("artifacts").join(target));
+    let mut hasher = DefaultHasher::new();
+    wasms.hash(&mut hasher);
+    let prefix = format!("crash-{:016x}", hasher.finish());
+    let _ = std::fs::create_dir_all(&dir);
+    for (i, wasm) in wasms.iter().enumerate() {
+        let wat = annotated_wat(wasm).unwrap_or_else(|e| format!(";; disassembly failed: {e}\n"));
+        let path = dir.join(format!("{prefix}-contract-{i}.wat"));
+        if std::fs::write(&path, wat).is_ok() {
+            eprintln!("wrote {}", path.display());
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT_WASMS: RefCell<Option<(String, Vec<Vec<u8>>)>> = const { RefCell::new(None) };
+}
+
+struct ClearCurrentWasms;
+
+impl Drop for ClearCurrentWasms {
+    fn drop(&mut self) {
+        CURRENT_WASMS.with(|c| c.borrow_mut().take());
+    }
+}
+
+/// Runs `f`, calling [dump_wat_for_crash] with `wasms` if it panics.
+///
+/// libfuzzer's panic hook aborts the process before the panic unwinds, so the
+/// dump can't happen in a `catch_unwind`. Instead the first call chains a hook
+/// in front of the one already installed, which dumps whatever wasms the
+/// panicking thread is running.
+pub fn dump_wat_on_panic<R>(target: &str, wasms: &[&[u8]], f: impl FnOnce() -> R) -> R {
+    static INSTALL_HOOK: Once = Once::new();
+    INSTALL_HOOK.call_once(|| {
+        let next = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if let Ok(Some((target, wasms))) = CURRENT_WASMS.try_with(|c| c.borrow_mut().take()) {
+                let wasms: Vec<&[u8]> = wasms.iter().map(Vec::as_slice).collect();
+                dump_wat_for_crash(&target, &wasms);
+            }
+            next(info)
+        }));
+    });
+    CURRENT_WASMS.with(|c| {
+        *c.borrow_mut() = Some((
+            target.to_string(),
+            wasms.iter().map(|w| w.to_vec()).collect(),
+        ))
+    });
+    let _clear = ClearCurrentWasms;
+    f()
+}
@@ -1 +1,3 @@
+mod crash;
 
+pub use crash::{dump_wat_for_crash, dump_wat_on_panic};
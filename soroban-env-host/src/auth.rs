@@ -623,7 +623,7 @@ impl AuthorizedFunction {
                 Ok(SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
                     contract_address: host.scaddress_from_address(contract_fn.contract_address)?,
                     function_name,
-                    args: host.vals_to_scval_vec(contract_fn.args.as_slice())?,
+                    args: host.vals_to_scval_vec(contract_fn.args.iter())?,
                 }))
             }
             AuthorizedFunction::CreateContractHostFn(create_contract_args) => {
@@ -7,7 +7,9 @@ mod wasmi_helper;
 pub(crate) use limits::DepthLimiter;
 pub use limits::{DEFAULT_HOST_DEPTH_LIMIT, DEFAULT_XDR_RW_LIMITS};
 pub use model::{MeteredCostComponent, ScaledU64};
-pub(crate) use wasmi_helper::{get_wasmi_config, load_calibrated_fuel_costs};
+pub(crate) use wasmi_helper::{
+    get_fuel_costs, get_wasmi_config, load_calibrated_fuel_costs, same_fuel_costs,
+};
 
 use std::{
     cell::{RefCell, RefMut},
@@ -23,6 +25,9 @@ use crate::{
 
 use dimension::{BudgetDimension, IsCpu, IsShadowMode};
 
+#[cfg(any(test, feature = "testutils"))]
+use crate::vm::WasmInsnProfile;
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CostTracker {
     pub iterations: u64,
@@ -39,6 +44,9 @@ struct BudgetTracker {
     meter_count: u32,
     #[cfg(any(test, feature = "testutils", feature = "bench"))]
     wasm_memory: u64,
+    // Breakdown of executed Wasm instructions, only collected when enabled
+    #[cfg(any(test, feature = "testutils"))]
+    wasm_insn_profile: Option<WasmInsnProfile>,
     // Tracks the real time (in nsecs) spent on various `CostType`
     time_tracker: [u64; ContractCostType::variants().len()],
 }
@@ -50,6 +58,8 @@ impl Default for BudgetTracker {
             meter_count: 0,
             #[cfg(any(test, feature = "testutils", feature = "bench"))]
             wasm_memory: 0,
+            #[cfg(any(test, feature = "testutils"))]
+            wasm_insn_profile: None,
             time_tracker: [0; ContractCostType::variants().len()],
         };
         for (ct, tracker) in ContractCostType::variants()
@@ -140,6 +150,28 @@ impl Default for BudgetTracker {
                 ContractCostType::Bls12381FrMul => (),
                 ContractCostType::Bls12381FrPow => init_input(), // input is number of bits in the u64 exponent excluding leading zeros
                 ContractCostType::Bls12381FrInv => (),
+                ContractCostType::CopySharedSeqNode => init_input(), // number of bytes in the node
+                ContractCostType::Bn254EncodeFp => (),
+                ContractCostType::Bn254DecodeFp => (),
+                ContractCostType::Bn254G1CheckPointOnCurve => (),
+                ContractCostType::Bn254G2CheckPointOnCurve => (),
+                ContractCostType::Bn254G2CheckPointInSubgroup => (),
+                ContractCostType::Bn254G1ProjectiveToAffine => (),
+                ContractCostType::Bn254G1Add => (),
+                ContractCostType::Bn254G1Mul => (),
+                ContractCostType::Bn254G1Msm => init_input(), // input is number of (G1,Fr) pairs
+                ContractCostType::Bn254Pairing => init_input(), // input is number of (G1,G2) pairs
+                ContractCostType::Bn254FrFromU256 => (),
+                ContractCostType::Bn254FrToU256 => (),
+                ContractCostType::Bn254FrAddSub => (),
+                ContractCostType::Bn254FrMul => (),
+                ContractCostType::Bn254FrPow => init_input(), // input is number of bits in the u64 exponent excluding leading zeros
+                ContractCostType::ComputeSha512Hash => init_input(), // number of bytes in the buffer
+                ContractCostType::ComputeBlake2b256Hash => init_input(), // number of bytes in the buffer
+                ContractCostType::ComputeBlake3Hash => init_input(), // number of bytes in the buffer
+                ContractCostType::ComputeRipemd160Hash => init_input(), // number of bytes in the buffer
+                ContractCostType::VerifyEcdsaSecp256k1Sig => (),
+                ContractCostType::Int256MulDiv => (),
             }
         }
         mt
@@ -157,6 +189,10 @@ impl BudgetTracker {
             tracker.mem = 0;
         }
         self.wasm_memory = 0;
+        #[cfg(any(test, feature = "testutils"))]
+        if let Some(profile) = self.wasm_insn_profile.as_mut() {
+            profile.0.clear();
+        }
     }
 
     fn track_time(&mut self, ty: ContractCostType, duration: u64) -> Result<(), HostError> {
@@ -598,6 +634,121 @@ impl Default for BudgetImpl {
                     cpu.const_term = 35421;
                     cpu.lin_term = ScaledU64(0);
                 }
+                // Copying a node is dominated by allocating it: the runner
+                // measures a flat 60-85ns for nodes of 16 to 168 bytes, next
+                // to 479ns for the 4947 instructions of `Int256Mul`. The
+                // linear term is that of `MemAlloc` and `MemCpy` combined.
+                ContractCostType::CopySharedSeqNode => {
+                    cpu.const_term = 860;
+                    cpu.lin_term = ScaledU64::from_unscaled_u64(1).safe_div(4);
+                }
+                // BN254 runs on the same arkworks code as BLS12-381, over a
+                // smaller field. Each model is that of its BLS12-381
+                // counterpart, scaled by the ratio of their run times (median
+                // of three runs). The G2 subgroup check is scaled from its
+                // BLS12-381 counterpart too, although arkworks multiplies by
+                // the group order for BN254 instead of using an endomorphism,
+                // which takes 3.5x as long.
+                ContractCostType::Bn254EncodeFp => {
+                    cpu.const_term = 432;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254DecodeFp => {
+                    cpu.const_term = 139;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1CheckPointOnCurve => {
+                    cpu.const_term = 756;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G2CheckPointOnCurve => {
+                    cpu.const_term = 2063;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G2CheckPointInSubgroup => {
+                    cpu.const_term = 3660838;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1ProjectiveToAffine => {
+                    cpu.const_term = 40144;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1Add => {
+                    cpu.const_term = 3719;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1Mul => {
+                    cpu.const_term = 1400987;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1Msm => {
+                    cpu.const_term = 957570;
+                    cpu.lin_term = ScaledU64(56161980);
+                }
+                ContractCostType::Bn254Pairing => {
+                    cpu.const_term = 9509723;
+                    cpu.lin_term = ScaledU64(501515669);
+                }
+                ContractCostType::Bn254FrFromU256 => {
+                    cpu.const_term = 1953;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                // The scalar field operations take too little time for the
+                // runners to time reliably, so they are scaled by the ratios
+                // of the median times measured on the reference machine
+                // instead: 0.98x for the conversion, 1.00x for additions,
+                // 1.01x for multiplications and 0.95x for exponentiations
+                // (0.94x for x^5 and 0.96x for x^11).
+                ContractCostType::Bn254FrToU256 => {
+                    cpu.const_term = 1132;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrAddSub => {
+                    cpu.const_term = 74;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrMul => {
+                    cpu.const_term = 335;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrPow => {
+                    cpu.const_term = 656;
+                    cpu.lin_term = ScaledU64(70830);
+                }
+                // The hashes below are calibrated by wall-clock time against
+                // `ComputeKeccak256Hash` (also a portable implementation) rather
+                // than `ComputeSha256Hash`, which runs on the SHA extensions where
+                // the CPU has them. BLAKE3's fit is noisier (R2 ~0.75) since its
+                // SIMD paths kick in per 1 KiB chunk.
+                ContractCostType::ComputeSha512Hash => {
+                    cpu.const_term = 4481;
+                    cpu.lin_term = ScaledU64(4654);
+                }
+                ContractCostType::ComputeBlake2b256Hash => {
+                    cpu.const_term = 1542;
+                    cpu.lin_term = ScaledU64(2716);
+                }
+                ContractCostType::ComputeBlake3Hash => {
+                    cpu.const_term = 988;
+                    cpu.lin_term = ScaledU64(657);
+                }
+                ContractCostType::ComputeRipemd160Hash => {
+                    cpu.const_term = 2473;
+                    cpu.lin_term = ScaledU64(9464);
+                }
+                // Calibrated by time against `RecoverEcdsaSecp256k1Key`, which
+                // a verification measures at 0.46x.
+                ContractCostType::VerifyEcdsaSecp256k1Sig => {
+                    cpu.const_term = 1074528;
+                    cpu.lin_term = ScaledU64(0);
+                }
+                // Calibrated by time against `Int256Div` (median of three runs),
+                // which the long division path of `u256_mul_div` measures at
+                // 1.82x.
+                ContractCostType::Int256MulDiv => {
+                    cpu.const_term = 8928;
+                    cpu.lin_term = ScaledU64(0);
+                }
             }
 
             // define the memory cost model parameters
@@ -890,6 +1041,94 @@ impl Default for BudgetImpl {
                     mem.const_term = 0;
                     mem.lin_term = ScaledU64(0);
                 }
+                ContractCostType::CopySharedSeqNode => {
+                    mem.const_term = 64;
+                    mem.lin_term = ScaledU64::from_unscaled_u64(1);
+                }
+                ContractCostType::Bn254EncodeFp => {
+                    mem.const_term = 40;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254DecodeFp => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1CheckPointOnCurve => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G2CheckPointOnCurve => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G2CheckPointInSubgroup => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1ProjectiveToAffine => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1Add => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1Mul => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254G1Msm => {
+                    mem.const_term = 72803;
+                    mem.lin_term = ScaledU64(350938);
+                }
+                ContractCostType::Bn254Pairing => {
+                    mem.const_term = 1821;
+                    mem.lin_term = ScaledU64(6232546);
+                }
+                ContractCostType::Bn254FrFromU256 => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrToU256 => {
+                    mem.const_term = 248;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrAddSub => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrMul => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Bn254FrPow => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(128);
+                }
+                ContractCostType::ComputeSha512Hash => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::ComputeBlake2b256Hash => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::ComputeBlake3Hash => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::ComputeRipemd160Hash => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::VerifyEcdsaSecp256k1Sig => {
+                    mem.const_term = 0;
+                    mem.lin_term = ScaledU64(0);
+                }
+                ContractCostType::Int256MulDiv => {
+                    mem.const_term = 99;
+                    mem.lin_term = ScaledU64(0);
+                }
             }
         }
 
@@ -1187,6 +1426,50 @@ impl Budget {
         });
     }
 
+    /// Runs a user provided closure with cpu and memory consumption capped
+    /// at `cpu_limit` and `mem_limit` on top of what has been consumed so far.
+    /// The caps can only tighten the enclosing limits, never relax them.
+    ///
+    /// Returns `Ok(Err(e))` if the closure failed by exceeding one of the caps
+    /// while the enclosing limits are still respected, so that the caller can
+    /// recover from it. Any other error, including exceeding the enclosing
+    /// limits, is returned as `Err` as usual.
+    pub(crate) fn with_scoped_limits<T, F>(
+        &self,
+        cpu_limit: u64,
+        mem_limit: u64,
+        f: F,
+    ) -> Result<Result<T, HostError>, HostError>
+    where
+        F: FnOnce() -> Result<T, HostError>,
+    {
+        self.with_mut_budget(|mut b| {
+            b.cpu_insns.push_scoped_limit(cpu_limit);
+            b.mem_bytes.push_scoped_limit(mem_limit);
+            Ok(())
+        })?;
+
+        let res = f();
+
+        let within_enclosing_limits = self.with_mut_budget(|mut b| {
+            b.cpu_insns.pop_scoped_limit()?;
+            b.mem_bytes.pop_scoped_limit()?;
+            Ok(b.cpu_insns.check_budget_limit(IsShadowMode(false)).is_ok()
+                && b.mem_bytes.check_budget_limit(IsShadowMode(false)).is_ok())
+        })?;
+
+        match res {
+            Err(e)
+                if within_enclosing_limits
+                    && e.error.is_type(ScErrorType::Budget)
+                    && e.error.is_code(ScErrorCode::ExceededLimit) =>
+            {
+                Ok(Err(e))
+            }
+            res => res.map(Ok),
+        }
+    }
+
     pub(crate) fn is_in_shadow_mode(&self) -> Result<bool, HostError> {
         Ok(self.0.try_borrow_or_err()?.is_in_shadow_mode)
     }
@@ -34,6 +34,12 @@ pub(crate) struct BudgetDimension {
 
     /// Similar to `total_count`, but towards the `shadow_limit`
     pub(crate) shadow_total_count: u64,
+
+    /// Stack of nested caps on `total_count`, pushed and popped around
+    /// budget-capped sub-calls. Each entry is an absolute value of
+    /// `total_count` and never exceeds the entry below it (or `limit`), so
+    /// the innermost entry is always the effective limit.
+    pub(crate) scoped_limits: Vec<u64>,
 }
 
 impl Default for BudgetDimension {
@@ -44,6 +50,7 @@ impl Default for BudgetDimension {
             total_count: 0,
             shadow_limit: 0,
             shadow_total_count: 0,
+            scoped_limits: Vec::new(),
         }
     }
 }
@@ -126,12 +133,36 @@ impl BudgetDimension {
     }
 
     pub(crate) fn get_remaining(&self) -> u64 {
-        self.limit.saturating_sub(self.total_count)
+        self.effective_limit().saturating_sub(self.total_count)
+    }
+
+    /// The limit `total_count` is currently compared against: the innermost
+    /// scoped limit if there is one, otherwise `limit`.
+    pub(crate) fn effective_limit(&self) -> u64 {
+        self.scoped_limits.last().copied().unwrap_or(self.limit)
+    }
+
+    /// Caps any further consumption at `cap` on top of the current
+    /// `total_count`, without ever raising the effective limit.
+    pub(crate) fn push_scoped_limit(&mut self, cap: u64) {
+        let scoped_limit = self
+            .total_count
+            .saturating_add(cap)
+            .min(self.effective_limit());
+        self.scoped_limits.push(scoped_limit);
+    }
+
+    pub(crate) fn pop_scoped_limit(&mut self) -> Result<(), HostError> {
+        self.scoped_limits
+            .pop()
+            .map(|_| ())
+            .ok_or_else(|| (ScErrorType::Budget, ScErrorCode::InternalError).into())
     }
 
     pub(crate) fn reset(&mut self, limit: u64) {
         self.limit = limit;
         self.shadow_limit = limit;
+        self.scoped_limits.clear();
         self.reset_count();
     }
 
@@ -144,7 +175,7 @@ impl BudgetDimension {
         let over_limit = if is_shadow.0 {
             self.shadow_total_count > self.shadow_limit
         } else {
-            self.total_count > self.limit
+            self.total_count > self.effective_limit()
         };
 
         if over_limit {
@@ -10,7 +10,11 @@ use crate::{budget::Budget, HostError};
 use crate::host::error::TryBorrowOrErr;
 
 #[cfg(any(test, feature = "testutils"))]
-use crate::{budget::model::ScaledU64, xdr::ContractCostType};
+use crate::{
+    budget::model::ScaledU64,
+    vm::{WasmInsnHistogram, WasmInsnProfile},
+    xdr::{ContractCostType, Hash},
+};
 
 #[cfg(any(test, feature = "testutils", feature = "bench"))]
 impl Budget {
@@ -164,6 +168,78 @@ impl Budget {
             ScaledU64::from_unscaled_u64(lin_mem),
         )
     }
+
+    pub(crate) fn enable_wasm_insn_profiling(&self, enabled: bool) -> Result<(), HostError> {
+        let mut bgt = self.0.try_borrow_mut_or_err()?;
+        match (enabled, &bgt.tracker.wasm_insn_profile) {
+            (true, Some(_)) => (),
+            (true, None) => bgt.tracker.wasm_insn_profile = Some(WasmInsnProfile::default()),
+            (false, _) => bgt.tracker.wasm_insn_profile = None,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_wasm_insn_profiling_enabled(&self) -> Result<bool, HostError> {
+        Ok(self
+            .0
+            .try_borrow_or_err()?
+            .tracker
+            .wasm_insn_profile
+            .is_some())
+    }
+
+    pub(crate) fn has_static_wasm_insn_profile(
+        &self,
+        contract_id: &Hash,
+    ) -> Result<bool, HostError> {
+        let bgt = self.0.try_borrow_or_err()?;
+        Ok(bgt
+            .tracker
+            .wasm_insn_profile
+            .as_ref()
+            .is_some_and(|profile| profile.0.contains_key(contract_id)))
+    }
+
+    pub(crate) fn set_static_wasm_insn_profile(
+        &self,
+        contract_id: &Hash,
+        insns: WasmInsnHistogram,
+        fuel: WasmInsnHistogram,
+    ) -> Result<(), HostError> {
+        let mut bgt = self.0.try_borrow_mut_or_err()?;
+        if let Some(profile) = bgt.tracker.wasm_insn_profile.as_mut() {
+            let entry = profile.0.entry(contract_id.clone()).or_default();
+            entry.static_insns = insns;
+            entry.static_fuel = fuel;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_fuel_to_wasm_insn_profile(
+        &self,
+        contract_id: &Hash,
+        fuel: u64,
+    ) -> Result<(), HostError> {
+        let mut bgt = self.0.try_borrow_mut_or_err()?;
+        if let Some(profile) = bgt.tracker.wasm_insn_profile.as_mut() {
+            let entry = profile.0.entry(contract_id.clone()).or_default();
+            entry.fuel_consumed = entry.fuel_consumed.saturating_add(fuel);
+        }
+        Ok(())
+    }
+
+    /// Returns the Wasm instruction profile collected so far, or `None` if
+    /// profiling is not enabled. See [Host::enable_wasm_insn_profiling].
+    ///
+    /// [Host::enable_wasm_insn_profiling]: crate::Host::enable_wasm_insn_profiling
+    pub fn wasm_insn_profile(&self) -> Result<Option<WasmInsnProfile>, HostError> {
+        Ok(self
+            .0
+            .try_borrow_or_err()?
+            .tracker
+            .wasm_insn_profile
+            .clone())
+    }
 }
 
 #[cfg(any(test, feature = "recording_mode", feature = "testutils"))]
@@ -113,9 +113,19 @@ pub(crate) fn load_calibrated_fuel_costs() -> FuelCosts {
     fuel_costs
 }
 
+pub(crate) fn get_fuel_costs(budget: &Budget) -> Result<FuelCosts, HostError> {
+    Ok(budget.0.try_borrow_or_err()?.fuel_costs)
+}
+
+// `FuelCosts` doesn't implement `PartialEq`. Only its public fields are
+// compared, the private ones always have the wasmi default values.
+pub(crate) fn same_fuel_costs(a: &FuelCosts, b: &FuelCosts) -> bool {
+    (a.base, a.entity, a.load, a.store, a.call) == (b.base, b.entity, b.load, b.store, b.call)
+}
+
 pub(crate) fn get_wasmi_config(budget: &Budget) -> Result<wasmi::Config, HostError> {
     let mut config = wasmi::Config::default();
-    let fuel_costs = budget.0.try_borrow_or_err()?.fuel_costs;
+    let fuel_costs = get_fuel_costs(budget)?;
 
     // Turn off most optional wasm features, leaving on some post-MVP features
     // commonly enabled by Rust and Clang. Make sure all unused features are
@@ -13,6 +13,7 @@ pub(crate) trait BuiltinContract {
 }
 
 pub(crate) use stellar_asset_contract::StellarAssetContract;
+pub use stellar_asset_contract::{stellar_asset_contract_spec, stellar_asset_contract_spec_xdr};
 
 pub(crate) mod account_contract;
 
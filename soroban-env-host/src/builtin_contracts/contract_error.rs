@@ -1,10 +1,12 @@
 #![allow(non_local_definitions)]
 use num_derive::FromPrimitive;
+use soroban_builtin_sdk_macros::contracterror;
 use soroban_env_common::Error;
 
 // Use the same error type for all the built-in contract errors.
 // In theory we could have a separate enum for each built-in contract, but it's
 // not clear how to distinguish them if multiple built-in contracts are involved.
+#[contracterror]
 #[derive(Debug, FromPrimitive, PartialEq, Eq)]
 pub(crate) enum ContractError {
     InternalError = 1,
@@ -12,3 +12,42 @@ mod storage_types;
 pub(crate) mod test_stellar_asset_contract;
 
 pub(crate) use contract::StellarAssetContract;
+
+use crate::{
+    builtin_contracts::contract_error::ContractError,
+    xdr::{Limits, ReadXdr, ScSpecEntry},
+    HostError,
+};
+
+// The XDR-encoded spec entries of the types in the contract's function
+// signatures, followed by its error codes.
+fn type_specs_xdr() -> Vec<&'static [u8]> {
+    let mut specs = StellarAssetContract::type_specs_xdr();
+    ContractError::collect_spec_xdr(&mut specs);
+    specs
+}
+
+/// Returns the interface spec of the Stellar Asset Contract implemented by
+/// this host, as concatenated XDR-encoded `ScSpecEntry`s. This is the same
+/// format as the `contractspecv0` custom section of a Wasm contract.
+pub fn stellar_asset_contract_spec_xdr() -> Vec<u8> {
+    StellarAssetContract::FN_SPECS_XDR
+        .iter()
+        .copied()
+        .chain(type_specs_xdr())
+        .flat_map(|entry| entry.iter().copied())
+        .collect()
+}
+
+/// Returns the interface spec of the Stellar Asset Contract implemented by
+/// this host: its functions, followed by its types and error codes.
+pub fn stellar_asset_contract_spec() -> Result<Vec<ScSpecEntry>, HostError> {
+    StellarAssetContract::FN_SPECS_XDR
+        .iter()
+        .copied()
+        .chain(type_specs_xdr())
+        // Limits::none here is okay since the entries are generated from
+        // the contract's own definitions.
+        .map(|entry| Ok(ScSpecEntry::from_xdr(entry, Limits::none())?))
+        .collect()
+}
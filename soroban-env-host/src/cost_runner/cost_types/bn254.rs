@@ -0,0 +1,210 @@
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::pairing::PairingOutput;
+
+use crate::{
+    cost_runner::{CostRunner, CostType},
+    impl_const_cost_runner_for_bls_consume_sample, impl_const_cost_runner_for_bls_deref_sample,
+    impl_lin_cost_runner_for_bls_deref_sample,
+    xdr::ContractCostType::{
+        self, Bn254DecodeFp, Bn254EncodeFp, Bn254FrAddSub, Bn254FrFromU256, Bn254FrMul, Bn254FrPow,
+        Bn254FrToU256, Bn254G1Add, Bn254G1CheckPointOnCurve, Bn254G1Msm, Bn254G1Mul,
+        Bn254G1ProjectiveToAffine, Bn254G2CheckPointInSubgroup, Bn254G2CheckPointOnCurve,
+        Bn254Pairing,
+    },
+    Host, U256Val,
+};
+use std::hint::black_box;
+
+pub struct Bn254EncodeFpRun;
+pub struct Bn254DecodeFpRun;
+pub struct Bn254G1CheckPointOnCurveRun;
+pub struct Bn254G2CheckPointOnCurveRun;
+pub struct Bn254G2CheckPointInSubgroupRun;
+pub struct Bn254G1ProjectiveToAffineRun;
+pub struct Bn254G1AddRun;
+pub struct Bn254G1MulRun;
+pub struct Bn254G1MsmRun;
+pub struct Bn254PairingRun;
+pub struct Bn254FrFromU256Run;
+pub struct Bn254FrToU256Run;
+pub struct Bn254FrAddRun;
+pub struct Bn254FrSubRun;
+pub struct Bn254FrMulRun;
+pub struct Bn254FrPowRun;
+
+#[derive(Clone)]
+pub struct Bn254EncodeFpSample(pub Fq, pub Vec<u8>);
+#[derive(Clone)]
+pub struct Bn254DecodeFpSample(pub Vec<u8>, pub &'static str);
+#[derive(Clone)]
+pub struct Bn254G1CheckPointOnCurveSample(pub G1Affine, pub ContractCostType);
+#[derive(Clone)]
+pub struct Bn254G2CheckPointOnCurveSample(pub G2Affine, pub ContractCostType);
+#[derive(Clone)]
+pub struct Bn254G2CheckPointInSubgroupSample(pub G2Affine, pub ContractCostType);
+#[derive(Clone)]
+pub struct Bn254G1ProjectiveToAffineSample(pub G1Projective);
+#[derive(Clone)]
+pub struct Bn254G1AddSample(pub G1Affine, pub G1Affine);
+#[derive(Clone)]
+pub struct Bn254G1MulSample(pub G1Affine, pub Fr);
+#[derive(Clone)]
+pub struct Bn254G1MsmSample(pub Vec<G1Affine>, pub Vec<Fr>);
+#[derive(Clone)]
+pub struct Bn254PairingSample(pub Vec<G1Affine>, pub Vec<G2Affine>);
+#[derive(Clone)]
+pub struct Bn254FrFromU256Sample(pub U256Val);
+#[derive(Clone)]
+pub struct Bn254FrToU256Sample(pub Fr);
+#[derive(Clone)]
+pub struct Bn254FrAddSubMulSample(pub Fr, pub Fr);
+#[derive(Clone)]
+pub struct Bn254FrPowSample(pub Fr, pub u64);
+
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254EncodeFpRun,
+    Bn254EncodeFp,
+    bn254_fp_to_be_bytes,
+    Bn254EncodeFpSample,
+    (),
+    fp,
+    buf
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254DecodeFpRun,
+    Bn254DecodeFp,
+    bn254_fp_from_be_bytes,
+    Bn254DecodeFpSample,
+    Fq,
+    buf,
+    tag
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254G1CheckPointOnCurveRun,
+    Bn254G1CheckPointOnCurve,
+    check_point_is_on_curve,
+    Bn254G1CheckPointOnCurveSample,
+    bool,
+    pt,
+    ty
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254G2CheckPointOnCurveRun,
+    Bn254G2CheckPointOnCurve,
+    check_point_is_on_curve,
+    Bn254G2CheckPointOnCurveSample,
+    bool,
+    pt,
+    ty
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254G2CheckPointInSubgroupRun,
+    Bn254G2CheckPointInSubgroup,
+    check_point_is_in_subgroup,
+    Bn254G2CheckPointInSubgroupSample,
+    bool,
+    pt,
+    ty
+);
+impl_const_cost_runner_for_bls_consume_sample!(
+    Bn254G1ProjectiveToAffineRun,
+    Bn254G1ProjectiveToAffine,
+    bn254_g1_projective_into_affine,
+    Bn254G1ProjectiveToAffineSample,
+    G1Affine,
+    p0
+);
+impl_const_cost_runner_for_bls_consume_sample!(
+    Bn254G1AddRun,
+    Bn254G1Add,
+    bn254_g1_add_internal,
+    Bn254G1AddSample,
+    G1Projective,
+    p0,
+    p1
+);
+impl_const_cost_runner_for_bls_consume_sample!(
+    Bn254G1MulRun,
+    Bn254G1Mul,
+    bn254_g1_mul_internal,
+    Bn254G1MulSample,
+    G1Projective,
+    p0,
+    scalar
+);
+impl_const_cost_runner_for_bls_consume_sample!(
+    Bn254FrFromU256Run,
+    Bn254FrFromU256,
+    bn254_fr_from_u256val,
+    Bn254FrFromU256Sample,
+    Fr,
+    sv
+);
+
+// The scalar field operations are only used by Poseidon, which charges them
+// through `PoseidonScalar`.
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254FrToU256Run,
+    Bn254FrToU256,
+    poseidon_field_element_to_u256val,
+    Bn254FrToU256Sample,
+    U256Val,
+    fe
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254FrAddRun,
+    Bn254FrAddSub,
+    poseidon_fr_add_internal,
+    Bn254FrAddSubMulSample,
+    (),
+    lhs,
+    rhs
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254FrSubRun,
+    Bn254FrAddSub,
+    poseidon_fr_sub_internal,
+    Bn254FrAddSubMulSample,
+    (),
+    lhs,
+    rhs
+);
+impl_const_cost_runner_for_bls_deref_sample!(
+    Bn254FrMulRun,
+    Bn254FrMul,
+    poseidon_fr_mul_internal,
+    Bn254FrAddSubMulSample,
+    (),
+    lhs,
+    rhs
+);
+impl_lin_cost_runner_for_bls_deref_sample!(
+    Bn254FrPowRun,
+    Bn254FrPow,
+    poseidon_fr_pow_internal,
+    Bn254FrPowSample,
+    Fr,
+    lhs,
+    rhs
+);
+
+impl_lin_cost_runner_for_bls_deref_sample!(
+    Bn254G1MsmRun,
+    Bn254G1Msm,
+    bn254_g1_msm_internal,
+    Bn254G1MsmSample,
+    G1Projective,
+    vp,
+    vs
+);
+
+type InternalPairingOutput = PairingOutput<Bn254>;
+impl_lin_cost_runner_for_bls_deref_sample!(
+    Bn254PairingRun,
+    Bn254Pairing,
+    bn254_pairing_internal,
+    Bn254PairingSample,
+    InternalPairingOutput,
+    vp1,
+    vp2
+);
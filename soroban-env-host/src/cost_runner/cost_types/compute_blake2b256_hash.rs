@@ -0,0 +1,33 @@
+use std::hint::black_box;
+
+use crate::{
+    cost_runner::{CostRunner, CostType},
+    xdr::ContractCostType::ComputeBlake2b256Hash,
+};
+
+pub struct ComputeBlake2b256HashRun;
+
+impl CostRunner for ComputeBlake2b256HashRun {
+    const COST_TYPE: CostType = CostType::Contract(ComputeBlake2b256Hash);
+
+    type SampleType = Vec<u8>;
+
+    type RecycledType = (Option<[u8; 32]>, Vec<u8>);
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let hash = black_box(
+            host.blake2b256_hash_from_bytes_raw(sample.as_slice())
+                .expect("blake2b256"),
+        );
+        (Some(hash), sample)
+    }
+
+    fn run_baseline_iter(
+        host: &crate::Host,
+        _iter: u64,
+        sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box(host.charge_budget(ComputeBlake2b256Hash, Some(0)).unwrap());
+        black_box((None, sample))
+    }
+}
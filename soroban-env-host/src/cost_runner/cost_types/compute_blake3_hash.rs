@@ -0,0 +1,33 @@
+use std::hint::black_box;
+
+use crate::{
+    cost_runner::{CostRunner, CostType},
+    xdr::ContractCostType::ComputeBlake3Hash,
+};
+
+pub struct ComputeBlake3HashRun;
+
+impl CostRunner for ComputeBlake3HashRun {
+    const COST_TYPE: CostType = CostType::Contract(ComputeBlake3Hash);
+
+    type SampleType = Vec<u8>;
+
+    type RecycledType = (Option<[u8; 32]>, Vec<u8>);
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let hash = black_box(
+            host.blake3_hash_from_bytes_raw(sample.as_slice())
+                .expect("blake3"),
+        );
+        (Some(hash), sample)
+    }
+
+    fn run_baseline_iter(
+        host: &crate::Host,
+        _iter: u64,
+        sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box(host.charge_budget(ComputeBlake3Hash, Some(0)).unwrap());
+        black_box((None, sample))
+    }
+}
@@ -0,0 +1,33 @@
+use std::hint::black_box;
+
+use crate::{
+    cost_runner::{CostRunner, CostType},
+    xdr::ContractCostType::ComputeRipemd160Hash,
+};
+
+pub struct ComputeRipemd160HashRun;
+
+impl CostRunner for ComputeRipemd160HashRun {
+    const COST_TYPE: CostType = CostType::Contract(ComputeRipemd160Hash);
+
+    type SampleType = Vec<u8>;
+
+    type RecycledType = (Option<[u8; 20]>, Vec<u8>);
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let hash = black_box(
+            host.ripemd160_hash_from_bytes_raw(sample.as_slice())
+                .expect("ripemd160"),
+        );
+        (Some(hash), sample)
+    }
+
+    fn run_baseline_iter(
+        host: &crate::Host,
+        _iter: u64,
+        sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box(host.charge_budget(ComputeRipemd160Hash, Some(0)).unwrap());
+        black_box((None, sample))
+    }
+}
@@ -0,0 +1,33 @@
+use std::hint::black_box;
+
+use crate::{
+    cost_runner::{CostRunner, CostType},
+    xdr::ContractCostType::ComputeSha512Hash,
+};
+
+pub struct ComputeSha512HashRun;
+
+impl CostRunner for ComputeSha512HashRun {
+    const COST_TYPE: CostType = CostType::Contract(ComputeSha512Hash);
+
+    type SampleType = Vec<u8>;
+
+    type RecycledType = (Option<[u8; 64]>, Vec<u8>);
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let hash = black_box(
+            host.sha512_hash_from_bytes_raw(sample.as_slice())
+                .expect("sha512"),
+        );
+        (Some(hash), sample)
+    }
+
+    fn run_baseline_iter(
+        host: &crate::Host,
+        _iter: u64,
+        sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box(host.charge_budget(ComputeSha512Hash, Some(0)).unwrap());
+        black_box((None, sample))
+    }
+}
@@ -0,0 +1,31 @@
+use std::hint::black_box;
+
+use crate::{
+    budget::AsBudget,
+    cost_runner::{CostRunner, CostType},
+    xdr::ContractCostType::{CopySharedSeqNode, MemCpy},
+    Host, MeteredVector, U32Val, Val,
+};
+
+pub struct CopySharedSeqNodeRun;
+
+impl CostRunner for CopySharedSeqNodeRun {
+    const COST_TYPE: CostType = CostType::Contract(CopySharedSeqNode);
+
+    type SampleType = MeteredVector<Val>;
+
+    type RecycledType = (Option<MeteredVector<Val>>, MeteredVector<Val>);
+
+    fn run_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let value = U32Val::from(0).to_val();
+        let res = black_box(sample.set(0, value, host.as_budget()).unwrap());
+        (Some(res), sample)
+    }
+
+    fn run_baseline_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        // `set` also charges for the access to the element.
+        black_box(host.charge_budget(CopySharedSeqNode, Some(0)).unwrap());
+        black_box(host.charge_budget(MemCpy, Some(0)).unwrap());
+        black_box((None, sample))
+    }
+}
@@ -1,7 +1,13 @@
 mod bls12_381;
+mod bn254;
+mod compute_blake2b256_hash;
+mod compute_blake3_hash;
 mod compute_ed25519_pubkey;
 mod compute_keccak256_hash;
+mod compute_ripemd160_hash;
 mod compute_sha256_hash;
+mod compute_sha512_hash;
+mod copy_shared_seq_node;
 mod decode_ecdsa_curve256_sig;
 mod host_mem_alloc;
 mod host_mem_cmp;
@@ -13,6 +19,7 @@ mod recover_ecdsa_secp256k1_key;
 mod sec1_decode_point_uncompressed;
 mod val_deser;
 mod val_ser;
+mod verify_ecdsa_secp256k1_sig;
 mod verify_ecdsa_secp256r1_sig;
 mod verify_ed25519_sig;
 mod visit_object;
@@ -20,9 +27,15 @@ mod vm_ops;
 mod wasm_insn_exec;
 
 pub use bls12_381::*;
+pub use bn254::*;
+pub use compute_blake2b256_hash::*;
+pub use compute_blake3_hash::*;
 pub use compute_ed25519_pubkey::*;
 pub use compute_keccak256_hash::*;
+pub use compute_ripemd160_hash::*;
 pub use compute_sha256_hash::*;
+pub use compute_sha512_hash::*;
+pub use copy_shared_seq_node::*;
 pub use decode_ecdsa_curve256_sig::*;
 pub use host_mem_alloc::*;
 pub use host_mem_cmp::*;
@@ -34,6 +47,7 @@ pub use recover_ecdsa_secp256k1_key::*;
 pub use sec1_decode_point_uncompressed::*;
 pub use val_deser::*;
 pub use val_ser::*;
+pub use verify_ecdsa_secp256k1_sig::*;
 pub use verify_ecdsa_secp256r1_sig::*;
 pub use verify_ed25519_sig::*;
 pub use visit_object::*;
@@ -2,8 +2,10 @@ use std::hint::black_box;
 
 use crate::{
     cost_runner::{CostRunner, CostType},
-    xdr::ContractCostType::{Int256AddSub, Int256Div, Int256Mul, Int256Pow, Int256Shift},
-    Env, I256Val, U32Val,
+    xdr::ContractCostType::{
+        Int256AddSub, Int256Div, Int256Mul, Int256MulDiv, Int256Pow, Int256Shift,
+    },
+    Env, I256Val, U256Val, U32Val,
 };
 
 macro_rules! impl_int256_cost_runner {
@@ -42,3 +44,29 @@ impl_int256_cost_runner!(Int256MulRun, i256_mul, Int256Mul, (I256Val, I256Val));
 impl_int256_cost_runner!(Int256DivRun, i256_div, Int256Div, (I256Val, I256Val));
 impl_int256_cost_runner!(Int256PowRun, i256_pow, Int256Pow, (I256Val, U32Val));
 impl_int256_cost_runner!(Int256ShiftRun, i256_shl, Int256Shift, (I256Val, U32Val));
+
+pub struct Int256MulDivRun;
+
+impl CostRunner for Int256MulDivRun {
+    const COST_TYPE: CostType = CostType::Contract(Int256MulDiv);
+
+    type SampleType = (U256Val, U256Val, U256Val);
+
+    type RecycledType = Option<U256Val>;
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        black_box(
+            host.u256_mul_div(sample.0, sample.1, sample.2, U32Val::from(0))
+                .ok(),
+        )
+    }
+
+    fn run_baseline_iter(
+        host: &crate::Host,
+        _iter: u64,
+        _sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box(host.charge_budget(Int256MulDiv, None).unwrap());
+        black_box(None)
+    }
+}
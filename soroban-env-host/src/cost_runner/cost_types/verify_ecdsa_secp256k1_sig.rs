@@ -0,0 +1,39 @@
+use crate::{
+    cost_runner::{CostRunner, CostType},
+    xdr::{ContractCostType::VerifyEcdsaSecp256k1Sig, Hash},
+};
+use k256::ecdsa::{Signature, VerifyingKey};
+use std::hint::black_box;
+pub struct VerifyEcdsaSecp256k1SigRun;
+
+#[derive(Clone)]
+pub struct VerifyEcdsaSecp256k1SigSample {
+    pub pub_key: VerifyingKey,
+    pub msg_hash: Hash,
+    pub sig: Signature,
+}
+
+impl CostRunner for VerifyEcdsaSecp256k1SigRun {
+    const COST_TYPE: CostType = CostType::Contract(VerifyEcdsaSecp256k1Sig);
+
+    type SampleType = VerifyEcdsaSecp256k1SigSample;
+
+    type RecycledType = Self::SampleType;
+
+    fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        black_box(
+            host.secp256k1_verify_signature(&sample.pub_key, &sample.msg_hash, &sample.sig)
+                .unwrap(),
+        );
+        black_box(sample)
+    }
+
+    fn run_baseline_iter(
+        host: &crate::Host,
+        _iter: u64,
+        sample: Self::SampleType,
+    ) -> Self::RecycledType {
+        black_box(host.charge_budget(VerifyEcdsaSecp256k1Sig, None).unwrap());
+        black_box(sample)
+    }
+}
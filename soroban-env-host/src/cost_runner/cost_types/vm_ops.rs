@@ -4,13 +4,13 @@ use crate::{
     xdr::{ContractCostType::VmInstantiation, Hash},
     Vm,
 };
-use std::{hint::black_box, rc::Rc};
+use std::{hint::black_box, rc::Rc, sync::Arc};
 
 #[derive(Clone)]
 pub struct VmInstantiationSample {
     pub id: Option<Hash>,
     pub wasm: Vec<u8>,
-    pub module: Rc<ParsedModule>,
+    pub module: Arc<ParsedModule>,
 }
 
 // Protocol 20 coarse and unified cost model
@@ -33,6 +33,7 @@ impl CostRunner for VmInstantiationRun {
                 &sample.wasm[..],
                 sample.module.cost_inputs.clone(),
                 ModuleParseCostMode::Normal,
+                None,
             )
             .unwrap(),
         );
@@ -73,7 +74,7 @@ mod v21 {
 
                 type SampleType = VmInstantiationSample;
 
-                type RecycledType = (Option<Rc<ParsedModule>>, Vec<u8>);
+                type RecycledType = (Option<Arc<ParsedModule>>, Vec<u8>);
 
                 fn run_iter(
                     host: &crate::Host,
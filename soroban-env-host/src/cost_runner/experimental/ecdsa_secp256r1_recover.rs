@@ -1,7 +1,7 @@
 use crate::{
-    budget::CostTracker,
+    budget::{AsBudget, CostTracker},
     cost_runner::{CostRunner, CostType},
-    xdr::Hash,
+    xdr::{ContractCostType, Hash},
 };
 use ecdsa::RecoveryId;
 use p256::ecdsa::Signature;
@@ -32,19 +32,25 @@ impl CostRunner for EcdsaSecp256r1RecoverRun {
 
     fn run_iter(host: &crate::Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
         black_box(
-            host.ecdsa_secp256r1_recover_key(&sample.msg_hash, &sample.sig, sample.recovery_id)
-                .unwrap(),
+            host.recover_key_ecdsa_secp256r1_internal(
+                &sample.msg_hash,
+                &sample.sig,
+                sample.recovery_id,
+            )
+            .unwrap(),
         );
         black_box(sample)
     }
 
-    fn get_tracker(_host: &crate::Host, _sample: &Self::SampleType) -> CostTracker {
-        CostTracker {
-            iterations: Self::RUN_ITERATIONS,
-            inputs: None,
-            cpu: 0,
-            mem: 0,
-        }
+    // Reports the cost type this is charged as, so that the measured cost can
+    // be compared with the charged one.
+    fn get_tracker(host: &crate::Host, _sample: &Self::SampleType) -> CostTracker {
+        let mut tracker = host
+            .as_budget()
+            .get_tracker(ContractCostType::VerifyEcdsaSecp256r1Sig)
+            .unwrap();
+        tracker.iterations = Self::RUN_ITERATIONS;
+        tracker
     }
 
     fn run_baseline_iter(
@@ -0,0 +1,48 @@
+use super::ExperimentalCostType::Ed25519BatchVerify;
+use crate::{
+    budget::{AsBudget, CostTracker},
+    cost_runner::{CostRunner, CostType},
+    xdr::ContractCostType::VerifyEd25519Sig,
+    Host,
+};
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::hint::black_box;
+
+pub struct Ed25519BatchVerifyRun;
+
+#[derive(Clone)]
+pub struct Ed25519BatchVerifySample {
+    pub keys: Vec<VerifyingKey>,
+    pub msgs: Vec<Vec<u8>>,
+    pub sigs: Vec<Signature>,
+}
+
+// The batch is charged one `VerifyEd25519Sig` per signature (see
+// `crypto::ed25519_batch`), so this reports that tracker. If the charge is
+// right, the fitted cost per iteration is no higher than `VerifyEd25519Sig`'s.
+impl CostRunner for Ed25519BatchVerifyRun {
+    const COST_TYPE: CostType = CostType::Experimental(Ed25519BatchVerify);
+
+    const RUN_ITERATIONS: u64 = 1;
+
+    type SampleType = Ed25519BatchVerifySample;
+
+    type RecycledType = (Option<u32>, Self::SampleType);
+
+    fn run_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let res = black_box(
+            host.verify_sigs_ed25519_batch_internal(&sample.msgs, &sample.keys, &sample.sigs)
+                .unwrap(),
+        );
+        (res, sample)
+    }
+
+    fn run_baseline_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        black_box(host.charge_budget(VerifyEd25519Sig, Some(0)).unwrap());
+        black_box((None, sample))
+    }
+
+    fn get_tracker(host: &Host, _sample: &Self::SampleType) -> CostTracker {
+        host.as_budget().get_tracker(VerifyEd25519Sig).unwrap()
+    }
+}
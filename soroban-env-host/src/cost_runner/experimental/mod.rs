@@ -1,16 +1,18 @@
 mod bls12_381;
 mod decode_secp256r1_sig;
-mod ecdsa_secp256k1_verify;
 mod ecdsa_secp256r1_recover;
+mod ed25519_batch_verify;
 mod ed25519_scalar_mut;
+mod poseidon;
 mod read_xdr;
 mod sec1_decode_point_compressed;
 
 pub use bls12_381::*;
 pub use decode_secp256r1_sig::*;
-pub use ecdsa_secp256k1_verify::*;
 pub use ecdsa_secp256r1_recover::*;
+pub use ed25519_batch_verify::*;
 pub use ed25519_scalar_mut::*;
+pub use poseidon::*;
 pub use read_xdr::*;
 pub use sec1_decode_point_compressed::*;
 
@@ -24,7 +26,6 @@ pub enum ExperimentalCostType {
     EcdsaSecp256r1Recover,
     Sec1DecodePointCompressed,
     DecodeSecp256r1Signature,
-    EcdsaSecp256k1Verify,
     Bls12381G1AffineDeserializeUncompressed,
     Bls12381G1AffineSerializeUncompressed,
     Bls12381G2AffineDeserializeUncompressed,
@@ -32,6 +33,9 @@ pub enum ExperimentalCostType {
     Bls12381Fp2DeserializeUncompressed,
     Bls12381G1ComputeYFromX,
     Bls12381G2ComputeYFromX,
+    PoseidonPermutation,
+    Poseidon2Permutation,
+    Ed25519BatchVerify,
 }
 
 impl Name for ExperimentalCostType {
@@ -44,7 +48,6 @@ impl Name for ExperimentalCostType {
             ExperimentalCostType::EcdsaSecp256r1Recover => "EcdsaSecp256r1Recover",
             ExperimentalCostType::Sec1DecodePointCompressed => "Sec1DecodePointCompressed",
             ExperimentalCostType::DecodeSecp256r1Signature => "DecodeSecp256r1Signature",
-            ExperimentalCostType::EcdsaSecp256k1Verify => "EcdsaSecp256k1Verify",
             ExperimentalCostType::Bls12381G1AffineDeserializeUncompressed => {
                 "Bls12381G1AffineDeserializeUncompressed"
             }
@@ -62,6 +65,9 @@ impl Name for ExperimentalCostType {
             }
             ExperimentalCostType::Bls12381G1ComputeYFromX => "Bls12381G1ComputeYFromX",
             ExperimentalCostType::Bls12381G2ComputeYFromX => "Bls12381G2ComputeYFromX",
+            ExperimentalCostType::PoseidonPermutation => "PoseidonPermutation",
+            ExperimentalCostType::Poseidon2Permutation => "Poseidon2Permutation",
+            ExperimentalCostType::Ed25519BatchVerify => "Ed25519BatchVerify",
         }
     }
 }
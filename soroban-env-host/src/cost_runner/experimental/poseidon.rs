@@ -0,0 +1,129 @@
+use ark_bls12_381::Fr;
+
+use super::ExperimentalCostType::*;
+use crate::{
+    budget::{AsBudget, CostTracker},
+    cost_runner::{CostRunner, CostType},
+    crypto::poseidon::{Poseidon2Params, PoseidonParams},
+    xdr::ContractCostType,
+    Host,
+};
+use std::hint::black_box;
+
+// The Poseidon permutations are charged as a combination of `Fr` operations of
+// their field (see `crypto::poseidon`). These runners measure a whole
+// permutation over BLS12-381's `Fr`, and report the sum of the trackers of the
+// charged cost types, so that the two can be compared.
+fn poseidon_charged_tracker(host: &Host) -> CostTracker {
+    let mut res = CostTracker::default();
+    for ty in [
+        ContractCostType::Bls12381FrPow,
+        ContractCostType::Bls12381FrMul,
+        ContractCostType::Bls12381FrAddSub,
+    ] {
+        let tracker = host.as_budget().get_tracker(ty).unwrap();
+        res.cpu = res.cpu.saturating_add(tracker.cpu);
+        res.mem = res.mem.saturating_add(tracker.mem);
+    }
+    res.iterations = 1;
+    res
+}
+
+pub struct PoseidonPermutationRun;
+
+#[derive(Clone)]
+pub struct PoseidonPermutationSample {
+    pub input: Vec<Fr>,
+    pub d: u32,
+    pub rounds_f: u32,
+    pub rounds_p: u32,
+    // Row-major, as in `PoseidonParams`.
+    pub mds: Vec<Fr>,
+    pub round_constants: Vec<Fr>,
+}
+
+impl CostRunner for PoseidonPermutationRun {
+    const COST_TYPE: CostType = CostType::Experimental(PoseidonPermutation);
+
+    const RUN_ITERATIONS: u64 = 1;
+
+    type SampleType = PoseidonPermutationSample;
+
+    type RecycledType = (Option<Self::SampleType>, Option<Vec<Fr>>);
+
+    fn run_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let params = PoseidonParams {
+            t: sample.input.len(),
+            d: sample.d,
+            rounds_f: sample.rounds_f,
+            rounds_p: sample.rounds_p,
+            mds: &sample.mds,
+            round_constants: &sample.round_constants,
+        };
+        let res = host
+            .poseidon_permutation_internal(&sample.input, &params)
+            .unwrap();
+        black_box((Some(sample), Some(res)))
+    }
+
+    fn run_baseline_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        black_box(
+            host.charge_budget(ContractCostType::Bls12381FrAddSub, None)
+                .unwrap(),
+        );
+        black_box((Some(sample), None))
+    }
+
+    fn get_tracker(host: &Host, _sample: &Self::SampleType) -> CostTracker {
+        poseidon_charged_tracker(host)
+    }
+}
+
+pub struct Poseidon2PermutationRun;
+
+#[derive(Clone)]
+pub struct Poseidon2PermutationSample {
+    pub input: Vec<Fr>,
+    pub d: u32,
+    pub rounds_f: u32,
+    pub rounds_p: u32,
+    pub mat_internal_diag_m_1: Vec<Fr>,
+    pub round_constants: Vec<Fr>,
+}
+
+impl CostRunner for Poseidon2PermutationRun {
+    const COST_TYPE: CostType = CostType::Experimental(Poseidon2Permutation);
+
+    const RUN_ITERATIONS: u64 = 1;
+
+    type SampleType = Poseidon2PermutationSample;
+
+    type RecycledType = (Option<Self::SampleType>, Option<Vec<Fr>>);
+
+    fn run_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        let params = Poseidon2Params {
+            t: sample.input.len(),
+            d: sample.d,
+            rounds_f: sample.rounds_f,
+            rounds_p: sample.rounds_p,
+            mat_internal_diag_m_1: &sample.mat_internal_diag_m_1,
+            round_constants: &sample.round_constants,
+        };
+        let res = host
+            .poseidon2_permutation_internal(&sample.input, &params)
+            .unwrap();
+        black_box((Some(sample), Some(res)))
+    }
+
+    fn run_baseline_iter(host: &Host, _iter: u64, sample: Self::SampleType) -> Self::RecycledType {
+        black_box(
+            host.charge_budget(ContractCostType::Bls12381FrAddSub, None)
+                .unwrap(),
+        );
+        black_box((Some(sample), None))
+    }
+
+    fn get_tracker(host: &Host, _sample: &Self::SampleType) -> CostTracker {
+        poseidon_charged_tracker(host)
+    }
+}
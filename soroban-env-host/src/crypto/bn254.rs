@@ -0,0 +1,302 @@
+// BN254 (alt_bn128) support, following the encoding used by the Ethereum
+// precompiles (EIP-196 and EIP-197):
+//
+// - An `Fp` element is 32 bytes, big-endian, and must be less than the
+//   field modulus.
+// - A G1 point is `be_bytes(X) || be_bytes(Y)` (64 bytes). The point at
+//   infinity is encoded as all zeros. There are no flag bits.
+// - A G2 point is `be_bytes(X_c1) || be_bytes(X_c0) || be_bytes(Y_c1) ||
+//   be_bytes(Y_c0)` (128 bytes), again with all zeros meaning infinity.
+//
+// Every operation is charged under its own `Bn254*` cost type. G1 of BN254
+// has cofactor 1, so only G2 points need a subgroup check; arkworks has no
+// endomorphism-based check for BN254's G2 and multiplies by the group order
+// instead, which the `Bn254G2CheckPointInSubgroup` model accounts for.
+use crate::{
+    host_object::HostVec,
+    xdr::{ContractCostType, ScBytes, ScErrorCode, ScErrorType},
+    Bool, BytesObject, Env, Host, HostError, TryFromVal, U256Object, U256Small, U256Val, Val,
+    VecObject, U256,
+};
+use ark_bn254::{
+    g1::Config as G1Config, Bn254, Fq, Fq12, Fq2, Fr, G1Affine, G1Projective, G2Affine,
+};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_serialize::{CanonicalDeserialize, Compress, Validate};
+use std::cmp::Ordering;
+use std::ops::{Add, Mul};
+
+pub(crate) const BN254_FP_SERIALIZED_SIZE: usize = 32;
+pub(crate) const BN254_FP12_SERIALIZED_SIZE: usize = BN254_FP_SERIALIZED_SIZE * 12;
+pub(crate) const BN254_G1_SERIALIZED_SIZE: usize = BN254_FP_SERIALIZED_SIZE * 2;
+pub(crate) const BN254_G2_SERIALIZED_SIZE: usize = BN254_FP_SERIALIZED_SIZE * 4;
+pub(crate) const BN254_FR_SERIALIZED_SIZE: usize = 32;
+
+impl Host {
+    fn bn254_invalid_input(&self, msg: &str) -> HostError {
+        self.err(ScErrorType::Crypto, ScErrorCode::InvalidInput, msg, &[])
+    }
+
+    // Decodes a single big-endian `Fp` element. `bytes` must be exactly
+    // `BN254_FP_SERIALIZED_SIZE` long, which the callers guarantee.
+    pub(crate) fn bn254_fp_from_be_bytes(&self, bytes: &[u8], tag: &str) -> Result<Fq, HostError> {
+        self.charge_budget(ContractCostType::Bn254DecodeFp, None)?;
+        let mut buf = [0u8; BN254_FP_SERIALIZED_SIZE];
+        buf.copy_from_slice(bytes);
+        buf.reverse();
+        // `CanonicalDeserialize for Fp<P, N>` expects little-endian bytes and
+        // rejects any value >= the prime modulus. BN254's modulus is 254 bits
+        // so the top two bits of the input must be zero, i.e. they can never
+        // be interpreted as flags.
+        Fq::deserialize_with_mode(&buf[..], Compress::No, Validate::Yes).map_err(|_e| {
+            self.bn254_invalid_input(
+                format!("bn254 {tag}: field element is not less than the modulus").as_str(),
+            )
+        })
+    }
+
+    pub(crate) fn bn254_fp_to_be_bytes(&self, fp: &Fq, out: &mut [u8]) -> Result<(), HostError> {
+        self.charge_budget(ContractCostType::Bn254EncodeFp, None)?;
+        // `into_bigint` undoes the Montgomery form, `to_bytes_be` always
+        // produces 32 bytes for the 4-limb `BigInt`.
+        out.copy_from_slice(&fp.into_bigint().to_bytes_be());
+        Ok(())
+    }
+
+    fn bn254_check_encoding_len<const EXPECTED_SIZE: usize>(
+        &self,
+        bytes: &[u8],
+        tag: &str,
+    ) -> Result<(), HostError> {
+        if bytes.len() != EXPECTED_SIZE {
+            return Err(self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InvalidInput,
+                format!("bn254 {tag}: invalid input length to deserialize").as_str(),
+                &[
+                    Val::from_u32(bytes.len() as u32).into(),
+                    Val::from_u32(EXPECTED_SIZE as u32).into(),
+                ],
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn bn254_g1_affine_from_bytes(&self, bytes: &[u8]) -> Result<G1Affine, HostError> {
+        self.bn254_check_encoding_len::<BN254_G1_SERIALIZED_SIZE>(bytes, "G1")?;
+        if bytes.iter().all(|b| *b == 0) {
+            return Ok(G1Affine::zero());
+        }
+        let x = self.bn254_fp_from_be_bytes(&bytes[0..32], "G1")?;
+        let y = self.bn254_fp_from_be_bytes(&bytes[32..64], "G1")?;
+        let pt = G1Affine::new_unchecked(x, y);
+        // G1 of BN254 has cofactor 1, so every point on the curve is in the
+        // prime-order subgroup and no subgroup check is needed.
+        if !self.check_point_is_on_curve(&pt, &ContractCostType::Bn254G1CheckPointOnCurve)? {
+            return Err(self.bn254_invalid_input("bn254 G1: point not on curve"));
+        }
+        Ok(pt)
+    }
+
+    pub(crate) fn bn254_g2_affine_from_bytes(&self, bytes: &[u8]) -> Result<G2Affine, HostError> {
+        self.bn254_check_encoding_len::<BN254_G2_SERIALIZED_SIZE>(bytes, "G2")?;
+        if bytes.iter().all(|b| *b == 0) {
+            return Ok(G2Affine::zero());
+        }
+        // EIP-197 orders the `Fp2` coefficients as `(c1, c0)`.
+        let x_c1 = self.bn254_fp_from_be_bytes(&bytes[0..32], "G2")?;
+        let x_c0 = self.bn254_fp_from_be_bytes(&bytes[32..64], "G2")?;
+        let y_c1 = self.bn254_fp_from_be_bytes(&bytes[64..96], "G2")?;
+        let y_c0 = self.bn254_fp_from_be_bytes(&bytes[96..128], "G2")?;
+        let pt = G2Affine::new_unchecked(Fq2::new(x_c0, x_c1), Fq2::new(y_c0, y_c1));
+        if !self.check_point_is_on_curve(&pt, &ContractCostType::Bn254G2CheckPointOnCurve)? {
+            return Err(self.bn254_invalid_input("bn254 G2: point not on curve"));
+        }
+        if !self.check_point_is_in_subgroup(&pt, &ContractCostType::Bn254G2CheckPointInSubgroup)? {
+            return Err(self.bn254_invalid_input("bn254 G2: point not in the correct subgroup"));
+        }
+        Ok(pt)
+    }
+
+    pub(crate) fn bn254_g1_affine_deserialize_from_bytesobj(
+        &self,
+        bo: BytesObject,
+    ) -> Result<G1Affine, HostError> {
+        self.visit_obj(bo, |bytes: &ScBytes| {
+            self.bn254_g1_affine_from_bytes(bytes.as_slice())
+        })
+    }
+
+    pub(crate) fn bn254_g1_projective_into_affine(
+        &self,
+        g1: G1Projective,
+    ) -> Result<G1Affine, HostError> {
+        self.charge_budget(ContractCostType::Bn254G1ProjectiveToAffine, None)?;
+        Ok(g1.into_affine())
+    }
+
+    pub(crate) fn bn254_g1_affine_serialize(
+        &self,
+        g1: &G1Affine,
+    ) -> Result<BytesObject, HostError> {
+        let mut buf = [0u8; BN254_G1_SERIALIZED_SIZE];
+        // The point at infinity has no coordinates and stays all zeros.
+        if let Some((x, y)) = g1.xy() {
+            self.bn254_fp_to_be_bytes(x, &mut buf[0..32])?;
+            self.bn254_fp_to_be_bytes(y, &mut buf[32..64])?;
+        }
+        self.add_host_object(self.scbytes_from_slice(&buf)?)
+    }
+
+    pub(crate) fn bn254_g1_projective_serialize(
+        &self,
+        g1: G1Projective,
+    ) -> Result<BytesObject, HostError> {
+        let g1_affine = self.bn254_g1_projective_into_affine(g1)?;
+        self.bn254_g1_affine_serialize(&g1_affine)
+    }
+
+    pub(crate) fn bn254_fr_from_u256val(&self, sv: U256Val) -> Result<Fr, HostError> {
+        self.charge_budget(ContractCostType::Bn254FrFromU256, None)?;
+        let fr = if let Ok(small) = U256Small::try_from(sv) {
+            Fr::from_le_bytes_mod_order(&u64::from(small).to_le_bytes())
+        } else {
+            let obj: U256Object = sv.try_into()?;
+            self.visit_obj(obj, |u: &U256| {
+                Ok(Fr::from_le_bytes_mod_order(&u.to_le_bytes()))
+            })?
+        };
+        Ok(fr)
+    }
+
+    pub(crate) fn bn254_fr_vec_from_vecobj(&self, vs: VecObject) -> Result<Vec<Fr>, HostError> {
+        let len: u32 = self.vec_len(vs)?.into();
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(len as u64 * BN254_FR_SERIALIZED_SIZE as u64),
+        )?;
+        let mut scalars: Vec<Fr> = Vec::with_capacity(len as usize);
+        self.visit_obj(vs, |vs: &HostVec| {
+            for s in vs.iter() {
+                scalars.push(self.bn254_fr_from_u256val(U256Val::try_from_val(self, s)?)?);
+            }
+            Ok(())
+        })?;
+        Ok(scalars)
+    }
+
+    fn bn254_affine_vec_from_vecobj<const EXPECTED_SIZE: usize, T>(
+        &self,
+        vp: VecObject,
+        decode: impl Fn(&[u8]) -> Result<T, HostError>,
+    ) -> Result<Vec<T>, HostError> {
+        let len: u32 = self.vec_len(vp)?.into();
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(len as u64 * EXPECTED_SIZE as u64),
+        )?;
+        let mut points: Vec<T> = Vec::with_capacity(len as usize);
+        self.visit_obj(vp, |vp: &HostVec| {
+            for p in vp.iter() {
+                let bo = BytesObject::try_from_val(self, p)?;
+                points.push(self.visit_obj(bo, |bytes: &ScBytes| decode(bytes.as_slice()))?);
+            }
+            Ok(())
+        })?;
+        Ok(points)
+    }
+
+    pub(crate) fn bn254_g1_vec_from_vecobj(
+        &self,
+        vp: VecObject,
+    ) -> Result<Vec<G1Affine>, HostError> {
+        self.bn254_affine_vec_from_vecobj::<BN254_G1_SERIALIZED_SIZE, _>(vp, |b| {
+            self.bn254_g1_affine_from_bytes(b)
+        })
+    }
+
+    pub(crate) fn bn254_g2_vec_from_vecobj(
+        &self,
+        vp: VecObject,
+    ) -> Result<Vec<G2Affine>, HostError> {
+        self.bn254_affine_vec_from_vecobj::<BN254_G2_SERIALIZED_SIZE, _>(vp, |b| {
+            self.bn254_g2_affine_from_bytes(b)
+        })
+    }
+
+    pub(crate) fn bn254_g1_add_internal(
+        &self,
+        p0: G1Affine,
+        p1: G1Affine,
+    ) -> Result<G1Projective, HostError> {
+        self.charge_budget(ContractCostType::Bn254G1Add, None)?;
+        Ok(p0.add(p1))
+    }
+
+    pub(crate) fn bn254_g1_mul_internal(
+        &self,
+        p0: G1Affine,
+        scalar: Fr,
+    ) -> Result<G1Projective, HostError> {
+        self.charge_budget(ContractCostType::Bn254G1Mul, None)?;
+        Ok(p0.mul(scalar))
+    }
+
+    pub(crate) fn bn254_g1_msm_internal(
+        &self,
+        points: &[G1Affine],
+        scalars: &[Fr],
+    ) -> Result<G1Projective, HostError> {
+        self.msm_internal::<G1Config>(points, scalars, &ContractCostType::Bn254G1Msm, "bn254 G1")
+    }
+
+    pub(crate) fn bn254_pairing_internal(
+        &self,
+        vp1: &Vec<G1Affine>,
+        vp2: &Vec<G2Affine>,
+    ) -> Result<PairingOutput<Bn254>, HostError> {
+        self.charge_budget(ContractCostType::Bn254Pairing, Some(vp1.len() as u64))?;
+        if vp1.len() != vp2.len() || vp1.len() == 0 {
+            return Err(self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InvalidInput,
+                format!(
+                    "bn254 pairing: invalid input vector lengths ({}, {})",
+                    vp1.len(),
+                    vp2.len()
+                )
+                .as_str(),
+                &[],
+            ));
+        }
+        // This calls into `Bn<Config>::multi_miller_loop`. The panic analysis
+        // of `pairing_internal` carries over: the lengths are checked equal
+        // above (`zip_eq`), and pairs containing a point at infinity are
+        // filtered out before `xy().unwrap()` is reached.
+        let mlo = Bn254::multi_miller_loop(vp1, vp2);
+        // final_exponentiation returning None means the `mlo.0.is_zero()`
+        Bn254::final_exponentiation(mlo).ok_or_else(|| {
+            self.bn254_invalid_input(
+                "bn254 final_exponentiation has failed, most likely multi_miller_loop produced infinity",
+            )
+        })
+    }
+
+    pub(crate) fn bn254_check_pairing_output(
+        &self,
+        output: &PairingOutput<Bn254>,
+    ) -> Result<Bool, HostError> {
+        self.charge_budget(
+            ContractCostType::MemCmp,
+            Some(BN254_FP12_SERIALIZED_SIZE as u64),
+        )?;
+        match output.0.cmp(&Fq12::ONE) {
+            Ordering::Equal => Ok(true.into()),
+            _ => Ok(false.into()),
+        }
+    }
+}
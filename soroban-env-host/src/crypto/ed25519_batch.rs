@@ -0,0 +1,148 @@
+use crate::{
+    budget::AsBudget,
+    host::metered_clone::MeteredContainer,
+    host_object::HostVec,
+    xdr::{ContractCostType, ScBytes, ScErrorCode, ScErrorType},
+    BytesObject, Env, Host, HostError, TryFromVal, VecObject,
+};
+use core::mem::size_of;
+use ed25519_dalek::{Signature, VerifyingKey};
+
+// The signatures of a batch are verified one by one with `verify_strict`, like
+// `verify_sig_ed25519`, and charged one `VerifyEd25519Sig` each.
+//
+// `ed25519_dalek::verify_batch` checks a random linear combination of the
+// signatures' verification equations without multiplying by the cofactor, so
+// a small-order component of a signature's `R` or a key is cancelled whenever
+// its random coefficient is a multiple of the component's order (for order 2,
+// half the time). That would accept signatures `verify_strict` rejects, unless
+// every `R` and key is first checked to be torsion-free. Measured against
+// `verify_strict`, such a check costs 0.65x (variable-time) to 0.85x
+// (`is_torsion_free`) a verification per point, on top of 0.36x to 0.40x per
+// signature for the batch equation, so checking two points per signature is
+// more expensive than verifying each signature on its own.
+
+impl Host {
+    pub(crate) fn ed25519_batch_len(
+        &self,
+        public_keys: VecObject,
+        messages: VecObject,
+        signatures: VecObject,
+    ) -> Result<u32, HostError> {
+        let keys_len: u32 = self.vec_len(public_keys)?.into();
+        let msgs_len: u32 = self.vec_len(messages)?.into();
+        let sigs_len: u32 = self.vec_len(signatures)?.into();
+        if keys_len != msgs_len || keys_len != sigs_len || keys_len == 0 {
+            return Err(self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InvalidInput,
+                format!(
+                    "ed25519 batch verify: invalid input vector lengths ({keys_len}, {msgs_len}, {sigs_len})"
+                )
+                .as_str(),
+                &[],
+            ));
+        }
+        Ok(keys_len)
+    }
+
+    pub(crate) fn ed25519_pub_key_vec_from_vecobj(
+        &self,
+        vk: VecObject,
+    ) -> Result<Vec<VerifyingKey>, HostError> {
+        let len: u32 = self.vec_len(vk)?.into();
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(len as u64 * size_of::<VerifyingKey>() as u64),
+        )?;
+        let mut keys = Vec::with_capacity(len as usize);
+        self.visit_obj(vk, |vk: &HostVec| {
+            for k in vk.iter() {
+                keys.push(
+                    self.ed25519_pub_key_from_bytesobj_input(BytesObject::try_from_val(self, k)?)?,
+                );
+            }
+            Ok(())
+        })?;
+        Ok(keys)
+    }
+
+    pub(crate) fn ed25519_signature_vec_from_vecobj(
+        &self,
+        vs: VecObject,
+    ) -> Result<Vec<Signature>, HostError> {
+        let len: u32 = self.vec_len(vs)?.into();
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(len as u64 * size_of::<Signature>() as u64),
+        )?;
+        let mut sigs = Vec::with_capacity(len as usize);
+        self.visit_obj(vs, |vs: &HostVec| {
+            for s in vs.iter() {
+                sigs.push(self.ed25519_signature_from_bytesobj_input(
+                    "sig",
+                    BytesObject::try_from_val(self, s)?,
+                )?);
+            }
+            Ok(())
+        })?;
+        Ok(sigs)
+    }
+
+    pub(crate) fn bytes_vec_from_vecobj(&self, vb: VecObject) -> Result<Vec<Vec<u8>>, HostError> {
+        let len: u32 = self.vec_len(vb)?.into();
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(len as u64 * size_of::<Vec<u8>>() as u64),
+        )?;
+        let mut res = Vec::with_capacity(len as usize);
+        self.visit_obj(vb, |vb: &HostVec| {
+            for b in vb.iter() {
+                let bytes =
+                    self.visit_obj(BytesObject::try_from_val(self, b)?, |b: &ScBytes| {
+                        Vec::<u8>::charge_bulk_init_cpy(
+                            b.as_slice().len() as u64,
+                            self.as_budget(),
+                        )?;
+                        Ok(b.as_slice().to_vec())
+                    })?;
+                res.push(bytes);
+            }
+            Ok(())
+        })?;
+        Ok(res)
+    }
+
+    /// Verifies a batch of ed25519 signatures, returning `None` if all of
+    /// them are valid and the index of the first invalid one otherwise.
+    pub(crate) fn verify_sigs_ed25519_batch_internal(
+        &self,
+        payloads: &[Vec<u8>],
+        verifying_keys: &[VerifyingKey],
+        sigs: &[Signature],
+    ) -> Result<Option<u32>, HostError> {
+        let _span = tracy_span!("ed25519 batch verify");
+        let n = sigs.len();
+        if payloads.len() != n || verifying_keys.len() != n {
+            return Err(self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InvalidInput,
+                "ed25519 batch verify: invalid input lengths",
+                &[],
+            ));
+        }
+        for (i, ((payload, key), sig)) in payloads
+            .iter()
+            .zip(verifying_keys.iter())
+            .zip(sigs.iter())
+            .enumerate()
+        {
+            match self.verify_sig_ed25519_internal(payload, key, sig) {
+                Ok(()) => (),
+                Err(e) if e.error.is_type(ScErrorType::Crypto) => return Ok(Some(i as u32)),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+}
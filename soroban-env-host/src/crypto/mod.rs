@@ -6,18 +6,24 @@ use crate::{
     xdr::{ContractCostType, Hash, ScBytes, ScErrorCode, ScErrorType},
     BytesObject, Error, Host, HostError, U32Val, Val,
 };
+use blake2::{digest::consts::U32, Blake2b};
 use elliptic_curve::scalar::IsHigh;
 use hex_literal::hex;
 use hmac::{Hmac, Mac};
 use rand::RngCore;
 use rand_chacha::ChaCha20Rng;
-use sha2::Sha256;
+use ripemd::Ripemd160;
+use sha2::{Sha256, Sha512};
 use sha3::Keccak256;
 
 use ecdsa::{signature::hazmat::PrehashVerifier, PrimeCurve, Signature, SignatureSize};
 use elliptic_curve::CurveArithmetic;
 use generic_array::ArrayLength;
 pub(crate) mod bls12_381;
+pub(crate) mod bn254;
+pub(crate) mod ed25519_batch;
+pub(crate) mod poseidon;
+pub(crate) mod poseidon_params;
 
 impl Host {
     // Ed25519 functions
@@ -102,12 +108,10 @@ impl Host {
             })
     }
 
-    pub(crate) fn secp256r1_decode_sec1_uncompressed_pubkey(
-        &self,
-        bytes: &[u8],
-    ) -> Result<p256::ecdsa::VerifyingKey, HostError> {
+    // Checks that `bytes` starts with the tag of an uncompressed SEC-1 point.
+    // Only the tag is checked here, the rest is left to the curve's decoder.
+    fn sec1_check_uncompressed_tag(&self, bytes: &[u8]) -> Result<(), HostError> {
         use sec1::point::Tag;
-        self.charge_budget(ContractCostType::Sec1DecodePointUncompressed, None)?;
         // check and make sure the key was encoded in uncompressed format
         let tag = bytes
             .first()
@@ -130,7 +134,15 @@ impl Host {
                 &[],
             ));
         }
+        Ok(())
+    }
 
+    pub(crate) fn secp256r1_decode_sec1_uncompressed_pubkey(
+        &self,
+        bytes: &[u8],
+    ) -> Result<p256::ecdsa::VerifyingKey, HostError> {
+        self.charge_budget(ContractCostType::Sec1DecodePointUncompressed, None)?;
+        self.sec1_check_uncompressed_tag(bytes)?;
         p256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map_err(|_| {
             self.err(
                 ScErrorType::Crypto,
@@ -150,6 +162,39 @@ impl Host {
         })
     }
 
+    // There is no secp256r1 recovery cost type yet, so this is charged as
+    // verifications. Recovery decompresses `R`, computes the key with a
+    // double scalar multiplication like the one of a verification, and then
+    // verifies the signature against the key. The `EcdsaSecp256r1Recover`
+    // runner measures it at 2.16x a verification (2.0x to 2.3x from the 10th
+    // to the 90th percentile of random signatures), so it is charged as three.
+    pub(crate) fn recover_key_ecdsa_secp256r1_internal(
+        &self,
+        hash: &Hash,
+        sig: &Signature<p256::NistP256>,
+        rid: ecdsa::RecoveryId,
+    ) -> Result<ScBytes, HostError> {
+        let _span = tracy_span!("p256 recover");
+        self.as_budget()
+            .bulk_charge(ContractCostType::VerifyEcdsaSecp256r1Sig, 3, None)?;
+        let recovered_key =
+            p256::ecdsa::VerifyingKey::recover_from_prehash(hash.as_slice(), sig, rid).map_err(
+                |_| {
+                    self.err(
+                        ScErrorType::Crypto,
+                        ScErrorCode::InvalidInput,
+                        "ECDSA-secp256r1 signature recovery failed",
+                        &[],
+                    )
+                },
+            )?;
+        Ok(ScBytes::from(crate::xdr::BytesM::try_from(
+            recovered_key
+                .to_encoded_point(/*compress:*/ false)
+                .as_bytes(),
+        )?))
+    }
+
     // ECDSA functions
     pub(crate) fn ecdsa_signature_from_bytes<C>(
         &self,
@@ -196,30 +241,90 @@ impl Host {
     // ECDSA secp256k1 functions
 
     // NB: not metered as it's a trivial constant cost, just converting a byte to a byte,
-    // and always done exactly once as part of the secp256k1 recovery path.
-    pub(crate) fn secp256k1_recovery_id_from_u32val(
+    // and always done exactly once as part of the ECDSA recovery paths.
+    fn ecdsa_recovery_id_from_u32val(
         &self,
         recovery_id: U32Val,
-    ) -> Result<k256::ecdsa::RecoveryId, HostError> {
+        msg: &'static str,
+    ) -> Result<ecdsa::RecoveryId, HostError> {
         let rid32: u32 = u32::from(recovery_id);
-        if rid32 > k256::ecdsa::RecoveryId::MAX as u32 {
+        if rid32 > ecdsa::RecoveryId::MAX as u32 {
             return Err(self.err(
                 ScErrorType::Crypto,
                 ScErrorCode::InvalidInput,
-                "invalid ECDSA-secp256k1 recovery ID",
+                msg,
                 &[recovery_id.to_val()],
             ));
         }
-        k256::ecdsa::RecoveryId::try_from(rid32 as u8).map_err(|_| {
+        ecdsa::RecoveryId::try_from(rid32 as u8).map_err(|_| {
             self.err(
                 ScErrorType::Crypto,
                 ScErrorCode::InvalidInput,
-                "invalid ECDSA-secp256k1 recovery ID",
+                msg,
                 &[recovery_id.to_val()],
             )
         })
     }
 
+    pub(crate) fn secp256k1_recovery_id_from_u32val(
+        &self,
+        recovery_id: U32Val,
+    ) -> Result<k256::ecdsa::RecoveryId, HostError> {
+        self.ecdsa_recovery_id_from_u32val(recovery_id, "invalid ECDSA-secp256k1 recovery ID")
+    }
+
+    pub(crate) fn secp256r1_recovery_id_from_u32val(
+        &self,
+        recovery_id: U32Val,
+    ) -> Result<ecdsa::RecoveryId, HostError> {
+        self.ecdsa_recovery_id_from_u32val(recovery_id, "invalid ECDSA-secp256r1 recovery ID")
+    }
+
+    pub(crate) fn secp256k1_decode_sec1_uncompressed_pubkey(
+        &self,
+        bytes: &[u8],
+    ) -> Result<k256::ecdsa::VerifyingKey, HostError> {
+        self.charge_budget(ContractCostType::Sec1DecodePointUncompressed, None)?;
+        self.sec1_check_uncompressed_tag(bytes)?;
+        k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map_err(|_| {
+            self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InvalidInput,
+                "invalid ECDSA public key",
+                &[],
+            )
+        })
+    }
+
+    pub(crate) fn secp256k1_public_key_from_bytesobj_input(
+        &self,
+        k: BytesObject,
+    ) -> Result<k256::ecdsa::VerifyingKey, HostError> {
+        self.visit_obj(k, |bytes: &ScBytes| {
+            self.secp256k1_decode_sec1_uncompressed_pubkey(bytes.as_slice())
+        })
+    }
+
+    pub(crate) fn secp256k1_verify_signature(
+        &self,
+        verifying_key: &k256::ecdsa::VerifyingKey,
+        msg_hash: &Hash,
+        sig: &Signature<k256::Secp256k1>,
+    ) -> Result<(), HostError> {
+        let _span = tracy_span!("secp256k1 verify");
+        self.charge_budget(ContractCostType::VerifyEcdsaSecp256k1Sig, None)?;
+        verifying_key
+            .verify_prehash(msg_hash.as_slice(), sig)
+            .map_err(|_| {
+                self.err(
+                    ScErrorType::Crypto,
+                    ScErrorCode::InvalidInput,
+                    "failed secp256k1 verification",
+                    &[],
+                )
+            })
+    }
+
     pub(crate) fn recover_key_ecdsa_secp256k1_internal(
         &self,
         hash: &Hash,
@@ -302,6 +407,62 @@ impl Host {
             Ok(hash)
         })
     }
+
+    // SHA-512, BLAKE2b-256, BLAKE3 and RIPEMD-160 functions
+
+    pub(crate) fn sha512_hash_from_bytes_raw(&self, bytes: &[u8]) -> Result<[u8; 64], HostError> {
+        let _span = tracy_span!("sha512");
+        self.charge_budget(
+            ContractCostType::ComputeSha512Hash,
+            Some(bytes.len() as u64),
+        )?;
+        Ok(<Sha512 as sha2::Digest>::digest(bytes).into())
+    }
+
+    pub(crate) fn blake2b256_hash_from_bytes_raw(
+        &self,
+        bytes: &[u8],
+    ) -> Result<[u8; 32], HostError> {
+        let _span = tracy_span!("blake2b256");
+        self.charge_budget(
+            ContractCostType::ComputeBlake2b256Hash,
+            Some(bytes.len() as u64),
+        )?;
+        Ok(<Blake2b<U32> as blake2::Digest>::digest(bytes).into())
+    }
+
+    pub(crate) fn blake3_hash_from_bytes_raw(&self, bytes: &[u8]) -> Result<[u8; 32], HostError> {
+        let _span = tracy_span!("blake3");
+        self.charge_budget(
+            ContractCostType::ComputeBlake3Hash,
+            Some(bytes.len() as u64),
+        )?;
+        Ok(blake3::hash(bytes).into())
+    }
+
+    pub(crate) fn ripemd160_hash_from_bytes_raw(
+        &self,
+        bytes: &[u8],
+    ) -> Result<[u8; 20], HostError> {
+        let _span = tracy_span!("ripemd160");
+        self.charge_budget(
+            ContractCostType::ComputeRipemd160Hash,
+            Some(bytes.len() as u64),
+        )?;
+        Ok(<Ripemd160 as ripemd::Digest>::digest(bytes).into())
+    }
+
+    // Hashes the contents of `x` with `hash` and returns the result as a new
+    // `Vec`, charging for the `N`-byte output.
+    pub(crate) fn fixed_hash_from_bytesobj_input<const N: usize>(
+        &self,
+        x: BytesObject,
+        hash: impl FnOnce(&[u8]) -> Result<[u8; N], HostError>,
+    ) -> Result<Vec<u8>, HostError> {
+        Vec::<u8>::charge_bulk_init_cpy(N as u64, self.as_budget())?;
+        self.visit_obj(x, |bytes: &ScBytes| hash(bytes.as_slice()))
+            .map(|x| x.to_vec())
+    }
 }
 
 pub(crate) fn sha256_hash_from_bytes_raw(
@@ -0,0 +1,742 @@
+// Poseidon and Poseidon2 permutations over the scalar fields of BLS12-381 and
+// BN254, and a sponge hash over a few standard instances of them.
+//
+// The parameters of the permutations (round constants and linear layers) are
+// supplied by the caller rather than being baked into the host, so that any
+// instance can be used. The standard instances used by `poseidon_hash` (those
+// of circom and of the Poseidon2 reference implementation, see
+// `crypto::poseidon_params`) are built into the host instead, so that contracts
+// don't need to pass several kilobytes of constants around for every hash.
+//
+// There are no dedicated Poseidon `ContractCostType`s, so the permutations are
+// charged upfront as the equivalent number of scalar field additions,
+// multiplications and exponentiations, under the `Fr` cost types of the curve
+// the field belongs to (see `PoseidonScalar`).
+use crate::{
+    budget::AsBudget,
+    host_object::HostVec,
+    xdr::{ContractCostType, ScErrorCode, ScErrorType},
+    Env, EnvBase, Host, HostError, Symbol, SymbolStr, TryFromVal, U256Object, U256Small, U256Val,
+    Val, VecObject, U256,
+};
+use ark_ff::{BigInteger, PrimeField};
+
+pub(crate) const POSEIDON_MAX_STATE_SIZE: u32 = 24;
+pub(crate) const POSEIDON_SBOX_DEGREES: [u32; 4] = [3, 5, 7, 11];
+pub(crate) const POSEIDON2_STATE_SIZES: [u32; 8] = [2, 3, 4, 8, 12, 16, 20, 24];
+
+const POSEIDON_FIELD_ELEMENT_SIZE: u64 = 32;
+
+// The 4x4 MDS matrix used by the external rounds of Poseidon2.
+const POSEIDON2_M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+// The parameters of a Poseidon permutation with a state of size `t`. `mds` is
+// the `t`x`t` MDS matrix and `round_constants` holds `t` constants for each of
+// the `rounds_f + rounds_p` rounds, both in row-major order.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PoseidonParams<'a, F> {
+    pub(crate) t: usize,
+    pub(crate) d: u32,
+    pub(crate) rounds_f: u32,
+    pub(crate) rounds_p: u32,
+    pub(crate) mds: &'a [F],
+    pub(crate) round_constants: &'a [F],
+}
+
+// The parameters of a Poseidon2 permutation, with `round_constants` laid out as
+// in `PoseidonParams`. The external matrix is determined by `t`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Poseidon2Params<'a, F> {
+    pub(crate) t: usize,
+    pub(crate) d: u32,
+    pub(crate) rounds_f: u32,
+    pub(crate) rounds_p: u32,
+    pub(crate) mat_internal_diag_m_1: &'a [F],
+    pub(crate) round_constants: &'a [F],
+}
+
+// A named parameter set of `poseidon_hash`.
+#[derive(Clone, Copy, Debug)]
+enum PoseidonInstance<F: 'static> {
+    Poseidon(&'static PoseidonParams<'static, F>),
+    Poseidon2(&'static Poseidon2Params<'static, F>),
+}
+
+impl<F> PoseidonInstance<F> {
+    fn t(&self) -> usize {
+        match self {
+            PoseidonInstance::Poseidon(p) => p.t,
+            PoseidonInstance::Poseidon2(p) => p.t,
+        }
+    }
+}
+
+// The scalar fields Poseidon is supported over, along with the cost types its
+// field operations are charged as.
+pub(crate) trait PoseidonScalar: PrimeField {
+    const FROM_U256: ContractCostType;
+    const TO_U256: ContractCostType;
+    const ADD_SUB: ContractCostType;
+    const MUL: ContractCostType;
+    const POW: ContractCostType;
+}
+
+impl PoseidonScalar for ark_bls12_381::Fr {
+    const FROM_U256: ContractCostType = ContractCostType::Bls12381FrFromU256;
+    const TO_U256: ContractCostType = ContractCostType::Bls12381FrToU256;
+    const ADD_SUB: ContractCostType = ContractCostType::Bls12381FrAddSub;
+    const MUL: ContractCostType = ContractCostType::Bls12381FrMul;
+    const POW: ContractCostType = ContractCostType::Bls12381FrPow;
+}
+
+impl PoseidonScalar for ark_bn254::Fr {
+    const FROM_U256: ContractCostType = ContractCostType::Bn254FrFromU256;
+    const TO_U256: ContractCostType = ContractCostType::Bn254FrToU256;
+    const ADD_SUB: ContractCostType = ContractCostType::Bn254FrAddSub;
+    const MUL: ContractCostType = ContractCostType::Bn254FrMul;
+    const POW: ContractCostType = ContractCostType::Bn254FrPow;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PoseidonField {
+    Bls12381,
+    Bn254,
+}
+
+impl Host {
+    fn poseidon_invalid_input(&self, msg: &str, args: &[Val]) -> HostError {
+        self.err(ScErrorType::Crypto, ScErrorCode::InvalidInput, msg, args)
+    }
+
+    pub(crate) fn poseidon_field_from_symbol(
+        &self,
+        field: Symbol,
+    ) -> Result<PoseidonField, HostError> {
+        let name = SymbolStr::try_from_val(self, &field)?;
+        match AsRef::<str>::as_ref(&name) {
+            "BLS12_381" => Ok(PoseidonField::Bls12381),
+            "BN254" => Ok(PoseidonField::Bn254),
+            _ => Err(self.poseidon_invalid_input("unsupported poseidon field", &[field.to_val()])),
+        }
+    }
+
+    // Unlike `fr_from_u256val`, field elements are not reduced: any value that
+    // is not less than the field modulus is rejected, so that distinct inputs
+    // can never be mapped to the same hash.
+    pub(crate) fn poseidon_field_element_from_val<F: PoseidonScalar>(
+        &self,
+        v: Val,
+    ) -> Result<F, HostError> {
+        self.charge_budget(F::FROM_U256, None)?;
+        let sv = U256Val::try_from_val(self, &v)?;
+        let bytes = if let Ok(small) = U256Small::try_from(sv) {
+            U256::from(u64::from(small)).to_be_bytes()
+        } else {
+            let obj: U256Object = sv.try_into()?;
+            self.visit_obj(obj, |u: &U256| Ok(u.to_be_bytes()))?
+        };
+        let fe = F::from_be_bytes_mod_order(&bytes);
+        // The round trip below is what rejects non-canonical encodings.
+        self.charge_budget(F::TO_U256, None)?;
+        if fe.into_bigint().to_bytes_be().as_slice() != bytes.as_slice() {
+            return Err(self.poseidon_invalid_input(
+                "poseidon field element is not less than the field modulus",
+                &[v],
+            ));
+        }
+        Ok(fe)
+    }
+
+    pub(crate) fn poseidon_field_element_to_u256val<F: PoseidonScalar>(
+        &self,
+        fe: &F,
+    ) -> Result<U256Val, HostError> {
+        self.charge_budget(F::TO_U256, None)?;
+        let mut bytes = [0u8; 32];
+        let be = fe.into_bigint().to_bytes_be();
+        // Both supported scalar fields fit in 4 limbs, i.e. exactly 32 bytes.
+        if be.len() != bytes.len() {
+            return Err(self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InternalError,
+                "unexpected poseidon field element size",
+                &[],
+            ));
+        }
+        bytes.copy_from_slice(&be);
+        Ok(U256Val::try_from_val(self, &U256::from_be_bytes(bytes))?)
+    }
+
+    // Decodes the `expected_len` field elements of `vo` and appends them to
+    // `out`, which the caller has already charged for.
+    fn poseidon_field_elements_from_vecobj<F: PoseidonScalar>(
+        &self,
+        vo: VecObject,
+        expected_len: u32,
+        name: &str,
+        out: &mut Vec<F>,
+    ) -> Result<(), HostError> {
+        let len: u32 = self.vec_len(vo)?.into();
+        if len != expected_len {
+            return Err(self.poseidon_invalid_input(
+                &format!("poseidon {name} has unexpected length"),
+                &[len.into(), expected_len.into()],
+            ));
+        }
+        self.visit_obj(vo, |hv: &HostVec| {
+            for v in hv.iter() {
+                out.push(self.poseidon_field_element_from_val(*v)?);
+            }
+            Ok(())
+        })
+    }
+
+    pub(crate) fn poseidon_field_vec_from_vecobj<F: PoseidonScalar>(
+        &self,
+        vo: VecObject,
+        expected_len: u32,
+        name: &str,
+    ) -> Result<Vec<F>, HostError> {
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(expected_len as u64 * POSEIDON_FIELD_ELEMENT_SIZE),
+        )?;
+        let mut res: Vec<F> = Vec::with_capacity(expected_len as usize);
+        self.poseidon_field_elements_from_vecobj(vo, expected_len, name, &mut res)?;
+        Ok(res)
+    }
+
+    pub(crate) fn poseidon_field_matrix_from_vecobj<F: PoseidonScalar>(
+        &self,
+        vo: VecObject,
+        rows: u32,
+        cols: u32,
+        name: &str,
+    ) -> Result<Vec<F>, HostError> {
+        let len: u32 = self.vec_len(vo)?.into();
+        if len != rows {
+            return Err(self.poseidon_invalid_input(
+                &format!("poseidon {name} has unexpected number of rows"),
+                &[len.into(), rows.into()],
+            ));
+        }
+        // `rows` and `cols` are bounded by the sizes of host vectors, which
+        // are much smaller than `u32::MAX`, so the product can't overflow.
+        let elements = rows as u64 * cols as u64;
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(elements.saturating_mul(POSEIDON_FIELD_ELEMENT_SIZE)),
+        )?;
+        let mut res: Vec<F> = Vec::with_capacity(elements as usize);
+        self.visit_obj(vo, |hv: &HostVec| {
+            for row in hv.iter() {
+                let row = VecObject::try_from_val(self, row)?;
+                self.poseidon_field_elements_from_vecobj(row, cols, name, &mut res)?;
+            }
+            Ok(())
+        })?;
+        Ok(res)
+    }
+
+    pub(crate) fn poseidon_field_vec_to_vecobj<F: PoseidonScalar>(
+        &self,
+        state: &[F],
+    ) -> Result<VecObject, HostError> {
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(state.len() as u64 * POSEIDON_FIELD_ELEMENT_SIZE),
+        )?;
+        let mut vals: Vec<Val> = Vec::with_capacity(state.len());
+        for fe in state {
+            vals.push(self.poseidon_field_element_to_u256val(fe)?.to_val());
+        }
+        self.vec_new_from_slice(&vals)
+    }
+
+    fn poseidon_check_common_params(
+        &self,
+        t: u32,
+        d: u32,
+        rounds_f: u32,
+        rounds_p: u32,
+    ) -> Result<(), HostError> {
+        if !(2..=POSEIDON_MAX_STATE_SIZE).contains(&t) {
+            return Err(self.poseidon_invalid_input("unsupported poseidon state size", &[t.into()]));
+        }
+        if !POSEIDON_SBOX_DEGREES.contains(&d) {
+            return Err(
+                self.poseidon_invalid_input("unsupported poseidon s-box degree", &[d.into()])
+            );
+        }
+        if rounds_f == 0 || rounds_f % 2 != 0 {
+            return Err(self.poseidon_invalid_input(
+                "poseidon number of full rounds must be even and non-zero",
+                &[rounds_f.into()],
+            ));
+        }
+        // The constants for all the rounds are supplied by the caller, so the
+        // number of rounds is bounded by the size of the input vectors. This
+        // only guards against overflow.
+        if rounds_f.checked_add(rounds_p).is_none() {
+            return Err(self.poseidon_invalid_input(
+                "poseidon number of rounds is too large",
+                &[rounds_f.into(), rounds_p.into()],
+            ));
+        }
+        Ok(())
+    }
+
+    fn poseidon_check_round_constants<F>(
+        &self,
+        t: usize,
+        rounds: usize,
+        round_constants: &[F],
+    ) -> Result<(), HostError> {
+        if Some(round_constants.len()) != rounds.checked_mul(t) {
+            return Err(
+                self.poseidon_invalid_input("poseidon round constants have unexpected shape", &[])
+            );
+        }
+        Ok(())
+    }
+
+    // Charges for `sboxes` s-box applications, `muls` multiplications and
+    // `adds` additions in the scalar field.
+    fn poseidon_charge_field_ops<F: PoseidonScalar>(
+        &self,
+        sboxes: u64,
+        muls: u64,
+        adds: u64,
+        d: u32,
+    ) -> Result<(), HostError> {
+        self.as_budget().bulk_charge(
+            F::POW,
+            sboxes,
+            Some(64 - (d as u64).leading_zeros() as u64),
+        )?;
+        self.as_budget().bulk_charge(F::MUL, muls, None)?;
+        self.as_budget().bulk_charge(F::ADD_SUB, adds, None)
+    }
+
+    // The Poseidon permutation (https://eprint.iacr.org/2019/458), as in the
+    // reference implementation: every round adds `t` round constants, applies
+    // the s-box to the whole state (full rounds) or to its first element
+    // (partial rounds), and multiplies the state by the MDS matrix.
+    pub(crate) fn poseidon_permutation_internal<F: PoseidonScalar>(
+        &self,
+        input: &[F],
+        params: &PoseidonParams<F>,
+    ) -> Result<Vec<F>, HostError> {
+        let PoseidonParams {
+            t,
+            d,
+            rounds_f,
+            rounds_p,
+            mds,
+            round_constants,
+        } = *params;
+        if input.len() != t {
+            return Err(self.poseidon_invalid_input(
+                "poseidon input has unexpected length",
+                &[(input.len() as u32).into(), (t as u32).into()],
+            ));
+        }
+        self.poseidon_check_common_params(t as u32, d, rounds_f, rounds_p)?;
+        if mds.len() != t * t {
+            return Err(
+                self.poseidon_invalid_input("poseidon mds matrix has unexpected shape", &[])
+            );
+        }
+        let rounds = (rounds_f + rounds_p) as usize;
+        self.poseidon_check_round_constants(t, rounds, round_constants)?;
+
+        let t64 = t as u64;
+        let (rounds_f, rounds_p) = (rounds_f as u64, rounds_p as u64);
+        let rounds64 = rounds_f + rounds_p;
+        self.poseidon_charge_field_ops::<F>(
+            rounds_f.saturating_mul(t64).saturating_add(rounds_p),
+            rounds64.saturating_mul(t64 * t64),
+            rounds64.saturating_mul(t64 * t64),
+            d,
+        )?;
+
+        let half_f = (rounds_f / 2) as usize;
+        let exp = [d as u64];
+        let mut state = input.to_vec();
+        for (r, rc) in round_constants.chunks_exact(t).enumerate() {
+            for (s, c) in state.iter_mut().zip(rc.iter()) {
+                *s += c;
+            }
+            if r < half_f || r >= rounds - half_f {
+                for s in state.iter_mut() {
+                    *s = s.pow(exp);
+                }
+            } else {
+                state[0] = state[0].pow(exp);
+            }
+            state = mds
+                .chunks_exact(t)
+                .map(|row| row.iter().zip(state.iter()).map(|(m, s)| *m * s).sum())
+                .collect();
+        }
+        Ok(state)
+    }
+
+    // Multiplies the state by the external matrix of Poseidon2, which is fully
+    // determined by the state size.
+    fn poseidon2_external_matmul<F: PrimeField>(state: &mut [F]) {
+        let t = state.len();
+        if t < 4 {
+            // circ(2, 1) and circ(2, 1, 1)
+            let sum: F = state.iter().sum();
+            for s in state.iter_mut() {
+                *s += sum;
+            }
+            return;
+        }
+        // circ(2 * M4, M4, ..., M4), i.e. M4 applied to every chunk of four
+        // elements, plus the sum of all the chunks.
+        for chunk in state.chunks_exact_mut(4) {
+            let x = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            for (out, row) in chunk.iter_mut().zip(POSEIDON2_M4.iter()) {
+                *out = row.iter().zip(x.iter()).map(|(m, x)| F::from(*m) * x).sum();
+            }
+        }
+        if t > 4 {
+            let mut sums = [F::zero(); 4];
+            for chunk in state.chunks_exact(4) {
+                for (sum, s) in sums.iter_mut().zip(chunk.iter()) {
+                    *sum += s;
+                }
+            }
+            for (i, s) in state.iter_mut().enumerate() {
+                *s += sums[i % 4];
+            }
+        }
+    }
+
+    // The Poseidon2 permutation (https://eprint.iacr.org/2023/323), as in the
+    // reference implementation. The internal matrix is `1 + diag(mat_internal_diag_m_1)`
+    // (where `1` is the all-ones matrix). The partial rounds only use the first
+    // round constant of their row, the remaining ones are ignored.
+    pub(crate) fn poseidon2_permutation_internal<F: PoseidonScalar>(
+        &self,
+        input: &[F],
+        params: &Poseidon2Params<F>,
+    ) -> Result<Vec<F>, HostError> {
+        let Poseidon2Params {
+            t,
+            d,
+            rounds_f,
+            rounds_p,
+            mat_internal_diag_m_1,
+            round_constants,
+        } = *params;
+        if input.len() != t {
+            return Err(self.poseidon_invalid_input(
+                "poseidon input has unexpected length",
+                &[(input.len() as u32).into(), (t as u32).into()],
+            ));
+        }
+        self.poseidon_check_common_params(t as u32, d, rounds_f, rounds_p)?;
+        if !POSEIDON2_STATE_SIZES.contains(&(t as u32)) {
+            return Err(self
+                .poseidon_invalid_input("unsupported poseidon2 state size", &[(t as u32).into()]));
+        }
+        if mat_internal_diag_m_1.len() != t {
+            return Err(self.poseidon_invalid_input(
+                "poseidon2 internal matrix diagonal has unexpected length",
+                &[],
+            ));
+        }
+        let rounds = (rounds_f + rounds_p) as usize;
+        self.poseidon_check_round_constants(t, rounds, round_constants)?;
+
+        // An external matrix multiplication costs at most `4t` multiplications
+        // (by small constants) and `6t` additions, an internal one `t`
+        // multiplications and `2t` additions.
+        let t64 = t as u64;
+        let (rounds_f, rounds_p) = (rounds_f as u64, rounds_p as u64);
+        let external = rounds_f.saturating_add(1);
+        self.poseidon_charge_field_ops::<F>(
+            rounds_f.saturating_mul(t64).saturating_add(rounds_p),
+            external
+                .saturating_mul(4 * t64)
+                .saturating_add(rounds_p.saturating_mul(t64)),
+            external
+                .saturating_mul(6 * t64)
+                .saturating_add(rounds_f.saturating_mul(t64))
+                .saturating_add(rounds_p.saturating_mul(2 * t64 + 1)),
+            d,
+        )?;
+
+        let half_f = (rounds_f / 2) as usize;
+        let exp = [d as u64];
+        let mut state = input.to_vec();
+        Self::poseidon2_external_matmul(&mut state);
+        for (r, rc) in round_constants.chunks_exact(t).enumerate() {
+            if r < half_f || r >= rounds - half_f {
+                for (s, c) in state.iter_mut().zip(rc.iter()) {
+                    *s = (*s + c).pow(exp);
+                }
+                Self::poseidon2_external_matmul(&mut state);
+            } else {
+                state[0] = (state[0] + rc[0]).pow(exp);
+                let sum: F = state.iter().sum();
+                for (s, m) in state.iter_mut().zip(mat_internal_diag_m_1.iter()) {
+                    *s = *s * m + sum;
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    // The number of partial rounds of a permutation with `rows` rows of round
+    // constants, of which `rounds_f` are for the full rounds.
+    fn poseidon_partial_rounds(&self, rounds_f: u32, rows: u32) -> Result<u32, HostError> {
+        rows.checked_sub(rounds_f).ok_or_else(|| {
+            self.poseidon_invalid_input(
+                "poseidon round constants have fewer rows than full rounds",
+                &[rows.into(), rounds_f.into()],
+            )
+        })
+    }
+
+    // Decodes the host function arguments in field `F` and runs the Poseidon
+    // permutation.
+    fn poseidon_permutation_in_field<F: PoseidonScalar>(
+        &self,
+        input: VecObject,
+        d: u32,
+        rounds_f: u32,
+        mds: VecObject,
+        round_constants: VecObject,
+    ) -> Result<VecObject, HostError> {
+        let t: u32 = self.vec_len(input)?.into();
+        let rows: u32 = self.vec_len(round_constants)?.into();
+        let rounds_p = self.poseidon_partial_rounds(rounds_f, rows)?;
+        self.poseidon_check_common_params(t, d, rounds_f, rounds_p)?;
+        let input = self.poseidon_field_vec_from_vecobj::<F>(input, t, "input")?;
+        let mds = self.poseidon_field_matrix_from_vecobj::<F>(mds, t, t, "mds matrix")?;
+        let round_constants = self.poseidon_field_matrix_from_vecobj::<F>(
+            round_constants,
+            rows,
+            t,
+            "round constants",
+        )?;
+        let params = PoseidonParams {
+            t: t as usize,
+            d,
+            rounds_f,
+            rounds_p,
+            mds: &mds,
+            round_constants: &round_constants,
+        };
+        let res = self.poseidon_permutation_internal(&input, &params)?;
+        self.poseidon_field_vec_to_vecobj(&res)
+    }
+
+    fn poseidon2_permutation_in_field<F: PoseidonScalar>(
+        &self,
+        input: VecObject,
+        d: u32,
+        rounds_f: u32,
+        mat_internal_diag_m_1: VecObject,
+        round_constants: VecObject,
+    ) -> Result<VecObject, HostError> {
+        let t: u32 = self.vec_len(input)?.into();
+        let rows: u32 = self.vec_len(round_constants)?.into();
+        let rounds_p = self.poseidon_partial_rounds(rounds_f, rows)?;
+        self.poseidon_check_common_params(t, d, rounds_f, rounds_p)?;
+        let input = self.poseidon_field_vec_from_vecobj::<F>(input, t, "input")?;
+        let diag = self.poseidon_field_vec_from_vecobj::<F>(
+            mat_internal_diag_m_1,
+            t,
+            "internal matrix diagonal",
+        )?;
+        let round_constants = self.poseidon_field_matrix_from_vecobj::<F>(
+            round_constants,
+            rows,
+            t,
+            "round constants",
+        )?;
+        let params = Poseidon2Params {
+            t: t as usize,
+            d,
+            rounds_f,
+            rounds_p,
+            mat_internal_diag_m_1: &diag,
+            round_constants: &round_constants,
+        };
+        let res = self.poseidon2_permutation_internal(&input, &params)?;
+        self.poseidon_field_vec_to_vecobj(&res)
+    }
+
+    pub(crate) fn poseidon_permutation_impl(
+        &self,
+        input: VecObject,
+        field: Symbol,
+        d: u32,
+        rounds_f: u32,
+        mds: VecObject,
+        round_constants: VecObject,
+    ) -> Result<VecObject, HostError> {
+        match self.poseidon_field_from_symbol(field)? {
+            PoseidonField::Bls12381 => self.poseidon_permutation_in_field::<ark_bls12_381::Fr>(
+                input,
+                d,
+                rounds_f,
+                mds,
+                round_constants,
+            ),
+            PoseidonField::Bn254 => self.poseidon_permutation_in_field::<ark_bn254::Fr>(
+                input,
+                d,
+                rounds_f,
+                mds,
+                round_constants,
+            ),
+        }
+    }
+
+    pub(crate) fn poseidon2_permutation_impl(
+        &self,
+        input: VecObject,
+        field: Symbol,
+        d: u32,
+        rounds_f: u32,
+        mat_internal_diag_m_1: VecObject,
+        round_constants: VecObject,
+    ) -> Result<VecObject, HostError> {
+        match self.poseidon_field_from_symbol(field)? {
+            PoseidonField::Bls12381 => self.poseidon2_permutation_in_field::<ark_bls12_381::Fr>(
+                input,
+                d,
+                rounds_f,
+                mat_internal_diag_m_1,
+                round_constants,
+            ),
+            PoseidonField::Bn254 => self.poseidon2_permutation_in_field::<ark_bn254::Fr>(
+                input,
+                d,
+                rounds_f,
+                mat_internal_diag_m_1,
+                round_constants,
+            ),
+        }
+    }
+
+    // A sponge over the permutation of `instance`, with a rate of `t - 1` and
+    // the capacity element first: starting from the all-zero state, every
+    // chunk of `t - 1` inputs is added to the last `t - 1` elements of the
+    // state, followed by a permutation. The hash is the first element of the
+    // final state. There is no padding, so the number of inputs must be a
+    // non-zero multiple of `t - 1`; for exactly `t - 1` inputs this is circom's
+    // `Poseidon(t - 1)`.
+    fn poseidon_hash_in_field<F: PoseidonScalar>(
+        &self,
+        inputs: VecObject,
+        instance: PoseidonInstance<F>,
+    ) -> Result<U256Val, HostError> {
+        let t = instance.t();
+        let rate = t - 1;
+        let len: u32 = self.vec_len(inputs)?.into();
+        if len == 0 || len as usize % rate != 0 {
+            return Err(self.poseidon_invalid_input(
+                "poseidon hash input length must be a non-zero multiple of t - 1",
+                &[len.into(), (rate as u32).into()],
+            ));
+        }
+        let inputs = self.poseidon_field_vec_from_vecobj::<F>(inputs, len, "hash input")?;
+        self.charge_budget(
+            ContractCostType::MemAlloc,
+            Some(t as u64 * POSEIDON_FIELD_ELEMENT_SIZE),
+        )?;
+        let mut state = vec![F::zero(); t];
+        for chunk in inputs.chunks_exact(rate) {
+            self.as_budget()
+                .bulk_charge(F::ADD_SUB, rate as u64, None)?;
+            for (s, x) in state[1..].iter_mut().zip(chunk.iter()) {
+                *s += x;
+            }
+            state = match instance {
+                PoseidonInstance::Poseidon(params) => {
+                    self.poseidon_permutation_internal(&state, params)?
+                }
+                PoseidonInstance::Poseidon2(params) => {
+                    self.poseidon2_permutation_internal(&state, params)?
+                }
+            };
+        }
+        self.poseidon_field_element_to_u256val(&state[0])
+    }
+
+    pub(crate) fn poseidon_hash_impl(
+        &self,
+        inputs: VecObject,
+        params: Symbol,
+    ) -> Result<U256Val, HostError> {
+        use super::poseidon_params::*;
+        use PoseidonInstance::{Poseidon, Poseidon2};
+        let name = SymbolStr::try_from_val(self, &params)?;
+        match AsRef::<str>::as_ref(&name) {
+            "CIRCOM_BN254_T2" => self.poseidon_hash_in_field(inputs, Poseidon(&CIRCOM_BN254_T2)),
+            "CIRCOM_BN254_T3" => self.poseidon_hash_in_field(inputs, Poseidon(&CIRCOM_BN254_T3)),
+            "CIRCOM_BN254_T4" => self.poseidon_hash_in_field(inputs, Poseidon(&CIRCOM_BN254_T4)),
+            "CIRCOM_BN254_T5" => self.poseidon_hash_in_field(inputs, Poseidon(&CIRCOM_BN254_T5)),
+            "CIRCOM_BN254_T6" => self.poseidon_hash_in_field(inputs, Poseidon(&CIRCOM_BN254_T6)),
+            "POSEIDON2_BN254_T3" => {
+                self.poseidon_hash_in_field(inputs, Poseidon2(&POSEIDON2_BN254_T3))
+            }
+            "POSEIDON2_BLS12_381_T3" => {
+                self.poseidon_hash_in_field(inputs, Poseidon2(&POSEIDON2_BLS12_381_T3))
+            }
+            _ => Err(self
+                .poseidon_invalid_input("unsupported poseidon parameter set", &[params.to_val()])),
+        }
+    }
+}
+
+// Single field operations, charged the way the permutations charge for them,
+// so that the cost runners can measure the models the permutations rely on.
+#[cfg(feature = "bench")]
+impl Host {
+    pub(crate) fn poseidon_fr_add_internal<F: PoseidonScalar>(
+        &self,
+        lhs: &mut F,
+        rhs: &F,
+    ) -> Result<(), HostError> {
+        self.charge_budget(F::ADD_SUB, None)?;
+        *lhs += rhs;
+        Ok(())
+    }
+
+    pub(crate) fn poseidon_fr_sub_internal<F: PoseidonScalar>(
+        &self,
+        lhs: &mut F,
+        rhs: &F,
+    ) -> Result<(), HostError> {
+        self.charge_budget(F::ADD_SUB, None)?;
+        *lhs -= rhs;
+        Ok(())
+    }
+
+    pub(crate) fn poseidon_fr_mul_internal<F: PoseidonScalar>(
+        &self,
+        lhs: &mut F,
+        rhs: &F,
+    ) -> Result<(), HostError> {
+        self.charge_budget(F::MUL, None)?;
+        *lhs *= rhs;
+        Ok(())
+    }
+
+    pub(crate) fn poseidon_fr_pow_internal<F: PoseidonScalar>(
+        &self,
+        lhs: &F,
+        rhs: &u64,
+    ) -> Result<F, HostError> {
+        self.charge_budget(F::POW, Some(64 - rhs.leading_zeros() as u64))?;
+        Ok(lhs.pow([*rhs]))
+    }
+}
@@ -336,6 +336,45 @@ pub fn invoke_host_function_with_trace_hook<T: AsRef<[u8]>, I: ExactSizeIterator
     base_prng_seed: T,
     diagnostic_events: &mut Vec<DiagnosticEvent>,
     trace_hook: Option<TraceHook>,
+) -> Result<InvokeHostFunctionResult, HostError> {
+    invoke_host_function_with_trace_hook_and_module_cache(
+        budget,
+        enable_diagnostics,
+        encoded_host_fn,
+        encoded_resources,
+        encoded_source_account,
+        encoded_auth_entries,
+        ledger_info,
+        encoded_ledger_entries,
+        encoded_ttl_entries,
+        base_prng_seed,
+        diagnostic_events,
+        trace_hook,
+        None,
+    )
+}
+
+/// Same as `invoke_host_function_with_trace_hook` but also allows to pass a
+/// `SharedModuleCache` to take the parsed contracts from. The resources
+/// consumed are the same as without it.
+#[allow(clippy::too_many_arguments)]
+pub fn invoke_host_function_with_trace_hook_and_module_cache<
+    T: AsRef<[u8]>,
+    I: ExactSizeIterator<Item = T>,
+>(
+    budget: &Budget,
+    enable_diagnostics: bool,
+    encoded_host_fn: T,
+    encoded_resources: T,
+    encoded_source_account: T,
+    encoded_auth_entries: I,
+    ledger_info: LedgerInfo,
+    encoded_ledger_entries: I,
+    encoded_ttl_entries: I,
+    base_prng_seed: T,
+    diagnostic_events: &mut Vec<DiagnosticEvent>,
+    trace_hook: Option<TraceHook>,
+    module_cache: Option<crate::vm::SharedModuleCache>,
 ) -> Result<InvokeHostFunctionResult, HostError> {
     let _span0 = tracy_span!("invoke_host_function");
 
@@ -354,6 +393,9 @@ pub fn invoke_host_function_with_trace_hook<T: AsRef<[u8]>, I: ExactSizeIterator
 
     let storage = Storage::with_enforcing_footprint_and_map(footprint, storage_map);
     let host = Host::with_storage_and_budget(storage, budget.clone());
+    if let Some(module_cache) = module_cache {
+        host.set_shared_module_cache(module_cache)?;
+    }
     let have_trace_hook = trace_hook.is_some();
     if let Some(th) = trace_hook {
         host.set_trace_hook(Some(th))?;
@@ -537,9 +579,43 @@ pub fn invoke_host_function_in_recording_mode(
     ledger_snapshot: Rc<dyn SnapshotSource>,
     base_prng_seed: [u8; 32],
     diagnostic_events: &mut Vec<DiagnosticEvent>,
+) -> Result<InvokeHostFunctionRecordingModeResult, HostError> {
+    invoke_host_function_in_recording_mode_with_module_cache(
+        budget,
+        enable_diagnostics,
+        host_fn,
+        source_account,
+        auth_entries,
+        ledger_info,
+        ledger_snapshot,
+        base_prng_seed,
+        diagnostic_events,
+        None,
+    )
+}
+
+/// Same as `invoke_host_function_in_recording_mode` but also allows to pass a
+/// `SharedModuleCache` to take the parsed contracts from. The recorded
+/// resources are the same as without it.
+#[cfg(any(test, feature = "recording_mode"))]
+#[allow(clippy::too_many_arguments)]
+pub fn invoke_host_function_in_recording_mode_with_module_cache(
+    budget: &Budget,
+    enable_diagnostics: bool,
+    host_fn: &HostFunction,
+    source_account: &AccountId,
+    auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+    ledger_info: LedgerInfo,
+    ledger_snapshot: Rc<dyn SnapshotSource>,
+    base_prng_seed: [u8; 32],
+    diagnostic_events: &mut Vec<DiagnosticEvent>,
+    module_cache: Option<crate::vm::SharedModuleCache>,
 ) -> Result<InvokeHostFunctionRecordingModeResult, HostError> {
     let storage = Storage::with_recording_footprint(ledger_snapshot.clone());
     let host = Host::with_storage_and_budget(storage, budget.clone());
+    if let Some(module_cache) = module_cache {
+        host.set_shared_module_cache(module_cache)?;
+    }
     let is_recording_auth = auth_entries.is_none();
     let ledger_seq = ledger_info.sequence_number;
     let host_function = host.xdr_roundtrip(host_fn)?;
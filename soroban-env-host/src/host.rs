@@ -6,11 +6,13 @@ use crate::{
     budget::{AsBudget, Budget},
     events::{diagnostic::DiagnosticLevel, Events, InternalEventsBuffer},
     host_object::{HostMap, HostObject, HostVec},
-    impl_bignum_host_fns, impl_bignum_host_fns_rhs_u32, impl_bls12_381_fr_arith_host_fns,
-    impl_wrapping_obj_from_num, impl_wrapping_obj_to_num,
+    impl_bignum_host_fns, impl_bignum_host_fns_checked, impl_bignum_host_fns_rhs_u32,
+    impl_bignum_host_fns_rhs_u32_checked, impl_bignum_host_fns_rhs_u32_saturating,
+    impl_bignum_host_fns_saturating, impl_bls12_381_fr_arith_host_fns, impl_wrapping_obj_from_num,
+    impl_wrapping_obj_to_num,
     num::*,
     storage::Storage,
-    vm::ModuleCache,
+    vm::{ModuleCache, SharedModule, SharedModuleCache, VersionedContractCodeCostInputs},
     xdr::{
         int128_helpers, AccountId, Asset, ContractCostType, ContractEventType, ContractExecutable,
         ContractIdPreimage, ContractIdPreimageFromAddress, CreateContractArgsV2, Duration, Hash,
@@ -39,12 +41,13 @@ pub(crate) mod metered_map;
 pub(crate) mod metered_vector;
 pub(crate) mod metered_xdr;
 mod num;
+mod persistent_seq;
 pub(crate) mod prng;
+mod string_helper;
 pub(crate) mod trace;
 mod validity;
 
 pub use error::HostError;
-use frame::CallParams;
 pub use prng::{Seed, SEED_BYTES};
 pub use trace::{TraceEvent, TraceHook, TraceRecord, TraceState};
 
@@ -63,6 +66,8 @@ pub(crate) use frame::Frame;
 #[cfg(any(test, feature = "recording_mode"))]
 use rand_chacha::ChaCha20Rng;
 
+#[cfg(any(test, feature = "testutils"))]
+use crate::vm::VmMemorySnapshot;
 #[cfg(any(test, feature = "testutils"))]
 use invocation_metering::InvocationMeter;
 
@@ -92,6 +97,7 @@ pub(crate) const MIN_LEDGER_PROTOCOL_VERSION: u32 = 22;
 #[derive(Clone, Default)]
 struct HostImpl {
     module_cache: RefCell<Option<ModuleCache>>,
+    shared_module_cache: RefCell<Option<SharedModuleCache>>,
     shared_linker: RefCell<Option<wasmi::Linker<Host>>>,
     source_account: RefCell<Option<AccountId>>,
     ledger: RefCell<Option<LedgerInfo>>,
@@ -174,6 +180,14 @@ struct HostImpl {
 
     #[cfg(any(test, feature = "testutils"))]
     pub(crate) invocation_meter: RefCell<InvocationMeter>,
+
+    // Opt-in debugging aid: a copy of the memory of the first contract VM
+    // that failed during the last top-level invocation. See
+    // `Host::enable_failed_vm_memory_capture`.
+    #[cfg(any(test, feature = "testutils"))]
+    capture_failed_vm_memory: RefCell<bool>,
+    #[cfg(any(test, feature = "testutils"))]
+    failed_vm_memory: RefCell<Option<VmMemorySnapshot>>,
 }
 
 // Host is a newtype on Rc<HostImpl> so we can impl Env for it below.
@@ -217,6 +231,12 @@ impl_checked_borrow_helpers!(
     try_borrow_module_cache,
     try_borrow_module_cache_mut
 );
+impl_checked_borrow_helpers!(
+    shared_module_cache,
+    Option<SharedModuleCache>,
+    try_borrow_shared_module_cache,
+    try_borrow_shared_module_cache_mut
+);
 impl_checked_borrow_helpers!(
     shared_linker,
     Option<wasmi::Linker<Host>>,
@@ -339,6 +359,22 @@ impl_checked_borrow_helpers!(
     try_borrow_need_to_build_module_cache_mut
 );
 
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    capture_failed_vm_memory,
+    bool,
+    try_borrow_capture_failed_vm_memory,
+    try_borrow_capture_failed_vm_memory_mut
+);
+
+#[cfg(any(test, feature = "testutils"))]
+impl_checked_borrow_helpers!(
+    failed_vm_memory,
+    Option<VmMemorySnapshot>,
+    try_borrow_failed_vm_memory,
+    try_borrow_failed_vm_memory_mut
+);
+
 impl Debug for HostImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "HostImpl(...)")
@@ -360,6 +396,7 @@ impl Host {
         let _client = tracy_client::Client::start();
         Self(Rc::new(HostImpl {
             module_cache: RefCell::new(None),
+            shared_module_cache: RefCell::new(None),
             shared_linker: RefCell::new(None),
             source_account: RefCell::new(None),
             ledger: RefCell::new(None),
@@ -392,6 +429,10 @@ impl Host {
             need_to_build_module_cache: RefCell::new(false),
             #[cfg(any(test, feature = "testutils"))]
             invocation_meter: Default::default(),
+            #[cfg(any(test, feature = "testutils"))]
+            capture_failed_vm_memory: RefCell::new(false),
+            #[cfg(any(test, feature = "testutils"))]
+            failed_vm_memory: RefCell::new(None),
         }))
     }
 
@@ -405,6 +446,31 @@ impl Host {
         Ok(())
     }
 
+    /// Sets the [SharedModuleCache] to take the parsed modules of the invoked
+    /// contracts from, instead of parsing them. This has to be called before
+    /// the first contract invocation, which builds the module cache of the
+    /// host. Fails if the budget of the host doesn't have the fuel costs that
+    /// the cache was built with.
+    pub fn set_shared_module_cache(&self, cache: SharedModuleCache) -> Result<(), HostError> {
+        cache.check_fuel_costs(self)?;
+        *self.try_borrow_shared_module_cache_mut()? = Some(cache);
+        Ok(())
+    }
+
+    // Returns the module parsed from the Wasm with the given hash and cost
+    // inputs from the shared module cache, if any. The module still has to be
+    // charged for with `ParsedModule::reuse`.
+    pub(crate) fn get_shared_module(
+        &self,
+        wasm_hash: &Hash,
+        cost_inputs: &VersionedContractCodeCostInputs,
+    ) -> Result<Option<SharedModule>, HostError> {
+        Ok(self
+            .try_borrow_shared_module_cache()?
+            .as_ref()
+            .and_then(|cache| cache.get(wasm_hash, cost_inputs)))
+    }
+
     #[cfg(any(test, feature = "recording_mode"))]
     pub fn in_storage_recording_mode(&self) -> Result<bool, HostError> {
         if let crate::storage::FootprintMode::Recording(_) = self.try_borrow_storage()?.mode {
@@ -1061,7 +1127,9 @@ impl EnvBase for Host {
                 ));
             }
             metered_clone::charge_shallow_copy::<Val>(hv.len() as u64, self)?;
-            vals.copy_from_slice(hv.as_slice());
+            for (dst, src) in vals.iter_mut().zip(hv.iter()) {
+                *dst = *src;
+            }
             Ok(())
         })?;
         let res = Ok(Val::VOID);
@@ -1567,6 +1635,88 @@ impl VmCallerEnv for Host {
     impl_bignum_host_fns_rhs_u32!(i256_shl, checked_shl, I256, I256Val, Int256Shift);
     impl_bignum_host_fns_rhs_u32!(i256_shr, checked_shr, I256, I256Val, Int256Shift);
 
+    impl_bignum_host_fns_checked!(u256_checked_add, checked_add, U256, U256Val, Int256AddSub);
+    impl_bignum_host_fns_checked!(u256_checked_sub, checked_sub, U256, U256Val, Int256AddSub);
+    impl_bignum_host_fns_checked!(u256_checked_mul, checked_mul, U256, U256Val, Int256Mul);
+    impl_bignum_host_fns_rhs_u32_checked!(u256_checked_pow, checked_pow, U256, U256Val, Int256Pow);
+    impl_bignum_host_fns_checked!(i256_checked_add, checked_add, I256, I256Val, Int256AddSub);
+    impl_bignum_host_fns_checked!(i256_checked_sub, checked_sub, I256, I256Val, Int256AddSub);
+    impl_bignum_host_fns_checked!(i256_checked_mul, checked_mul, I256, I256Val, Int256Mul);
+    impl_bignum_host_fns_rhs_u32_checked!(i256_checked_pow, checked_pow, I256, I256Val, Int256Pow);
+
+    impl_bignum_host_fns_saturating!(
+        u256_saturating_add,
+        saturating_add,
+        U256,
+        U256Val,
+        Int256AddSub
+    );
+    impl_bignum_host_fns_saturating!(
+        u256_saturating_sub,
+        saturating_sub,
+        U256,
+        U256Val,
+        Int256AddSub
+    );
+    impl_bignum_host_fns_saturating!(
+        u256_saturating_mul,
+        saturating_mul,
+        U256,
+        U256Val,
+        Int256Mul
+    );
+    impl_bignum_host_fns_rhs_u32_saturating!(
+        u256_saturating_pow,
+        saturating_pow,
+        U256,
+        U256Val,
+        Int256Pow
+    );
+    impl_bignum_host_fns_saturating!(
+        i256_saturating_add,
+        saturating_add,
+        I256,
+        I256Val,
+        Int256AddSub
+    );
+    impl_bignum_host_fns_saturating!(
+        i256_saturating_sub,
+        saturating_sub,
+        I256,
+        I256Val,
+        Int256AddSub
+    );
+    impl_bignum_host_fns_saturating!(
+        i256_saturating_mul,
+        saturating_mul,
+        I256,
+        I256Val,
+        Int256Mul
+    );
+    impl_bignum_host_fns_rhs_u32_saturating!(
+        i256_saturating_pow,
+        saturating_pow,
+        I256,
+        I256Val,
+        Int256Pow
+    );
+
+    fn u256_mul_div(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        a: U256Val,
+        b: U256Val,
+        c: U256Val,
+        rounding: U32Val,
+    ) -> Result<U256Val, HostError> {
+        let rounding = self.mul_div_rounding_from_u32val(rounding)?;
+        let a: U256 = a.to_val().try_into_val(self)?;
+        let b: U256 = b.to_val().try_into_val(self)?;
+        let c: U256 = c.to_val().try_into_val(self)?;
+        let res = self.u256_mul_div_internal(a, b, c, rounding)?;
+        Ok(res.try_into_val(self)?)
+    }
+
     // endregion: "int" module functions
     // region: "map" module functions
 
@@ -1581,7 +1731,7 @@ impl VmCallerEnv for Host {
         k: Val,
         v: Val,
     ) -> Result<MapObject, HostError> {
-        let mnew = self.visit_obj(m, |hm: &HostMap| hm.insert(k, v, self))?;
+        let mnew = self.visit_obj(m, |hm: &HostMap| self.updatable_map(hm)?.insert(k, v, self))?;
         self.add_host_object(mnew)
     }
 
@@ -1609,7 +1759,7 @@ impl VmCallerEnv for Host {
         m: MapObject,
         k: Val,
     ) -> Result<MapObject, HostError> {
-        match self.visit_obj(m, |hm: &HostMap| hm.remove(&k, self))? {
+        match self.visit_obj(m, |hm: &HostMap| self.updatable_map(hm)?.remove(&k, self))? {
             Some((mnew, _)) => Ok(self.add_host_object(mnew)?),
             None => Err(self.err(
                 ScErrorType::Object,
@@ -1794,7 +1944,7 @@ impl VmCallerEnv for Host {
                 vmcaller,
                 &vm,
                 vals_pos.into(),
-                mapobj.map.as_slice(),
+                mapobj.map.iter(),
                 |pair| {
                     Ok(u64::to_le_bytes(
                         self.absolute_to_relative(pair.1)?.get_payload(),
@@ -1824,7 +1974,7 @@ impl VmCallerEnv for Host {
         let i: u32 = i.into();
         let vnew = self.visit_obj(v, |hv: &HostVec| {
             self.validate_index_lt_bound(i, hv.len())?;
-            hv.set(i as usize, x, self.as_budget())
+            self.updatable_vec(hv)?.set(i as usize, x, self.as_budget())
         })?;
         self.add_host_object(vnew)
     }
@@ -1851,7 +2001,7 @@ impl VmCallerEnv for Host {
         let i: u32 = i.into();
         let vnew = self.visit_obj(v, |hv: &HostVec| {
             self.validate_index_lt_bound(i, hv.len())?;
-            hv.remove(i as usize, self.as_budget())
+            self.updatable_vec(hv)?.remove(i as usize, self.as_budget())
         })?;
         self.add_host_object(vnew)
     }
@@ -1867,7 +2017,9 @@ impl VmCallerEnv for Host {
         v: VecObject,
         x: Val,
     ) -> Result<VecObject, HostError> {
-        let vnew = self.visit_obj(v, |hv: &HostVec| hv.push_front(x, self.as_budget()))?;
+        let vnew = self.visit_obj(v, |hv: &HostVec| {
+            self.updatable_vec(hv)?.push_front(x, self.as_budget())
+        })?;
         self.add_host_object(vnew)
     }
 
@@ -1876,7 +2028,9 @@ impl VmCallerEnv for Host {
         _vmcaller: &mut VmCaller<Host>,
         v: VecObject,
     ) -> Result<VecObject, HostError> {
-        let vnew = self.visit_obj(v, |hv: &HostVec| hv.pop_front(self.as_budget()))?;
+        let vnew = self.visit_obj(v, |hv: &HostVec| {
+            self.updatable_vec(hv)?.pop_front(self.as_budget())
+        })?;
         self.add_host_object(vnew)
     }
 
@@ -1886,7 +2040,9 @@ impl VmCallerEnv for Host {
         v: VecObject,
         x: Val,
     ) -> Result<VecObject, HostError> {
-        let vnew = self.visit_obj(v, |hv: &HostVec| hv.push_back(x, self.as_budget()))?;
+        let vnew = self.visit_obj(v, |hv: &HostVec| {
+            self.updatable_vec(hv)?.push_back(x, self.as_budget())
+        })?;
         self.add_host_object(vnew)
     }
 
@@ -1895,7 +2051,9 @@ impl VmCallerEnv for Host {
         _vmcaller: &mut VmCaller<Host>,
         v: VecObject,
     ) -> Result<VecObject, HostError> {
-        let vnew = self.visit_obj(v, |hv: &HostVec| hv.pop_back(self.as_budget()))?;
+        let vnew = self.visit_obj(v, |hv: &HostVec| {
+            self.updatable_vec(hv)?.pop_back(self.as_budget())
+        })?;
         self.add_host_object(vnew)
     }
 
@@ -1921,7 +2079,8 @@ impl VmCallerEnv for Host {
         let i: u32 = i.into();
         let vnew = self.visit_obj(v, |hv: &HostVec| {
             self.validate_index_le_bound(i, hv.len())?;
-            hv.insert(i as usize, x, self.as_budget())
+            self.updatable_vec(hv)?
+                .insert(i as usize, x, self.as_budget())
         })?;
         self.add_host_object(vnew)
     }
@@ -2054,7 +2213,7 @@ impl VmCallerEnv for Host {
                 vmcaller,
                 &vm,
                 pos,
-                vecobj.as_slice(),
+                vecobj.iter(),
                 |x| {
                     Ok(u64::to_le_bytes(
                         self.absolute_to_relative(*x)?.get_payload(),
@@ -2385,23 +2544,14 @@ impl VmCallerEnv for Host {
         #[cfg(any(test, feature = "testutils"))]
         let _invocation_meter_scope = self.maybe_meter_invocation()?;
 
-        let argvec = self.call_args_from_obj(args)?;
         // this is the recommended path of calling a contract, with `reentry`
         // always set `ContractReentryMode::Prohibited`
-        let res = self.call_n_internal(
-            &self.contract_id_from_address(contract_address)?,
+        self.call_from_guest(
+            contract_address,
             func,
-            argvec.as_slice(),
-            CallParams::default_external_call(),
-        );
-        if let Err(e) = &res {
-            self.error(
-                e.error,
-                "contract call failed",
-                &[func.to_val(), args.to_val()],
-            );
-        }
-        res
+            args,
+            ContractReentryMode::Prohibited,
+        )
     }
 
     // Notes on metering: covered by the components.
@@ -2415,54 +2565,75 @@ impl VmCallerEnv for Host {
         #[cfg(any(test, feature = "testutils"))]
         let _invocation_meter_scope = self.maybe_meter_invocation()?;
 
-        let argvec = self.call_args_from_obj(args)?;
-        // this is the "loosened" path of calling a contract.
-        // TODO: A `reentry` flag will be passed from `try_call` into here.
-        // For now, we are passing in `ContractReentryMode::Prohibited` to disable
-        // reentry.
-        let res = self.call_n_internal(
-            &self.contract_id_from_address(contract_address)?,
+        // this is the "loosened" path of calling a contract, re-entry is
+        // still prohibited (see `try_call_with_reentry`).
+        self.try_call_from_guest(
+            contract_address,
             func,
-            argvec.as_slice(),
-            CallParams::default_external_call(),
-        );
-        match res {
-            Ok(rv) => Ok(rv),
-            Err(e) => {
-                self.error(
-                    e.error,
-                    "contract try_call failed",
-                    &[func.to_val(), args.to_val()],
-                );
-                // Only allow to gracefully handle the recoverable errors.
-                // Non-recoverable errors should still cause guest to panic and
-                // abort execution.
-                if e.is_recoverable() {
-                    // Pass contract error _codes_ through, while switching
-                    // from Err(ce) to Ok(ce), i.e. recovering.
-                    if e.error.is_type(ScErrorType::Contract) {
-                        Ok(e.error.to_val())
-                    } else {
-                        // Narrow all the remaining host errors down to a single
-                        // error type. We don't want to expose the granular host
-                        // errors to the guest, consistently with how every
-                        // other host function works. This reduces the risk of
-                        // implementation being 'locked' into specific error
-                        // codes due to them being exposed to the guest and
-                        // hashed into blockchain.
-                        // The granular error codes are still observable with
-                        // diagnostic events.
-                        Ok(Error::from_type_and_code(
-                            ScErrorType::Context,
-                            ScErrorCode::InvalidAction,
-                        )
-                        .to_val())
-                    }
-                } else {
-                    Err(e)
-                }
-            }
-        }
+            args,
+            ContractReentryMode::Prohibited,
+        )
+    }
+
+    fn call_with_reentry(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        contract_address: AddressObject,
+        func: Symbol,
+        args: VecObject,
+        reentry_mode: U32Val,
+    ) -> Result<Val, HostError> {
+        #[cfg(any(test, feature = "testutils"))]
+        let _invocation_meter_scope = self.maybe_meter_invocation()?;
+
+        let reentry_mode = self.reentry_mode_from_u32val(reentry_mode)?;
+        self.call_from_guest(contract_address, func, args, reentry_mode)
+    }
+
+    // Notes on metering: covered by the components.
+    fn try_call_with_reentry(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        contract_address: AddressObject,
+        func: Symbol,
+        args: VecObject,
+        reentry_mode: U32Val,
+    ) -> Result<Val, HostError> {
+        #[cfg(any(test, feature = "testutils"))]
+        let _invocation_meter_scope = self.maybe_meter_invocation()?;
+
+        let reentry_mode = self.reentry_mode_from_u32val(reentry_mode)?;
+        self.try_call_from_guest(contract_address, func, args, reentry_mode)
+    }
+
+    // Notes on metering: covered by the components.
+    fn try_call_with_budget(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        contract_address: AddressObject,
+        func: Symbol,
+        args: VecObject,
+        cpu_limit: U64Val,
+        mem_limit: U64Val,
+    ) -> Result<Val, HostError> {
+        #[cfg(any(test, feature = "testutils"))]
+        let _invocation_meter_scope = self.maybe_meter_invocation()?;
+
+        let cpu_limit: u64 = cpu_limit.try_into_val(self)?;
+        let mem_limit: u64 = mem_limit.try_into_val(self)?;
+        let res = self
+            .as_budget()
+            .with_scoped_limits(cpu_limit, mem_limit, || {
+                self.try_call_from_guest(
+                    contract_address,
+                    func,
+                    args,
+                    ContractReentryMode::Prohibited,
+                )
+            })?;
+        // Exceeding the caps is the only error that is not recovered from by
+        // `try_call_from_guest`; surface it as is.
+        Ok(res.unwrap_or_else(|e| e.error.to_val()))
     }
 
     // endregion: "call" module functions
@@ -2868,6 +3039,131 @@ impl VmCallerEnv for Host {
         self.add_host_object(self.scbytes_from_vec(vnew)?)
     }
 
+    fn string_append(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s1: StringObject,
+        s2: StringObject,
+    ) -> Result<StringObject, HostError> {
+        let vnew = self.visit_obj(s1, |ss1: &ScString| {
+            self.visit_obj(s2, |ss2: &ScString| {
+                let len = self.validate_usize_sum_fits_in_u32(ss1.len(), ss2.len())?;
+                let mut vnew = Vec::<u8>::with_metered_capacity(len, self)?;
+                vnew.extend_from_slice(ss1.as_slice());
+                vnew.extend_from_slice(ss2.as_slice());
+                Ok(vnew)
+            })
+        })?;
+        self.add_host_object(ScString(vnew.try_into()?))
+    }
+
+    fn string_slice(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: StringObject,
+        start: U32Val,
+        end: U32Val,
+    ) -> Result<StringObject, HostError> {
+        let start: u32 = start.into();
+        let end: u32 = end.into();
+        let vnew = self.visit_obj(s, |ss: &ScString| {
+            let range = self.valid_range_from_start_end_bound(start, end, ss.len())?;
+            self.metered_slice_to_vec(
+                &ss.as_slice()
+                    .get(range)
+                    .ok_or_else(|| self.err_oob_object_index(None))?,
+            )
+        })?;
+        self.add_host_object(ScString(vnew.try_into()?))
+    }
+
+    fn string_is_valid_utf8(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: StringObject,
+    ) -> Result<Bool, HostError> {
+        let res = self.visit_obj(s, |ss: &ScString| self.metered_is_valid_utf8(ss.as_slice()))?;
+        Ok(res.into())
+    }
+
+    fn string_find(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: StringObject,
+        pattern: StringObject,
+        from: U32Val,
+    ) -> Result<Val, HostError> {
+        let res = self.visit_obj(s, |ss: &ScString| {
+            self.visit_obj(pattern, |sp: &ScString| {
+                self.metered_find_in_slice(ss.as_slice(), sp.as_slice(), from.into())
+            })
+        })?;
+        match res {
+            Some(i) => Ok(U32Val::from(i).to_val()),
+            None => Ok(Val::VOID.to_val()),
+        }
+    }
+
+    fn string_to_bytes(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: StringObject,
+    ) -> Result<BytesObject, HostError> {
+        let vnew = self.visit_obj(s, |ss: &ScString| self.metered_slice_to_vec(ss.as_slice()))?;
+        self.add_host_object(self.scbytes_from_vec(vnew)?)
+    }
+
+    fn bytes_to_string(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        b: BytesObject,
+    ) -> Result<StringObject, HostError> {
+        let vnew = self.visit_obj(b, |sb: &ScBytes| self.metered_slice_to_vec(sb.as_slice()))?;
+        self.add_host_object(ScString(vnew.try_into()?))
+    }
+
+    fn string_from_i128(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        v: I128Val,
+    ) -> Result<StringObject, HostError> {
+        let v = self.map_err(i128::try_from_val(self, &v.to_val()))?;
+        let s = self.i128_to_decimal_scstring(v)?;
+        self.add_host_object(s)
+    }
+
+    fn string_to_i128(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: StringObject,
+    ) -> Result<I128Val, HostError> {
+        let v = self.visit_obj(s, |ss: &ScString| {
+            self.i128_from_decimal_bytes(ss.as_slice())
+        })?;
+        self.map_err(I128Val::try_from_val(self, &v))
+    }
+
+    fn string_from_u256(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        v: U256Val,
+    ) -> Result<StringObject, HostError> {
+        let v = self.map_err(U256::try_from_val(self, &v.to_val()))?;
+        let s = self.u256_to_decimal_scstring(v)?;
+        self.add_host_object(s)
+    }
+
+    fn string_to_u256(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        s: StringObject,
+    ) -> Result<U256Val, HostError> {
+        let v = self.visit_obj(s, |ss: &ScString| {
+            self.u256_from_decimal_bytes(ss.as_slice())
+        })?;
+        self.map_err(U256Val::try_from_val(self, &v))
+    }
+
     // endregion: "buf" module functions
     // region: "crypto" module functions
 
@@ -2891,6 +3187,51 @@ impl VmCallerEnv for Host {
         self.add_host_object(self.scbytes_from_vec(hash)?)
     }
 
+    // Notes on metering: covered by components.
+    fn compute_hash_sha512(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        x: BytesObject,
+    ) -> Result<BytesObject, HostError> {
+        let hash =
+            self.fixed_hash_from_bytesobj_input(x, |bytes| self.sha512_hash_from_bytes_raw(bytes))?;
+        self.add_host_object(self.scbytes_from_vec(hash)?)
+    }
+
+    // Notes on metering: covered by components.
+    fn compute_hash_blake2b256(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        x: BytesObject,
+    ) -> Result<BytesObject, HostError> {
+        let hash = self.fixed_hash_from_bytesobj_input(x, |bytes| {
+            self.blake2b256_hash_from_bytes_raw(bytes)
+        })?;
+        self.add_host_object(self.scbytes_from_vec(hash)?)
+    }
+
+    // Notes on metering: covered by components.
+    fn compute_hash_blake3(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        x: BytesObject,
+    ) -> Result<BytesObject, HostError> {
+        let hash =
+            self.fixed_hash_from_bytesobj_input(x, |bytes| self.blake3_hash_from_bytes_raw(bytes))?;
+        self.add_host_object(self.scbytes_from_vec(hash)?)
+    }
+
+    // Notes on metering: covered by components.
+    fn compute_hash_ripemd160(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        x: BytesObject,
+    ) -> Result<BytesObject, HostError> {
+        let hash = self
+            .fixed_hash_from_bytesobj_input(x, |bytes| self.ripemd160_hash_from_bytes_raw(bytes))?;
+        self.add_host_object(self.scbytes_from_vec(hash)?)
+    }
+
     // Notes on metering: covered by components.
     fn verify_sig_ed25519(
         &self,
@@ -2907,6 +3248,23 @@ impl VmCallerEnv for Host {
         Ok(res?.into())
     }
 
+    fn verify_sigs_ed25519_batch(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        public_keys: VecObject,
+        messages: VecObject,
+        signatures: VecObject,
+    ) -> Result<Val, HostError> {
+        self.ed25519_batch_len(public_keys, messages, signatures)?;
+        let verifying_keys = self.ed25519_pub_key_vec_from_vecobj(public_keys)?;
+        let sigs = self.ed25519_signature_vec_from_vecobj(signatures)?;
+        let payloads = self.bytes_vec_from_vecobj(messages)?;
+        match self.verify_sigs_ed25519_batch_internal(&payloads, &verifying_keys, &sigs)? {
+            None => Ok(Val::VOID.into()),
+            Some(i) => Ok(U32Val::from(i).into()),
+        }
+    }
+
     fn recover_key_ecdsa_secp256k1(
         &self,
         _vmcaller: &mut VmCaller<Host>,
@@ -2935,6 +3293,34 @@ impl VmCallerEnv for Host {
         Ok(res.into())
     }
 
+    fn verify_sig_ecdsa_secp256k1(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        public_key: BytesObject,
+        msg_digest: BytesObject,
+        signature: BytesObject,
+    ) -> Result<Void, HostError> {
+        let pk = self.secp256k1_public_key_from_bytesobj_input(public_key)?;
+        let sig = self.ecdsa_signature_from_bytesobj_input::<k256::Secp256k1>(signature)?;
+        let msg_hash = self.hash_from_bytesobj_input("msg_digest", msg_digest)?;
+        let res = self.secp256k1_verify_signature(&pk, &msg_hash, &sig)?;
+        Ok(res.into())
+    }
+
+    fn recover_key_ecdsa_secp256r1(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        msg_digest: BytesObject,
+        signature: BytesObject,
+        recovery_id: U32Val,
+    ) -> Result<BytesObject, HostError> {
+        let sig = self.ecdsa_signature_from_bytesobj_input::<p256::NistP256>(signature)?;
+        let rid = self.secp256r1_recovery_id_from_u32val(recovery_id)?;
+        let hash = self.hash_from_bytesobj_input("msg_digest", msg_digest)?;
+        let rk = self.recover_key_ecdsa_secp256r1_internal(&hash, &sig, rid)?;
+        self.add_host_object(rk)
+    }
+
     fn bls12_381_check_g1_is_in_subgroup(
         &self,
         _vmcaller: &mut VmCaller<Host>,
@@ -3131,6 +3517,114 @@ impl VmCallerEnv for Host {
         self.fr_to_u256val(res)
     }
 
+    fn bn254_g1_add(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        p0: BytesObject,
+        p1: BytesObject,
+    ) -> Result<BytesObject, HostError> {
+        let p0 = self.bn254_g1_affine_deserialize_from_bytesobj(p0)?;
+        let p1 = self.bn254_g1_affine_deserialize_from_bytesobj(p1)?;
+        let res = self.bn254_g1_add_internal(p0, p1)?;
+        self.bn254_g1_projective_serialize(res)
+    }
+
+    fn bn254_g1_mul(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        p0: BytesObject,
+        scalar: U256Val,
+    ) -> Result<BytesObject, HostError> {
+        let p0 = self.bn254_g1_affine_deserialize_from_bytesobj(p0)?;
+        let scalar = self.bn254_fr_from_u256val(scalar)?;
+        let res = self.bn254_g1_mul_internal(p0, scalar)?;
+        self.bn254_g1_projective_serialize(res)
+    }
+
+    fn bn254_g1_msm(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        vp: VecObject,
+        vs: VecObject,
+    ) -> Result<BytesObject, HostError> {
+        let points = self.bn254_g1_vec_from_vecobj(vp)?;
+        let scalars = self.bn254_fr_vec_from_vecobj(vs)?;
+        let res = self.bn254_g1_msm_internal(&points, &scalars)?;
+        self.bn254_g1_projective_serialize(res)
+    }
+
+    fn bn254_multi_pairing_check(
+        &self,
+        vmcaller: &mut VmCaller<Host>,
+        vp1: VecObject,
+        vp2: VecObject,
+    ) -> Result<Bool, HostError> {
+        let l1: u32 = self.vec_len(vmcaller, vp1)?.into();
+        let l2: u32 = self.vec_len(vmcaller, vp2)?.into();
+        if l1 != l2 || l1 == 0 {
+            return Err(self.err(
+                ScErrorType::Crypto,
+                ScErrorCode::InvalidInput,
+                format!("bn254 multi-pairing-check: invalid input vector lengths {l1} and {l2}")
+                    .as_str(),
+                &[],
+            ));
+        }
+        let vp1 = self.bn254_g1_vec_from_vecobj(vp1)?;
+        let vp2 = self.bn254_g2_vec_from_vecobj(vp2)?;
+        let output = self.bn254_pairing_internal(&vp1, &vp2)?;
+        self.bn254_check_pairing_output(&output)
+    }
+
+    fn poseidon_permutation(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        input: VecObject,
+        field: Symbol,
+        d: U32Val,
+        rounds_f: U32Val,
+        mds: VecObject,
+        round_constants: VecObject,
+    ) -> Result<VecObject, HostError> {
+        self.poseidon_permutation_impl(
+            input,
+            field,
+            d.into(),
+            rounds_f.into(),
+            mds,
+            round_constants,
+        )
+    }
+
+    fn poseidon2_permutation(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        input: VecObject,
+        field: Symbol,
+        d: U32Val,
+        rounds_f: U32Val,
+        mat_internal_diag_m_1: VecObject,
+        round_constants: VecObject,
+    ) -> Result<VecObject, HostError> {
+        self.poseidon2_permutation_impl(
+            input,
+            field,
+            d.into(),
+            rounds_f.into(),
+            mat_internal_diag_m_1,
+            round_constants,
+        )
+    }
+
+    fn poseidon_hash(
+        &self,
+        _vmcaller: &mut VmCaller<Host>,
+        inputs: VecObject,
+        params: Symbol,
+    ) -> Result<U256Val, HostError> {
+        self.poseidon_hash_impl(inputs, params)
+    }
+
     // endregion: "crypto" module functions
     // region: "test" module functions
 
@@ -192,12 +192,15 @@ impl Host {
 
     // Metering: covered by vals_to_vec
     pub(crate) fn vecobject_to_scval_vec(&self, args: VecObject) -> Result<VecM<ScVal>, HostError> {
-        self.visit_obj(args, |hv: &HostVec| self.vals_to_scval_vec(hv.as_slice()))
+        self.visit_obj(args, |hv: &HostVec| self.vals_to_scval_vec(hv.iter()))
     }
 
-    pub(crate) fn vals_to_scval_vec(&self, vals: &[Val]) -> Result<VecM<ScVal>, HostError> {
-        vals.iter()
-            .map(|v| self.from_host_val(*v))
+    pub(crate) fn vals_to_scval_vec<'a>(
+        &self,
+        vals: impl ExactSizeIterator<Item = &'a Val>,
+    ) -> Result<VecM<ScVal>, HostError> {
+        let len = vals.len();
+        vals.map(|v| self.from_host_val(*v))
             .metered_collect::<Result<Vec<ScVal>, HostError>>(self)??
             .try_into()
             .map_err(|_| {
@@ -205,7 +208,7 @@ impl Host {
                     self,
                     (ScErrorType::Object, ScErrorCode::ExceededLimit),
                     "vector size limit exceeded",
-                    vals.len()
+                    len
                 )
             })
     }
@@ -524,3 +527,23 @@ impl Host {
         }
     }
 }
+
+#[cfg(feature = "json")]
+impl Host {
+    /// Converts `val` to the human-readable JSON representation described in
+    /// [crate::json]. Conversion is metered like any other conversion of a
+    /// [Val] to an [ScVal].
+    pub fn val_to_json(&self, val: Val) -> Result<serde_json::Value, HostError> {
+        let scval = self.from_host_val(val)?;
+        crate::json::to_json(&scval)
+            .map_err(|e| self.error(e, "failed to render value as JSON", &[val]))
+    }
+
+    /// Converts the human-readable JSON representation of a value back to a
+    /// [Val], adding any objects it contains to this host.
+    pub fn val_from_json(&self, json: &serde_json::Value) -> Result<Val, HostError> {
+        let scval = crate::json::from_json(json)
+            .map_err(|e| self.error(e, "failed to parse value from JSON", &[]))?;
+        self.to_host_val(&scval)
+    }
+}
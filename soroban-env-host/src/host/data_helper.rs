@@ -564,7 +564,7 @@ impl Host {
     // footprint in enforcing mode.
     // "testutils" are not covered by budget metering.
     pub fn setup_storage_footprint(&self, footprint: Footprint) -> Result<(), HostError> {
-        for (key, access_type) in footprint.0.map {
+        for (key, access_type) in footprint.0.into_vec(self.as_budget())? {
             self.setup_storage_entry(key, None, access_type)?;
         }
         Ok(())
@@ -29,7 +29,7 @@ use crate::{
     U128Small, U128Val, U256Object, U256Small, U256Val, U32Val, U64Object, U64Small, U64Val, Val,
     VecObject, Void, I256, U256,
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
 use wasmi::Value;
 
 // Declared size (bytes) of a single element. This value determines the metering input for clone
@@ -264,6 +264,11 @@ impl<T> DeclaredSizeForMetering for Rc<T> {
     const DECLARED_SIZE: u64 = 16;
 }
 
+// Same as Rc.
+impl<T> DeclaredSizeForMetering for Arc<T> {
+    const DECLARED_SIZE: u64 = 16;
+}
+
 // RefCell is the underlying data plus an `isize` flag
 impl<T: DeclaredSizeForMetering> DeclaredSizeForMetering for RefCell<T> {
     const DECLARED_SIZE: u64 = T::DECLARED_SIZE + 8;
@@ -532,6 +537,7 @@ mod test {
 
         // composite types
         expect!["8"].assert_eq(size_of::<Rc<ScVal>>().to_string().as_str());
+        expect!["8"].assert_eq(size_of::<Arc<ScVal>>().to_string().as_str());
         expect!["72"].assert_eq(size_of::<RefCell<ScVal>>().to_string().as_str());
         expect!["16"].assert_eq(size_of::<&[ScVal]>().to_string().as_str());
         expect!["72"].assert_eq(size_of::<(Val, ScVal)>().to_string().as_str());
@@ -704,6 +710,7 @@ mod test {
 
         // composite types
         assert_mem_size_le_declared_size!(Rc<ScVal>);
+        assert_mem_size_le_declared_size!(Arc<ScVal>);
         assert_mem_size_le_declared_size!(RefCell<ScVal>);
         assert_mem_size_le_declared_size!(&[ScVal]);
         assert_mem_size_le_declared_size!((Val, ScVal));
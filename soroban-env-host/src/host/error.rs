@@ -1,6 +1,7 @@
 use crate::{
     budget::AsBudget,
     events::Events,
+    vm::WasmBacktrace,
     xdr::{self, LedgerKey, ScAddress, ScError, ScErrorCode, ScErrorType},
     ConversionError, EnvBase, Error, Host, TryFromVal, U32Val, Val,
 };
@@ -18,6 +19,7 @@ use super::metered_clone::MeteredClone;
 #[derive(Clone)]
 pub(crate) struct DebugInfo {
     events: Events,
+    wasm_backtrace: Option<WasmBacktrace>,
     #[cfg(any(test, feature = "backtrace"))]
     backtrace: Backtrace,
 }
@@ -61,6 +63,21 @@ impl DebugInfo {
         Ok(())
     }
 
+    fn write_wasm_backtrace(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(bt) = &self.wasm_backtrace {
+            writeln!(f)?;
+            writeln!(f, "Wasm backtrace of contract calls (outermost first):")?;
+            for (i, frame) in bt.frames.iter().enumerate() {
+                write!(f, "   {}: {} [contract {}", i, frame, frame.contract_id)?;
+                if let Some(idx) = frame.func_index {
+                    write!(f, ", func #{}", idx)?;
+                }
+                writeln!(f, "]")?;
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(not(any(test, feature = "backtrace")))]
     fn write_backtrace(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         Ok(())
@@ -121,6 +138,7 @@ impl Debug for HostError {
         writeln!(f, "HostError: {:?}", self.error)?;
         if let Some(info) = &self.info {
             info.write_events(f)?;
+            info.write_wasm_backtrace(f)?;
             info.write_backtrace(f)
         } else {
             writeln!(f, "DebugInfo not available")
@@ -152,6 +170,24 @@ impl HostError {
         }
     }
 
+    /// Returns the symbolic Wasm backtrace captured when the error crossed a
+    /// VM boundary, if debug info was collected.
+    pub fn wasm_backtrace(&self) -> Option<&WasmBacktrace> {
+        self.info
+            .as_ref()
+            .and_then(|info| info.wasm_backtrace.as_ref())
+    }
+
+    /// Attaches a Wasm backtrace to the debug info of this error, unless it
+    /// has no debug info or already carries a (necessarily deeper) one.
+    pub(crate) fn set_wasm_backtrace_if_missing(&mut self, bt: WasmBacktrace) {
+        if let Some(info) = &mut self.info {
+            if info.wasm_backtrace.is_none() {
+                info.wasm_backtrace = Some(bt);
+            }
+        }
+    }
+
     /// Identifies whether the error can be meaningfully recovered from.
     ///
     /// We consider errors that occur due to broken execution preconditions (
@@ -294,6 +330,7 @@ impl Host {
                         #[cfg(any(test, feature = "backtrace"))]
                         backtrace: Backtrace::new_unresolved(),
                         events,
+                        wasm_backtrace: None,
                     }));
                 }
                 Ok(())
@@ -11,8 +11,8 @@ use crate::{
         ContractExecutable, ContractIdPreimage, CreateContractArgsV2, Hash, HostFunction,
         HostFunctionType, ScAddress, ScContractInstance, ScErrorCode, ScErrorType, ScVal,
     },
-    AddressObject, Error, Host, HostError, Object, Symbol, SymbolStr, TryFromVal, TryIntoVal, Val,
-    Vm, DEFAULT_HOST_DEPTH_LIMIT,
+    AddressObject, Error, Host, HostError, Object, Symbol, SymbolStr, TryFromVal, TryIntoVal,
+    U32Val, Val, VecObject, Vm, DEFAULT_HOST_DEPTH_LIMIT,
 };
 
 #[cfg(any(test, feature = "testutils"))]
@@ -27,10 +27,30 @@ pub(crate) enum ContractReentryMode {
     /// possible for a contract to do a self-call via host).
     SelfAllowed,
     /// Re-entry is fully allowed.
-    #[allow(dead_code)]
     Allowed,
 }
 
+impl Host {
+    /// Decodes the `reentry_mode` argument of the `*_with_reentry` host
+    /// functions.
+    pub(crate) fn reentry_mode_from_u32val(
+        &self,
+        mode: U32Val,
+    ) -> Result<ContractReentryMode, HostError> {
+        match u32::from(mode) {
+            0 => Ok(ContractReentryMode::Prohibited),
+            1 => Ok(ContractReentryMode::SelfAllowed),
+            2 => Ok(ContractReentryMode::Allowed),
+            _ => Err(self.err(
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+                "invalid contract re-entry mode",
+                &[mode.to_val()],
+            )),
+        }
+    }
+}
+
 /// All the contract functions starting with double underscore are considered
 /// to be reserved by the Soroban host and can't be directly called by another
 /// contracts.
@@ -111,6 +131,14 @@ impl CallParams {
         }
     }
 
+    pub(crate) fn external_call_with_reentry(reentry_mode: ContractReentryMode) -> Self {
+        Self {
+            reentry_mode,
+            internal_host_call: false,
+            treat_missing_function_as_noop: false,
+        }
+    }
+
     #[allow(unused)]
     pub(crate) fn default_internal_call() -> Self {
         Self {
@@ -167,6 +195,16 @@ impl Frame {
             Frame::TestContract(tc) => Some(&tc.instance),
         }
     }
+
+    fn instance_mut(&mut self) -> Option<&mut ScContractInstance> {
+        match self {
+            Frame::ContractVM { instance, .. } => Some(instance),
+            Frame::HostFunction(_) => None,
+            Frame::StellarAssetContract(_, _, _, instance) => Some(instance),
+            #[cfg(any(test, feature = "testutils"))]
+            Frame::TestContract(tc) => Some(&mut tc.instance),
+        }
+    }
     #[cfg(any(test, feature = "testutils"))]
     fn is_contract_vm(&self) -> bool {
         matches!(self, Frame::ContractVM { .. })
@@ -461,6 +499,7 @@ impl Host {
                         // Everything else is some sort of contract call.
                         _ => {
                             is_top_contract_invocation = true;
+                            self.clear_failed_vm_memory()?;
                             if let Some(contract_invocation_hook) =
                                 self.try_borrow_top_contract_invocation_hook()?.as_ref()
                             {
@@ -712,6 +751,8 @@ impl Host {
         #[cfg(not(any(test, feature = "recording_mode")))]
         self.build_module_cache_if_needed()?;
         let contract_id = id.metered_clone(self)?;
+        #[cfg(any(test, feature = "testutils"))]
+        self.add_static_insns_to_wasm_insn_profile(&contract_id, wasm_hash)?;
         let parsed_module = if let Some(cache) = &*self.try_borrow_module_cache()? {
             // Check that storage thinks the entry exists before
             // checking the cache: this seems like overkill but it
@@ -793,7 +834,18 @@ impl Host {
         #[cfg(not(any(test, feature = "recording_mode")))]
         let cost_mode = crate::vm::ModuleParseCostMode::Normal;
 
-        Vm::new_with_cost_inputs(self, contract_id, code.as_slice(), costs, cost_mode)
+        // The module can be taken from the shared module cache, if any, even
+        // when it's not in the module cache, since it comes with its own
+        // engine.
+        let shared_module = self.get_shared_module(wasm_hash, &costs)?;
+        Vm::new_with_cost_inputs(
+            self,
+            contract_id,
+            code.as_slice(),
+            costs,
+            cost_mode,
+            shared_module,
+        )
     }
 
     pub(crate) fn get_contract_protocol_version(
@@ -815,6 +867,89 @@ impl Host {
         }
     }
 
+    // Shared implementation of the `call` and `call_with_reentry` host
+    // functions.
+    // Notes on metering: this is covered by the called components.
+    pub(crate) fn call_from_guest(
+        &self,
+        contract_address: AddressObject,
+        func: Symbol,
+        args: VecObject,
+        reentry_mode: ContractReentryMode,
+    ) -> Result<Val, HostError> {
+        let argvec = self.call_args_from_obj(args)?;
+        let res = self.call_n_internal(
+            &self.contract_id_from_address(contract_address)?,
+            func,
+            argvec.as_slice(),
+            CallParams::external_call_with_reentry(reentry_mode),
+        );
+        if let Err(e) = &res {
+            self.error(
+                e.error,
+                "contract call failed",
+                &[func.to_val(), args.to_val()],
+            );
+        }
+        res
+    }
+
+    // Shared implementation of the `try_call` and `try_call_with_reentry` host
+    // functions.
+    // Notes on metering: this is covered by the called components.
+    pub(crate) fn try_call_from_guest(
+        &self,
+        contract_address: AddressObject,
+        func: Symbol,
+        args: VecObject,
+        reentry_mode: ContractReentryMode,
+    ) -> Result<Val, HostError> {
+        let argvec = self.call_args_from_obj(args)?;
+        let res = self.call_n_internal(
+            &self.contract_id_from_address(contract_address)?,
+            func,
+            argvec.as_slice(),
+            CallParams::external_call_with_reentry(reentry_mode),
+        );
+        match res {
+            Ok(rv) => Ok(rv),
+            Err(e) => {
+                self.error(
+                    e.error,
+                    "contract try_call failed",
+                    &[func.to_val(), args.to_val()],
+                );
+                // Only allow to gracefully handle the recoverable errors.
+                // Non-recoverable errors should still cause guest to panic and
+                // abort execution.
+                if e.is_recoverable() {
+                    // Pass contract error _codes_ through, while switching
+                    // from Err(ce) to Ok(ce), i.e. recovering.
+                    if e.error.is_type(ScErrorType::Contract) {
+                        Ok(e.error.to_val())
+                    } else {
+                        // Narrow all the remaining host errors down to a single
+                        // error type. We don't want to expose the granular host
+                        // errors to the guest, consistently with how every
+                        // other host function works. This reduces the risk of
+                        // implementation being 'locked' into specific error
+                        // codes due to them being exposed to the guest and
+                        // hashed into blockchain.
+                        // The granular error codes are still observable with
+                        // diagnostic events.
+                        Ok(Error::from_type_and_code(
+                            ScErrorType::Context,
+                            ScErrorCode::InvalidAction,
+                        )
+                        .to_val())
+                    }
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
     // Notes on metering: this is covered by the called components.
     pub(crate) fn call_n_internal(
         &self,
@@ -839,36 +974,103 @@ impl Host {
             ));
         }
 
-        if !matches!(call_params.reentry_mode, ContractReentryMode::Allowed) {
-            let reentry_distance = self
-                .try_borrow_context_stack()?
-                .iter()
-                .rev()
-                .filter_map(|c| c.frame.contract_id())
-                .position(|caller| caller == id);
-
-            match (call_params.reentry_mode, reentry_distance) {
-                // Non-reentrant calls, or calls in Allowed mode,
-                // or immediate-reentry calls in SelfAllowed mode
-                // are all acceptable.
-                (_, None)
-                | (ContractReentryMode::Allowed, _)
-                | (ContractReentryMode::SelfAllowed, Some(0)) => (),
-
-                // But any non-immediate-reentry in SelfAllowed mode,
-                // or any reentry at all in Prohibited mode, are errors.
-                (ContractReentryMode::SelfAllowed, Some(_))
-                | (ContractReentryMode::Prohibited, Some(_)) => {
-                    return Err(self.err(
-                        ScErrorType::Context,
-                        ScErrorCode::InvalidAction,
-                        "Contract re-entry is not allowed",
-                        &[],
-                    ));
+        let reentry_distance = self
+            .try_borrow_context_stack()?
+            .iter()
+            .rev()
+            .filter_map(|c| c.frame.contract_id())
+            .position(|caller| caller == id);
+
+        match (&call_params.reentry_mode, reentry_distance) {
+            // Non-reentrant calls, or calls in Allowed mode,
+            // or immediate-reentry calls in SelfAllowed mode
+            // are all acceptable.
+            (_, None)
+            | (ContractReentryMode::Allowed, _)
+            | (ContractReentryMode::SelfAllowed, Some(0)) => (),
+
+            // But any non-immediate-reentry in SelfAllowed mode,
+            // or any reentry at all in Prohibited mode, are errors.
+            (ContractReentryMode::SelfAllowed, Some(_))
+            | (ContractReentryMode::Prohibited, Some(_)) => {
+                return Err(self.err(
+                    ScErrorType::Context,
+                    ScErrorCode::InvalidAction,
+                    "Contract re-entry is not allowed",
+                    &[],
+                ));
+            }
+        }
+
+        // Re-entry requested by a contract (via `call_with_reentry`) shares the
+        // contract's instance storage between all of its frames: the caller
+        // side is written out before the call and reloaded after it. Internal
+        // host calls (i.e. `__check_auth` self-reentry) keep their original
+        // behavior.
+        if reentry_distance.is_some() && !call_params.internal_host_call {
+            self.persist_instance_storage_for_reentry(id)?;
+            let res = self.dispatch_call(id, func, args, call_params);
+            let reloaded = self.reload_instance_storage_after_reentry(id);
+            return res.and_then(|rv| reloaded.map(|_| rv));
+        }
+        self.dispatch_call(id, func, args, call_params)
+    }
+
+    // Writes out the modified instance storage of every frame of contract `id`
+    // on the context stack, so that the frame re-entering it starts from the
+    // up-to-date state.
+    fn persist_instance_storage_for_reentry(&self, id: &Hash) -> Result<(), HostError> {
+        let mut updated_instance_storage = None;
+        for ctx in self.try_borrow_context_stack_mut()?.iter_mut() {
+            if ctx.frame.contract_id() != Some(id) {
+                continue;
+            }
+            if let Some(storage) = &mut ctx.storage {
+                if storage.is_modified {
+                    updated_instance_storage = Some(self.host_map_to_scmap(&storage.map)?);
+                    storage.is_modified = false;
                 }
             }
         }
+        if updated_instance_storage.is_some() {
+            let key = self.contract_instance_ledger_key(id)?;
+            self.store_contract_instance(
+                None,
+                updated_instance_storage,
+                id.metered_clone(self)?,
+                &key,
+            )?;
+        }
+        Ok(())
+    }
 
+    // Drops the cached instance storage of every frame of contract `id` on the
+    // context stack after a re-entrant call has returned (and persisted its own
+    // instance storage on frame pop), so it's lazily re-initialized from the
+    // up-to-date instance.
+    fn reload_instance_storage_after_reentry(&self, id: &Hash) -> Result<(), HostError> {
+        let key = self.contract_instance_ledger_key(id)?;
+        let instance = self.retrieve_contract_instance_from_storage(&key)?;
+        for ctx in self.try_borrow_context_stack_mut()?.iter_mut() {
+            if ctx.frame.contract_id() != Some(id) {
+                continue;
+            }
+            if let Some(frame_instance) = ctx.frame.instance_mut() {
+                *frame_instance = instance.metered_clone(self)?;
+            }
+            ctx.storage = None;
+        }
+        Ok(())
+    }
+
+    // Notes on metering: this is covered by the called components.
+    fn dispatch_call(
+        &self,
+        id: &Hash,
+        func: Symbol,
+        args: &[Val],
+        call_params: CallParams,
+    ) -> Result<Val, HostError> {
         self.fn_call_diagnostics(id, &func, args);
 
         // Try dispatching the contract to the compiled-in registred
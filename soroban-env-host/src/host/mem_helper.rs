@@ -105,12 +105,12 @@ impl Host {
     // Note on metering: covers the cost of memcpy from bytes into the linear memory.
     // It does not include the cost of getting those bytes, which is done by the
     // closure and must be metered in the closure at the caller side.
-    pub(crate) fn metered_vm_write_vals_to_linear_memory<const VAL_SZ: usize, VAL>(
+    pub(crate) fn metered_vm_write_vals_to_linear_memory<'a, const VAL_SZ: usize, VAL: 'a>(
         &self,
         vmcaller: &mut VmCaller<Host>,
         vm: &Rc<Vm>,
         mem_pos: u32,
-        buf: &[VAL],
+        buf: impl ExactSizeIterator<Item = &'a VAL>,
         to_le_bytes: impl Fn(&VAL) -> Result<[u8; VAL_SZ], HostError>,
     ) -> Result<(), HostError> {
         let val_sz = self.usize_to_u32(VAL_SZ)?;
@@ -130,7 +130,7 @@ impl Host {
             .ok_or_else(|| self.err_oob_linear_memory())?;
 
         self.charge_budget(ContractCostType::MemCpy, Some(byte_len as u64))?;
-        for (src, dst) in buf.iter().zip(mem_slice.chunks_mut(VAL_SZ)) {
+        for (src, dst) in buf.zip(mem_slice.chunks_mut(VAL_SZ)) {
             if dst.len() != VAL_SZ {
                 // This should be impossible unless there's an error above, but just in case.
                 return Err(self.err(
@@ -16,7 +16,7 @@
 //! pointers. The rest of each struct is considered its "shallow" part and
 //! charged based on its declared size (see [`DeclaredSizeForMetering`]).
 
-use std::{cell::RefCell, mem, rc::Rc};
+use std::{cell::RefCell, mem, rc::Rc, sync::Arc};
 
 use crate::{
     budget::{AsBudget, DepthLimiter},
@@ -341,6 +341,9 @@ impl MeteredClone for Asset {}
 // cloning Rc is just a ref-count bump
 impl<T> MeteredClone for Rc<T> {}
 
+// same for Arc
+impl<T> MeteredClone for Arc<T> {}
+
 // cloning a RefCell clones its underlying data structure
 impl<T: MeteredClone> MeteredClone for RefCell<T> {
     const IS_SHALLOW: bool = T::IS_SHALLOW;
@@ -1,6 +1,10 @@
 use crate::{
     budget::{AsBudget, Budget},
-    host::{declared_size::DeclaredSizeForMetering, MeteredClone},
+    host::{
+        declared_size::DeclaredSizeForMetering,
+        persistent_seq::{compare_seqs, PersistentSeq, Seq, SeqIter},
+        MeteredClone,
+    },
     xdr::{ContractCostType, ScErrorCode, ScErrorType},
     Compare, Error, Host, HostError,
 };
@@ -12,7 +16,7 @@ use super::metered_vector::binary_search_by_pre_rust_182;
 const MAP_OOB: Error = Error::from_type_and_code(ScErrorType::Object, ScErrorCode::IndexBounds);
 
 pub struct MeteredOrdMap<K, V, Ctx> {
-    pub(crate) map: Vec<(K, V)>,
+    pub(crate) map: Seq<(K, V)>,
     ctx: PhantomData<Ctx>,
 }
 
@@ -99,11 +103,31 @@ where
 {
     pub fn new() -> Self {
         MeteredOrdMap {
-            map: Vec::new(),
+            map: Seq::default(),
             ctx: Default::default(),
         }
     }
 
+    fn from_shared(seq: PersistentSeq<(K, V)>) -> Self {
+        MeteredOrdMap {
+            map: Seq::Shared(seq),
+            ctx: Default::default(),
+        }
+    }
+
+    /// Returns whether the map is stored as a persistent, structurally shared
+    /// sequence, which all of its updates then share structure with.
+    pub fn is_shared(&self) -> bool {
+        self.map.is_shared()
+    }
+
+    /// Returns a copy of the map stored as a persistent, structurally shared
+    /// sequence. This is a full (charged) copy the first time, and an O(1)
+    /// one if the map is already shared.
+    pub fn to_shared(&self, ctx: &Ctx) -> Result<Self, HostError> {
+        Ok(Self::from_shared(self.map.to_shared(ctx.as_budget())?))
+    }
+
     pub fn from_map(map: Vec<(K, V)>, ctx: &Ctx) -> Result<Self, HostError> {
         if u32::try_from(map.len()).is_err() {
             return Err(MAP_OOB.into());
@@ -111,14 +135,11 @@ where
         // Allocation cost already paid for by caller, here just checks that input
         // has sorted and unique keys.
         let m = MeteredOrdMap {
-            map,
+            map: Seq::Flat(map),
             ctx: Default::default(),
         };
         m.charge_scan(ctx)?;
-        for w in m.map.as_slice().windows(2) {
-            let [a, b] = w else {
-                return Err((ScErrorType::Object, ScErrorCode::InternalError).into());
-            };
+        for (a, b) in m.map.iter().zip(m.map.iter().skip(1)) {
             match <Ctx as Compare<K>>::compare(ctx, &a.0, &b.0)? {
                 Ordering::Less => (),
                 _ => return Err((ScErrorType::Object, ScErrorCode::InvalidInput).into()),
@@ -163,7 +184,7 @@ where
         let _span = tracy_span!("map lookup");
         self.charge_binsearch(ctx)?;
         let mut err: Option<HostError> = None;
-        let res = binary_search_by_pre_rust_182(self.map.as_slice(), |probe| {
+        let res = binary_search_by_pre_rust_182(&self.map, |probe| {
             // We've already hit an error, return Ordering::Equal
             // to terminate search asap.
             if err.is_some() {
@@ -193,6 +214,10 @@ where
                 if replace_pos == usize::MAX - 1 {
                     return Err(MAP_OOB.into());
                 }
+                if let Seq::Shared(seq) = &self.map {
+                    let seq = seq.set(replace_pos, (key, value), ctx.as_budget())?;
+                    return Ok(Self::from_shared(seq));
+                }
                 let init = self.map.iter().take(replace_pos).cloned();
                 let fini = self.map.iter().skip(replace_pos.saturating_add(1)).cloned();
                 let iter = init.chain([(key, value)]).chain(fini);
@@ -204,6 +229,9 @@ where
                 // [0] new [1, 2]
                 if self.len() == u32::MAX as usize {
                     return Err(MAP_OOB.into());
+                } else if let Seq::Shared(seq) = &self.map {
+                    let seq = seq.insert(insert_pos, (key, value), ctx.as_budget())?;
+                    Ok(Self::from_shared(seq))
                 } else {
                     let init = self.map.iter().take(insert_pos).cloned();
                     let fini = self.map.iter().skip(insert_pos).cloned();
@@ -246,6 +274,16 @@ where
         K: Borrow<Q>,
         Ctx: Compare<Q, Error = HostError>,
     {
+        if let Seq::Shared(seq) = &self.map {
+            let Ok(found) = self.find(key, ctx)? else {
+                return Ok(None);
+            };
+            let new = Self::from_shared(seq.remove(found, ctx.as_budget())?);
+            let Some((_, res)) = self.map.get(found) else {
+                return Err((ScErrorType::Object, ScErrorCode::InternalError).into());
+            };
+            return Ok(Some((new, res.metered_clone(ctx.as_budget())?)));
+        }
         match self.find(key, ctx)? {
             Ok(found) if found > 0 => {
                 // There's a nonempty prefix to preserve.
@@ -299,10 +337,16 @@ where
         Ok(self.map.iter().map(|(_, v)| v))
     }
 
-    pub fn iter(&self, ctx: &Ctx) -> Result<impl Iterator<Item = &(K, V)>, HostError> {
+    pub fn iter(&self, ctx: &Ctx) -> Result<SeqIter<'_, (K, V)>, HostError> {
         self.charge_scan(ctx)?;
         Ok(self.map.iter())
     }
+
+    /// Returns the entries as a `Vec`. Flattening a shared map is charged as
+    /// a full copy of its entries.
+    pub fn into_vec(self, ctx: &Ctx) -> Result<Vec<(K, V)>, HostError> {
+        self.map.into_vec(ctx.as_budget())
+    }
 }
 
 impl<K, V, Ctx> DeclaredSizeForMetering for MeteredOrdMap<K, V, Ctx>
@@ -320,7 +364,7 @@ where
     Ctx: AsBudget,
 {
     fn charge_for_substructure(&self, budget: impl AsBudget) -> Result<(), HostError> {
-        self.map.charge_for_substructure(budget)
+        self.map.charge_for_substructure(budget.as_budget())
     }
 }
 
@@ -346,7 +390,7 @@ where
                     .saturating_mul(a.map.len().min(b.map.len()) as u64),
             ),
         )?;
-        <Self as Compare<Vec<(K, V)>>>::compare(self, &a.map, &b.map)
+        compare_seqs::<Self, (K, V)>(self, &a.map, &b.map)
     }
 }
 
@@ -372,24 +416,15 @@ where
                     .saturating_mul(a.map.len().min(b.map.len()) as u64),
             ),
         )?;
-        <Self as Compare<Vec<(K, V)>>>::compare(self, &a.map, &b.map)
+        compare_seqs::<Self, (K, V)>(self, &a.map, &b.map)
     }
 }
 
 impl<'a, K, V, Ctx> IntoIterator for &'a MeteredOrdMap<K, V, Ctx> {
     type Item = &'a (K, V);
-    type IntoIter = core::slice::Iter<'a, (K, V)>;
+    type IntoIter = SeqIter<'a, (K, V)>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.map.iter()
     }
 }
-
-impl<K, V, Ctx> IntoIterator for MeteredOrdMap<K, V, Ctx> {
-    type Item = (K, V);
-    type IntoIter = std::vec::IntoIter<(K, V)>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
-    }
-}
@@ -1,6 +1,10 @@
 use crate::{
     budget::{AsBudget, Budget},
-    host::{declared_size::DeclaredSizeForMetering, MeteredClone},
+    host::{
+        declared_size::DeclaredSizeForMetering,
+        persistent_seq::{compare_seqs, PersistentSeq, Seq, SeqIter},
+        MeteredClone,
+    },
     xdr::{ContractCostType, ScErrorCode, ScErrorType},
     Compare, Error, Host, HostError,
 };
@@ -11,7 +15,7 @@ const VEC_OOB: Error = Error::from_type_and_code(ScErrorType::Object, ScErrorCod
 
 #[derive(Clone)]
 pub struct MeteredVector<A> {
-    pub(crate) vec: Vec<A>,
+    pub(crate) vec: Seq<A>,
 }
 
 impl<A> Default for MeteredVector<A> {
@@ -66,7 +70,9 @@ where
 {
     // Constructs a empty new `MeteredVector`.
     pub fn new() -> Self {
-        Self { vec: Vec::new() }
+        Self {
+            vec: Seq::default(),
+        }
     }
 
     // Constructs a new, empty `MeteredVector` with at least the specified capacity.
@@ -84,16 +90,40 @@ where
         if u32::try_from(vec.len()).is_err() {
             Err(VEC_OOB.into())
         } else {
-            Ok(Self { vec })
+            Ok(Self {
+                vec: Seq::Flat(vec),
+            })
+        }
+    }
+
+    fn from_shared(seq: PersistentSeq<A>) -> Self {
+        Self {
+            vec: Seq::Shared(seq),
         }
     }
 
-    pub fn as_slice(&self) -> &[A] {
-        self.vec.as_slice()
+    /// Returns whether the vector is stored as a persistent, structurally
+    /// shared sequence, which all of its updates then share structure with.
+    pub fn is_shared(&self) -> bool {
+        self.vec.is_shared()
+    }
+
+    /// Returns a copy of the vector stored as a persistent, structurally
+    /// shared sequence. This is a full (charged) copy the first time, and an
+    /// O(1) one if the vector is already shared.
+    pub fn to_shared(&self, budget: &Budget) -> Result<Self, HostError> {
+        Ok(Self::from_shared(self.vec.to_shared(budget)?))
     }
 
-    pub fn as_mut_slice(&mut self) -> &mut [A] {
-        self.vec.as_mut_slice()
+    /// Returns the elements as a slice, if they are not stored shared.
+    pub fn as_slice(&self) -> Option<&[A]> {
+        self.vec.as_flat().map(|v| v.as_slice())
+    }
+
+    /// Returns the elements as a mutable slice, first flattening a shared
+    /// vector (charged as a copy of its elements).
+    pub fn as_mut_slice(&mut self, budget: &Budget) -> Result<&mut [A], HostError> {
+        self.vec.make_flat_mut(budget)
     }
 
     // This doesn't take ExactSizeIterator since that is not implemented for Chain
@@ -124,9 +154,16 @@ where
     }
 
     pub fn set(&self, index: usize, value: A, budget: &Budget) -> Result<Self, HostError> {
+        if let Seq::Shared(seq) = &self.vec {
+            self.charge_access(1, budget)?;
+            return Ok(Self::from_shared(seq.set(index, value, budget)?));
+        }
         let mut new = self.metered_clone(budget)?;
         new.charge_access(1, budget)?;
-        let cell: Result<&mut A, HostError> = new.vec.get_mut(index).ok_or_else(|| VEC_OOB.into());
+        let cell: Result<&mut A, HostError> = new
+            .as_mut_slice(budget)?
+            .get_mut(index)
+            .ok_or_else(|| VEC_OOB.into());
         *(cell?) = value;
         Ok(new)
     }
@@ -143,6 +180,8 @@ where
     pub fn push_front(&self, value: A, budget: &Budget) -> Result<Self, HostError> {
         if self.len() == u32::MAX as usize {
             Err(VEC_OOB.into())
+        } else if let Seq::Shared(seq) = &self.vec {
+            Ok(Self::from_shared(seq.insert(0, value, budget)?))
         } else {
             let iter = [value].into_iter().chain(self.vec.iter().cloned());
             Self::from_exact_iter(iter, budget)
@@ -152,6 +191,8 @@ where
     pub fn pop_front(&self, budget: &Budget) -> Result<Self, HostError> {
         if self.vec.is_empty() {
             Err(VEC_OOB.into())
+        } else if let Seq::Shared(seq) = &self.vec {
+            Ok(Self::from_shared(seq.remove(0, budget)?))
         } else {
             let iter = self.vec.iter().skip(1).cloned();
             Self::from_exact_iter(iter, budget)
@@ -161,6 +202,8 @@ where
     pub fn push_back(&self, value: A, budget: &Budget) -> Result<Self, HostError> {
         if self.len() == u32::MAX as usize {
             Err(VEC_OOB.into())
+        } else if let Seq::Shared(seq) = &self.vec {
+            Ok(Self::from_shared(seq.insert(seq.len(), value, budget)?))
         } else {
             let iter = self.vec.iter().cloned().chain([value]);
             Self::from_exact_iter(iter, budget)
@@ -189,6 +232,9 @@ where
             Err(VEC_OOB.into())
         } else {
             let count = Self::sub_or_err(self.vec.len(), 1)?;
+            if let Seq::Shared(seq) = &self.vec {
+                return Ok(Self::from_shared(seq.remove(count, budget)?));
+            }
             let iter = self.vec.iter().take(count).cloned();
             Self::from_exact_iter(iter, budget)
         }
@@ -197,6 +243,8 @@ where
     pub fn remove(&self, idx: usize, budget: &Budget) -> Result<Self, HostError> {
         if idx >= self.vec.len() || idx == usize::MAX - 1 {
             Err(VEC_OOB.into())
+        } else if let Seq::Shared(seq) = &self.vec {
+            Ok(Self::from_shared(seq.remove(idx, budget)?))
         } else {
             // [0, 1, 2]
             // del 1 => take(1) + skip(2)
@@ -224,6 +272,8 @@ where
         let len = self.vec.len();
         if index > len {
             Err(VEC_OOB.into())
+        } else if let Seq::Shared(seq) = &self.vec {
+            Ok(Self::from_shared(seq.insert(index, value, budget)?))
         } else if index == len {
             self.push_back(value, budget)
         } else if index == 0 {
@@ -243,9 +293,20 @@ where
     }
 
     pub fn slice(&self, range: Range<usize>, budget: &Budget) -> Result<Self, HostError> {
-        match self.vec.get(range) {
-            Some(slice) => Self::from_exact_iter(slice.iter().cloned(), budget),
-            None => Err(VEC_OOB.into()),
+        match &self.vec {
+            Seq::Flat(v) => match v.get(range) {
+                Some(slice) => Self::from_exact_iter(slice.iter().cloned(), budget),
+                None => Err(VEC_OOB.into()),
+            },
+            // Slices of shared vectors start out flat again, as copying the
+            // slice is linear in its length anyway.
+            Seq::Shared(seq) => {
+                if range.start > range.end || range.end > seq.len() {
+                    return Err(VEC_OOB.into());
+                }
+                let iter = self.vec.iter().skip(range.start).take(range.len());
+                Self::from_exact_iter(iter.cloned(), budget)
+            }
         }
     }
 
@@ -287,7 +348,7 @@ where
     {
         self.charge_binsearch(budget)?;
         let mut err: Option<HostError> = None;
-        let res = binary_search_by_pre_rust_182(self.vec.as_slice(), |probe| {
+        let res = binary_search_by_pre_rust_182(&self.vec, |probe| {
             // We've already hit an error, return Ordering::Equal
             // to terminate search asap.
             if err.is_some() {
@@ -307,12 +368,15 @@ where
         }
     }
 
-    pub fn iter(&self) -> std::slice::Iter<'_, A> {
+    pub fn iter(&self) -> SeqIter<'_, A> {
         self.vec.iter()
     }
 
     pub fn to_vec(&self, budget: &Budget) -> Result<Vec<A>, HostError> {
-        self.vec.metered_clone(budget)
+        match &self.vec {
+            Seq::Flat(v) => v.metered_clone(budget),
+            Seq::Shared(seq) => seq.to_vec(budget),
+        }
     }
 }
 
@@ -328,7 +392,7 @@ where
     A: MeteredClone,
 {
     fn charge_for_substructure(&self, budget: impl AsBudget) -> Result<(), HostError> {
-        self.vec.charge_for_substructure(budget)
+        self.vec.charge_for_substructure(budget.as_budget())
     }
 }
 
@@ -349,7 +413,7 @@ where
             ContractCostType::MemCpy,
             Some(Elt::DECLARED_SIZE.saturating_mul(a.vec.len().min(b.vec.len()) as u64)),
         )?;
-        <Self as Compare<Vec<Elt>>>::compare(self, &a.vec, &b.vec)
+        compare_seqs::<Self, Elt>(self, &a.vec, &b.vec)
     }
 }
 
@@ -370,7 +434,7 @@ where
             ContractCostType::MemCpy,
             Some(Elt::DECLARED_SIZE.saturating_mul(a.vec.len().min(b.vec.len()) as u64)),
         )?;
-        <Self as Compare<Vec<Elt>>>::compare(self, &a.vec, &b.vec)
+        compare_seqs::<Self, Elt>(self, &a.vec, &b.vec)
     }
 }
 
@@ -383,9 +447,10 @@ where
 ///
 /// The code is copied from the following file revision:
 /// https://github.com/rust-lang/rust/blob/8f7af88b33771ab5ec92a2a767a97a068f2ea17b/library/core/src/slice/mod.rs#L2769
-/// Modifications are no-op: switched &self to an explicit `slice` argument,
-/// removed assertions and unsafe blocks.
-pub(crate) fn binary_search_by_pre_rust_182<F, T>(slice: &[T], mut f: F) -> Result<usize, usize>
+/// Modifications are no-op: switched &self to an explicit `slice` argument
+/// (which is a [Seq] rather than a slice, for its `len` and `get`), removed
+/// assertions and unsafe blocks.
+pub(crate) fn binary_search_by_pre_rust_182<F, T>(slice: &Seq<T>, mut f: F) -> Result<usize, usize>
 where
     F: FnMut(&T) -> std::cmp::Ordering,
 {
@@ -1,3 +1,8 @@
+use crate::{
+    xdr::{ContractCostType, ScErrorCode, ScErrorType},
+    Host, HostError, U32Val, U256,
+};
+
 #[macro_export]
 macro_rules! impl_wrapping_obj_from_num {
     ($host_fn: ident, $hot: ty, $obj: ty, $num: ty) => {
@@ -67,6 +72,84 @@ macro_rules! impl_bignum_host_fns_rhs_u32 {
     };
 }
 
+#[macro_export]
+macro_rules! impl_bignum_host_fns_checked {
+    ($host_fn: ident, $method: ident, $num: ty, $valty: ty, $cost: ident) => {
+        fn $host_fn(
+            &self,
+            _vmcaller: &mut VmCaller<Self::VmUserState>,
+            lhs_val: $valty,
+            rhs_val: $valty,
+        ) -> Result<Val, Self::Error> {
+            use soroban_env_common::TryIntoVal;
+            self.charge_budget(ContractCostType::$cost, None)?;
+            let lhs: $num = lhs_val.to_val().try_into_val(self)?;
+            let rhs: $num = rhs_val.to_val().try_into_val(self)?;
+            match lhs.$method(rhs) {
+                Some(res) => Ok(res.try_into_val(self)?),
+                None => Ok(Val::VOID.to_val()),
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! impl_bignum_host_fns_rhs_u32_checked {
+    ($host_fn: ident, $method: ident, $num: ty, $valty: ty, $cost: ident) => {
+        fn $host_fn(
+            &self,
+            _vmcaller: &mut VmCaller<Self::VmUserState>,
+            lhs_val: $valty,
+            rhs_val: U32Val,
+        ) -> Result<Val, Self::Error> {
+            use soroban_env_common::TryIntoVal;
+            self.charge_budget(ContractCostType::$cost, None)?;
+            let lhs: $num = lhs_val.to_val().try_into_val(self)?;
+            match lhs.$method(rhs_val.into()) {
+                Some(res) => Ok(res.try_into_val(self)?),
+                None => Ok(Val::VOID.to_val()),
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! impl_bignum_host_fns_saturating {
+    ($host_fn: ident, $method: ident, $num: ty, $valty: ty, $cost: ident) => {
+        fn $host_fn(
+            &self,
+            _vmcaller: &mut VmCaller<Self::VmUserState>,
+            lhs_val: $valty,
+            rhs_val: $valty,
+        ) -> Result<$valty, Self::Error> {
+            use soroban_env_common::TryIntoVal;
+            self.charge_budget(ContractCostType::$cost, None)?;
+            let lhs: $num = lhs_val.to_val().try_into_val(self)?;
+            let rhs: $num = rhs_val.to_val().try_into_val(self)?;
+            let res: $num = lhs.$method(rhs);
+            Ok(res.try_into_val(self)?)
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! impl_bignum_host_fns_rhs_u32_saturating {
+    ($host_fn: ident, $method: ident, $num: ty, $valty: ty, $cost: ident) => {
+        fn $host_fn(
+            &self,
+            _vmcaller: &mut VmCaller<Self::VmUserState>,
+            lhs_val: $valty,
+            rhs_val: U32Val,
+        ) -> Result<$valty, Self::Error> {
+            use soroban_env_common::TryIntoVal;
+            self.charge_budget(ContractCostType::$cost, None)?;
+            let lhs: $num = lhs_val.to_val().try_into_val(self)?;
+            let res: $num = lhs.$method(rhs_val.into());
+            Ok(res.try_into_val(self)?)
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_bls12_381_fr_arith_host_fns {
     ($host_fn: ident, $method: ident) => {
@@ -83,3 +166,109 @@ macro_rules! impl_bls12_381_fr_arith_host_fns {
         }
     };
 }
+
+/// How [`Host::u256_mul_div_internal`] rounds the quotient.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MulDivRounding {
+    Down,
+    Up,
+    /// Rounds to the nearest integer, with ties rounded up.
+    Nearest,
+}
+
+// Returns the 512-bit product of `a` and `b` as its `(high, low)` halves.
+fn u256_widening_mul(a: U256, b: U256) -> (U256, U256) {
+    let (a_hi, a_lo) = a.into_words();
+    let (b_hi, b_lo) = b.into_words();
+    // None of the partial products of 128-bit words can overflow.
+    let ll = U256::from(a_lo).wrapping_mul(U256::from(b_lo));
+    let lh = U256::from(a_lo).wrapping_mul(U256::from(b_hi));
+    let hl = U256::from(a_hi).wrapping_mul(U256::from(b_lo));
+    let hh = U256::from(a_hi).wrapping_mul(U256::from(b_hi));
+    let (mid, mid_carry) = lh.overflowing_add(hl);
+    let (lo, lo_carry) = ll.overflowing_add(mid << 128);
+    // The whole product fits in 512 bits, so the high half can't overflow.
+    let mut hi = hh.wrapping_add(mid >> 128);
+    if mid_carry {
+        hi = hi.wrapping_add(U256::ONE << 128);
+    }
+    if lo_carry {
+        hi = hi.wrapping_add(U256::ONE);
+    }
+    (hi, lo)
+}
+
+// Divides the 512-bit number `(hi, lo)` by `d`, returning the quotient and the
+// remainder. `hi` must be less than `d`, so that the quotient fits in 256 bits.
+fn u512_div_rem(hi: U256, lo: U256, d: U256) -> (U256, U256) {
+    if hi == U256::ZERO {
+        return (lo / d, lo % d);
+    }
+    let mut rem = hi;
+    let mut quot = U256::ZERO;
+    for i in (0..256).rev() {
+        // `rem < d`, so shifting it left by one bit overflows at most once.
+        let carry = rem >> 255 != U256::ZERO;
+        rem = (rem << 1) | ((lo >> i) & U256::ONE);
+        quot <<= 1;
+        if carry || rem >= d {
+            rem = rem.wrapping_sub(d);
+            quot |= U256::ONE;
+        }
+    }
+    (quot, rem)
+}
+
+impl Host {
+    pub(crate) fn mul_div_rounding_from_u32val(
+        &self,
+        rounding: U32Val,
+    ) -> Result<MulDivRounding, HostError> {
+        match u32::from(rounding) {
+            0 => Ok(MulDivRounding::Down),
+            1 => Ok(MulDivRounding::Up),
+            2 => Ok(MulDivRounding::Nearest),
+            _ => Err(self.err(
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+                "invalid rounding mode",
+                &[rounding.to_val()],
+            )),
+        }
+    }
+
+    /// Computes `a * b / c` without losing precision in the intermediate
+    /// product.
+    pub(crate) fn u256_mul_div_internal(
+        &self,
+        a: U256,
+        b: U256,
+        c: U256,
+        rounding: MulDivRounding,
+    ) -> Result<U256, HostError> {
+        self.charge_budget(ContractCostType::Int256MulDiv, None)?;
+        let err_arith =
+            |msg: &str| self.err(ScErrorType::Object, ScErrorCode::ArithDomain, msg, &[]);
+        if c == U256::ZERO {
+            return Err(err_arith("division by zero"));
+        }
+        let (hi, lo) = u256_widening_mul(a, b);
+        if hi >= c {
+            return Err(err_arith("overflow has occured"));
+        }
+        let (quot, rem) = u512_div_rem(hi, lo, c);
+        let round_up = match rounding {
+            MulDivRounding::Down => false,
+            MulDivRounding::Up => rem != U256::ZERO,
+            // `rem < c`, so `rem >= c - rem` is `2 * rem >= c` without
+            // overflowing.
+            MulDivRounding::Nearest => rem >= c - rem,
+        };
+        if round_up {
+            quot.checked_add(U256::ONE)
+                .ok_or_else(|| err_arith("overflow has occured"))
+        } else {
+            Ok(quot)
+        }
+    }
+}
@@ -0,0 +1,713 @@
+//! This module provides the storage behind [`MeteredVector`] and
+//! [`MeteredOrdMap`]: either a flat `Vec` or a persistent, structurally-shared
+//! sequence.
+//!
+//! The flat representation is the one every released protocol uses: each
+//! "mutation" of an immutable host container produces a full copy of it, so
+//! building a container of n entries one at a time costs O(n^2).
+//!
+//! The shared representation is a B+-tree of `Rc` nodes in which every branch
+//! records the number of elements under each of its children, so elements can
+//! be addressed by position. A mutation copies only the O(log n) nodes on the
+//! path from the root to the affected leaf and shares every other node with
+//! the container it was derived from. It is only ever produced on request (see
+//! [`Seq::to_shared`]), which the host does for protocols from
+//! [`NEXT_PROTOCOL`](crate::vm::NEXT_PROTOCOL) on.
+//!
+//! Copying a node (allocating it, copying its slots and bumping the reference
+//! counts of a branch's children) is charged as `CopySharedSeqNode`, by the
+//! number of bytes in the node. Copying leaf elements' substructure is charged
+//! separately, like in any other clone.
+//!
+//! [`MeteredVector`]: super::metered_vector::MeteredVector
+//! [`MeteredOrdMap`]: super::metered_map::MeteredOrdMap
+
+use crate::{
+    budget::Budget,
+    host::MeteredClone,
+    xdr::{ContractCostType, ScErrorCode, ScErrorType},
+    Compare, Error, HostError,
+};
+
+use std::{cmp::Ordering, rc::Rc};
+
+const SEQ_OOB: Error = Error::from_type_and_code(ScErrorType::Object, ScErrorCode::IndexBounds);
+
+/// Maximum number of elements in a leaf and of children in a branch.
+pub(crate) const NODE_CAPACITY: usize = 32;
+
+/// Minimum number of elements in a leaf and of children in a branch, other
+/// than the root and the last node of each level built by
+/// [`PersistentSeq::from_slice`].
+const MIN_NODE_WIDTH: usize = NODE_CAPACITY / 2;
+
+/// Declared size of a single branch slot: a child pointer and its length.
+const BRANCH_SLOT_SIZE: u64 = 16;
+
+/// The elements of a [`MeteredVector`](super::metered_vector::MeteredVector)
+/// or [`MeteredOrdMap`](super::metered_map::MeteredOrdMap).
+#[derive(Clone)]
+pub(crate) enum Seq<A> {
+    Flat(Vec<A>),
+    Shared(PersistentSeq<A>),
+}
+
+impl<A> Default for Seq<A> {
+    fn default() -> Self {
+        Seq::Flat(Vec::new())
+    }
+}
+
+impl<A> Seq<A> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Seq::Flat(v) => v.len(),
+            Seq::Shared(s) => s.len,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn is_shared(&self) -> bool {
+        matches!(self, Seq::Shared(_))
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&A> {
+        match self {
+            Seq::Flat(v) => v.get(index),
+            Seq::Shared(s) => s.get(index),
+        }
+    }
+
+    pub(crate) fn first(&self) -> Option<&A> {
+        self.get(0)
+    }
+
+    pub(crate) fn last(&self) -> Option<&A> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub(crate) fn iter(&self) -> SeqIter<'_, A> {
+        match self {
+            Seq::Flat(v) => SeqIter::Flat(v.iter()),
+            Seq::Shared(s) => SeqIter::Shared(s.iter()),
+        }
+    }
+
+    /// Returns the elements as a `Vec`, if they are stored flat.
+    pub(crate) fn as_flat(&self) -> Option<&Vec<A>> {
+        match self {
+            Seq::Flat(v) => Some(v),
+            Seq::Shared(_) => None,
+        }
+    }
+}
+
+impl<A: MeteredClone> Seq<A> {
+    /// Returns the elements as a mutable slice, flattening a shared sequence
+    /// first. Flattening is charged as a full copy of the elements.
+    pub(crate) fn make_flat_mut(&mut self, budget: &Budget) -> Result<&mut [A], HostError> {
+        if let Seq::Shared(s) = self {
+            *self = Seq::Flat(s.to_vec(budget)?);
+        }
+        match self {
+            Seq::Flat(v) => Ok(v.as_mut_slice()),
+            Seq::Shared(_) => Err((ScErrorType::Object, ScErrorCode::InternalError).into()),
+        }
+    }
+
+    /// Returns the elements as a `Vec`. Flattening a shared sequence is
+    /// charged as a full copy of the elements.
+    pub(crate) fn into_vec(self, budget: &Budget) -> Result<Vec<A>, HostError> {
+        match self {
+            Seq::Flat(v) => Ok(v),
+            Seq::Shared(s) => s.to_vec(budget),
+        }
+    }
+
+    /// Returns a shared copy of the sequence: a cheap `Rc` copy if it is
+    /// already shared, else a tree built from (and charged as) a full copy of
+    /// its elements.
+    pub(crate) fn to_shared(&self, budget: &Budget) -> Result<PersistentSeq<A>, HostError> {
+        match self {
+            Seq::Flat(v) => PersistentSeq::from_slice(v.as_slice(), budget),
+            Seq::Shared(s) => Ok(s.clone()),
+        }
+    }
+
+    /// Charges for a clone of the sequence's substructure. Cloning a shared
+    /// sequence only clones the `Rc` of its root, so it is free: copying its
+    /// elements is charged if it's ever flattened.
+    pub(crate) fn charge_for_substructure(&self, budget: &Budget) -> Result<(), HostError> {
+        match self {
+            Seq::Flat(v) => v.charge_for_substructure(budget),
+            Seq::Shared(_) => Ok(()),
+        }
+    }
+}
+
+impl<A: std::hash::Hash> std::hash::Hash for Seq<A> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            Seq::Flat(v) => v.hash(state),
+            Seq::Shared(s) => {
+                state.write_usize(s.len);
+                for elt in s.iter() {
+                    elt.hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// Compares two sequences lexicographically, the same way `Compare<Vec<A>>`
+/// does (to which this delegates when both are flat).
+pub(crate) fn compare_seqs<C, A>(ctx: &C, a: &Seq<A>, b: &Seq<A>) -> Result<Ordering, C::Error>
+where
+    C: Compare<A>,
+{
+    if let (Some(a), Some(b)) = (a.as_flat(), b.as_flat()) {
+        return <C as Compare<Vec<A>>>::compare(ctx, a, b);
+    }
+    let (mut a, mut b) = (a.iter(), b.iter());
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ok(Ordering::Equal),
+            (None, Some(_)) => return Ok(Ordering::Less),
+            (Some(_), None) => return Ok(Ordering::Greater),
+            (Some(a), Some(b)) => match <C as Compare<A>>::compare(ctx, a, b)? {
+                Ordering::Equal => (),
+                unequal => return Ok(unequal),
+            },
+        }
+    }
+}
+
+// The children of a branch, each paired with the number of elements under it.
+type Children<A> = Vec<(usize, Rc<Node<A>>)>;
+
+enum Node<A> {
+    Leaf(Vec<A>),
+    Branch(Children<A>),
+}
+
+impl<A> Node<A> {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(elts) => elts.len(),
+            Node::Branch(children) => children.iter().map(|(n, _)| n).sum(),
+        }
+    }
+
+    // The number of elements of a leaf or of children of a branch.
+    fn width(&self) -> usize {
+        match self {
+            Node::Leaf(elts) => elts.len(),
+            Node::Branch(children) => children.len(),
+        }
+    }
+}
+
+/// A persistent sequence: cloning it is O(1) and every update returns a new
+/// sequence sharing all but O(log n) nodes with the original.
+///
+/// Nodes are split in halves when they overflow, and merged with (or refilled
+/// from) a sibling when removals leave them less than half full, so the tree
+/// stays balanced under any sequence of updates.
+pub(crate) struct PersistentSeq<A> {
+    root: Rc<Node<A>>,
+    len: usize,
+}
+
+impl<A> Clone for PersistentSeq<A> {
+    fn clone(&self) -> Self {
+        Self {
+            root: Rc::clone(&self.root),
+            len: self.len,
+        }
+    }
+}
+
+impl<A> PersistentSeq<A> {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn get(&self, mut index: usize) -> Option<&A> {
+        if index >= self.len {
+            return None;
+        }
+        let mut node = &*self.root;
+        loop {
+            match node {
+                Node::Leaf(elts) => return elts.get(index),
+                Node::Branch(children) => {
+                    let (pos, offset) = locate(children, index)?;
+                    node = &children.get(pos)?.1;
+                    index = offset;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_, A> {
+        Iter {
+            seq: self,
+            front: [].iter(),
+            back: [].iter(),
+            front_pos: 0,
+            back_pos: self.len,
+        }
+    }
+
+    /// Returns the leaf holding the element at `index`, along with the
+    /// element's index within it.
+    fn leaf_at(&self, mut index: usize) -> Option<(&[A], usize)> {
+        let mut node = &*self.root;
+        loop {
+            match node {
+                Node::Leaf(elts) => return Some((elts.as_slice(), index)),
+                Node::Branch(children) => {
+                    let (pos, offset) = locate(children, index)?;
+                    node = &children.get(pos)?.1;
+                    index = offset;
+                }
+            }
+        }
+    }
+
+    /// Collapses any chain of single-child branches at the root.
+    fn with_root(mut root: Rc<Node<A>>, len: usize) -> Self {
+        while let Node::Branch(children) = &*root {
+            match children.as_slice() {
+                [(_, only)] => {
+                    let only = Rc::clone(only);
+                    root = only;
+                }
+                _ => break,
+            }
+        }
+        Self { root, len }
+    }
+}
+
+impl<A: MeteredClone> PersistentSeq<A> {
+    /// Copies the elements into a `Vec`, charged as a full copy.
+    pub(crate) fn to_vec(&self, budget: &Budget) -> Result<Vec<A>, HostError> {
+        super::metered_clone::charge_heap_alloc::<A>(self.len as u64, budget)?;
+        super::metered_clone::charge_shallow_copy::<A>(self.len as u64, budget)?;
+        if !A::IS_SHALLOW {
+            for elt in self.iter() {
+                elt.charge_for_substructure(budget)?;
+            }
+        }
+        Ok(self.iter().cloned().collect())
+    }
+
+    /// Builds a balanced tree out of a copy of `elts`, with full nodes.
+    pub(crate) fn from_slice(elts: &[A], budget: &Budget) -> Result<Self, HostError> {
+        if u32::try_from(elts.len()).is_err() {
+            return Err(SEQ_OOB.into());
+        }
+        let mut level: Children<A> = Vec::new();
+        for chunk in elts.chunks(NODE_CAPACITY) {
+            let leaf = copy_leaf(chunk, budget)?;
+            level.push((chunk.len(), Rc::new(Node::Leaf(leaf))));
+        }
+        while level.len() > 1 {
+            let mut next = Vec::new();
+            for chunk in level.chunks(NODE_CAPACITY) {
+                let branch = copy_branch(chunk, budget)?;
+                let len = chunk.iter().map(|(n, _)| n).sum();
+                next.push((len, Rc::new(Node::Branch(branch))));
+            }
+            level = next;
+        }
+        let root = match level.pop() {
+            Some((_, root)) => root,
+            None => Rc::new(Node::Leaf(Vec::new())),
+        };
+        Ok(Self {
+            root,
+            len: elts.len(),
+        })
+    }
+
+    pub(crate) fn set(&self, index: usize, value: A, budget: &Budget) -> Result<Self, HostError> {
+        if index >= self.len {
+            return Err(SEQ_OOB.into());
+        }
+        let root = set_in(&self.root, index, value, budget)?;
+        Ok(Self {
+            root: Rc::new(root),
+            len: self.len,
+        })
+    }
+
+    pub(crate) fn insert(
+        &self,
+        index: usize,
+        value: A,
+        budget: &Budget,
+    ) -> Result<Self, HostError> {
+        if index > self.len || self.len >= u32::MAX as usize {
+            return Err(SEQ_OOB.into());
+        }
+        let len = self.len.saturating_add(1);
+        let root = match insert_in(&self.root, index, value, budget)? {
+            (node, None) => Rc::new(node),
+            (left, Some(right)) => {
+                let children = [(left.len(), Rc::new(left)), (right.len(), Rc::new(right))];
+                Rc::new(Node::Branch(copy_branch(&children, budget)?))
+            }
+        };
+        Ok(Self { root, len })
+    }
+
+    pub(crate) fn remove(&self, index: usize, budget: &Budget) -> Result<Self, HostError> {
+        if index >= self.len {
+            return Err(SEQ_OOB.into());
+        }
+        let len = self.len.saturating_sub(1);
+        let root = match remove_in(&self.root, index, budget)? {
+            Some(node) => Rc::new(node),
+            None => Rc::new(Node::Leaf(Vec::new())),
+        };
+        Ok(Self::with_root(root, len))
+    }
+}
+
+/// Finds the child of a branch holding the element at `index`, returning the
+/// child's position and the element's index within it.
+fn locate<A>(children: &[(usize, Rc<Node<A>>)], mut index: usize) -> Option<(usize, usize)> {
+    for (pos, (n, _)) in children.iter().enumerate() {
+        if index < *n {
+            return Some((pos, index));
+        }
+        index -= n;
+    }
+    None
+}
+
+/// Like [`locate`], but also accepts the position one past the last element,
+/// which it places at the end of the last child.
+fn locate_for_insert<A>(children: &[(usize, Rc<Node<A>>)], index: usize) -> Option<(usize, usize)> {
+    locate(children, index).or_else(|| {
+        let last = children.len().checked_sub(1)?;
+        let (n, _) = children.get(last)?;
+        Some((last, *n))
+    })
+}
+
+fn charge_node_copy(slots: usize, slot_size: u64, budget: &Budget) -> Result<(), HostError> {
+    budget.charge(
+        ContractCostType::CopySharedSeqNode,
+        Some(slot_size.saturating_mul(slots as u64)),
+    )
+}
+
+// Copies leaf elements with room for one more, so that an insertion into the
+// copy does not reallocate.
+fn copy_leaf<A: MeteredClone>(elts: &[A], budget: &Budget) -> Result<Vec<A>, HostError> {
+    charge_node_copy(elts.len().saturating_add(1), A::DECLARED_SIZE, budget)?;
+    A::bulk_charge_for_substructure(elts, budget)?;
+    let mut copy = Vec::with_capacity(elts.len().saturating_add(1));
+    copy.extend_from_slice(elts);
+    Ok(copy)
+}
+
+fn copy_branch<A>(
+    children: &[(usize, Rc<Node<A>>)],
+    budget: &Budget,
+) -> Result<Children<A>, HostError> {
+    charge_node_copy(children.len().saturating_add(1), BRANCH_SLOT_SIZE, budget)?;
+    let mut copy = Vec::with_capacity(children.len().saturating_add(1));
+    copy.extend(children.iter().map(|(n, c)| (*n, Rc::clone(c))));
+    Ok(copy)
+}
+
+fn set_in<A: MeteredClone>(
+    node: &Node<A>,
+    index: usize,
+    value: A,
+    budget: &Budget,
+) -> Result<Node<A>, HostError> {
+    match node {
+        Node::Leaf(elts) => {
+            let mut elts = copy_leaf(elts, budget)?;
+            let cell = elts.get_mut(index).ok_or(SEQ_OOB)?;
+            *cell = value;
+            Ok(Node::Leaf(elts))
+        }
+        Node::Branch(children) => {
+            let (pos, offset) = locate(children, index).ok_or(SEQ_OOB)?;
+            let (n, child) = children.get(pos).ok_or(SEQ_OOB)?;
+            let child = set_in(child, offset, value, budget)?;
+            let mut children = copy_branch(children, budget)?;
+            let slot = children.get_mut(pos).ok_or(SEQ_OOB)?;
+            *slot = (*n, Rc::new(child));
+            Ok(Node::Branch(children))
+        }
+    }
+}
+
+/// Inserts into a copy of `node`, returning the copy and, if it overflowed,
+/// the upper half split off of it.
+fn insert_in<A: MeteredClone>(
+    node: &Node<A>,
+    index: usize,
+    value: A,
+    budget: &Budget,
+) -> Result<(Node<A>, Option<Node<A>>), HostError> {
+    match node {
+        Node::Leaf(elts) => {
+            if index > elts.len() {
+                return Err(SEQ_OOB.into());
+            }
+            let mut elts = copy_leaf(elts, budget)?;
+            elts.insert(index, value);
+            if elts.len() > NODE_CAPACITY {
+                let upper = elts.split_off(elts.len() / 2);
+                Ok((Node::Leaf(elts), Some(Node::Leaf(upper))))
+            } else {
+                Ok((Node::Leaf(elts), None))
+            }
+        }
+        Node::Branch(children) => {
+            let (pos, offset) = locate_for_insert(children, index).ok_or(SEQ_OOB)?;
+            let (_, child) = children.get(pos).ok_or(SEQ_OOB)?;
+            let (left, right) = insert_in(child, offset, value, budget)?;
+            let mut children = copy_branch(children, budget)?;
+            let slot = children.get_mut(pos).ok_or(SEQ_OOB)?;
+            *slot = (left.len(), Rc::new(left));
+            if let Some(right) = right {
+                children.insert(pos.saturating_add(1), (right.len(), Rc::new(right)));
+            }
+            if children.len() > NODE_CAPACITY {
+                let upper = children.split_off(children.len() / 2);
+                Ok((Node::Branch(children), Some(Node::Branch(upper))))
+            } else {
+                Ok((Node::Branch(children), None))
+            }
+        }
+    }
+}
+
+/// Removes from a copy of `node`, returning `None` if the copy is left empty.
+/// The copy may be left less than half full, which its parent then fixes.
+fn remove_in<A: MeteredClone>(
+    node: &Node<A>,
+    index: usize,
+    budget: &Budget,
+) -> Result<Option<Node<A>>, HostError> {
+    match node {
+        Node::Leaf(elts) => {
+            if index >= elts.len() {
+                return Err(SEQ_OOB.into());
+            }
+            let mut elts = copy_leaf(elts, budget)?;
+            elts.remove(index);
+            Ok((!elts.is_empty()).then_some(Node::Leaf(elts)))
+        }
+        Node::Branch(children) => {
+            let (pos, offset) = locate(children, index).ok_or(SEQ_OOB)?;
+            let (_, child) = children.get(pos).ok_or(SEQ_OOB)?;
+            let child = remove_in(child, offset, budget)?;
+            let mut children = copy_branch(children, budget)?;
+            match child {
+                Some(child) => {
+                    let underflow = child.width() < MIN_NODE_WIDTH;
+                    let slot = children.get_mut(pos).ok_or(SEQ_OOB)?;
+                    *slot = (child.len(), Rc::new(child));
+                    if underflow {
+                        rebalance(&mut children, pos, budget)?;
+                    }
+                }
+                None => {
+                    children.remove(pos);
+                }
+            }
+            Ok((!children.is_empty()).then_some(Node::Branch(children)))
+        }
+    }
+}
+
+/// Fixes the underflowing child at `pos` of a (copied) branch, by merging it
+/// with a sibling if they fit in one node, or else by splitting their
+/// entries evenly between them. Either way, both end up at least half full.
+fn rebalance<A: MeteredClone>(
+    children: &mut Children<A>,
+    pos: usize,
+    budget: &Budget,
+) -> Result<(), HostError> {
+    if children.len() < 2 {
+        return Ok(());
+    }
+    let left = pos.min(children.len().saturating_sub(2));
+    let right = left.saturating_add(1);
+    let (_, l) = children.get(left).ok_or(SEQ_OOB)?;
+    let (_, r) = children.get(right).ok_or(SEQ_OOB)?;
+    let (merged, upper) = match (&**l, &**r) {
+        (Node::Leaf(l), Node::Leaf(r)) => {
+            let mut elts = concat_leaves(l, r, budget)?;
+            let upper = split_if_overflowing(&mut elts, A::DECLARED_SIZE, budget)?;
+            (Node::Leaf(elts), upper.map(Node::Leaf))
+        }
+        (Node::Branch(l), Node::Branch(r)) => {
+            let mut children = concat_branches(l, r, budget)?;
+            let upper = split_if_overflowing(&mut children, BRANCH_SLOT_SIZE, budget)?;
+            (Node::Branch(children), upper.map(Node::Branch))
+        }
+        // Siblings are always at the same depth.
+        _ => return Err((ScErrorType::Object, ScErrorCode::InternalError).into()),
+    };
+    let slot = children.get_mut(left).ok_or(SEQ_OOB)?;
+    *slot = (merged.len(), Rc::new(merged));
+    match upper {
+        Some(upper) => {
+            let slot = children.get_mut(right).ok_or(SEQ_OOB)?;
+            *slot = (upper.len(), Rc::new(upper));
+        }
+        None => {
+            children.remove(right);
+        }
+    }
+    Ok(())
+}
+
+fn concat_leaves<A: MeteredClone>(l: &[A], r: &[A], budget: &Budget) -> Result<Vec<A>, HostError> {
+    let len = l.len().saturating_add(r.len());
+    charge_node_copy(len, A::DECLARED_SIZE, budget)?;
+    A::bulk_charge_for_substructure(l, budget)?;
+    A::bulk_charge_for_substructure(r, budget)?;
+    let mut elts = Vec::with_capacity(len);
+    elts.extend_from_slice(l);
+    elts.extend_from_slice(r);
+    Ok(elts)
+}
+
+fn concat_branches<A>(
+    l: &[(usize, Rc<Node<A>>)],
+    r: &[(usize, Rc<Node<A>>)],
+    budget: &Budget,
+) -> Result<Children<A>, HostError> {
+    let len = l.len().saturating_add(r.len());
+    charge_node_copy(len, BRANCH_SLOT_SIZE, budget)?;
+    let mut children = Vec::with_capacity(len);
+    children.extend(l.iter().chain(r).map(|(n, c)| (*n, Rc::clone(c))));
+    Ok(children)
+}
+
+// Splits the upper half off of a merged node that doesn't fit in one node.
+fn split_if_overflowing<T>(
+    entries: &mut Vec<T>,
+    slot_size: u64,
+    budget: &Budget,
+) -> Result<Option<Vec<T>>, HostError> {
+    if entries.len() <= NODE_CAPACITY {
+        return Ok(None);
+    }
+    let mid = entries.len() / 2;
+    charge_node_copy(entries.len().saturating_sub(mid), slot_size, budget)?;
+    Ok(Some(entries.split_off(mid)))
+}
+
+/// Iterator over the elements of a [`PersistentSeq`]. It looks up one leaf
+/// at a time from the root, from either end, so it allocates nothing.
+pub struct Iter<'a, A> {
+    seq: &'a PersistentSeq<A>,
+    front: std::slice::Iter<'a, A>,
+    back: std::slice::Iter<'a, A>,
+    // The elements in `front_pos..back_pos` are in neither `front` nor `back`.
+    front_pos: usize,
+    back_pos: usize,
+}
+
+impl<'a, A> Iterator for Iter<'a, A> {
+    type Item = &'a A;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elt) = self.front.next() {
+                return Some(elt);
+            }
+            if self.front_pos >= self.back_pos {
+                return self.back.next();
+            }
+            let (leaf, offset) = self.seq.leaf_at(self.front_pos)?;
+            let end = leaf
+                .len()
+                .min(offset.saturating_add(self.back_pos - self.front_pos));
+            let elts = leaf.get(offset..end)?;
+            self.front_pos = self.front_pos.saturating_add(elts.len());
+            self.front = elts.iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self
+            .front
+            .len()
+            .saturating_add(self.back.len())
+            .saturating_add(self.back_pos.saturating_sub(self.front_pos));
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, A> DoubleEndedIterator for Iter<'a, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elt) = self.back.next_back() {
+                return Some(elt);
+            }
+            if self.front_pos >= self.back_pos {
+                return self.front.next_back();
+            }
+            let (leaf, offset) = self.seq.leaf_at(self.back_pos - 1)?;
+            let end = offset.saturating_add(1);
+            let start = end.saturating_sub(self.back_pos - self.front_pos);
+            let elts = leaf.get(start..end)?;
+            self.back_pos = self.back_pos.saturating_sub(elts.len());
+            self.back = elts.iter();
+        }
+    }
+}
+
+impl<'a, A> ExactSizeIterator for Iter<'a, A> {}
+
+/// Iterator over the elements of a [`Seq`].
+pub enum SeqIter<'a, A> {
+    Flat(std::slice::Iter<'a, A>),
+    Shared(Iter<'a, A>),
+}
+
+impl<'a, A> Iterator for SeqIter<'a, A> {
+    type Item = &'a A;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            SeqIter::Flat(it) => it.next(),
+            SeqIter::Shared(it) => it.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            SeqIter::Flat(it) => it.size_hint(),
+            SeqIter::Shared(it) => it.size_hint(),
+        }
+    }
+}
+
+impl<'a, A> DoubleEndedIterator for SeqIter<'a, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            SeqIter::Flat(it) => it.next_back(),
+            SeqIter::Shared(it) => it.next_back(),
+        }
+    }
+}
+
+impl<'a, A> ExactSizeIterator for SeqIter<'a, A> {}
@@ -135,7 +135,7 @@ impl Prng {
         // We charge for both the PRNG draws and the swaps here (as "memcpys").
         self.charge_prng_bytes(budget, 16u64.saturating_mul(v.len() as u64))?;
         budget.charge(ContractCostType::MemCpy, Some(v.len() as u64))?;
-        v2.as_mut_slice().shuffle(&mut self.0);
+        v2.as_mut_slice(budget)?.shuffle(&mut self.0);
         Ok(v2)
     }
 
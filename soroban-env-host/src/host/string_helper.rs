@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use crate::{
+    budget::AsBudget,
+    host::metered_clone::MeteredContainer,
+    xdr::{ContractCostType, ScErrorCode, ScErrorType, ScString},
+    Host, HostError, U256,
+};
+
+// The longest decimal representations of the supported integer types.
+const I128_MAX_DECIMAL_LEN: usize = 40;
+const U256_MAX_DECIMAL_LEN: usize = 78;
+
+// Converting between a 256-bit (or smaller) integer and its decimal
+// representation takes one 256-bit division (formatting) or one
+// multiply-and-add (parsing) per chunk of this many digits.
+const DECIMAL_DIGITS_PER_CHUNK: u64 = 19;
+
+impl Host {
+    // Notes on metering: every candidate position is compared against the
+    // whole pattern in the worst case, which is charged upfront.
+    pub(crate) fn metered_find_in_slice(
+        &self,
+        haystack: &[u8],
+        pattern: &[u8],
+        from: u32,
+    ) -> Result<Option<u32>, HostError> {
+        self.validate_index_le_bound(from, haystack.len())?;
+        let haystack = &haystack[from as usize..];
+        if pattern.is_empty() {
+            return Ok(Some(from));
+        }
+        if pattern.len() > haystack.len() {
+            return Ok(None);
+        }
+        let positions = haystack.len() - pattern.len() + 1;
+        self.charge_budget(
+            ContractCostType::MemCmp,
+            Some((positions as u64).saturating_mul(pattern.len() as u64)),
+        )?;
+        // `haystack` fits in a `u32` as it comes from a host object.
+        Ok(haystack
+            .windows(pattern.len())
+            .position(|w| w == pattern)
+            .map(|i| from + i as u32))
+    }
+
+    // Notes on metering: a linear scan of the bytes, charged like comparing
+    // them.
+    pub(crate) fn metered_is_valid_utf8(&self, s: &[u8]) -> Result<bool, HostError> {
+        self.charge_budget(ContractCostType::MemCmp, Some(s.len() as u64))?;
+        Ok(std::str::from_utf8(s).is_ok())
+    }
+
+    fn charge_decimal_conversion(&self, digits: usize) -> Result<(), HostError> {
+        self.as_budget().bulk_charge(
+            ContractCostType::Int256Div,
+            (digits as u64).div_ceil(DECIMAL_DIGITS_PER_CHUNK),
+            None,
+        )
+    }
+
+    fn scstring_from_decimal(&self, s: String) -> Result<ScString, HostError> {
+        Vec::<u8>::charge_bulk_init_cpy(s.len() as u64, self.as_budget())?;
+        Ok(ScString(s.into_bytes().try_into()?))
+    }
+
+    pub(crate) fn i128_to_decimal_scstring(&self, v: i128) -> Result<ScString, HostError> {
+        self.charge_decimal_conversion(I128_MAX_DECIMAL_LEN)?;
+        self.scstring_from_decimal(v.to_string())
+    }
+
+    pub(crate) fn u256_to_decimal_scstring(&self, v: U256) -> Result<ScString, HostError> {
+        self.charge_decimal_conversion(U256_MAX_DECIMAL_LEN)?;
+        self.scstring_from_decimal(v.to_string())
+    }
+
+    // Only plain decimal numbers are accepted: an optional `-` (when `signed`)
+    // followed by at least one ASCII digit, without whitespace or a `+`. The
+    // length is bounded by `max_len` so that the charge stays small.
+    fn decimal_str_from_bytes<'a>(
+        &self,
+        s: &'a [u8],
+        signed: bool,
+        max_len: usize,
+    ) -> Result<&'a str, HostError> {
+        let digits = match s {
+            [b'-', rest @ ..] if signed => rest,
+            _ => s,
+        };
+        if digits.is_empty() || s.len() > max_len || !digits.iter().all(u8::is_ascii_digit) {
+            return Err(self.err(
+                ScErrorType::Value,
+                ScErrorCode::InvalidInput,
+                "invalid decimal integer string",
+                &[],
+            ));
+        }
+        self.charge_decimal_conversion(s.len())?;
+        // All the bytes are ASCII, so this can't fail.
+        std::str::from_utf8(s).map_err(|_| {
+            self.err(
+                ScErrorType::Value,
+                ScErrorCode::InternalError,
+                "decimal integer string is not ASCII",
+                &[],
+            )
+        })
+    }
+
+    fn err_decimal_overflow(&self) -> HostError {
+        self.err(
+            ScErrorType::Value,
+            ScErrorCode::ArithDomain,
+            "decimal integer string is out of range",
+            &[],
+        )
+    }
+
+    pub(crate) fn i128_from_decimal_bytes(&self, s: &[u8]) -> Result<i128, HostError> {
+        // Leading zeros are allowed, so allow some room for them.
+        let s = self.decimal_str_from_bytes(s, true, 2 * I128_MAX_DECIMAL_LEN)?;
+        i128::from_str(s).map_err(|_| self.err_decimal_overflow())
+    }
+
+    pub(crate) fn u256_from_decimal_bytes(&self, s: &[u8]) -> Result<U256, HostError> {
+        let s = self.decimal_str_from_bytes(s, false, 2 * U256_MAX_DECIMAL_LEN)?;
+        U256::from_str_radix(s, 10).map_err(|_| self.err_decimal_overflow())
+    }
+}
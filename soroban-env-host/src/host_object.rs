@@ -1,13 +1,14 @@
 #![allow(dead_code)]
 
 use crate::{
-    budget::Budget,
+    budget::{AsBudget, Budget},
     host::{
         metered_clone::{self, MeteredClone},
         metered_map::MeteredOrdMap,
         metered_vector::MeteredVector,
     },
     num::{I256, U256},
+    vm::NEXT_PROTOCOL,
     xdr::{self, ContractCostType, ScErrorCode, ScErrorType, SCSYMBOL_LIMIT},
     AddressObject, BytesObject, Compare, DurationObject, DurationSmall, Host, HostError,
     I128Object, I128Small, I256Object, I256Small, I64Object, I64Small, MapObject, Object,
@@ -15,6 +16,8 @@ use crate::{
     TryFromVal, U128Object, U128Small, U256Object, U256Small, U64Object, U64Small, Val, VecObject,
 };
 
+use std::borrow::Cow;
+
 pub(crate) type HostMap = MeteredOrdMap<Val, Val, Host>;
 pub(crate) type HostVec = MeteredVector<Val>;
 
@@ -389,6 +392,37 @@ impl Host {
         Ok(HOT::new_from_handle(handle))
     }
 
+    /// Returns whether host vectors and maps are updated as persistent,
+    /// structurally shared sequences, which copy O(log n) nodes per update
+    /// rather than the whole container. This is the case from
+    /// [`NEXT_PROTOCOL`] on; a host without ledger info keeps flat containers.
+    pub(crate) fn shares_container_structure(&self) -> Result<bool, HostError> {
+        Ok(self
+            .try_borrow_ledger()?
+            .as_ref()
+            .is_some_and(|li| li.protocol_version >= NEXT_PROTOCOL))
+    }
+
+    /// Returns the vector a host function should update in place of `hv`:
+    /// `hv` itself, or a structurally shared copy of it if the protocol shares
+    /// container structure and it is not shared yet.
+    pub(crate) fn updatable_vec<'a>(&self, hv: &'a HostVec) -> Result<Cow<'a, HostVec>, HostError> {
+        if !hv.is_shared() && self.shares_container_structure()? {
+            Ok(Cow::Owned(hv.to_shared(self.as_budget())?))
+        } else {
+            Ok(Cow::Borrowed(hv))
+        }
+    }
+
+    /// Like [`Host::updatable_vec`], for maps.
+    pub(crate) fn updatable_map<'a>(&self, hm: &'a HostMap) -> Result<Cow<'a, HostMap>, HostError> {
+        if !hm.is_shared() && self.shares_container_structure()? {
+            Ok(Cow::Owned(hm.to_shared(self)?))
+        } else {
+            Ok(Cow::Borrowed(hm))
+        }
+    }
+
     pub(crate) fn visit_obj_untyped<F, U>(
         &self,
         obj: impl Into<Object>,
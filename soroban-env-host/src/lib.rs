@@ -27,6 +27,7 @@ pub mod budget;
 pub mod events;
 pub use events::diagnostic::DiagnosticLevel;
 mod builtin_contracts;
+pub use builtin_contracts::{stellar_asset_contract_spec, stellar_asset_contract_spec_xdr};
 pub(crate) mod crypto;
 mod host;
 pub(crate) mod host_object;
@@ -4,6 +4,10 @@ mod address;
 mod auth;
 mod basic;
 mod bls12_381;
+#[cfg(feature = "next")]
+mod bn254;
+#[cfg(feature = "next")]
+mod budget_capped_call;
 mod budget_metering;
 mod bytes;
 mod complex;
@@ -14,25 +18,35 @@ mod e2e_tests;
 mod event;
 mod finish;
 mod frame;
+mod golden_trace;
 mod host;
 mod hostile;
 #[cfg(opt_build)]
 mod hostile_opt;
+mod insn_profile;
 mod invocation;
+#[cfg(feature = "json")]
+mod json;
 mod ledger;
 mod lifecycle;
 mod lifetime_extension;
 mod linear_memory;
 mod map;
+mod memory_snapshot;
 #[cfg(feature = "testutils")]
 mod metering_benchmark;
 mod num;
+#[cfg(feature = "next")]
+mod poseidon;
 mod post_mvp;
 mod prng;
 mod protocol_gate;
+#[cfg(feature = "next")]
+mod reentry;
 mod stellar_asset_contract;
 mod storage;
 mod str;
 mod symbol;
 mod tuple;
 mod vec;
+mod wasm_backtrace;
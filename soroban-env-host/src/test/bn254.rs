@@ -0,0 +1,280 @@
+use crate::{
+    xdr::{ScErrorCode, ScErrorType},
+    BytesObject, Env, EnvBase, Host, HostError, U256Val, U32Val, Val, VecObject,
+};
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use hex::FromHex;
+use rand::{rngs::StdRng, SeedableRng};
+
+const MODULUS: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+const SUBGROUP_ORDER: &str = "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+
+// `2 * G1::generator()`, as used in the EIP-196 test vectors.
+const G1_DOUBLE_GENERATOR: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+
+// `G2::generator()` in the EIP-197 encoding, i.e. `(x_c1, x_c0, y_c1, y_c0)`.
+const G2_GENERATOR: &str = "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c21800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+fn fq_be_bytes(fq: &Fq) -> Vec<u8> {
+    fq.into_bigint().to_bytes_be()
+}
+
+fn g1_bytes(pt: &G1Affine) -> Vec<u8> {
+    match pt.xy() {
+        Some((x, y)) => [fq_be_bytes(x), fq_be_bytes(y)].concat(),
+        None => vec![0; 64],
+    }
+}
+
+fn g2_bytes(pt: &G2Affine) -> Vec<u8> {
+    match pt.xy() {
+        Some((x, y)) => [
+            fq_be_bytes(&x.c1),
+            fq_be_bytes(&x.c0),
+            fq_be_bytes(&y.c1),
+            fq_be_bytes(&y.c0),
+        ]
+        .concat(),
+        None => vec![0; 128],
+    }
+}
+
+fn bytes_obj(host: &Host, bytes: &[u8]) -> Result<BytesObject, HostError> {
+    host.bytes_new_from_slice(bytes)
+}
+
+fn g1_obj(host: &Host, pt: &G1Affine) -> Result<BytesObject, HostError> {
+    bytes_obj(host, &g1_bytes(pt))
+}
+
+fn g2_obj(host: &Host, pt: &G2Affine) -> Result<BytesObject, HostError> {
+    bytes_obj(host, &g2_bytes(pt))
+}
+
+fn u256_val(host: &Host, bytes: &[u8]) -> Result<U256Val, HostError> {
+    let mut pieces = [0u64; 4];
+    for (i, chunk) in bytes.chunks(8).enumerate() {
+        pieces[i] = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    Ok(host
+        .obj_from_u256_pieces(pieces[0], pieces[1], pieces[2], pieces[3])?
+        .into())
+}
+
+fn fr_val(host: &Host, fr: &Fr) -> Result<U256Val, HostError> {
+    u256_val(host, &fr.into_bigint().to_bytes_be())
+}
+
+fn obj_vec(host: &Host, vals: Vec<Val>) -> Result<VecObject, HostError> {
+    host.vec_new_from_slice(&vals)
+}
+
+fn assert_bytes_eq(host: &Host, bo: BytesObject, expected: &[u8]) -> Result<(), HostError> {
+    let len: u32 = host.bytes_len(bo)?.into();
+    let mut buf = vec![0u8; len as usize];
+    host.bytes_copy_to_slice(bo, U32Val::from(0), &mut buf)?;
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+fn assert_invalid_input<T: std::fmt::Debug>(res: Result<T, HostError>) {
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Crypto, ScErrorCode::InvalidInput)
+    ));
+}
+
+fn g2_not_in_subgroup(rng: &mut StdRng) -> G2Affine {
+    loop {
+        let x = Fq2::rand(rng);
+        if let Some(pt) = G2Affine::get_point_from_x_unchecked(x, true) {
+            if !pt.is_in_correct_subgroup_assuming_on_curve() {
+                return pt;
+            }
+        }
+    }
+}
+
+#[test]
+fn g1_add_and_mul_known_vectors() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    let g = g1_obj(&host, &G1Affine::generator())?;
+    let expected = Vec::from_hex(G1_DOUBLE_GENERATOR).unwrap();
+
+    let sum = host.bn254_g1_add(g, g)?;
+    assert_bytes_eq(&host, sum, &expected)?;
+
+    let product = host.bn254_g1_mul(g, U256Val::from_u32(2))?;
+    assert_bytes_eq(&host, product, &expected)?;
+
+    // multiplying by the subgroup order yields the point at infinity
+    let order = u256_val(&host, &Vec::from_hex(SUBGROUP_ORDER).unwrap())?;
+    let zero = host.bn254_g1_mul(g, order)?;
+    assert_bytes_eq(&host, zero, &[0u8; 64])?;
+    Ok(())
+}
+
+#[test]
+fn g1_ops_match_reference() -> Result<(), HostError> {
+    let mut rng = StdRng::from_seed([0xff; 32]);
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    for _ in 0..10 {
+        let p0 = G1Affine::rand(&mut rng);
+        let p1 = G1Affine::rand(&mut rng);
+        let s = Fr::rand(&mut rng);
+
+        let res = host.bn254_g1_add(g1_obj(&host, &p0)?, g1_obj(&host, &p1)?)?;
+        assert_bytes_eq(&host, res, &g1_bytes(&(p0 + p1).into_affine()))?;
+
+        let res = host.bn254_g1_mul(g1_obj(&host, &p0)?, fr_val(&host, &s)?)?;
+        assert_bytes_eq(&host, res, &g1_bytes(&(p0 * s).into_affine()))?;
+    }
+    // adding the point at infinity is the identity
+    let p0 = G1Affine::rand(&mut rng);
+    let res = host.bn254_g1_add(g1_obj(&host, &p0)?, bytes_obj(&host, &[0u8; 64])?)?;
+    assert_bytes_eq(&host, res, &g1_bytes(&p0))?;
+    // adding a point to its negation gives the point at infinity
+    let res = host.bn254_g1_add(g1_obj(&host, &p0)?, g1_obj(&host, &-p0)?)?;
+    assert_bytes_eq(&host, res, &[0u8; 64])?;
+    Ok(())
+}
+
+#[test]
+fn g1_msm() -> Result<(), HostError> {
+    let mut rng = StdRng::from_seed([0xff; 32]);
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let points: Vec<G1Affine> = (0..5).map(|_| G1Affine::rand(&mut rng)).collect();
+    let scalars: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+    let expected = points
+        .iter()
+        .zip(scalars.iter())
+        .fold(G1Affine::zero().into_group(), |acc, (p, s)| acc + *p * s);
+
+    let vp = obj_vec(
+        &host,
+        points
+            .iter()
+            .map(|p| g1_obj(&host, p).unwrap().to_val())
+            .collect(),
+    )?;
+    let vs = obj_vec(
+        &host,
+        scalars
+            .iter()
+            .map(|s| fr_val(&host, s).unwrap().to_val())
+            .collect(),
+    )?;
+    let res = host.bn254_g1_msm(vp, vs)?;
+    assert_bytes_eq(&host, res, &g1_bytes(&expected.into_affine()))?;
+
+    // vector lengths must match and be non-zero
+    let vs_short = obj_vec(&host, vec![fr_val(&host, &scalars[0])?.to_val()])?;
+    assert_invalid_input(host.bn254_g1_msm(vp, vs_short));
+    let empty = host.vec_new()?;
+    assert_invalid_input(host.bn254_g1_msm(empty, empty));
+    Ok(())
+}
+
+#[test]
+fn multi_pairing_check() -> Result<(), HostError> {
+    let mut rng = StdRng::from_seed([0xff; 32]);
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let g2 = bytes_obj(&host, &Vec::from_hex(G2_GENERATOR).unwrap())?;
+    assert_bytes_eq(&host, g2, &g2_bytes(&G2Affine::generator()))?;
+
+    // e(a * G1, b * G2) * e(-(a * b) * G1, G2) == 1
+    let a = Fr::rand(&mut rng);
+    let b = Fr::rand(&mut rng);
+    let g1 = G1Affine::generator();
+    let vp1 = obj_vec(
+        &host,
+        vec![
+            g1_obj(&host, &(g1 * a).into_affine())?.to_val(),
+            g1_obj(&host, &(g1 * (-(a * b))).into_affine())?.to_val(),
+        ],
+    )?;
+    let vp2 = obj_vec(
+        &host,
+        vec![
+            g2_obj(&host, &(G2Affine::generator() * b).into_affine())?.to_val(),
+            g2.to_val(),
+        ],
+    )?;
+    assert!(host.bn254_multi_pairing_check(vp1, vp2)?.to_val().is_true());
+
+    // random pairs do not multiply to 1
+    let vp1 = obj_vec(
+        &host,
+        (0..3)
+            .map(|_| g1_obj(&host, &G1Affine::rand(&mut rng)).unwrap().to_val())
+            .collect(),
+    )?;
+    let vp2 = obj_vec(
+        &host,
+        (0..3)
+            .map(|_| g2_obj(&host, &G2Affine::rand(&mut rng)).unwrap().to_val())
+            .collect(),
+    )?;
+    assert!(host
+        .bn254_multi_pairing_check(vp1, vp2)?
+        .to_val()
+        .is_false());
+
+    // pairs involving the point at infinity contribute 1
+    let vp1 = obj_vec(&host, vec![bytes_obj(&host, &[0u8; 64])?.to_val()])?;
+    let vp2 = obj_vec(&host, vec![g2.to_val()])?;
+    assert!(host.bn254_multi_pairing_check(vp1, vp2)?.to_val().is_true());
+
+    // vector lengths must match and be non-zero
+    let vp2 = obj_vec(&host, vec![g2.to_val(), g2.to_val()])?;
+    assert_invalid_input(host.bn254_multi_pairing_check(vp1, vp2));
+    let empty = host.vec_new()?;
+    assert_invalid_input(host.bn254_multi_pairing_check(empty, empty));
+    Ok(())
+}
+
+#[test]
+fn invalid_points() -> Result<(), HostError> {
+    let mut rng = StdRng::from_seed([0xff; 32]);
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let g = g1_obj(&host, &G1Affine::generator())?;
+    let modulus = Vec::from_hex(MODULUS).unwrap();
+
+    // wrong length
+    assert_invalid_input(host.bn254_g1_add(g, bytes_obj(&host, &[0u8; 63])?));
+    assert_invalid_input(host.bn254_g1_add(g, bytes_obj(&host, &[0u8; 65])?));
+    // not on curve: (1, 3)
+    let mut not_on_curve = [0u8; 64];
+    not_on_curve[31] = 1;
+    not_on_curve[63] = 3;
+    assert_invalid_input(host.bn254_g1_add(g, bytes_obj(&host, &not_on_curve)?));
+    // coordinate not less than the modulus: (p + 1, 2) is (1, 2) if reduced
+    let mut out_of_range = g1_bytes(&G1Affine::generator());
+    out_of_range[0..32].copy_from_slice(&modulus);
+    out_of_range[31] += 1;
+    assert_invalid_input(host.bn254_g1_mul(bytes_obj(&host, &out_of_range)?, U256Val::from_u32(1)));
+
+    // G2 checks are exercised through the pairing
+    let vp1 = obj_vec(&host, vec![g.to_val()])?;
+    let mut short = g2_bytes(&G2Affine::generator());
+    short.pop();
+    let mut g2_out_of_range = g2_bytes(&G2Affine::generator());
+    g2_out_of_range[0..32].copy_from_slice(&modulus);
+    for bad in [
+        short,
+        vec![1u8; 128],
+        g2_out_of_range,
+        g2_bytes(&g2_not_in_subgroup(&mut rng)),
+    ] {
+        let vp2 = obj_vec(&host, vec![bytes_obj(&host, &bad)?.to_val()])?;
+        assert_invalid_input(host.bn254_multi_pairing_check(vp1, vp2));
+    }
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::rc::Rc;
+
+use soroban_synth_wasm::{Arity, ModEmitter, Operand};
+use wasm_encoder::{BlockType, Instruction};
+
+use crate::{
+    budget::AsBudget,
+    xdr::{ContractCostType, Hash, ScAddress, ScErrorCode, ScErrorType},
+    AddressObject, ContractFunctionSet, Env, EnvBase, Error, Host, HostError, Symbol, SymbolStr,
+    TryFromVal, U32Val, U64Val, Val, VecObject,
+};
+
+fn sym(s: &str) -> Symbol {
+    Symbol::try_from_small_str(s).unwrap()
+}
+
+fn u64val(host: &Host, v: u64) -> U64Val {
+    U64Val::try_from_val(host, &v).unwrap()
+}
+
+// Burns `n` units of cpu (`burn_cpu`) or memory (`burn_mem`) budget. Budget
+// errors are escalated so that the host sees them rather than a plain trap.
+struct Burner;
+impl ContractFunctionSet for Burner {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        let name = SymbolStr::try_from_val(host, func).unwrap().to_string();
+        if name == "__constructor" {
+            return Some(().into());
+        }
+        let n = u32::from(U32Val::try_from(args[0]).unwrap());
+        match name.as_str() {
+            "burn_cpu" => {
+                for _ in 0..n {
+                    host.charge_budget(ContractCostType::WasmInsnExec, None)
+                        .unwrap_or_else(|e| host.escalate_error_to_panic(e));
+                }
+                Some(().into())
+            }
+            "burn_mem" => {
+                host.charge_budget(ContractCostType::MemAlloc, Some(n as u64))
+                    .unwrap_or_else(|e| host.escalate_error_to_panic(e));
+                Some(().into())
+            }
+            _ => None,
+        }
+    }
+}
+
+// Calls `args[1]` on the contract `args[0]` with the argument `args[2]`,
+// capping it at `args[3]` cpu instructions and `args[4]` memory bytes. The
+// result is returned wrapped in a vec, as contracts may not return
+// non-contract errors.
+struct Router;
+impl ContractFunctionSet for Router {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        if SymbolStr::try_from_val(host, func).unwrap().to_string() == "__constructor" {
+            return Some(().into());
+        }
+        let callee = AddressObject::try_from_val(host, &args[0]).unwrap();
+        let func = Symbol::try_from(args[1]).unwrap();
+        let call_args = host.vec_new_from_slice(&[args[2]]).unwrap();
+        let cpu_limit = U64Val::try_from(args[3]).unwrap();
+        let mem_limit = U64Val::try_from(args[4]).unwrap();
+        let res = host
+            .try_call_with_budget(callee, func, call_args, cpu_limit, mem_limit)
+            .unwrap();
+        Some(host.vec_new_from_slice(&[res]).unwrap().into())
+    }
+}
+
+fn register(host: &Host, id: u8, contract: Rc<dyn ContractFunctionSet>) -> AddressObject {
+    let address = host
+        .add_host_object(ScAddress::Contract(Hash([id; 32])))
+        .unwrap();
+    host.register_test_contract(address, contract).unwrap();
+    address
+}
+
+fn route(
+    host: &Host,
+    func: &str,
+    n: u32,
+    cpu_limit: u64,
+    mem_limit: u64,
+) -> Result<Val, HostError> {
+    let router = register(host, 0, Rc::new(Router));
+    let burner = register(host, 1, Rc::new(Burner));
+    let args = host.vec_new_from_slice(&[
+        burner.into(),
+        sym(func).into(),
+        U32Val::from(n).into(),
+        u64val(host, cpu_limit).into(),
+        u64val(host, mem_limit).into(),
+    ])?;
+    let res = VecObject::try_from_val(host, &host.call(router, sym("route"), args)?)?;
+    host.vec_get(res, U32Val::from(0))
+}
+
+#[test]
+fn callee_within_caps_succeeds() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    let res = route(&host, "burn_cpu", 100, 1_000_000, 1_000_000)?;
+    assert!(res.is_void());
+    Ok(())
+}
+
+#[test]
+fn callee_exceeding_cpu_cap_is_recoverable() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    let res = route(&host, "burn_cpu", 1_000_000, 100_000, 1_000_000)?;
+    assert_eq!(
+        Error::try_from(res)?,
+        (ScErrorType::Budget, ScErrorCode::ExceededLimit).into()
+    );
+    // The callee could not consume more than its cap on top of what the
+    // caller itself has consumed.
+    assert!(host.as_budget().get_cpu_insns_consumed()? < 1_000_000);
+    Ok(())
+}
+
+#[test]
+fn callee_exceeding_mem_cap_is_recoverable() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    let res = route(&host, "burn_mem", 1_000_000, 1_000_000, 100_000)?;
+    assert_eq!(
+        Error::try_from(res)?,
+        (ScErrorType::Budget, ScErrorCode::ExceededLimit).into()
+    );
+    Ok(())
+}
+
+#[test]
+fn caps_do_not_extend_transaction_budget() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    host.budget_ref().reset_limits(1_000_000, 1_000_000)?;
+    let res = route(&host, "burn_cpu", 1_000_000, u64::MAX, u64::MAX);
+    assert!(res.is_err());
+    assert_eq!(host.as_budget().get_cpu_insns_remaining()?, 0);
+    Ok(())
+}
+
+#[test]
+fn nested_caps_are_attributed_to_the_tightest_scope() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let budget = host.as_budget();
+    budget.reset_limits(10_000_000, 10_000_000)?;
+    let burn = || -> Result<(), HostError> {
+        loop {
+            budget.charge(ContractCostType::WasmInsnExec, None)?;
+        }
+    };
+
+    // The inner cap is larger than what remains of the outer one, so the outer
+    // scope is the one that gets to recover.
+    let res = budget.with_scoped_limits(10_000, u64::MAX, || {
+        budget.with_scoped_limits(1_000_000, u64::MAX, burn)
+    })?;
+    assert!(res.is_err());
+    assert!(budget.get_cpu_insns_consumed()? < 11_000);
+
+    // The tighter inner cap is recovered from by the inner scope itself.
+    let res = budget.with_scoped_limits(1_000_000, u64::MAX, || {
+        let inner = budget.with_scoped_limits(10_000, u64::MAX, burn)?;
+        assert!(inner.is_err());
+        Ok(())
+    })?;
+    assert!(res.is_ok());
+
+    // All the scopes are gone and the remaining budget is usable again.
+    assert!(budget.get_cpu_insns_remaining()? > 9_000_000);
+    budget.charge(ContractCostType::WasmInsnExec, None)?;
+    Ok(())
+}
+
+// A Wasm contract exporting `spin`, which loops until it runs out of fuel.
+fn spinner_wasm() -> Vec<u8> {
+    let mut fe = ModEmitter::default_with_test_protocol().func(Arity(0), 0);
+    fe.insn(&Instruction::Loop(BlockType::Empty));
+    fe.insn(&Instruction::Br(0));
+    fe.end();
+    fe.trap();
+    fe.finish_and_export("spin").finish()
+}
+
+// A Wasm contract exporting `route(callee, cpu_limit, mem_limit)`, which calls
+// `spin` on `callee` within the given caps and then counts down from `n` in a
+// loop of its own, before returning the result of the call wrapped in a vec.
+fn wasm_router(n: i64) -> Vec<u8> {
+    let mut fe = ModEmitter::default_with_test_protocol().func(Arity(3), 3);
+    let callee = fe.alloc_arg("AddressObject");
+    let cpu_limit = fe.alloc_arg("U64Val");
+    let mem_limit = fe.alloc_arg("U64Val");
+    let args = fe.alloc_local("VecObject");
+    let res = fe.alloc_local("Val");
+    let i = fe.alloc_local("i64");
+    fe.vec_new();
+    fe.local_set(args);
+    fe.try_call_with_budget(callee, sym("spin"), args, cpu_limit, mem_limit);
+    fe.local_set(res);
+    fe.i64_const(n);
+    fe.local_set(i);
+    fe.insn(&Instruction::Loop(BlockType::Empty));
+    fe.local_get(i);
+    fe.i64_const(1);
+    fe.i64_sub();
+    fe.local_tee(i);
+    fe.i64_const(0);
+    fe.i64_ne();
+    fe.insn(&Instruction::BrIf(0));
+    fe.end();
+    fe.vec_new();
+    fe.vec_push_back(Operand::StackTop, res);
+    fe.finish_and_export("route").finish()
+}
+
+#[test]
+fn wasm_callee_running_out_of_fuel_is_recoverable() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    host.as_budget().reset_limits(10_000_000, 10_000_000)?;
+    let cpu_limit = 1_000_000;
+    let spinner = host.register_test_contract_wasm(spinner_wasm().as_slice());
+    // The caller's own loop needs several times more fuel than the callee's
+    // cap, so it only completes if the caller is refilled from the enclosing
+    // limits once the capped scope is gone.
+    let router = host.register_test_contract_wasm(wasm_router(100_000).as_slice());
+    let args = host.vec_new_from_slice(&[
+        spinner.into(),
+        u64val(&host, cpu_limit).into(),
+        u64val(&host, 1_000_000).into(),
+    ])?;
+    let res = VecObject::try_from_val(&*host, &host.call(router, sym("route"), args)?)?;
+    // The callee only ever executes Wasm, so it can only have been stopped by
+    // running out of the fuel it was given for the cap.
+    assert_eq!(
+        Error::try_from(host.vec_get(res, U32Val::from(0))?)?,
+        (ScErrorType::Budget, ScErrorCode::ExceededLimit).into()
+    );
+    assert!(host.as_budget().get_cpu_insns_consumed()? > 2 * cpu_limit);
+    Ok(())
+}
@@ -97,6 +97,228 @@ fn keccak256_test() {
     ));
 }
 
+#[cfg(feature = "next")]
+fn compute_hash_bytes(
+    host: &Host,
+    hash: impl Fn(crate::BytesObject) -> Result<crate::BytesObject, HostError>,
+    input_bytes: &[u8],
+) -> Result<Vec<u8>, HostError> {
+    let hash_obj = hash(host.bytes_new_from_slice(input_bytes)?)?;
+    let mut buf = vec![0u8; u32::from(host.bytes_len(hash_obj)?) as usize];
+    host.bytes_copy_to_slice(hash_obj, U32Val::from(0), &mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn sha512_test() {
+    let host = observe_host!(Host::test_host());
+    let compute_hash = |input_bytes: &[u8]| -> Result<Vec<u8>, HostError> {
+        compute_hash_bytes(&host, |x| host.compute_hash_sha512(x), input_bytes)
+    };
+    assert_eq!(
+        compute_hash(&[]).unwrap().encode_hex::<String>(),
+        "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"abc").unwrap().encode_hex::<String>(),
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"test vector for soroban")
+            .unwrap()
+            .encode_hex::<String>(),
+        "6d4e7ca8020ae8d9da82c3f64b7bf78ebab725e7b9a65861ff301eac292dd240e775f33734e4a9c70a242456ac4e19c2ab43a1f4e4f102c4e5a8ad2dc7297341".to_string()
+    );
+    let long_vec = vec![1u8; 1_000_000];
+    assert_eq!(
+        compute_hash(long_vec.as_slice())
+            .unwrap()
+            .encode_hex::<String>(),
+        "1a32b7c186fac5b7492c2fc74a081382b05c07e6adb30e583a25f16053e55fdbba0dbce00449c70554a12be6f8a57244bb4115f6b21a705e27d94c862b3be86a".to_string()
+    );
+
+    host.budget_ref().reset_default().unwrap();
+    let too_long_vec = vec![1u8; 10_000_000];
+    assert!(is_budget_exceeded(
+        compute_hash(too_long_vec.as_slice()).err().unwrap()
+    ));
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn blake2b256_test() {
+    let host = observe_host!(Host::test_host());
+    let compute_hash = |input_bytes: &[u8]| -> Result<Vec<u8>, HostError> {
+        compute_hash_bytes(&host, |x| host.compute_hash_blake2b256(x), input_bytes)
+    };
+    assert_eq!(
+        compute_hash(&[]).unwrap().encode_hex::<String>(),
+        "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"abc").unwrap().encode_hex::<String>(),
+        "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"test vector for soroban")
+            .unwrap()
+            .encode_hex::<String>(),
+        "2f771c0d77e5b9fbce393e3d0028e1cd9d6a2ef379af70936dee5f7af2d52101".to_string()
+    );
+    let long_vec = vec![1u8; 1_000_000];
+    assert_eq!(
+        compute_hash(long_vec.as_slice())
+            .unwrap()
+            .encode_hex::<String>(),
+        "13c7dc790f13c181dcd58ee98e1b7db2572458cc94fa57105c6cbf15300ab8d2".to_string()
+    );
+
+    host.budget_ref().reset_default().unwrap();
+    let too_long_vec = vec![1u8; 10_000_000];
+    assert!(is_budget_exceeded(
+        compute_hash(too_long_vec.as_slice()).err().unwrap()
+    ));
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn blake3_test() {
+    let host = observe_host!(Host::test_host());
+    let compute_hash = |input_bytes: &[u8]| -> Result<Vec<u8>, HostError> {
+        compute_hash_bytes(&host, |x| host.compute_hash_blake3(x), input_bytes)
+    };
+    assert_eq!(
+        compute_hash(&[]).unwrap().encode_hex::<String>(),
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"abc").unwrap().encode_hex::<String>(),
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"test vector for soroban")
+            .unwrap()
+            .encode_hex::<String>(),
+        "246b2b1a40f4b6acfae7f465b542ecbd9f13200f0cbf1774939d9d2739f7f422".to_string()
+    );
+    let long_vec = vec![1u8; 1_000_000];
+    assert_eq!(
+        compute_hash(long_vec.as_slice())
+            .unwrap()
+            .encode_hex::<String>(),
+        "5f604a775ab3deb582d7065752232448f475693ae54b61a5f0fb8e88e277b9c9".to_string()
+    );
+
+    host.budget_ref().reset_default().unwrap();
+    // BLAKE3 is cheap enough to hash 10 MB within the default budget.
+    let too_long_vec = vec![1u8; 20_000_000];
+    assert!(is_budget_exceeded(
+        compute_hash(too_long_vec.as_slice()).err().unwrap()
+    ));
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn ripemd160_test() {
+    let host = observe_host!(Host::test_host());
+    let compute_hash = |input_bytes: &[u8]| -> Result<Vec<u8>, HostError> {
+        compute_hash_bytes(&host, |x| host.compute_hash_ripemd160(x), input_bytes)
+    };
+    assert_eq!(
+        compute_hash(&[]).unwrap().encode_hex::<String>(),
+        "9c1185a5c5e9fc54612808977ee8f548b2258d31".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"abc").unwrap().encode_hex::<String>(),
+        "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc".to_string()
+    );
+    assert_eq!(
+        compute_hash(b"test vector for soroban")
+            .unwrap()
+            .encode_hex::<String>(),
+        "375bd311198ddd32cfeb9377c97801b044b13241".to_string()
+    );
+    let long_vec = vec![1u8; 1_000_000];
+    assert_eq!(
+        compute_hash(long_vec.as_slice())
+            .unwrap()
+            .encode_hex::<String>(),
+        "748b7a85aec0e5d6dc4e0e237b750d66f3a7c188".to_string()
+    );
+
+    host.budget_ref().reset_default().unwrap();
+    let too_long_vec = vec![1u8; 10_000_000];
+    assert!(is_budget_exceeded(
+        compute_hash(too_long_vec.as_slice()).err().unwrap()
+    ));
+}
+
+#[cfg(feature = "next")]
+type HashFn = fn(&Host, crate::BytesObject) -> Result<crate::BytesObject, HostError>;
+
+#[cfg(feature = "next")]
+#[test]
+fn hashes_are_charged_under_their_own_cost_types() -> Result<(), HostError> {
+    use crate::xdr::ContractCostType;
+    let host = Host::test_host();
+    let input = host.bytes_new_from_slice(&[1u8; 10_000])?;
+    let charged_cpu = |hash: HashFn, ty: ContractCostType| -> Result<u64, HostError> {
+        host.budget_ref().reset_unlimited()?;
+        hash(&host, input)?;
+        let tracker = host.budget_ref().get_tracker(ty)?;
+        assert_eq!(tracker.iterations, 1);
+        assert_eq!(tracker.inputs, Some(10_000));
+        assert_eq!(
+            host.budget_ref()
+                .get_tracker(ContractCostType::ComputeSha256Hash)?
+                .iterations,
+            0
+        );
+        Ok(tracker.cpu)
+    };
+    let keccak = charged_cpu(
+        |host, x| host.compute_hash_keccak256(x),
+        ContractCostType::ComputeKeccak256Hash,
+    )?;
+    // Bounds (in percent of keccak256's charge) around the hashing time
+    // measured relative to keccak256 over 10 KB: 78%, 46%, 11% and 159%.
+    let hashes: [(HashFn, ContractCostType, u64, u64); 4] = [
+        (
+            |host, x| host.compute_hash_sha512(x),
+            ContractCostType::ComputeSha512Hash,
+            60,
+            100,
+        ),
+        (
+            |host, x| host.compute_hash_blake2b256(x),
+            ContractCostType::ComputeBlake2b256Hash,
+            30,
+            60,
+        ),
+        (
+            |host, x| host.compute_hash_blake3(x),
+            ContractCostType::ComputeBlake3Hash,
+            5,
+            20,
+        ),
+        (
+            |host, x| host.compute_hash_ripemd160(x),
+            ContractCostType::ComputeRipemd160Hash,
+            120,
+            200,
+        ),
+    ];
+    for (hash, ty, lo, hi) in hashes {
+        let cpu = charged_cpu(hash, ty)?;
+        assert!(
+            (keccak * lo / 100..=keccak * hi / 100).contains(&cpu),
+            "{ty:?} charged {cpu} cpu vs {keccak} for keccak256"
+        );
+    }
+    Ok(())
+}
+
 #[test]
 fn ed25519_verify_test() {
     let host = observe_host!(Host::test_host());
@@ -419,3 +641,218 @@ fn test_secp256r1_signature_verification() -> Result<(), HostError> {
 
     Ok(())
 }
+
+#[cfg(feature = "next")]
+#[test]
+fn test_secp256r1_key_recovery() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+
+    let recover_key = |msg_digest: &str,
+                       signature: &str,
+                       recovery_id: u32|
+     -> Result<String, HostError> {
+        let msg_digest_obj = host.bytes_new_from_slice(&hex::decode(msg_digest).unwrap())?;
+        let signature_obj = host.bytes_new_from_slice(&hex::decode(signature).unwrap())?;
+        host.budget_ref().reset_default().unwrap();
+        host.recover_key_ecdsa_secp256r1(msg_digest_obj, signature_obj, U32Val::from(recovery_id))
+            .map(|pk_obj| {
+                host.fixed_length_bytes_from_bytesobj_input::<Vec<u8>, 65>("pk", pk_obj)
+                    .unwrap()
+                    .encode_hex::<String>()
+            })
+    };
+
+    // The valid NIST vector from `test_secp256r1_signature_verification`.
+    let public_key = "04e424dc61d4bb3cb7ef4344a7f8957a0c5134e16f7a67c074f82e6e12f49abf3c970eed7aa2bc48651545949de1dddaf0127e5965ac85d1243d6f60e7dfaee927";
+    let msg_digest = "d1b8ef21eb4182ee270638061063a3f3c16c114e33937f69fb232cc833965a94";
+    let signature = "bf96b99aa49c705c910be33142017c642ff540c76349b9dab72f981fd9347f4f17c55095819089c2e03b9cd415abdf12444e323075d98f31920b9e0f57ec871c";
+
+    // Exactly one of the y-parities recovers the signer's key.
+    let recovered: Vec<String> = (0..2)
+        .filter_map(|rid| recover_key(msg_digest, signature, rid).ok())
+        .collect();
+    assert_eq!(
+        recovered
+            .iter()
+            .filter(|k| k.as_str() == public_key)
+            .count(),
+        1
+    );
+
+    // Bad recovery ids.
+    assert!(is_crypto_error(
+        recover_key(msg_digest, signature, 4).err().unwrap()
+    ));
+    assert!(is_crypto_error(
+        recover_key(msg_digest, signature, u32::MAX).err().unwrap()
+    ));
+    // Malformed digest (missing one byte)
+    assert!(is_object_error(
+        recover_key(&msg_digest[..62], signature, 0).err().unwrap()
+    ));
+    // Malformed signature (one extra byte)
+    assert!(is_crypto_error(
+        recover_key(msg_digest, &format!("{signature}00"), 0)
+            .err()
+            .unwrap()
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn ed25519_batch_verify_test() -> Result<(), HostError> {
+    use crate::{xdr::ContractCostType, Val, VecObject};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    let host = observe_host!(Host::test_host());
+    let bytes_vec = |items: &[Vec<u8>]| -> Result<VecObject, HostError> {
+        let vals = items
+            .iter()
+            .map(|b| host.bytes_new_from_slice(b).map(Val::from))
+            .collect::<Result<Vec<Val>, HostError>>()?;
+        host.vec_new_from_slice(&vals)
+    };
+    let signing_keys: Vec<SigningKey> = (0..16u8)
+        .map(|i| SigningKey::from_bytes(&[i; 32]))
+        .collect();
+    let keys: Vec<Vec<u8>> = signing_keys
+        .iter()
+        .map(|k| k.verifying_key().to_bytes().to_vec())
+        .collect();
+    let msgs: Vec<Vec<u8>> = (0..16u8).map(|i| vec![i; 10 + i as usize]).collect();
+    let sigs: Vec<Vec<u8>> = signing_keys
+        .iter()
+        .zip(msgs.iter())
+        .map(|(k, m)| k.sign(m).to_bytes().to_vec())
+        .collect();
+    let verify = |keys: &[Vec<u8>], msgs: &[Vec<u8>], sigs: &[Vec<u8>]| {
+        let (keys, msgs, sigs) = (bytes_vec(keys)?, bytes_vec(msgs)?, bytes_vec(sigs)?);
+        host.budget_ref().reset_default()?;
+        host.verify_sigs_ed25519_batch(keys, msgs, sigs)
+    };
+
+    // All valid.
+    assert!(verify(&keys, &msgs, &sigs)?.is_void());
+
+    // The index of the first invalid signature is reported.
+    let mut bad_sigs = sigs.clone();
+    bad_sigs[5][0] ^= 1;
+    bad_sigs[9][0] ^= 1;
+    assert_eq!(
+        u32::from(U32Val::try_from(verify(&keys, &msgs, &bad_sigs)?)?),
+        5
+    );
+    let mut bad_msgs = msgs.clone();
+    bad_msgs[7].push(0);
+    assert_eq!(
+        u32::from(U32Val::try_from(verify(&keys, &bad_msgs, &sigs)?)?),
+        7
+    );
+
+    // A signature with a small-order `R` is rejected, like in
+    // `verify_sig_ed25519`.
+    let mut small_order_sigs = sigs.clone();
+    small_order_sigs[2] = [[1u8].as_slice(), &[0u8; 63]].concat();
+    assert_eq!(
+        u32::from(U32Val::try_from(verify(&keys, &msgs, &small_order_sigs)?)?),
+        2
+    );
+
+    // Malformed inputs.
+    assert!(HostError::result_matches_err(
+        verify(&keys[1..], &msgs, &sigs),
+        (ScErrorType::Crypto, ScErrorCode::InvalidInput),
+    ));
+    assert!(HostError::result_matches_err(
+        verify(&[], &[], &[]),
+        (ScErrorType::Crypto, ScErrorCode::InvalidInput),
+    ));
+    let mut short_sigs = sigs.clone();
+    short_sigs[0].pop();
+    assert!(verify(&keys, &msgs, &short_sigs).is_err());
+
+    // A batch costs the same as verifying the signatures one by one.
+    verify(&keys, &msgs, &sigs)?;
+    let batch_cost = host
+        .budget_ref()
+        .get_tracker(ContractCostType::VerifyEd25519Sig)?
+        .cpu;
+    host.budget_ref().reset_default()?;
+    for i in 0..keys.len() {
+        let key = host.bytes_new_from_slice(&keys[i])?;
+        let msg = host.bytes_new_from_slice(&msgs[i])?;
+        let sig = host.bytes_new_from_slice(&sigs[i])?;
+        host.verify_sig_ed25519(key, msg, sig)?;
+    }
+    let single_cost = host
+        .budget_ref()
+        .get_tracker(ContractCostType::VerifyEd25519Sig)?
+        .cpu;
+    assert_eq!(batch_cost, single_cost);
+    for n in [1, 7, 8, 16] {
+        verify(&keys[..n], &msgs[..n], &sigs[..n])?;
+        let tracker = host
+            .budget_ref()
+            .get_tracker(ContractCostType::VerifyEd25519Sig)?;
+        assert_eq!(tracker.iterations, n as u64);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn ed25519_batch_rejects_mixed_order_r() -> Result<(), HostError> {
+    use crate::{Val, VecObject};
+    use curve25519_dalek::{
+        constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY, traits::IsIdentity, Scalar,
+    };
+    use sha2::{Digest, Sha512};
+
+    let host = observe_host!(Host::test_host());
+    let bytes_vec = |items: &[Vec<u8>]| -> Result<VecObject, HostError> {
+        let vals = items
+            .iter()
+            .map(|b| host.bytes_new_from_slice(b).map(Val::from))
+            .collect::<Result<Vec<Val>, HostError>>()?;
+        host.vec_new_from_slice(&vals)
+    };
+    // The point of order 2, (0, -1).
+    let mut t_bytes = [0xffu8; 32];
+    t_bytes[0] = 0xec;
+    t_bytes[31] = 0x7f;
+    let t = CompressedEdwardsY(t_bytes).decompress().unwrap();
+    assert!(t.is_small_order() && !t.is_identity());
+
+    // Sign with `R = r*B + T`, so that `R - (s*B - k*A) = T`: the signature
+    // only holds up to the cofactor, which `verify_strict` rejects and an
+    // unchecked batch equation accepts whenever the random coefficient of `T`
+    // is even.
+    let a = Scalar::from_bytes_mod_order([7u8; 32]);
+    let a_point = (a * ED25519_BASEPOINT_POINT).compress();
+    let mut keys = vec![];
+    let mut msgs = vec![];
+    let mut sigs = vec![];
+    for i in 0..8u8 {
+        let msg = vec![i; 32];
+        let r = Scalar::from_bytes_mod_order([i + 1; 32]);
+        let r_point = (r * ED25519_BASEPOINT_POINT + t).compress();
+        let k = Scalar::from_hash(
+            Sha512::new()
+                .chain_update(r_point.as_bytes())
+                .chain_update(a_point.as_bytes())
+                .chain_update(&msg),
+        );
+        let s = r + k * a;
+        keys.push(a_point.as_bytes().to_vec());
+        sigs.push([r_point.as_bytes().as_slice(), s.as_bytes()].concat());
+        msgs.push(msg);
+    }
+    let (keys, msgs, sigs) = (bytes_vec(&keys)?, bytes_vec(&msgs)?, bytes_vec(&sigs)?);
+    // The coefficients are random, so check repeatedly.
+    for _ in 0..32 {
+        let res = host.verify_sigs_ed25519_batch(keys, msgs, sigs)?;
+        assert_eq!(u32::from(U32Val::try_from(res)?), 0);
+    }
+    Ok(())
+}
@@ -5,7 +5,8 @@ use crate::{
     budget::Budget,
     builtin_contracts::testutils::TestSigner,
     e2e_invoke::{
-        invoke_host_function, invoke_host_function_in_recording_mode, ledger_entry_to_ledger_key,
+        invoke_host_function_in_recording_mode_with_module_cache,
+        invoke_host_function_with_trace_hook_and_module_cache, ledger_entry_to_ledger_key,
         LedgerEntryChange, LedgerEntryLiveUntilChange,
     },
     e2e_testutils::{
@@ -13,7 +14,11 @@ use crate::{
         get_contract_id_preimage, get_wasm_hash, get_wasm_key, ledger_entry, wasm_entry,
         AuthContractInvocationNode, CreateContractData,
     },
-    testutils::MockSnapshotSource,
+    storage::Storage,
+    testutils::{
+        check_recording_matches_enforcing, DifferentialInvocation, Divergence, MockSnapshotSource,
+    },
+    vm::SharedModuleCache,
     xdr::{
         AccountId, ContractDataDurability, ContractDataEntry, ContractEvent, ContractExecutable,
         ContractIdPreimage, ContractIdPreimageFromAddress, CreateContractArgs, DiagnosticEvent,
@@ -218,6 +223,31 @@ fn invoke_host_function_helper(
     ledger_info: &LedgerInfo,
     ledger_entries_with_ttl: Vec<(LedgerEntry, Option<u32>)>,
     prng_seed: &[u8; 32],
+) -> Result<InvokeHostFunctionHelperResult, HostError> {
+    invoke_host_function_helper_with_module_cache(
+        enable_diagnostics,
+        host_fn,
+        resources,
+        source_account,
+        auth_entries,
+        ledger_info,
+        ledger_entries_with_ttl,
+        prng_seed,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn invoke_host_function_helper_with_module_cache(
+    enable_diagnostics: bool,
+    host_fn: &HostFunction,
+    resources: &SorobanResources,
+    source_account: &AccountId,
+    auth_entries: Vec<SorobanAuthorizationEntry>,
+    ledger_info: &LedgerInfo,
+    ledger_entries_with_ttl: Vec<(LedgerEntry, Option<u32>)>,
+    prng_seed: &[u8; 32],
+    module_cache: Option<SharedModuleCache>,
 ) -> Result<InvokeHostFunctionHelperResult, HostError> {
     let limits = Limits::none();
     let encoded_host_fn = host_fn.to_xdr(limits.clone()).unwrap();
@@ -262,7 +292,7 @@ fn invoke_host_function_helper(
         .reset_cpu_limit(resources.instructions as u64)
         .unwrap();
     let mut diagnostic_events = Vec::<DiagnosticEvent>::new();
-    let res = invoke_host_function(
+    let res = invoke_host_function_with_trace_hook_and_module_cache(
         &budget,
         enable_diagnostics,
         encoded_host_fn,
@@ -274,6 +304,8 @@ fn invoke_host_function_helper(
         encoded_ttl_entries.into_iter(),
         prng_seed.to_vec(),
         &mut diagnostic_events,
+        None,
+        module_cache,
     )?;
     Ok(InvokeHostFunctionHelperResult {
         invoke_result: res
@@ -299,6 +331,31 @@ fn invoke_host_function_recording_helper(
     ledger_entries_with_ttl: Vec<(LedgerEntry, Option<u32>)>,
     prng_seed: &[u8; 32],
     max_instructions_override: Option<u64>,
+) -> Result<InvokeHostFunctionRecordingHelperResult, HostError> {
+    invoke_host_function_recording_helper_with_module_cache(
+        enable_diagnostics,
+        host_fn,
+        source_account,
+        auth_entries,
+        ledger_info,
+        ledger_entries_with_ttl,
+        prng_seed,
+        max_instructions_override,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn invoke_host_function_recording_helper_with_module_cache(
+    enable_diagnostics: bool,
+    host_fn: &HostFunction,
+    source_account: &AccountId,
+    auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+    ledger_info: &LedgerInfo,
+    ledger_entries_with_ttl: Vec<(LedgerEntry, Option<u32>)>,
+    prng_seed: &[u8; 32],
+    max_instructions_override: Option<u64>,
+    module_cache: Option<SharedModuleCache>,
 ) -> Result<InvokeHostFunctionRecordingHelperResult, HostError> {
     let budget = Budget::default();
     if let Some(max_insns) = max_instructions_override {
@@ -306,7 +363,7 @@ fn invoke_host_function_recording_helper(
     }
     let snapshot = Rc::new(MockSnapshotSource::from_entries(ledger_entries_with_ttl));
     let mut diagnostic_events = vec![];
-    let res = invoke_host_function_in_recording_mode(
+    let res = invoke_host_function_in_recording_mode_with_module_cache(
         &budget,
         enable_diagnostics,
         host_fn,
@@ -316,6 +373,7 @@ fn invoke_host_function_recording_helper(
         snapshot,
         *prng_seed,
         &mut diagnostic_events,
+        module_cache,
     )?;
     Ok(InvokeHostFunctionRecordingHelperResult {
         invoke_result: res.invoke_result,
@@ -2087,6 +2145,134 @@ fn test_classic_account_auth_using_simulation() {
     assert!(res.invoke_result.is_ok());
 }
 
+#[test]
+fn test_classic_account_auth_recording_matches_enforcing() {
+    let mut prng = StdRng::from_seed([123; 32]);
+    let keys = vec![SigningKey::generate(&mut prng)];
+    let signer = TestSigner::account(&keys[0]);
+    let contracts = vec![
+        CreateContractData::new([1; 32], AUTH_TEST_CONTRACT),
+        CreateContractData::new([2; 32], AUTH_TEST_CONTRACT),
+    ];
+    let tree = AuthContractInvocationNode {
+        address: contracts[0].contract_address.clone(),
+        children: vec![AuthContractInvocationNode {
+            address: contracts[1].contract_address.clone(),
+            children: vec![],
+        }],
+    };
+    let source_account = get_account_id([123; 32]);
+    let host_fn = auth_contract_invocation(vec![signer.sc_address()], tree);
+    let ledger_info = default_ledger_info();
+    let invocation = DifferentialInvocation {
+        host_fn,
+        source_account,
+        ledger_info: ledger_info.clone(),
+        ledger_entries: vec![
+            (
+                contracts[0].wasm_entry.clone(),
+                Some(ledger_info.sequence_number + 100),
+            ),
+            (
+                contracts[0].contract_entry.clone(),
+                Some(ledger_info.sequence_number + 1000),
+            ),
+            (
+                contracts[1].contract_entry.clone(),
+                Some(ledger_info.sequence_number + 1000),
+            ),
+            (account_entry(&signer.account_id()), None),
+        ],
+        signers: keys.clone(),
+        prng_seed: prng_seed(),
+    };
+    let outcome = check_recording_matches_enforcing(&invocation).unwrap();
+    outcome.assert_no_divergence();
+    assert!(outcome.invoke_result.is_ok());
+
+    // Without a signer the recorded auth can't be satisfied in enforcing
+    // mode, which must be reported as a divergence.
+    let outcome = check_recording_matches_enforcing(&DifferentialInvocation {
+        signers: vec![],
+        ..invocation
+    })
+    .unwrap();
+    assert!(matches!(
+        outcome.divergences.as_slice(),
+        [Divergence::InvokeResult { .. }]
+    ));
+}
+
+#[test]
+fn test_recording_and_enforcing_failures_diverge_on_different_errors() {
+    let mut prng = StdRng::from_seed([123; 32]);
+    let keys = vec![SigningKey::generate(&mut prng)];
+    let signer = TestSigner::account(&keys[0]);
+    let contracts = vec![
+        CreateContractData::new([1; 32], AUTH_TEST_CONTRACT),
+        CreateContractData::new([2; 32], AUTH_TEST_CONTRACT),
+    ];
+    let tree = AuthContractInvocationNode {
+        address: contracts[0].contract_address.clone(),
+        children: vec![AuthContractInvocationNode {
+            address: contracts[1].contract_address.clone(),
+            children: vec![],
+        }],
+    };
+    let source_account = get_account_id([123; 32]);
+    let host_fn = auth_contract_invocation(vec![signer.sc_address()], tree);
+    let ledger_info = default_ledger_info();
+    let entries_without_instance = |missing: usize| {
+        let mut entries = vec![(
+            contracts[0].wasm_entry.clone(),
+            Some(ledger_info.sequence_number + 100),
+        )];
+        for (i, c) in contracts.iter().enumerate() {
+            if i != missing {
+                entries.push((
+                    c.contract_entry.clone(),
+                    Some(ledger_info.sequence_number + 1000),
+                ));
+            }
+        }
+        entries.push((account_entry(&signer.account_id()), None));
+        entries
+    };
+    let invocation = DifferentialInvocation {
+        host_fn,
+        source_account,
+        ledger_info: ledger_info.clone(),
+        ledger_entries: entries_without_instance(0),
+        signers: keys.clone(),
+        prng_seed: prng_seed(),
+    };
+
+    // Without the instance of the called contract both runs fail the same
+    // way before anything is authorized.
+    let outcome = check_recording_matches_enforcing(&invocation).unwrap();
+    outcome.assert_no_divergence();
+    assert!(outcome.invoke_result.is_err());
+
+    // Without the instance of the nested contract the recording run fails
+    // when calling it, having never recorded its auth, so the enforcing run
+    // fails on auth instead.
+    let outcome = check_recording_matches_enforcing(&DifferentialInvocation {
+        ledger_entries: entries_without_instance(1),
+        ..invocation
+    })
+    .unwrap();
+    match outcome.divergences.as_slice() {
+        [Divergence::InvokeResult {
+            recording: Err(recording),
+            enforcing: Err(enforcing),
+        }] => {
+            assert!(recording.error.is_type(ScErrorType::Storage));
+            assert!(enforcing.error.is_type(ScErrorType::Auth));
+        }
+        divergences => panic!("unexpected divergences: {divergences:#?}"),
+    }
+}
+
 // Test that when running on a protocol that supports the ModuleCache, when
 // doing work that would be significantly different under cached instantiation,
 // we get a cost estimate from recording mode that still matches the cost of the
@@ -2154,6 +2340,232 @@ fn test_cap_54_55_56_module_cache_recording_fidelity() {
     }
 }
 
+type LedgerEntriesWithTtl = Vec<(LedgerEntry, Option<u32>)>;
+
+fn sum_of_add_invocation(
+    refined_cost_inputs: bool,
+) -> (
+    HostFunction,
+    AccountId,
+    LedgerEntriesWithTtl,
+    [CreateContractData; 2],
+) {
+    let add_cd = CreateContractData::new_with_refined_contract_cost_inputs(
+        [111; 32],
+        ADD_I32,
+        refined_cost_inputs,
+    );
+    let sum_cd = CreateContractData::new_with_refined_contract_cost_inputs(
+        [222; 32],
+        SUM_I32,
+        refined_cost_inputs,
+    );
+    let ledger_info = default_ledger_info();
+    let host_fn = invoke_contract_host_fn(
+        &sum_cd.contract_address,
+        "sum",
+        vec![
+            ScVal::Address(add_cd.contract_address.clone()),
+            ScVal::Vec(Some(ScVec(
+                vec![ScVal::I32(1), ScVal::I32(2), ScVal::I32(3)]
+                    .try_into()
+                    .unwrap(),
+            ))),
+        ],
+    );
+    let ledger_entries_with_ttl = [&add_cd, &sum_cd]
+        .iter()
+        .flat_map(|cd| {
+            [
+                (
+                    cd.wasm_entry.clone(),
+                    Some(ledger_info.sequence_number + 100),
+                ),
+                (
+                    cd.contract_entry.clone(),
+                    Some(ledger_info.sequence_number + 1000),
+                ),
+            ]
+        })
+        .collect();
+    (
+        host_fn,
+        sum_cd.deployer.clone(),
+        ledger_entries_with_ttl,
+        [add_cd, sum_cd],
+    )
+}
+
+fn shared_module_cache(contracts: &[&CreateContractData]) -> SharedModuleCache {
+    let host = Host::with_storage_and_budget(Storage::default(), Budget::default());
+    host.set_ledger_info(default_ledger_info()).unwrap();
+    SharedModuleCache::new(
+        &host,
+        contracts.iter().map(|cd| match &cd.wasm_entry.data {
+            LedgerEntryData::ContractCode(code) => code,
+            _ => panic!("not a contract code entry"),
+        }),
+    )
+    .unwrap()
+}
+
+// Test that taking the parsed contracts from a shared module cache doesn't
+// change the resources consumed in either recording or enforcing mode, no
+// matter whether the cache has all, some or none of the invoked contracts.
+#[test]
+fn test_shared_module_cache_resources_match() {
+    for refined_cost_inputs in [false, true] {
+        let (host_fn, source_account, ledger_entries_with_ttl, [add_cd, sum_cd]) =
+            sum_of_add_invocation(refined_cost_inputs);
+        let ledger_info = default_ledger_info();
+        let recording_result = invoke_host_function_recording_helper(
+            false,
+            &host_fn,
+            &source_account,
+            None,
+            &ledger_info,
+            ledger_entries_with_ttl.clone(),
+            &prng_seed(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(recording_result.invoke_result.unwrap(), ScVal::I32(6));
+        let mut resources = recording_result.resources.clone();
+        resources.instructions =
+            (resources.instructions as f64 * (1.0 + RECORDING_MODE_INSTRUCTIONS_RANGE)) as u32;
+        let enforcing_result = invoke_host_function_helper(
+            false,
+            &host_fn,
+            &resources,
+            &source_account,
+            recording_result.auth.clone(),
+            &ledger_info,
+            ledger_entries_with_ttl.clone(),
+            &prng_seed(),
+        )
+        .unwrap();
+
+        // The cost inputs of the contracts in the ledger don't match the ones
+        // in this cache, so its modules must not be used.
+        let (_, _, _, [other_add_cd, other_sum_cd]) = sum_of_add_invocation(!refined_cost_inputs);
+        let module_caches = [
+            shared_module_cache(&[&add_cd, &sum_cd]),
+            shared_module_cache(&[&add_cd]),
+            shared_module_cache(&[&other_add_cd, &other_sum_cd]),
+            shared_module_cache(&[]),
+        ];
+        for module_cache in module_caches {
+            let recording_result_with_cache =
+                invoke_host_function_recording_helper_with_module_cache(
+                    false,
+                    &host_fn,
+                    &source_account,
+                    None,
+                    &ledger_info,
+                    ledger_entries_with_ttl.clone(),
+                    &prng_seed(),
+                    None,
+                    Some(module_cache.clone()),
+                )
+                .unwrap();
+            assert_eq!(
+                recording_result_with_cache.invoke_result.unwrap(),
+                ScVal::I32(6)
+            );
+            assert_eq!(
+                recording_result_with_cache.resources,
+                recording_result.resources
+            );
+
+            let enforcing_result_with_cache = invoke_host_function_helper_with_module_cache(
+                false,
+                &host_fn,
+                &resources,
+                &source_account,
+                recording_result.auth.clone(),
+                &ledger_info,
+                ledger_entries_with_ttl.clone(),
+                &prng_seed(),
+                Some(module_cache),
+            )
+            .unwrap();
+            assert_eq!(
+                enforcing_result_with_cache.invoke_result.unwrap(),
+                ScVal::I32(6)
+            );
+            assert_eq!(
+                enforcing_result_with_cache
+                    .budget
+                    .get_cpu_insns_consumed()
+                    .unwrap(),
+                enforcing_result.budget.get_cpu_insns_consumed().unwrap()
+            );
+            assert_eq!(
+                enforcing_result_with_cache
+                    .budget
+                    .get_mem_bytes_consumed()
+                    .unwrap(),
+                enforcing_result.budget.get_mem_bytes_consumed().unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_shared_module_cache_across_threads() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let (host_fn, source_account, ledger_entries_with_ttl, [add_cd, sum_cd]) =
+        sum_of_add_invocation(true);
+    let ledger_info = default_ledger_info();
+    let simulate = |module_cache: Option<SharedModuleCache>| {
+        let res = invoke_host_function_recording_helper_with_module_cache(
+            false,
+            &host_fn,
+            &source_account,
+            None,
+            &ledger_info,
+            ledger_entries_with_ttl.clone(),
+            &prng_seed(),
+            None,
+            module_cache,
+        )
+        .unwrap();
+        assert_eq!(res.invoke_result.unwrap(), ScVal::I32(6));
+        res.resources
+    };
+    let expected_resources = simulate(None);
+    let module_cache = shared_module_cache(&[&add_cd, &sum_cd]);
+    assert_send_sync(&module_cache);
+    assert_eq!(module_cache.len(), 2);
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| simulate(Some(module_cache.clone()))))
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), expected_resources);
+        }
+    });
+}
+
+// The engine of a shared module cache meters fuel with the fuel costs of the
+// budget it was built with, so hosts with different fuel costs can't use it.
+#[test]
+fn test_shared_module_cache_rejects_different_fuel_costs() {
+    let (_, _, _, [add_cd, sum_cd]) = sum_of_add_invocation(true);
+    let module_cache = shared_module_cache(&[&add_cd, &sum_cd]);
+
+    let host = Host::with_storage_and_budget(Storage::default(), Budget::default());
+    assert!(host.set_shared_module_cache(module_cache.clone()).is_ok());
+
+    let budget = Budget::default();
+    budget.reset_fuel_config().unwrap();
+    let host = Host::with_storage_and_budget(Storage::default(), budget);
+    let err = host.set_shared_module_cache(module_cache).unwrap_err();
+    assert!(err.error.is_type(ScErrorType::Context));
+    assert!(err.error.is_code(ScErrorCode::InvalidInput));
+}
+
 #[test]
 fn test_deployer_operations_using_simulation() {
     let deployer_contract = CreateContractData::new([1; 32], DEPLOYER_TEST_CONTRACT);
@@ -0,0 +1,55 @@
+use crate::{
+    testutils::{GoldenTraceHost, GoldenTraceMode},
+    Env, Host, HostError, U32Val,
+};
+use std::path::PathBuf;
+
+fn golden_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("soroban-golden-trace-{}", std::process::id()))
+        .join(name)
+        .with_extension("json")
+}
+
+fn push_n(host: &Host, n: u32) -> Result<(), HostError> {
+    let mut v = host.vec_new()?;
+    for i in 0..n {
+        v = host.vec_push_back(v, U32Val::from(i).into())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn golden_trace_roundtrip() -> Result<(), HostError> {
+    let path = golden_path("roundtrip");
+
+    let host = GoldenTraceHost::new_with_mode(Host::test_host(), &path, GoldenTraceMode::Update);
+    push_n(&host, 3)?;
+    host.finish().unwrap();
+    assert!(path.exists());
+
+    // Same invocations: no mismatch.
+    let host = GoldenTraceHost::new_with_mode(Host::test_host(), &path, GoldenTraceMode::Check);
+    push_n(&host, 3)?;
+    host.finish().unwrap();
+
+    // Different invocations: mismatch with a diff naming the new host call.
+    let host = GoldenTraceHost::new_with_mode(Host::test_host(), &path, GoldenTraceMode::Check);
+    push_n(&host, 4)?;
+    let mismatch = host.finish().unwrap_err();
+    assert_eq!(mismatch.path, path);
+    assert!(mismatch.diff.contains("vec_push_back"));
+    assert!(mismatch.to_string().contains("UPDATE_OBSERVATIONS=1"));
+
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "changed since last recording")]
+fn golden_trace_mismatch_panics_on_drop() {
+    let path = golden_path("missing");
+    let _ = std::fs::remove_file(&path);
+    let host = GoldenTraceHost::new_with_mode(Host::test_host(), &path, GoldenTraceMode::Check);
+    push_n(&host, 1).unwrap();
+}
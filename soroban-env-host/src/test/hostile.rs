@@ -600,6 +600,28 @@ fn excessive_logging() -> Result<(), HostError> {
     Bls12381FrMul                      0              0              
     Bls12381FrPow                      0              0              
     Bls12381FrInv                      0              0              
+    CopySharedSeqNode                  0              0              
+    Bn254EncodeFp                      0              0              
+    Bn254DecodeFp                      0              0              
+    Bn254G1CheckPointOnCurve           0              0              
+    Bn254G2CheckPointOnCurve           0              0              
+    Bn254G2CheckPointInSubgroup        0              0              
+    Bn254G1ProjectiveToAffine          0              0              
+    Bn254G1Add                         0              0              
+    Bn254G1Mul                         0              0              
+    Bn254G1Msm                         0              0              
+    Bn254Pairing                       0              0              
+    Bn254FrFromU256                    0              0              
+    Bn254FrToU256                      0              0              
+    Bn254FrAddSub                      0              0              
+    Bn254FrMul                         0              0              
+    Bn254FrPow                         0              0              
+    ComputeSha512Hash                  0              0              
+    ComputeBlake2b256Hash              0              0              
+    ComputeBlake3Hash                  0              0              
+    ComputeRipemd160Hash               0              0              
+    VerifyEcdsaSecp256k1Sig            0              0              
+    Int256MulDiv                       0              0              
     =================================================================
 
     "#]];
@@ -0,0 +1,112 @@
+use crate::{
+    host_object::HostVec,
+    vm::{ContractInsnProfile, WasmInsnClass},
+    xdr::ContractCostType,
+    AddressObject, Env, Host, HostError, Symbol,
+};
+use soroban_synth_wasm::{Arity, ModEmitter, Operand};
+
+fn call_test_fn(host: &Host, contract_id_obj: AddressObject) -> Result<(), HostError> {
+    host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("test")?,
+        host.add_host_object(HostVec::new())?,
+    )?;
+    Ok(())
+}
+
+fn single_profile(host: &Host) -> ContractInsnProfile {
+    let profile = host
+        .budget_ref()
+        .wasm_insn_profile()
+        .unwrap()
+        .expect("profiling not enabled");
+    assert_eq!(profile.0.len(), 1);
+    profile.0.into_values().next().unwrap()
+}
+
+fn test_contract() -> Vec<u8> {
+    let mut me = ModEmitter::default_with_test_protocol();
+    let f0 = me.import_func("t", "_", Arity(0));
+    let mut fe = me.func(Arity(0), 0);
+    fe.call_func(f0);
+    fe.drop();
+    fe.push(Operand::Const64(1));
+    fe.push(Operand::Const64(2));
+    fe.i64_add();
+    fe.drop();
+    fe.push(Symbol::try_from_small_str("pass").unwrap());
+    fe.finish_and_export("test").finish()
+}
+
+#[test]
+fn wasm_insn_profile_counts_by_class() -> Result<(), HostError> {
+    let wasm = test_contract();
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_wasm_insn_profiling(true)?;
+    let contract = host.register_test_contract_wasm(&wasm);
+    call_test_fn(&host, contract)?;
+    let profile = single_profile(&host);
+    let hist = &profile.static_insns;
+    assert_eq!(hist.get(WasmInsnClass::Call), 1);
+    assert_eq!(hist.get(WasmInsnClass::Arithmetic), 1);
+    assert_eq!(hist.get(WasmInsnClass::LoadStore), 0);
+    // drop, const, const, drop, const, end
+    assert_eq!(hist.get(WasmInsnClass::Other), 6);
+    assert_eq!(hist.total(), 8);
+
+    // The profiled contract is the only one executed, so it consumed all of
+    // the fuel charged to the budget.
+    let fuel = host
+        .budget_ref()
+        .get_tracker(ContractCostType::WasmInsnExec)?
+        .iterations;
+    assert!(fuel > 0);
+    assert_eq!(profile.fuel_consumed, fuel);
+    let estimated = profile.static_mix_scaled_to_fuel();
+    assert!(estimated.get(WasmInsnClass::Other) > estimated.get(WasmInsnClass::Call));
+    assert!(estimated.total() <= fuel);
+
+    // Fuel accumulates across calls, static counts don't.
+    call_test_fn(&host, contract)?;
+    let profile = single_profile(&host);
+    assert_eq!(profile.fuel_consumed, 2 * fuel);
+    assert_eq!(profile.static_insns.total(), 8);
+
+    // The profile is a static estimate, so it's reported on its own rather
+    // than along with the budget.
+    let report = format!("{}", host.budget_ref().wasm_insn_profile()?.unwrap());
+    assert!(report.contains("static estimate, not executed counts"));
+    assert!(!format!("{}", host.budget_ref()).contains("Wasm instruction mix"));
+    Ok(())
+}
+
+#[test]
+fn wasm_insn_profiling_does_not_affect_budget() -> Result<(), HostError> {
+    let wasm = test_contract();
+    let mut consumed = vec![];
+    for enabled in [false, true] {
+        let host = Host::test_host_with_recording_footprint();
+        host.enable_wasm_insn_profiling(enabled)?;
+        let contract = host.register_test_contract_wasm(&wasm);
+        call_test_fn(&host, contract)?;
+        consumed.push((
+            host.budget_ref().get_cpu_insns_consumed()?,
+            host.budget_ref().get_mem_bytes_consumed()?,
+        ));
+    }
+    assert_eq!(consumed[0], consumed[1]);
+    Ok(())
+}
+
+#[test]
+fn wasm_insn_profile_is_off_by_default() -> Result<(), HostError> {
+    let mut fe = ModEmitter::default_with_test_protocol().func(Arity(0), 0);
+    fe.push(Symbol::try_from_small_str("pass").unwrap());
+    let wasm = fe.finish_and_export("test").finish();
+    let host = Host::test_host_with_recording_footprint();
+    let contract = host.register_test_contract_wasm(&wasm);
+    call_test_fn(&host, contract)?;
+    assert!(host.budget_ref().wasm_insn_profile()?.is_none());
+    Ok(())
+}
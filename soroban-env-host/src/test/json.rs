@@ -0,0 +1,27 @@
+use crate::{
+    testutils::generate_account_id, xdr::ScAddress, AddressObject, Env, EnvBase, Host, HostError,
+};
+use serde_json::json;
+
+#[test]
+fn val_json_round_trip() -> Result<(), HostError> {
+    let host = Host::test_host_with_recording_footprint();
+    let addr: AddressObject =
+        host.add_host_object(ScAddress::Account(generate_account_id(&host)))?;
+    let i = host.obj_from_i128_pieces(-1, 0)?;
+    let vec = host.vec_new_from_slice(&[addr.to_val(), i.to_val()])?;
+    let json = host.val_to_json(vec.to_val())?;
+    let items = json["vec"].as_array().unwrap();
+    assert!(items[0]["address"].as_str().unwrap().starts_with('G'));
+    assert_eq!(items[1], json!({"i128": "-18446744073709551616"}));
+
+    let back = host.val_from_json(&json)?;
+    assert_eq!(host.obj_cmp(back, vec.to_val())?, 0);
+    Ok(())
+}
+
+#[test]
+fn malformed_val_json_is_rejected() {
+    let host = Host::test_host_with_recording_footprint();
+    assert!(host.val_from_json(&json!({"i128": 5})).is_err());
+}
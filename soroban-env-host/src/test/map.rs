@@ -616,3 +616,84 @@ fn linear_memory_operations() -> Result<(), HostError> {
     }
     Ok(())
 }
+
+#[test]
+fn shared_map_matches_flat_map() -> Result<(), HostError> {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn payloads(host: &Host, m: &MeteredOrdMap<Val, Val, Host>) -> Vec<(u64, u64)> {
+        m.iter(host)
+            .unwrap()
+            .map(|(k, v)| (k.get_payload(), v.get_payload()))
+            .collect()
+    }
+
+    // Applies the same random puts and deletes to a flat and a shared map,
+    // checking that they agree and that earlier versions of the shared map
+    // are left intact.
+    let host = Host::test_host();
+    host.budget_ref().reset_unlimited()?;
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut flat: MeteredOrdMap<Val, Val, Host> = MeteredOrdMap::new();
+    let mut shared = flat.to_shared(&host)?;
+    let mut versions = vec![];
+    for i in 0..3000u32 {
+        let k = U32Val::from(rng.gen_range(0..1000u32)).to_val();
+        let v = U32Val::from(i).to_val();
+        if rng.gen_range(0..4) == 0 {
+            if let Some((new_flat, flat_val)) = flat.remove(&k, &host)? {
+                let (new_shared, shared_val) = shared.remove(&k, &host)?.unwrap();
+                assert_eq!(flat_val.get_payload(), shared_val.get_payload());
+                (flat, shared) = (new_flat, new_shared);
+            } else {
+                assert!(shared.remove(&k, &host)?.is_none());
+            }
+        } else {
+            flat = flat.insert(k, v, &host)?;
+            shared = shared.insert(k, v, &host)?;
+        }
+        assert!(shared.is_shared());
+        assert_eq!(
+            flat.get(&k, &host)?.map(|v| v.get_payload()),
+            shared.get(&k, &host)?.map(|v| v.get_payload())
+        );
+        if i % 100 == 0 {
+            versions.push((payloads(&host, &flat), shared.clone()));
+        }
+    }
+    assert_eq!(payloads(&host, &flat), payloads(&host, &shared));
+    for (expected, shared) in versions {
+        assert_eq!(expected, payloads(&host, &shared));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn map_put_shares_structure_from_next_protocol() -> Result<(), HostError> {
+    use crate::vm::NEXT_PROTOCOL;
+
+    fn build(host: &Host, n: u32) -> Result<(MapObject, u64), HostError> {
+        host.budget_ref().reset_unlimited()?;
+        let mut m = host.map_new()?;
+        for i in 0..n {
+            m = host.map_put(m, U32Val::from(i).into(), U32Val::from(i).into())?;
+        }
+        Ok((m, host.budget_ref().get_mem_bytes_consumed()?))
+    }
+
+    let n = 2000;
+    let next = Host::test_host();
+    next.with_mut_ledger_info(|li| li.protocol_version = NEXT_PROTOCOL)?;
+    let prev = Host::test_host();
+    prev.with_mut_ledger_info(|li| li.protocol_version = NEXT_PROTOCOL - 1)?;
+    let (m_next, mem_next) = build(&next, n)?;
+    let (m_prev, mem_prev) = build(&prev, n)?;
+
+    let m_next: ScVal = next.from_host_val(m_next.to_val())?;
+    let m_prev: ScVal = prev.from_host_val(m_prev.to_val())?;
+    assert_eq!(m_next, m_prev);
+    // Copying whole maps costs O(n^2) memory, copying paths O(n log n).
+    assert_ge!(mem_prev, 10 * mem_next);
+    Ok(())
+}
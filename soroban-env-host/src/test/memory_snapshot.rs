@@ -0,0 +1,66 @@
+use crate::{host_object::HostVec, Env, Host, HostError, Symbol, U32Val, Val};
+use soroban_synth_wasm::{Arity, ModEmitter};
+
+fn trapping_contract_with_memory(val: Val) -> Vec<u8> {
+    // The default module already defines and exports a one-page memory.
+    let mut me = ModEmitter::default_with_test_protocol();
+    me.define_global_i64(42, true, Some("counter"));
+    me.define_data_segment(0x100, val.get_payload().to_le_bytes().to_vec());
+    let mut fe = me.func(Arity(0), 0);
+    fe.trap();
+    fe.finish_and_export("boom").finish()
+}
+
+fn call_boom(host: &Host, wasm: &[u8]) -> Result<(), HostError> {
+    let contract_id_obj = host.register_test_contract_wasm(wasm);
+    let res = host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("boom")?,
+        host.add_host_object(HostVec::new())?,
+    );
+    assert!(res.is_err());
+    Ok(())
+}
+
+#[test]
+fn failed_vm_memory_is_captured_when_enabled() -> Result<(), HostError> {
+    let val: Val = U32Val::from(7).into();
+    let wasm = trapping_contract_with_memory(val);
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_failed_vm_memory_capture(true)?;
+    call_boom(&host, &wasm)?;
+
+    let snapshot = host
+        .take_failed_vm_memory()?
+        .expect("missing memory snapshot");
+    assert_eq!(snapshot.function, "boom");
+    assert_eq!(snapshot.memory.len(), 0x1_0000);
+    assert_eq!(
+        snapshot.read_val(0x100).map(|v| v.get_payload()),
+        Some(val.get_payload())
+    );
+    assert_eq!(snapshot.read_vals(0x100, 2).map(|v| v.len()), Some(2));
+    assert!(snapshot.read_bytes(0xffff, 2).is_none());
+    assert!(matches!(
+        snapshot.global("counter"),
+        Some(wasmi::Value::I64(42))
+    ));
+
+    let path = std::env::temp_dir().join("soroban-failed-vm-memory.bin");
+    snapshot.dump_to_file(&path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), snapshot.memory);
+    std::fs::remove_file(&path).unwrap();
+
+    // Taking the snapshot clears it.
+    assert!(host.take_failed_vm_memory()?.is_none());
+    Ok(())
+}
+
+#[test]
+fn failed_vm_memory_is_not_captured_by_default() -> Result<(), HostError> {
+    let wasm = trapping_contract_with_memory(Val::VOID.into());
+    let host = Host::test_host_with_recording_footprint();
+    call_boom(&host, &wasm)?;
+    assert!(host.take_failed_vm_memory()?.is_none());
+    Ok(())
+}
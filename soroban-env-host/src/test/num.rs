@@ -437,3 +437,343 @@ fn test_u256_bytes_roundtrip() -> Result<(), HostError> {
     assert_eq!(num, num_back);
     Ok(())
 }
+
+#[cfg(feature = "next")]
+fn check_num_arith_checked<T, V, F>(
+    host: &Host,
+    lhs: T,
+    rhs: T,
+    f: F,
+    expected: Option<T>,
+) -> Result<(), HostError>
+where
+    V: TryFromVal<Host, T> + Into<Val>,
+    HostError: From<<V as TryFromVal<Host, T>>::Error>,
+    F: FnOnce(&Host, V, V) -> Result<Val, HostError>,
+{
+    let lhs: V = V::try_from_val(host, &lhs)?;
+    let rhs: V = V::try_from_val(host, &rhs)?;
+    let res_back = f(host, lhs, rhs)?;
+    match expected {
+        Some(expected) => {
+            let res: V = V::try_from_val(host, &expected)?;
+            assert_eq!(host.compare(&res.into(), &res_back)?, Ordering::Equal);
+        }
+        None => assert!(res_back.is_void()),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+fn check_num_arith_rhs_u32_checked<T, V, F>(
+    host: &Host,
+    lhs: T,
+    rhs: u32,
+    f: F,
+    expected: Option<T>,
+) -> Result<(), HostError>
+where
+    V: TryFromVal<Host, T> + Into<Val>,
+    HostError: From<<V as TryFromVal<Host, T>>::Error>,
+    F: FnOnce(&Host, V, U32Val) -> Result<Val, HostError>,
+{
+    let lhs: V = V::try_from_val(host, &lhs)?;
+    let res_back = f(host, lhs, U32Val::from(rhs))?;
+    match expected {
+        Some(expected) => {
+            let res: V = V::try_from_val(host, &expected)?;
+            assert_eq!(host.compare(&res.into(), &res_back)?, Ordering::Equal);
+        }
+        None => assert!(res_back.is_void()),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn test_u256_checked_and_saturating_arith() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    // checked
+    check_num_arith_checked(
+        &host,
+        U256::MAX - 2,
+        U256::new(1),
+        Host::u256_checked_add,
+        Some(U256::MAX - 1),
+    )?;
+    check_num_arith_checked(
+        &host,
+        U256::MAX - 2,
+        U256::new(3),
+        Host::u256_checked_add,
+        None,
+    )?;
+    check_num_arith_checked(
+        &host,
+        U256::new(1),
+        U256::new(1),
+        Host::u256_checked_sub,
+        Some(U256::ZERO),
+    )?;
+    check_num_arith_checked(
+        &host,
+        U256::ZERO,
+        U256::new(1),
+        Host::u256_checked_sub,
+        None,
+    )?;
+    check_num_arith_checked(
+        &host,
+        U256::new(5),
+        U256::new(3),
+        Host::u256_checked_mul,
+        Some(U256::new(15)),
+    )?;
+    check_num_arith_checked(&host, U256::MAX, U256::new(2), Host::u256_checked_mul, None)?;
+    check_num_arith_rhs_u32_checked(
+        &host,
+        U256::new(2),
+        5,
+        Host::u256_checked_pow,
+        Some(U256::new(32)),
+    )?;
+    check_num_arith_rhs_u32_checked(&host, U256::new(2), 256, Host::u256_checked_pow, None)?;
+
+    // saturating
+    check_num_arith_ok(
+        &host,
+        U256::MAX - 2,
+        U256::new(3),
+        Host::u256_saturating_add,
+        U256::MAX,
+    )?;
+    check_num_arith_ok(
+        &host,
+        U256::new(1),
+        U256::new(3),
+        Host::u256_saturating_sub,
+        U256::ZERO,
+    )?;
+    check_num_arith_ok(
+        &host,
+        U256::new(5),
+        U256::new(3),
+        Host::u256_saturating_mul,
+        U256::new(15),
+    )?;
+    check_num_arith_ok(
+        &host,
+        U256::MAX,
+        U256::new(2),
+        Host::u256_saturating_mul,
+        U256::MAX,
+    )?;
+    check_num_arith_rhs_u32_ok(
+        &host,
+        U256::new(2),
+        255,
+        Host::u256_saturating_pow,
+        U256::ONE << 255,
+    )?;
+    check_num_arith_rhs_u32_ok(
+        &host,
+        U256::new(2),
+        256,
+        Host::u256_saturating_pow,
+        U256::MAX,
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn test_i256_checked_and_saturating_arith() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    // checked
+    check_num_arith_checked(
+        &host,
+        I256::MAX - 2,
+        I256::new(1),
+        Host::i256_checked_add,
+        Some(I256::MAX - 1),
+    )?;
+    check_num_arith_checked(
+        &host,
+        I256::MAX - 2,
+        I256::new(3),
+        Host::i256_checked_add,
+        None,
+    )?;
+    check_num_arith_checked(
+        &host,
+        I256::MIN + 2,
+        I256::new(1),
+        Host::i256_checked_sub,
+        Some(I256::MIN + 1),
+    )?;
+    check_num_arith_checked(
+        &host,
+        I256::MIN + 2,
+        I256::new(3),
+        Host::i256_checked_sub,
+        None,
+    )?;
+    check_num_arith_checked(
+        &host,
+        I256::new(-5),
+        I256::new(3),
+        Host::i256_checked_mul,
+        Some(I256::new(-15)),
+    )?;
+    check_num_arith_checked(
+        &host,
+        I256::MIN,
+        I256::new(-1),
+        Host::i256_checked_mul,
+        None,
+    )?;
+    check_num_arith_rhs_u32_checked(
+        &host,
+        I256::new(-2),
+        3,
+        Host::i256_checked_pow,
+        Some(I256::new(-8)),
+    )?;
+    check_num_arith_rhs_u32_checked(&host, I256::new(2), 255, Host::i256_checked_pow, None)?;
+
+    // saturating
+    check_num_arith_ok(
+        &host,
+        I256::MAX - 2,
+        I256::new(3),
+        Host::i256_saturating_add,
+        I256::MAX,
+    )?;
+    check_num_arith_ok(
+        &host,
+        I256::MIN + 2,
+        I256::new(3),
+        Host::i256_saturating_sub,
+        I256::MIN,
+    )?;
+    check_num_arith_ok(
+        &host,
+        I256::MIN,
+        I256::new(-1),
+        Host::i256_saturating_mul,
+        I256::MAX,
+    )?;
+    check_num_arith_ok(
+        &host,
+        I256::MAX,
+        I256::new(-2),
+        Host::i256_saturating_mul,
+        I256::MIN,
+    )?;
+    check_num_arith_rhs_u32_ok(
+        &host,
+        I256::new(-2),
+        255,
+        Host::i256_saturating_pow,
+        I256::MIN,
+    )?;
+    check_num_arith_rhs_u32_ok(
+        &host,
+        I256::new(-2),
+        256,
+        Host::i256_saturating_pow,
+        I256::MAX,
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn test_u256_mul_div() -> Result<(), HostError> {
+    use crate::xdr::ContractCostType;
+    let host = observe_host!(Host::test_host());
+    let mul_div = |a: U256, b: U256, c: U256, rounding: u32| -> Result<U256, HostError> {
+        let res = host.u256_mul_div(
+            U256Val::try_from_val(&*host, &a)?,
+            U256Val::try_from_val(&*host, &b)?,
+            U256Val::try_from_val(&*host, &c)?,
+            U32Val::from(rounding),
+        )?;
+        Ok(res.to_val().try_into_val(&*host)?)
+    };
+
+    // Small values, with each rounding mode.
+    assert_eq!(
+        mul_div(U256::new(7), U256::new(1), U256::new(2), 0)?,
+        U256::new(3)
+    );
+    assert_eq!(
+        mul_div(U256::new(7), U256::new(1), U256::new(2), 1)?,
+        U256::new(4)
+    );
+    assert_eq!(
+        mul_div(U256::new(7), U256::new(1), U256::new(2), 2)?,
+        U256::new(4)
+    );
+    assert_eq!(
+        mul_div(U256::new(4), U256::new(1), U256::new(3), 1)?,
+        U256::new(2)
+    );
+    assert_eq!(
+        mul_div(U256::new(4), U256::new(1), U256::new(3), 2)?,
+        U256::new(1)
+    );
+    assert_eq!(
+        mul_div(U256::new(6), U256::new(1), U256::new(3), 1)?,
+        U256::new(2)
+    );
+
+    // The intermediate product doesn't fit in 256 bits.
+    assert_eq!(mul_div(U256::MAX, U256::MAX, U256::MAX, 0)?, U256::MAX);
+    assert_eq!(
+        mul_div(U256::MAX, U256::MAX - 1, U256::MAX, 1)?,
+        U256::MAX - 1
+    );
+    let e20 = U256::new(10u128.pow(20));
+    let e40 = e20 * e20;
+    let e30 = U256::new(10u128.pow(30));
+    assert_eq!(mul_div(e40, e40, e30, 0)?, e40 * U256::new(10u128.pow(10)));
+    // 10^80 / (3 * 10^30) = 33..33.33
+    let third = (e40 * U256::new(10u128.pow(10)) - 1) / 3;
+    assert_eq!(mul_div(e40, e40, e30 * 3, 0)?, third);
+    assert_eq!(mul_div(e40, e40, e30 * 3, 1)?, third + 1);
+    assert_eq!(mul_div(e40, e40, e30 * 3, 2)?, third);
+    // 2 * 10^80 / (3 * 10^30) = 66..66.67
+    assert_eq!(mul_div(e40 * 2, e40, e30 * 3, 0)?, third * 2);
+    assert_eq!(mul_div(e40 * 2, e40, e30 * 3, 2)?, third * 2 + 1);
+
+    // Errors.
+    let code = (ScErrorType::Object, ScErrorCode::ArithDomain);
+    assert!(HostError::result_matches_err(
+        mul_div(U256::new(1), U256::new(1), U256::ZERO, 0),
+        code
+    ));
+    assert!(HostError::result_matches_err(
+        mul_div(U256::MAX, U256::new(3), U256::new(2), 0),
+        code
+    ));
+    assert!(HostError::result_matches_err(
+        mul_div(U256::new(1), U256::new(1), U256::new(1), 3),
+        (ScErrorType::Value, ScErrorCode::InvalidInput)
+    ));
+
+    // It is charged as a single `Int256MulDiv`.
+    host.budget_ref().reset_default()?;
+    mul_div(U256::MAX, U256::MAX - 1, U256::MAX, 0)?;
+    let budget = host.budget_ref();
+    assert_eq!(
+        budget
+            .get_tracker(ContractCostType::Int256MulDiv)?
+            .iterations,
+        1
+    );
+    for ty in [ContractCostType::Int256Mul, ContractCostType::Int256Div] {
+        assert_eq!(budget.get_tracker(ty)?.iterations, 0);
+    }
+    Ok(())
+}
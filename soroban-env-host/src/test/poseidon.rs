@@ -0,0 +1,495 @@
+use crate::{
+    crypto::poseidon_params::*,
+    xdr::{ContractCostType, ScErrorCode, ScErrorType},
+    Env, EnvBase, Host, HostError, Symbol, TryFromVal, U256Val, U32Val, Val, VecObject, U256,
+};
+use ark_ff::{BigInteger, PrimeField};
+use std::collections::VecDeque;
+
+// The Grain LFSR used by the reference implementations of Poseidon and
+// Poseidon2 to generate their round constants (and the MDS matrix of
+// Poseidon), see appendix F of https://eprint.iacr.org/2019/458.
+struct Grain {
+    state: VecDeque<bool>,
+    field_bits: u32,
+}
+
+impl Grain {
+    fn new<F: PrimeField>(t: u64, rounds_f: u64, rounds_p: u64) -> Self {
+        let field_bits = F::MODULUS_BIT_SIZE;
+        let mut state = VecDeque::with_capacity(80);
+        // prime field, x^d s-box, field size, t, rounds_f, rounds_p
+        for (v, len) in [
+            (1, 2),
+            (0, 4),
+            (field_bits as u64, 12),
+            (t, 12),
+            (rounds_f, 10),
+            (rounds_p, 10),
+        ] {
+            for i in (0..len).rev() {
+                state.push_back((v >> i) & 1 == 1);
+            }
+        }
+        state.extend([true; 30]);
+        let mut grain = Grain { state, field_bits };
+        for _ in 0..160 {
+            grain.next_bit();
+        }
+        grain
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.pop_front();
+        self.state.push_back(bit);
+        bit
+    }
+
+    fn random_bit(&mut self) -> bool {
+        loop {
+            let keep = self.next_bit();
+            let bit = self.next_bit();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    fn random_bytes(&mut self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for pos in (0..self.field_bits as usize).rev() {
+            if self.random_bit() {
+                bytes[31 - pos / 8] |= 1 << (pos % 8);
+            }
+        }
+        bytes
+    }
+
+    // Round constants are sampled by rejection.
+    fn field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let bytes = self.random_bytes();
+            let fe = F::from_be_bytes_mod_order(&bytes);
+            if fe.into_bigint().to_bytes_be() == bytes {
+                return fe;
+            }
+        }
+    }
+
+    // MDS matrix entries are reduced instead.
+    fn field_element_mod_order<F: PrimeField>(&mut self) -> F {
+        F::from_be_bytes_mod_order(&self.random_bytes())
+    }
+}
+
+fn poseidon_params<F: PrimeField>(
+    t: usize,
+    rounds_f: usize,
+    rounds_p: usize,
+) -> (Vec<Vec<F>>, Vec<Vec<F>>) {
+    let mut grain = Grain::new::<F>(t as u64, rounds_f as u64, rounds_p as u64);
+    let round_constants: Vec<Vec<F>> = (0..rounds_f + rounds_p)
+        .map(|_| (0..t).map(|_| grain.field_element()).collect())
+        .collect();
+    let xs: Vec<F> = (0..t).map(|_| grain.field_element_mod_order()).collect();
+    let ys: Vec<F> = (0..t).map(|_| grain.field_element_mod_order()).collect();
+    let mds = xs
+        .iter()
+        .map(|x| ys.iter().map(|y| (*x + y).inverse().unwrap()).collect())
+        .collect();
+    (mds, round_constants)
+}
+
+fn poseidon2_round_constants<F: PrimeField>(
+    t: usize,
+    rounds_f: usize,
+    rounds_p: usize,
+) -> Vec<Vec<F>> {
+    let mut grain = Grain::new::<F>(t as u64, rounds_f as u64, rounds_p as u64);
+    (0..rounds_f + rounds_p)
+        .map(|r| {
+            if r >= rounds_f / 2 && r < rounds_f / 2 + rounds_p {
+                let mut rc = vec![F::zero(); t];
+                rc[0] = grain.field_element();
+                rc
+            } else {
+                (0..t).map(|_| grain.field_element()).collect()
+            }
+        })
+        .collect()
+}
+
+fn fe_to_val<F: PrimeField>(host: &Host, fe: &F) -> Val {
+    let bytes: [u8; 32] = fe.into_bigint().to_bytes_be().try_into().unwrap();
+    U256Val::try_from_val(host, &U256::from_be_bytes(bytes))
+        .unwrap()
+        .to_val()
+}
+
+fn vec_of<F: PrimeField>(host: &Host, v: &[F]) -> VecObject {
+    let vals: Vec<Val> = v.iter().map(|fe| fe_to_val(host, fe)).collect();
+    host.vec_new_from_slice(&vals).unwrap()
+}
+
+fn matrix_of<F: PrimeField>(host: &Host, m: &[Vec<F>]) -> VecObject {
+    let rows: Vec<Val> = m.iter().map(|row| vec_of(host, row).to_val()).collect();
+    host.vec_new_from_slice(&rows).unwrap()
+}
+
+fn u256s_of(host: &Host, v: VecObject) -> Vec<U256> {
+    let len: u32 = host.vec_len(v).unwrap().into();
+    (0..len)
+        .map(|i| U256::try_from_val(host, &host.vec_get(v, i.into()).unwrap()).unwrap())
+        .collect()
+}
+
+fn hex(s: &str) -> U256 {
+    U256::from_str_radix(s, 16).unwrap()
+}
+
+fn state<F: PrimeField>(t: u64) -> Vec<F> {
+    (0..t).map(F::from).collect()
+}
+
+fn field(name: &str) -> Symbol {
+    Symbol::try_from_small_str(name).unwrap()
+}
+
+fn hash(host: &Host, inputs: &[u64], params: &str) -> Result<U256, HostError> {
+    let inputs: Vec<ark_bn254::Fr> = inputs.iter().copied().map(Into::into).collect();
+    let params = Symbol::try_from_val(host, &params)?;
+    let res = host.poseidon_hash(vec_of(host, &inputs), params)?;
+    Ok(U256::try_from_val(host, &res.to_val())?)
+}
+
+#[test]
+fn poseidon_bn254_matches_circom() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let (mds, rc) = poseidon_params::<ark_bn254::Fr>(3, 8, 57);
+    // circom's `Poseidon(2)` hashes `[a, b]` as the first element of the
+    // permutation of `[0, a, b]`.
+    let res = host.poseidon_permutation(
+        vec_of(&host, &state::<ark_bn254::Fr>(3)),
+        field("BN254"),
+        U32Val::from(5),
+        U32Val::from(8),
+        matrix_of(&host, &mds),
+        matrix_of(&host, &rc),
+    )?;
+    assert_eq!(
+        u256s_of(&host, res)[0],
+        hex("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+    );
+    Ok(())
+}
+
+#[test]
+fn poseidon2_bn254_matches_reference() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let rc = poseidon2_round_constants::<ark_bn254::Fr>(3, 8, 56);
+    let diag: Vec<ark_bn254::Fr> = [1u64, 1, 2].into_iter().map(Into::into).collect();
+    let res = host.poseidon2_permutation(
+        vec_of(&host, &state::<ark_bn254::Fr>(3)),
+        field("BN254"),
+        U32Val::from(5),
+        U32Val::from(8),
+        vec_of(&host, &diag),
+        matrix_of(&host, &rc),
+    )?;
+    assert_eq!(
+        u256s_of(&host, res),
+        vec![
+            hex("0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033"),
+            hex("303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570"),
+            hex("1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn poseidon2_bls12_381_matches_reference() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let rc = poseidon2_round_constants::<ark_bls12_381::Fr>(3, 8, 56);
+    let diag: Vec<ark_bls12_381::Fr> = [1u64, 1, 2].into_iter().map(Into::into).collect();
+    let res = host.poseidon2_permutation(
+        vec_of(&host, &state::<ark_bls12_381::Fr>(3)),
+        field("BLS12_381"),
+        U32Val::from(5),
+        U32Val::from(8),
+        vec_of(&host, &diag),
+        matrix_of(&host, &rc),
+    )?;
+    assert_eq!(
+        u256s_of(&host, res),
+        vec![
+            hex("1b152349b1950b6a8ca75ee4407b6e26ca5cca5650534e56ef3fd45761fbf5f0"),
+            hex("4c5793c87d51bdc2c08a32108437dc0000bd0275868f09ebc5f36919af5b3891"),
+            hex("1fc8ed171e67902ca49863159fe5ba6325318843d13976143b8125f08b50dc6b"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn poseidon2_larger_states() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    for t in [2u64, 4, 8, 12, 16, 20, 24] {
+        let rc = poseidon2_round_constants::<ark_bn254::Fr>(t as usize, 8, 56);
+        let diag: Vec<ark_bn254::Fr> = (1..=t).map(Into::into).collect();
+        let permute = |input: &[ark_bn254::Fr]| {
+            host.poseidon2_permutation(
+                vec_of(&host, input),
+                field("BN254"),
+                U32Val::from(5),
+                U32Val::from(8),
+                vec_of(&host, &diag),
+                matrix_of(&host, &rc),
+            )
+            .map(|res| u256s_of(&host, res))
+        };
+        let res = permute(&state(t))?;
+        assert_eq!(res.len(), t as usize);
+        let mut other = state::<ark_bn254::Fr>(t);
+        other[t as usize - 1] += ark_bn254::Fr::from(1u64);
+        assert_ne!(res, permute(&other)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn named_params_match_grain() {
+    fn flatten<F: Clone>(m: Vec<Vec<F>>) -> Vec<F> {
+        m.into_iter().flatten().collect()
+    }
+    for params in [
+        CIRCOM_BN254_T2,
+        CIRCOM_BN254_T3,
+        CIRCOM_BN254_T4,
+        CIRCOM_BN254_T5,
+        CIRCOM_BN254_T6,
+    ] {
+        let (mds, rc) = poseidon_params::<ark_bn254::Fr>(
+            params.t,
+            params.rounds_f as usize,
+            params.rounds_p as usize,
+        );
+        assert_eq!(params.mds, flatten(mds));
+        assert_eq!(params.round_constants, flatten(rc));
+    }
+    assert_eq!(
+        POSEIDON2_BN254_T3.round_constants,
+        flatten(poseidon2_round_constants::<ark_bn254::Fr>(3, 8, 56))
+    );
+    assert_eq!(
+        POSEIDON2_BLS12_381_T3.round_constants,
+        flatten(poseidon2_round_constants::<ark_bls12_381::Fr>(3, 8, 56))
+    );
+}
+
+#[test]
+fn poseidon_is_charged_in_its_own_field() -> Result<(), HostError> {
+    use ContractCostType::*;
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let iterations = |ty| host.budget_ref().get_tracker(ty).unwrap().iterations;
+
+    hash(&host, &[1, 2], "CIRCOM_BN254_T3")?;
+    for ty in [
+        Bn254FrFromU256,
+        Bn254FrToU256,
+        Bn254FrAddSub,
+        Bn254FrMul,
+        Bn254FrPow,
+    ] {
+        assert!(iterations(ty) > 0, "{ty:?} was not charged");
+    }
+    for ty in [
+        Bls12381FrFromU256,
+        Bls12381FrToU256,
+        Bls12381FrAddSub,
+        Bls12381FrMul,
+        Bls12381FrPow,
+    ] {
+        assert_eq!(iterations(ty), 0, "{ty:?} was charged");
+    }
+    Ok(())
+}
+
+#[test]
+fn poseidon_hash_matches_circom() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    // The test vectors of circomlibjs.
+    assert_eq!(
+        hash(&host, &[1], "CIRCOM_BN254_T2")?,
+        hex("29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133")
+    );
+    assert_eq!(
+        hash(&host, &[1, 2], "CIRCOM_BN254_T3")?,
+        hex("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+    );
+    assert_eq!(
+        hash(&host, &[1, 2, 3, 4], "CIRCOM_BN254_T5")?,
+        hex("299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465")
+    );
+    assert_eq!(
+        hash(&host, &[1, 2, 3, 4, 5], "CIRCOM_BN254_T6")?,
+        hex("0dab9449e4a1398a15224c0b15a49d598b2174d305a316c918125f8feeb123c0")
+    );
+    // The first element of the permutation of `[0, 1, 2]`, see the tests
+    // above.
+    assert_eq!(
+        hash(&host, &[1, 2], "POSEIDON2_BN254_T3")?,
+        hex("0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033")
+    );
+    let bls_inputs: Vec<ark_bls12_381::Fr> = [1u64, 2].into_iter().map(Into::into).collect();
+    let res = host.poseidon_hash(
+        vec_of(&host, &bls_inputs),
+        Symbol::try_from_val(&*host, &"POSEIDON2_BLS12_381_T3")?,
+    )?;
+    assert_eq!(
+        U256::try_from_val(&*host, &res.to_val())?,
+        hex("1b152349b1950b6a8ca75ee4407b6e26ca5cca5650534e56ef3fd45761fbf5f0")
+    );
+    Ok(())
+}
+
+#[test]
+fn poseidon_hash_absorbs_chunks() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    type Fr = ark_bn254::Fr;
+    let (mds, rc) = poseidon_params::<Fr>(3, 8, 57);
+    let (mds, rc) = (matrix_of(&host, &mds), matrix_of(&host, &rc));
+    let permute = |state: &[Fr]| -> Result<Vec<Fr>, HostError> {
+        let res = host.poseidon_permutation(
+            vec_of(&host, state),
+            field("BN254"),
+            U32Val::from(5),
+            U32Val::from(8),
+            mds,
+            rc,
+        )?;
+        Ok(u256s_of(&host, res)
+            .iter()
+            .map(|u| Fr::from_be_bytes_mod_order(&u.to_be_bytes()))
+            .collect())
+    };
+    let mut state = permute(&state::<Fr>(3))?;
+    state[1] += Fr::from(3u64);
+    state[2] += Fr::from(4u64);
+    let state = permute(&state)?;
+    assert_eq!(
+        hash(&host, &[1, 2, 3, 4], "CIRCOM_BN254_T3")?,
+        U256::from_be_bytes(state[0].into_bigint().to_bytes_be().try_into().unwrap())
+    );
+    Ok(())
+}
+
+#[test]
+fn poseidon_hash_rejects_invalid_inputs() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    let invalid_input = (ScErrorType::Crypto, ScErrorCode::InvalidInput);
+    // unknown parameter set
+    assert!(HostError::result_matches_err(
+        hash(&host, &[1, 2], "CIRCOM_BN254_T7"),
+        invalid_input
+    ));
+    // no inputs
+    assert!(HostError::result_matches_err(
+        hash(&host, &[], "CIRCOM_BN254_T3"),
+        invalid_input
+    ));
+    // not a multiple of the rate
+    assert!(HostError::result_matches_err(
+        hash(&host, &[1, 2, 3], "CIRCOM_BN254_T3"),
+        invalid_input
+    ));
+    Ok(())
+}
+
+#[test]
+fn permutation_rejects_invalid_parameters() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host());
+    host.budget_ref().reset_unlimited()?;
+    type Fr = ark_bn254::Fr;
+    let (mds, rc) = poseidon_params::<Fr>(3, 8, 57);
+    let mds = matrix_of(&host, &mds);
+    let rc = matrix_of(&host, &rc);
+    let input = vec_of(&host, &state::<Fr>(3));
+    let permute = |input, field, d: u32, rounds_f: u32, mds, rc| {
+        host.poseidon_permutation(
+            input,
+            field,
+            U32Val::from(d),
+            U32Val::from(rounds_f),
+            mds,
+            rc,
+        )
+    };
+    let invalid_input = (ScErrorType::Crypto, ScErrorCode::InvalidInput);
+
+    assert!(permute(input, field("BN254"), 5, 8, mds, rc).is_ok());
+    // unknown field
+    assert!(HostError::result_matches_err(
+        permute(input, field("BLS12_377"), 5, 8, mds, rc),
+        invalid_input
+    ));
+    // the mds matrix doesn't match the state
+    let larger_input = vec_of(&host, &state::<Fr>(4));
+    assert!(HostError::result_matches_err(
+        permute(larger_input, field("BN254"), 5, 8, mds, rc),
+        invalid_input
+    ));
+    // unsupported s-box degree
+    assert!(HostError::result_matches_err(
+        permute(input, field("BN254"), 4, 8, mds, rc),
+        invalid_input
+    ));
+    // odd number of full rounds
+    assert!(HostError::result_matches_err(
+        permute(input, field("BN254"), 5, 7, mds, rc),
+        invalid_input
+    ));
+    // more full rounds than round constants
+    assert!(HostError::result_matches_err(
+        permute(input, field("BN254"), 5, 66, mds, rc),
+        invalid_input
+    ));
+    // non-canonical field element
+    let modulus = U256::from_be_bytes(Fr::MODULUS.to_bytes_be().try_into().unwrap());
+    let zero = fe_to_val(&host, &Fr::from(0u64));
+    let non_canonical = host.vec_new_from_slice(&[
+        U256Val::try_from_val(&*host, &modulus)?.to_val(),
+        zero,
+        zero,
+    ])?;
+    assert!(HostError::result_matches_err(
+        permute(non_canonical, field("BN254"), 5, 8, mds, rc),
+        invalid_input
+    ));
+
+    // Poseidon2 only supports specific state sizes.
+    let rc = poseidon2_round_constants::<Fr>(5, 8, 56);
+    let diag: Vec<Fr> = (1..=5u64).map(Into::into).collect();
+    assert!(HostError::result_matches_err(
+        host.poseidon2_permutation(
+            vec_of(&host, &state::<Fr>(5)),
+            field("BN254"),
+            U32Val::from(5),
+            U32Val::from(8),
+            vec_of(&host, &diag),
+            matrix_of(&host, &rc),
+        ),
+        invalid_input
+    ));
+    Ok(())
+}
@@ -300,3 +300,64 @@ fn test_additional_protocol_guardrail_during_invocation() -> Result<(), HostErro
     );
     Ok(())
 }
+
+#[test]
+fn protocol_compatibility_report_for_wasm() -> Result<(), HostError> {
+    use crate::vm::{Vm, NEXT_PROTOCOL};
+    use soroban_synth_wasm::{Arity, ModEmitter};
+
+    let env_proto = INTERFACE_VERSION.protocol;
+
+    // A contract current to the env, importing an ungated host function, is
+    // linkable exactly at the env protocol.
+    let mut me = ModEmitter::new();
+    me.add_protocol_version_meta(env_proto);
+    me.import_func("t", "_", Arity(0));
+    let report = Vm::protocol_compatibility_report(&me.finish())?;
+    assert_eq!(report.interface_version.protocol, env_proto);
+    assert_eq!(report.imports.len(), 1);
+    assert_eq!(report.imports[0].name, "dummy0");
+    assert_eq!(report.linkable_protocols, Some(env_proto..=env_proto));
+    assert!(report.is_linkable_at(env_proto));
+    assert!(!report.is_linkable_at(env_proto + 1));
+    assert!(report.deprecated.is_empty());
+
+    // `protocol_gated_dummy` is only supported in protocol 19, which is before
+    // any contract protocol, so it is deprecated, removed in the next protocol
+    // and makes the contract unlinkable.
+    let wasm = wasm_util::wasm_module_calling_protocol_gated_host_fn(env_proto);
+    let report = Vm::protocol_compatibility_report(&wasm)?;
+    assert_eq!(report.imports[0].name, "protocol_gated_dummy");
+    assert_eq!(report.linkable_protocols, None);
+    assert_eq!(report.deprecated, report.imports);
+    assert_eq!(report.removed_in_next, report.imports);
+    assert!(NEXT_PROTOCOL > 19);
+
+    // A contract declaring protocol 18 imports a function gated to 19.
+    let wasm = wasm_util::wasm_module_calling_protocol_gated_host_fn(18);
+    let report = Vm::protocol_compatibility_report(&wasm)?;
+    assert_eq!(report.gated_beyond_contract_protocol, report.imports);
+    assert_eq!(report.linkable_protocols, None);
+
+    // An import unknown to the host is reported and prevents linking.
+    let mut me = ModEmitter::new();
+    me.add_protocol_version_meta(env_proto);
+    me.import_func("t", "_", Arity(0));
+    me.import_func("t", "zz", Arity(0));
+    let report = Vm::protocol_compatibility_report(&me.finish())?;
+    assert_eq!(report.imports.len(), 1);
+    assert_eq!(
+        report.unknown_imports,
+        vec![("t".to_string(), "zz".to_string())]
+    );
+    assert_eq!(report.linkable_protocols, None);
+
+    // A module without env meta is rejected.
+    let mut me = ModEmitter::new();
+    me.import_func("t", "_", Arity(0));
+    assert!(HostError::result_matches_err(
+        Vm::protocol_compatibility_report(&me.finish()),
+        (ScErrorType::WasmVm, ScErrorCode::InvalidInput)
+    ));
+    Ok(())
+}
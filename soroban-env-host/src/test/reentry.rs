@@ -0,0 +1,269 @@
+use std::rc::Rc;
+
+use soroban_synth_wasm::{Arity, ModEmitter, Operand};
+
+use crate::{
+    xdr::{Hash, ScAddress, ScErrorCode, ScErrorType},
+    AddressObject, ContractFunctionSet, Env, EnvBase, Error, Host, HostError, StorageType, Symbol,
+    SymbolStr, TryFromVal, U32Val, Val, VecObject, DEFAULT_HOST_DEPTH_LIMIT,
+};
+
+const PROHIBITED: u32 = 0;
+const SELF_ALLOWED: u32 = 1;
+const ALLOWED: u32 = 2;
+
+fn sym(s: &str) -> Symbol {
+    Symbol::try_from_small_str(s).unwrap()
+}
+
+fn fn_name(host: &Host, func: &Symbol) -> String {
+    SymbolStr::try_from_val(host, func).unwrap().to_string()
+}
+
+// A lending pool that lends to `borrower`, which calls back into the pool via
+// `repay` before `lend` returns. The outstanding amount is kept in instance
+// storage, so the outer `lend` frame must observe the update made by the
+// re-entered `repay` frame.
+struct Pool;
+impl ContractFunctionSet for Pool {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        let key = sym("debt");
+        match fn_name(host, func).as_str() {
+            "lend" => {
+                let borrower = AddressObject::try_from_val(host, &args[0]).unwrap();
+                let pool = host.get_current_contract_address().unwrap();
+                host.put_contract_data(key.into(), U32Val::from(1).into(), StorageType::Instance)
+                    .unwrap();
+                let args = host.vec_new_from_slice(&[pool.into(), args[1]]).unwrap();
+                let res = host.call(borrower, sym("borrow"), args).unwrap();
+                let debt = host
+                    .get_contract_data(key.into(), StorageType::Instance)
+                    .unwrap();
+                Some(host.vec_new_from_slice(&[res, debt]).unwrap().into())
+            }
+            "repay" => {
+                // The direct invoker (the borrower) is authorized implicitly.
+                let borrower = AddressObject::try_from_val(host, &args[0]).unwrap();
+                host.require_auth(borrower).unwrap();
+                host.put_contract_data(key.into(), U32Val::from(0).into(), StorageType::Instance)
+                    .unwrap();
+                Some(().into())
+            }
+            "repay_own" => {
+                // The pool itself is not the direct invoker of the re-entered
+                // frame, so it isn't authorized implicitly.
+                let pool = host.get_current_contract_address().unwrap();
+                host.require_auth(pool).unwrap();
+                Some(().into())
+            }
+            "__constructor" => Some(().into()),
+            _ => None,
+        }
+    }
+}
+
+struct Borrower;
+impl ContractFunctionSet for Borrower {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        match fn_name(host, func).as_str() {
+            "borrow" => {
+                let pool = AddressObject::try_from_val(host, &args[0]).unwrap();
+                let (func, mode) = match u32::from(U32Val::try_from(args[1]).unwrap()) {
+                    mode @ (PROHIBITED | SELF_ALLOWED | ALLOWED) => (sym("repay"), mode),
+                    _ => (sym("repay_own"), ALLOWED),
+                };
+                let me = host.get_current_contract_address().unwrap();
+                let args = host.vec_new_from_slice(&[me.into()]).unwrap();
+                let res = host
+                    .try_call_with_reentry(pool, func, args, U32Val::from(mode))
+                    .unwrap();
+                // Contracts may not return non-contract errors, so hand the
+                // result of the re-entrant call back wrapped in a vec.
+                Some(host.vec_new_from_slice(&[res]).unwrap().into())
+            }
+            "__constructor" => Some(().into()),
+            _ => None,
+        }
+    }
+}
+
+struct Recursive;
+impl ContractFunctionSet for Recursive {
+    fn call(&self, func: &Symbol, host: &Host, args: &[Val]) -> Option<Val> {
+        match fn_name(host, func).as_str() {
+            "recurse" => {
+                let n = u32::from(U32Val::try_from(args[0]).unwrap());
+                let mode = U32Val::try_from(args[1]).unwrap();
+                if n == 0 {
+                    return Some(U32Val::from(0).into());
+                }
+                let me = host.get_current_contract_address().unwrap();
+                let args = host
+                    .vec_new_from_slice(&[U32Val::from(n - 1).into(), mode.into()])
+                    .unwrap();
+                Some(
+                    host.call_with_reentry(me, sym("recurse"), args, mode)
+                        .unwrap(),
+                )
+            }
+            "__constructor" => Some(().into()),
+            _ => None,
+        }
+    }
+}
+
+fn register(host: &Host, id: u8, contract: Rc<dyn ContractFunctionSet>) -> AddressObject {
+    let address = host
+        .add_host_object(ScAddress::Contract(Hash([id; 32])))
+        .unwrap();
+    host.register_test_contract(address, contract).unwrap();
+    address
+}
+
+fn lend(host: &Host, mode: u32) -> Result<(Val, u32), HostError> {
+    let pool = register(host, 0, Rc::new(Pool));
+    let borrower = register(host, 1, Rc::new(Borrower));
+    let args = host.vec_new_from_slice(&[borrower.into(), U32Val::from(mode).into()])?;
+    let res = host.call(pool, sym("lend"), args)?;
+    let res = VecObject::try_from_val(host, &res)?;
+    let borrow_res = VecObject::try_from_val(host, &host.vec_get(res, U32Val::from(0))?)?;
+    let borrow_res = host.vec_get(borrow_res, U32Val::from(0))?;
+    let debt = U32Val::try_from(host.vec_get(res, U32Val::from(1))?)?;
+    Ok((borrow_res, debt.into()))
+}
+
+#[test]
+fn reentry_is_prohibited_unless_allowed() -> Result<(), HostError> {
+    let invalid_action: Error = (ScErrorType::Context, ScErrorCode::InvalidAction).into();
+    for mode in [PROHIBITED, SELF_ALLOWED] {
+        let host = observe_host!(Host::test_host_with_recording_footprint());
+        let (res, debt) = lend(&host, mode)?;
+        assert_eq!(Error::try_from(res)?, invalid_action);
+        assert_eq!(debt, 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn allowed_reentry_shares_instance_storage() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    let (res, debt) = lend(&host, ALLOWED)?;
+    assert!(res.is_void());
+    assert_eq!(debt, 0);
+    Ok(())
+}
+
+#[test]
+fn reentered_frame_is_not_authorized_by_outer_frame() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    host.enable_debug()?;
+    let (res, debt) = lend(&host, u32::MAX)?;
+    assert_eq!(
+        Error::try_from(res)?,
+        (ScErrorType::Context, ScErrorCode::InvalidAction).into()
+    );
+    assert_eq!(debt, 1);
+    // `try_call_with_reentry` only hands back a generic error, so check that
+    // the re-entered frame actually failed on `require_auth`.
+    let events = host.get_events()?.0;
+    assert!(events.iter().any(|e| e.failed_call
+        && format!("{}", e).contains("topics:[error, Error(Auth, InvalidAction)]")));
+    Ok(())
+}
+
+// A Wasm contract with a single function `set(v)` that stores `v` under `n` in
+// instance storage. Called with 1, it then re-enters itself as `set(2)` and
+// returns both the value observed by the re-entered frame and its own view of
+// `n` after the call. Called with anything else, it returns the previous value
+// of `n`. (A synthesized module can't define a second function calling host
+// functions, as all imports have to precede the first definition.)
+fn self_reentrant_wasm() -> Vec<u8> {
+    let key = sym("n");
+    let mut fe = ModEmitter::default_with_test_protocol().func(Arity(1), 3);
+    let v = fe.alloc_arg("U32Val");
+    let this = fe.alloc_local("AddressObject");
+    let args = fe.alloc_local("VecObject");
+    let seen = fe.alloc_local("Val");
+    fe.push(v).push(U32Val::from(1)).i64_eq();
+    fe.if_then(|fe| {
+        fe.put_contract_data(key, v, StorageType::Instance);
+        fe.drop();
+        fe.get_current_contract_address();
+        fe.local_set(this);
+        fe.vec_new();
+        fe.vec_push_back(Operand::StackTop, U32Val::from(2));
+        fe.local_set(args);
+        fe.call_with_reentry(this, sym("set"), args, U32Val::from(SELF_ALLOWED));
+        fe.local_set(seen);
+        fe.vec_new();
+        fe.vec_push_back(Operand::StackTop, seen);
+        fe.get_contract_data(key, StorageType::Instance);
+        fe.local_set(seen);
+        fe.vec_push_back(Operand::StackTop, seen);
+        fe.ret()
+    });
+    fe.get_contract_data(key, StorageType::Instance);
+    fe.local_set(seen);
+    fe.put_contract_data(key, v, StorageType::Instance);
+    fe.drop();
+    fe.push(seen);
+    fe.finish_and_export("set").finish()
+}
+
+#[test]
+fn wasm_reentry_persists_and_reloads_instance_storage() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    let contract = host.register_test_contract_wasm(self_reentrant_wasm().as_slice());
+    let args = host.vec_new_from_slice(&[U32Val::from(1).into()])?;
+    let res = host.call(contract, sym("set"), args)?;
+    let res = VecObject::try_from_val(&*host, &res)?;
+    let observed_by_inner = U32Val::try_from(host.vec_get(res, U32Val::from(0))?)?;
+    let observed_by_outer = U32Val::try_from(host.vec_get(res, U32Val::from(1))?)?;
+    assert_eq!(u32::from(observed_by_inner), 1);
+    assert_eq!(u32::from(observed_by_outer), 2);
+    Ok(())
+}
+
+#[test]
+fn invalid_reentry_mode_traps() -> Result<(), HostError> {
+    let host = observe_host!(Host::test_host_with_recording_footprint());
+    let contract = register(&host, 2, Rc::new(Recursive));
+    let args = host.vec_new()?;
+    let res = host.try_call_with_reentry(contract, sym("recurse"), args, U32Val::from(3));
+    assert!(HostError::result_matches_err(
+        res,
+        (ScErrorType::Value, ScErrorCode::InvalidInput)
+    ));
+    Ok(())
+}
+
+#[test]
+fn self_reentry_is_bounded_by_depth_limit() {
+    // Native test contracts recurse on the Rust stack, so give the deep case
+    // some room in unoptimized builds.
+    std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| -> Result<(), HostError> {
+            let host = Host::test_host_with_recording_footprint();
+            host.budget_ref().reset_unlimited()?;
+            let contract = register(&host, 2, Rc::new(Recursive));
+
+            let args = host.vec_new_from_slice(&[
+                U32Val::from(10).into(),
+                U32Val::from(SELF_ALLOWED).into(),
+            ])?;
+            let res = host.call(contract, sym("recurse"), args)?;
+            assert_eq!(u32::from(U32Val::try_from(res)?), 0);
+
+            let args = host.vec_new_from_slice(&[
+                U32Val::from(DEFAULT_HOST_DEPTH_LIMIT).into(),
+                U32Val::from(SELF_ALLOWED).into(),
+            ])?;
+            assert!(host.call(contract, sym("recurse"), args).is_err());
+            Ok(())
+        })
+        .unwrap()
+        .join()
+        .unwrap()
+        .unwrap();
+}
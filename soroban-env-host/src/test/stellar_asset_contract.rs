@@ -3242,3 +3242,114 @@ fn test_sac_reentry_is_not_allowed() {
         .error
         .is_type(ScErrorType::Auth));
 }
+
+#[test]
+fn test_spec_describes_functions_types_and_errors() {
+    use crate::{
+        builtin_contracts::stellar_asset_contract::public_types::AssetInfo,
+        stellar_asset_contract_spec, stellar_asset_contract_spec_xdr,
+        xdr::{
+            Limited, Limits, ReadXdr, ScSpecEntry, ScSpecFunctionInputV0, ScSpecTypeDef,
+            ScSpecTypeUdt, ScSpecUdtUnionCaseV0,
+        },
+    };
+
+    let spec = stellar_asset_contract_spec().unwrap();
+
+    let transfer = spec
+        .iter()
+        .find_map(|e| match e {
+            ScSpecEntry::FunctionV0(f) if f.name.0.to_utf8_string_lossy() == "transfer" => Some(f),
+            _ => None,
+        })
+        .unwrap();
+    let inputs: Vec<_> = transfer
+        .inputs
+        .iter()
+        .map(|ScSpecFunctionInputV0 { name, type_, .. }| {
+            (name.to_utf8_string_lossy(), type_.clone())
+        })
+        .collect();
+    assert_eq!(
+        inputs,
+        [
+            ("from".to_string(), ScSpecTypeDef::Address),
+            ("to".to_string(), ScSpecTypeDef::Address),
+            ("amount".to_string(), ScSpecTypeDef::I128),
+        ]
+    );
+    assert!(transfer.outputs.is_empty());
+
+    let balance = spec
+        .iter()
+        .find_map(|e| match e {
+            ScSpecEntry::FunctionV0(f) if f.name.0.to_utf8_string_lossy() == "balance" => Some(f),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(balance.outputs.to_vec(), [ScSpecTypeDef::I128]);
+
+    // Only the types in function signatures are described, along with the
+    // error codes. None of the contract's functions take or return one, so
+    // the error codes are all there is.
+    let non_fn_entries: Vec<_> = spec
+        .iter()
+        .filter(|e| !matches!(e, ScSpecEntry::FunctionV0(_)))
+        .collect();
+    assert!(matches!(
+        non_fn_entries.as_slice(),
+        [ScSpecEntry::UdtErrorEnumV0(_)]
+    ));
+
+    // A type is described along with the types it's made of.
+    let mut asset_info_specs = vec![];
+    AssetInfo::collect_spec_xdr(&mut asset_info_specs);
+    let names: Vec<_> = asset_info_specs
+        .iter()
+        .map(
+            |xdr| match ScSpecEntry::from_xdr(xdr, Limits::none()).unwrap() {
+                ScSpecEntry::UdtUnionV0(u) => u.name.to_utf8_string_lossy(),
+                ScSpecEntry::UdtStructV0(s) => s.name.to_utf8_string_lossy(),
+                e => panic!("unexpected entry {e:?}"),
+            },
+        )
+        .collect();
+    assert_eq!(
+        names,
+        ["AssetInfo", "AlphaNum4AssetInfo", "AlphaNum12AssetInfo"]
+    );
+    match ScSpecEntry::from_xdr(asset_info_specs[0], Limits::none()).unwrap() {
+        ScSpecEntry::UdtUnionV0(u) => match &u.cases[1] {
+            ScSpecUdtUnionCaseV0::TupleV0(c) => {
+                assert_eq!(c.name.to_utf8_string_lossy(), "AlphaNum4");
+                assert_eq!(
+                    c.type_.to_vec(),
+                    [ScSpecTypeDef::Udt(ScSpecTypeUdt {
+                        name: "AlphaNum4AssetInfo".try_into().unwrap()
+                    })]
+                );
+            }
+            _ => panic!("expected a tuple case"),
+        },
+        _ => panic!("expected a union"),
+    }
+
+    let errors = spec
+        .iter()
+        .find_map(|e| match e {
+            ScSpecEntry::UdtErrorEnumV0(e) => Some(e),
+            _ => None,
+        })
+        .unwrap();
+    assert!(errors.cases.iter().any(|c| {
+        c.name.to_utf8_string_lossy() == "BalanceError"
+            && c.value == ContractError::BalanceError as u32
+    }));
+
+    // The XDR form is the same entries, concatenated.
+    let xdr = stellar_asset_contract_spec_xdr();
+    let decoded = ScSpecEntry::read_xdr_iter(&mut Limited::new(xdr.as_slice(), Limits::none()))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(decoded, spec);
+}
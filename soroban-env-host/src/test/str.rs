@@ -31,3 +31,130 @@ fn str_conversions() -> Result<(), HostError> {
     }
     Ok(())
 }
+
+#[cfg(feature = "next")]
+fn string_bytes(host: &Host, s: StringObject) -> Result<Vec<u8>, HostError> {
+    let mut buf = vec![0u8; u32::from(host.string_len(s)?) as usize];
+    host.string_copy_to_slice(s, 0_u32.into(), &mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn str_manipulation() -> Result<(), HostError> {
+    use crate::{xdr::ScErrorCode, xdr::ScErrorType, U32Val};
+
+    let host = observe_host!(Host::test_host());
+    let hello = host.string_new_from_slice(b"hello ")?;
+    let world = host.string_new_from_slice(b"world")?;
+
+    let s = host.string_append(hello, world)?;
+    assert_eq!(string_bytes(&host, s)?, b"hello world");
+    let s2 = host.string_slice(s, 6_u32.into(), 11_u32.into())?;
+    assert_eq!(string_bytes(&host, s2)?, b"world");
+    // Comparison is covered by `obj_cmp`.
+    assert_eq!(host.obj_cmp(s2.to_val(), world.to_val())?, 0);
+    assert_eq!(host.obj_cmp(hello.to_val(), world.to_val())?, -1);
+    assert!(HostError::result_matches_err(
+        host.string_slice(s, 6_u32.into(), 12_u32.into()),
+        (ScErrorType::Object, ScErrorCode::IndexBounds)
+    ));
+
+    // Search.
+    let o = host.string_new_from_slice(b"o")?;
+    let found = host.string_find(s, o, 0_u32.into())?;
+    assert_eq!(u32::from(U32Val::try_from(found)?), 4);
+    let found = host.string_find(s, o, 5_u32.into())?;
+    assert_eq!(u32::from(U32Val::try_from(found)?), 7);
+    assert!(host.string_find(s, o, 8_u32.into())?.is_void());
+    let empty = host.string_new_from_slice(b"")?;
+    let found = host.string_find(s, empty, 11_u32.into())?;
+    assert_eq!(u32::from(U32Val::try_from(found)?), 11);
+    assert!(HostError::result_matches_err(
+        host.string_find(s, o, 12_u32.into()),
+        (ScErrorType::Object, ScErrorCode::IndexBounds)
+    ));
+
+    // UTF-8 validation and conversion to and from bytes.
+    assert!(bool::try_from(host.string_is_valid_utf8(s)?)?);
+    let bytes = host.bytes_new_from_slice(&[0x68, 0xc3, 0x28])?;
+    let invalid = host.bytes_to_string(bytes)?;
+    assert!(!bool::try_from(host.string_is_valid_utf8(invalid)?)?);
+    assert_eq!(string_bytes(&host, invalid)?, [0x68, 0xc3, 0x28]);
+    let bytes2 = host.string_to_bytes(invalid)?;
+    assert_eq!(host.obj_cmp(bytes.to_val(), bytes2.to_val())?, 0);
+    // Slicing may split a multi-byte character.
+    let split = host.string_slice(invalid, 0_u32.into(), 2_u32.into())?;
+    assert!(!bool::try_from(host.string_is_valid_utf8(split)?)?);
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn str_decimal_conversions() -> Result<(), HostError> {
+    use crate::{xdr::ScErrorCode, xdr::ScErrorType, I128Val, U256Val, U256};
+
+    let host = observe_host!(Host::test_host());
+
+    for v in [0, 1, -1, 42, i64::MAX as i128 + 1, i128::MIN, i128::MAX] {
+        let iv: I128Val = v.try_into_val(&*host)?;
+        let s = host.string_from_i128(iv)?;
+        assert_eq!(string_bytes(&host, s)?, v.to_string().as_bytes());
+        let back = host.string_to_i128(s)?;
+        let back: i128 = back.to_val().try_into_val(&*host)?;
+        assert_eq!(back, v);
+    }
+
+    for v in [
+        U256::ZERO,
+        U256::from(7u32),
+        U256::from(u128::MAX),
+        U256::MAX,
+    ] {
+        let uv: U256Val = v.try_into_val(&*host)?;
+        let s = host.string_from_u256(uv)?;
+        assert_eq!(string_bytes(&host, s)?, v.to_string().as_bytes());
+        let back = host.string_to_u256(s)?;
+        let back: U256 = back.to_val().try_into_val(&*host)?;
+        assert_eq!(back, v);
+    }
+
+    // Leading zeros are accepted.
+    let s = host.string_new_from_slice(b"-007")?;
+    let v: i128 = host.string_to_i128(s)?.to_val().try_into_val(&*host)?;
+    assert_eq!(v, -7);
+
+    // Anything but plain decimal digits is rejected.
+    for s in ["", "-", "+1", " 1", "1 ", "1_000", "0x10", "1.5", "١"] {
+        let s = host.string_new_from_slice(s.as_bytes())?;
+        assert!(HostError::result_matches_err(
+            host.string_to_i128(s),
+            (ScErrorType::Value, ScErrorCode::InvalidInput)
+        ));
+    }
+    let s = host.string_new_from_slice(b"-1")?;
+    assert!(HostError::result_matches_err(
+        host.string_to_u256(s),
+        (ScErrorType::Value, ScErrorCode::InvalidInput)
+    ));
+
+    // Out of range.
+    let s = host.string_new_from_slice(b"170141183460469231731687303715884105728")?;
+    assert!(HostError::result_matches_err(
+        host.string_to_i128(s),
+        (ScErrorType::Value, ScErrorCode::ArithDomain)
+    ));
+    let too_big = format!("{}0", U256::MAX);
+    let s = host.string_new_from_slice(too_big.as_bytes())?;
+    assert!(HostError::result_matches_err(
+        host.string_to_u256(s),
+        (ScErrorType::Value, ScErrorCode::ArithDomain)
+    ));
+    let too_long = "1".repeat(1000);
+    let s = host.string_new_from_slice(too_long.as_bytes())?;
+    assert!(HostError::result_matches_err(
+        host.string_to_u256(s),
+        (ScErrorType::Value, ScErrorCode::InvalidInput)
+    ));
+    Ok(())
+}
@@ -490,3 +490,151 @@ fn instantiate_oversized_vec_from_linear_memory() -> Result<(), HostError> {
 
     Ok(())
 }
+
+fn vec_payloads(v: &crate::MeteredVector<Val>) -> Vec<u64> {
+    v.iter().map(|x| x.get_payload()).collect()
+}
+
+#[test]
+fn shared_vec_matches_flat_vec() -> Result<(), HostError> {
+    use crate::{budget::AsBudget, MeteredVector};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Applies the same random updates to a flat and a shared vector, checking
+    // that they agree and that the shared vector's earlier versions, which
+    // share structure with the later ones, are left intact.
+    let host = Host::test_host();
+    host.budget_ref().reset_unlimited()?;
+    let budget = host.as_budget();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut flat: MeteredVector<Val> = MeteredVector::new();
+    let mut shared = flat.to_shared(budget)?;
+    assert!(shared.is_shared());
+    let mut versions = vec![];
+    for i in 0..3000u32 {
+        let x = U32Val::from(i).to_val();
+        let len = flat.len();
+        (flat, shared) = match rng.gen_range(0..6) {
+            _ if len == 0 => (flat.push_back(x, budget)?, shared.push_back(x, budget)?),
+            0 | 1 => (flat.push_back(x, budget)?, shared.push_back(x, budget)?),
+            2 => {
+                let idx = rng.gen_range(0..=len);
+                (flat.insert(idx, x, budget)?, shared.insert(idx, x, budget)?)
+            }
+            3 => {
+                let idx = rng.gen_range(0..len);
+                (flat.set(idx, x, budget)?, shared.set(idx, x, budget)?)
+            }
+            4 => {
+                let idx = rng.gen_range(0..len);
+                (flat.remove(idx, budget)?, shared.remove(idx, budget)?)
+            }
+            _ => (flat.pop_front(budget)?, shared.pop_front(budget)?),
+        };
+        assert!(shared.is_shared());
+        if i % 100 == 0 {
+            versions.push((vec_payloads(&flat), shared.clone()));
+        }
+    }
+    assert_eq!(vec_payloads(&flat), vec_payloads(&shared));
+    for (expected, shared) in versions {
+        assert_eq!(expected, vec_payloads(&shared));
+        assert_eq!(expected.len(), shared.len());
+        let rev: Vec<u64> = shared.iter().rev().map(|x| x.get_payload()).collect();
+        assert!(rev.iter().eq(expected.iter().rev()));
+        for (i, x) in expected.iter().enumerate() {
+            assert_eq!(*x, shared.get(i, budget)?.get_payload());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn shared_vec_stays_balanced_under_removals() -> Result<(), HostError> {
+    use crate::{budget::AsBudget, MeteredVector};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // A `set` copies one node per level of the tree, so the number of node
+    // copies it is charged for is the depth of the tree.
+    fn set_depth(v: &MeteredVector<Val>, budget: &crate::budget::Budget) -> Result<u64, HostError> {
+        let before = budget
+            .get_tracker(ContractCostType::CopySharedSeqNode)?
+            .iterations;
+        v.set(0, U32Val::from(0).to_val(), budget)?;
+        Ok(budget
+            .get_tracker(ContractCostType::CopySharedSeqNode)?
+            .iterations
+            - before)
+    }
+
+    let host = Host::test_host();
+    host.budget_ref().reset_unlimited()?;
+    let budget = host.as_budget();
+    let mut rng = StdRng::seed_from_u64(0);
+    let elts: Vec<Val> = (0..4096u32).map(|i| U32Val::from(i).to_val()).collect();
+    let mut flat = MeteredVector::from_vec(elts)?;
+    let mut shared = flat.to_shared(budget)?;
+    assert_eq!(set_depth(&shared, budget)?, 3);
+    while flat.len() > 64 {
+        let idx = rng.gen_range(0..flat.len());
+        (flat, shared) = (flat.remove(idx, budget)?, shared.remove(idx, budget)?);
+    }
+    assert_eq!(vec_payloads(&flat), vec_payloads(&shared));
+    // Nodes left less than half full are merged, so the tree shrinks to the
+    // depth of one built from scratch.
+    assert_eq!(set_depth(&shared, budget)?, 2);
+    assert_eq!(set_depth(&flat.to_shared(budget)?, budget)?, 2);
+
+    // Iterating from both ends at once meets in the middle.
+    let mut iter = shared.iter();
+    let (mut front, mut back) = (vec![], vec![]);
+    while let Some(x) = iter.next() {
+        front.push(x.get_payload());
+        back.extend(iter.next_back().map(|x| x.get_payload()));
+    }
+    front.extend(back.into_iter().rev());
+    assert_eq!(vec_payloads(&flat), front);
+
+    // Flattening a shared vector is charged as a full copy.
+    let before = budget
+        .get_tracker(ContractCostType::MemCpy)?
+        .inputs
+        .unwrap();
+    assert_eq!(shared.to_vec(budget)?.len(), 64);
+    let after = budget
+        .get_tracker(ContractCostType::MemCpy)?
+        .inputs
+        .unwrap();
+    assert_ge!(after - before, 64 * 8);
+    Ok(())
+}
+
+#[cfg(feature = "next")]
+#[test]
+fn vec_push_back_shares_structure_from_next_protocol() -> Result<(), HostError> {
+    use crate::vm::NEXT_PROTOCOL;
+
+    fn build(host: &Host, n: u32) -> Result<(VecObject, u64), HostError> {
+        host.budget_ref().reset_unlimited()?;
+        let mut v = host.vec_new()?;
+        for i in 0..n {
+            v = host.vec_push_back(v, U32Val::from(i).into())?;
+        }
+        Ok((v, host.budget_ref().get_mem_bytes_consumed()?))
+    }
+
+    let n = 4000;
+    let next = Host::test_host();
+    next.with_mut_ledger_info(|li| li.protocol_version = NEXT_PROTOCOL)?;
+    let prev = Host::test_host();
+    prev.with_mut_ledger_info(|li| li.protocol_version = NEXT_PROTOCOL - 1)?;
+    let (v_next, mem_next) = build(&next, n)?;
+    let (v_prev, mem_prev) = build(&prev, n)?;
+
+    let v_next: ScVal = next.from_host_val(v_next.to_val())?;
+    let v_prev: ScVal = prev.from_host_val(v_prev.to_val())?;
+    assert_eq!(v_next, v_prev);
+    // Copying whole vectors costs O(n^2) memory, copying paths O(n log n).
+    assert_ge!(mem_prev, 10 * mem_next);
+    Ok(())
+}
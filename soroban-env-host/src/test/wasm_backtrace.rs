@@ -0,0 +1,194 @@
+use crate::{
+    host_object::HostVec,
+    vm::WasmSymbols,
+    xdr::{ScErrorCode, ScErrorType},
+    Env, Host, HostError, Symbol,
+};
+use soroban_synth_wasm::{Arity, ModEmitter};
+
+fn leb128(mut n: u32, out: &mut Vec<u8>) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// Encodes the content of a `name` custom section holding only a
+// function-names subsection.
+fn name_section(names: &[(u32, &str)]) -> Vec<u8> {
+    let mut sub = vec![];
+    leb128(names.len() as u32, &mut sub);
+    for (idx, name) in names {
+        leb128(*idx, &mut sub);
+        leb128(name.len() as u32, &mut sub);
+        sub.extend_from_slice(name.as_bytes());
+    }
+    let mut data = vec![1];
+    leb128(sub.len() as u32, &mut data);
+    data.extend(sub);
+    data
+}
+
+#[test]
+fn name_section_symbols_are_demangled() {
+    let symbols = WasmSymbols::from_name_section(&name_section(&[
+        (0, "swap"),
+        (3, "_ZN4core9panicking5panic17h0123456789abcdefE"),
+    ]));
+    assert_eq!(symbols.func_index("swap"), Some(0));
+    assert_eq!(symbols.func_name(0).as_deref(), Some("swap"));
+    assert_eq!(
+        symbols.func_name(3).as_deref(),
+        Some("core::panicking::panic")
+    );
+    assert_eq!(symbols.func_name(1), None);
+
+    let frame = symbols.frame_for_export("00".into(), "not_named");
+    assert_eq!(frame.func_index, None);
+    assert_eq!(frame.func_name, "not_named");
+}
+
+#[test]
+fn malformed_name_section_is_ignored() {
+    let symbols = WasmSymbols::from_name_section(&[1, 0xff, 0xff]);
+    assert_eq!(symbols.func_name(0), None);
+}
+
+#[test]
+fn trap_carries_wasm_backtrace() -> Result<(), HostError> {
+    let mut me = ModEmitter::default_with_test_protocol();
+    me.custom_section("name", &name_section(&[(0, "boom")]));
+    let mut fe = me.func(Arity(0), 0);
+    fe.trap();
+    let wasm = fe.finish_and_export("boom").finish();
+
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let contract_id_obj = host.register_test_contract_wasm(wasm.as_slice());
+    let res = host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("boom")?,
+        host.add_host_object(HostVec::new())?,
+    );
+    let err = res.unwrap_err();
+    assert_eq!(
+        err.error,
+        (ScErrorType::WasmVm, ScErrorCode::InvalidAction).into()
+    );
+    let bt = err.wasm_backtrace().expect("missing wasm backtrace");
+    assert_eq!(bt.frames.len(), 1);
+    assert_eq!(bt.frames[0].func_index, Some(0));
+    assert_eq!(bt.to_string(), "boom");
+    assert!(format!("{:?}", err).contains("Wasm backtrace of contract calls (outermost first):"));
+    Ok(())
+}
+
+// Encodes DWARF sections describing a function `my_contract::swap` declared
+// at `/work/src/lib.rs:42`, as (section name, content) pairs.
+fn dwarf_sections() -> Vec<(&'static str, Vec<u8>)> {
+    use gimli::write::{AttributeValue, DwarfUnit, EndianVec, LineProgram, LineString, Sections};
+    let encoding = gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 4,
+        address_size: 4,
+    };
+    let mut dwarf = DwarfUnit::new(encoding);
+    let mut program = LineProgram::new(
+        encoding,
+        gimli::LineEncoding::default(),
+        LineString::String(b"/work".to_vec()),
+        LineString::String(b"src/lib.rs".to_vec()),
+        None,
+    );
+    let dir = program.default_directory();
+    let file = program.add_file(LineString::String(b"src/lib.rs".to_vec()), dir, None);
+    dwarf.unit.line_program = program;
+
+    let root = dwarf.unit.root();
+    let cu = dwarf.unit.get_mut(root);
+    cu.set(
+        gimli::DW_AT_name,
+        AttributeValue::String(b"src/lib.rs".to_vec()),
+    );
+    cu.set(
+        gimli::DW_AT_comp_dir,
+        AttributeValue::String(b"/work".to_vec()),
+    );
+    let ns = dwarf.unit.add(root, gimli::DW_TAG_namespace);
+    dwarf.unit.get_mut(ns).set(
+        gimli::DW_AT_name,
+        AttributeValue::String(b"my_contract".to_vec()),
+    );
+    let sp = dwarf.unit.add(ns, gimli::DW_TAG_subprogram);
+    let sp = dwarf.unit.get_mut(sp);
+    sp.set(gimli::DW_AT_name, AttributeValue::String(b"swap".to_vec()));
+    sp.set(
+        gimli::DW_AT_decl_file,
+        AttributeValue::FileIndex(Some(file)),
+    );
+    sp.set(gimli::DW_AT_decl_line, AttributeValue::Udata(42));
+
+    let mut sections = Sections::new(EndianVec::new(gimli::LittleEndian));
+    dwarf.write(&mut sections).unwrap();
+    let mut res = vec![];
+    sections
+        .for_each(|id, data| -> Result<(), ()> {
+            if !data.slice().is_empty() {
+                res.push((id.name(), data.slice().to_vec()));
+            }
+            Ok(())
+        })
+        .unwrap();
+    res
+}
+
+#[test]
+fn trap_is_symbolized_with_dwarf() -> Result<(), HostError> {
+    let mut me = ModEmitter::default_with_test_protocol();
+    me.custom_section("name", &name_section(&[(0, "swap")]));
+    for (name, data) in dwarf_sections() {
+        me.custom_section(name, &data);
+    }
+    let mut fe = me.func(Arity(0), 0);
+    fe.trap();
+    let wasm = fe.finish_and_export("swap").finish();
+
+    let host = Host::test_host_with_recording_footprint();
+    host.enable_debug()?;
+    let contract_id_obj = host.register_test_contract_wasm(wasm.as_slice());
+    let res = host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("swap")?,
+        host.add_host_object(HostVec::new())?,
+    );
+    let err = res.unwrap_err();
+    let bt = err.wasm_backtrace().expect("missing wasm backtrace");
+    assert_eq!(bt.frames.len(), 1);
+    assert_eq!(bt.frames[0].func_index, Some(0));
+    assert_eq!(bt.frames[0].func_name, "my_contract::swap");
+    assert_eq!(bt.frames[0].file.as_deref(), Some("/work/src/lib.rs"));
+    assert_eq!(bt.frames[0].line, Some(42));
+    assert_eq!(bt.to_string(), "my_contract::swap (/work/src/lib.rs:42)");
+    Ok(())
+}
+
+#[test]
+fn no_wasm_backtrace_without_debug_mode() -> Result<(), HostError> {
+    let mut fe = ModEmitter::default_with_test_protocol().func(Arity(0), 0);
+    fe.trap();
+    let wasm = fe.finish_and_export("boom").finish();
+    let host = Host::test_host_with_recording_footprint();
+    let contract_id_obj = host.register_test_contract_wasm(wasm.as_slice());
+    let res = host.call(
+        contract_id_obj,
+        Symbol::try_from_small_str("boom")?,
+        host.add_host_object(HostVec::new())?,
+    );
+    assert!(res.unwrap_err().wasm_backtrace().is_none());
+    Ok(())
+}
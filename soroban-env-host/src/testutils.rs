@@ -16,6 +16,15 @@ use rand::RngCore;
 use std::panic::{catch_unwind, set_hook, take_hook, UnwindSafe};
 use std::{cell::Cell, collections::BTreeMap, rc::Rc, sync::Once};
 
+pub mod differential;
+pub use differential::{
+    check_recording_matches_enforcing, DifferentialInvocation, DifferentialOutcome, Divergence,
+};
+#[cfg(any(test, feature = "json"))]
+pub mod golden_trace;
+#[cfg(any(test, feature = "json"))]
+pub use golden_trace::{GoldenTraceHost, GoldenTraceMismatch, GoldenTraceMode};
+
 /// Catch panics while suppressing the default panic hook that prints to the
 /// console.
 ///
@@ -506,6 +515,24 @@ impl Host {
         self.set_authorization_entries(auth_entries).unwrap();
     }
 
+    /// Installs a trace hook (replacing any existing one) that counts the
+    /// calls to each host function, for reporting which host functions a
+    /// fuzzer manages to reach. The returned map is updated as the host runs.
+    pub fn count_host_function_calls(
+        &self,
+    ) -> Result<Rc<std::cell::RefCell<BTreeMap<&'static str, u64>>>, HostError> {
+        use crate::host::TraceEvent;
+        let counts = Rc::new(std::cell::RefCell::new(BTreeMap::new()));
+        let counts2 = counts.clone();
+        self.set_trace_hook(Some(Rc::new(move |_, evt| {
+            if let TraceEvent::EnvCall(fname, _) = evt {
+                *counts2.borrow_mut().entry(fname).or_insert(0) += 1;
+            }
+            Ok(())
+        })))?;
+        Ok(counts)
+    }
+
     #[cfg(all(test, feature = "testutils"))]
     pub(crate) fn measured_call(
         &self,
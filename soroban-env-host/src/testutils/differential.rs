@@ -0,0 +1,345 @@
+//! Differential testing of recording-mode simulation against enforcing-mode
+//! execution.
+//!
+//! Embedders simulate a transaction in recording mode to discover its
+//! footprint, authorization entries and resource usage, then submit it to be
+//! applied in enforcing mode using exactly those outputs. Any difference in
+//! the observable outcome of the two runs is a bug: either simulation
+//! produced unusable resources, or the two modes behave differently.
+//!
+//! [check_recording_matches_enforcing] runs an invocation through both modes
+//! the way an embedder would and reports every [Divergence] it finds.
+
+use crate::{
+    budget::Budget,
+    builtin_contracts::testutils::TestSigner,
+    e2e_invoke::{
+        invoke_host_function, invoke_host_function_in_recording_mode, ledger_entry_to_ledger_key,
+        LedgerEntryChange, LedgerEntryLiveUntilChange,
+    },
+    testutils::MockSnapshotSource,
+    xdr::{
+        AccountId, ContractEvent, Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization,
+        HostFunction, LedgerEntry, LedgerKey, Limits, ReadXdr, ScErrorCode, ScErrorType, ScVal,
+        SorobanAuthorizationEntry, SorobanCredentials, TtlEntry, WriteXdr,
+    },
+    Host, HostError, LedgerInfo,
+};
+use ed25519_dalek::SigningKey;
+use sha2::{Digest, Sha256};
+use std::rc::Rc;
+
+/// The relative difference allowed between the instructions recorded in
+/// recording mode and those consumed in enforcing mode.
+///
+/// The two modes can't match exactly: for example, frame snapshots in
+/// enforcing mode contain all the auth entries and storage, while in recording
+/// mode they are populated lazily and so are smaller.
+pub const RECORDING_MODE_INSTRUCTIONS_RANGE: f64 = 0.02;
+
+/// An invocation to run in both recording and enforcing mode.
+#[derive(Clone)]
+pub struct DifferentialInvocation {
+    pub host_fn: HostFunction,
+    pub source_account: AccountId,
+    pub ledger_info: LedgerInfo,
+    /// Every ledger entry the invocation may access, with its live until
+    /// ledger (if it has one). Only the entries in the recorded footprint are
+    /// passed to the enforcing-mode run.
+    pub ledger_entries: Vec<(LedgerEntry, Option<u32>)>,
+    /// Keys of the accounts that sign the recorded address-credential auth
+    /// entries. Entries whose address has no signer here are left unsigned.
+    pub signers: Vec<SigningKey>,
+    pub prng_seed: [u8; 32],
+}
+
+/// A ledger change decoded from its XDR form, so that changes can be
+/// compared and printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparableLedgerChange {
+    pub read_only: bool,
+    pub key: LedgerKey,
+    pub old_entry_size_bytes: u32,
+    pub new_value: Option<LedgerEntry>,
+    pub ttl_change: Option<LedgerEntryLiveUntilChange>,
+}
+
+impl From<LedgerEntryChange> for ComparableLedgerChange {
+    fn from(c: LedgerEntryChange) -> Self {
+        Self {
+            read_only: c.read_only,
+            key: LedgerKey::from_xdr(c.encoded_key, Limits::none()).unwrap(),
+            old_entry_size_bytes: c.old_entry_size_bytes,
+            new_value: c
+                .encoded_new_value
+                .map(|v| LedgerEntry::from_xdr(v, Limits::none()).unwrap()),
+            ttl_change: c.ttl_change,
+        }
+    }
+}
+
+/// A difference between the outcome of an invocation in recording mode and
+/// its replay in enforcing mode.
+#[derive(Debug)]
+pub enum Divergence {
+    /// The invocations returned different values, failed with different
+    /// errors, or only one of them failed.
+    InvokeResult {
+        recording: Result<ScVal, HostError>,
+        enforcing: Result<ScVal, HostError>,
+    },
+    LedgerChanges {
+        recording: Vec<ComparableLedgerChange>,
+        enforcing: Vec<ComparableLedgerChange>,
+    },
+    Events {
+        recording: Vec<ContractEvent>,
+        enforcing: Vec<ContractEvent>,
+    },
+    EventsAndReturnValueSize {
+        recording: u32,
+        enforcing: u32,
+    },
+    /// The recorded instructions differ from those consumed in enforcing mode
+    /// by more than [RECORDING_MODE_INSTRUCTIONS_RANGE]. `enforcing` is `None`
+    /// when the enforcing run exceeded its (adjusted) instruction limit.
+    Budget {
+        recording: u64,
+        enforcing: Option<u64>,
+    },
+}
+
+/// The outcome of [check_recording_matches_enforcing].
+#[derive(Debug)]
+pub struct DifferentialOutcome {
+    /// The result of the invocation in recording mode.
+    pub invoke_result: Result<ScVal, HostError>,
+    pub divergences: Vec<Divergence>,
+}
+
+impl DifferentialOutcome {
+    /// Panics, listing every divergence, if there are any.
+    pub fn assert_no_divergence(&self) {
+        if !self.divergences.is_empty() {
+            panic!(
+                "recording and enforcing mode diverged for invocation returning {:?}: {:#?}",
+                self.invoke_result, self.divergences
+            );
+        }
+    }
+}
+
+fn within_range(a: u64, b: u64) -> bool {
+    a as f64 <= b as f64 * (1.0 + RECORDING_MODE_INSTRUCTIONS_RANGE)
+}
+
+fn is_budget_exceeded(e: &HostError) -> bool {
+    e.error.is_type(ScErrorType::Budget) && e.error.is_code(ScErrorCode::ExceededLimit)
+}
+
+fn same_result(a: &Result<ScVal, HostError>, b: &Result<ScVal, HostError>) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => a == b,
+        (Err(a), Err(b)) => a.error == b.error,
+        _ => false,
+    }
+}
+
+fn sign_auth_entry(
+    dummy_host: &Host,
+    ledger_info: &LedgerInfo,
+    signers: &[TestSigner],
+    mut entry: SorobanAuthorizationEntry,
+) -> SorobanAuthorizationEntry {
+    if let SorobanCredentials::Address(creds) = &mut entry.credentials {
+        let Some(signer) = signers.iter().find(|s| s.sc_address() == creds.address) else {
+            return entry;
+        };
+        let signature_expiration_ledger =
+            ledger_info.sequence_number + ledger_info.max_entry_ttl - 1;
+        let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+            network_id: ledger_info.network_id.try_into().unwrap(),
+            invocation: entry.root_invocation.clone(),
+            nonce: creds.nonce,
+            signature_expiration_ledger,
+        });
+        let payload: [u8; 32] = Sha256::digest(preimage.to_xdr(Limits::none()).unwrap()).into();
+        creds.signature = signer.sign(dummy_host, &payload);
+        creds.signature_expiration_ledger = signature_expiration_ledger;
+    }
+    entry
+}
+
+fn encode_ttl_entry(entry: &LedgerEntry, live_until: Option<u32>) -> Result<Vec<u8>, HostError> {
+    let Some(live_until_ledger_seq) = live_until else {
+        return Ok(vec![]);
+    };
+    let key = ledger_entry_to_ledger_key(entry, &Budget::default())?;
+    let key_hash: [u8; 32] = Sha256::digest(key.to_xdr(Limits::none())?).into();
+    Ok(TtlEntry {
+        key_hash: Hash(key_hash),
+        live_until_ledger_seq,
+    }
+    .to_xdr(Limits::none())?)
+}
+
+/// Runs `invocation` in recording mode, then replays it in enforcing mode with
+/// the recorded footprint, the recorded auth entries signed by
+/// `invocation.signers` and the recorded instructions (adjusted by
+/// [RECORDING_MODE_INSTRUCTIONS_RANGE]), and compares the outcomes.
+///
+/// Returns an error only if the recording-mode run fails outside of the
+/// invoked function itself, in which case there is nothing to replay.
+pub fn check_recording_matches_enforcing(
+    invocation: &DifferentialInvocation,
+) -> Result<DifferentialOutcome, HostError> {
+    let DifferentialInvocation {
+        host_fn,
+        source_account,
+        ledger_info,
+        ledger_entries,
+        signers,
+        prng_seed,
+    } = invocation;
+
+    let snapshot = Rc::new(MockSnapshotSource::from_entries(ledger_entries.clone()));
+    let recording = invoke_host_function_in_recording_mode(
+        &Budget::default(),
+        false,
+        host_fn,
+        source_account,
+        None,
+        ledger_info.clone(),
+        snapshot,
+        *prng_seed,
+        &mut vec![],
+    )?;
+
+    let dummy_host = Host::test_host();
+    let test_signers: Vec<TestSigner> = signers.iter().map(TestSigner::account).collect();
+    let auth_entries = recording
+        .auth
+        .iter()
+        .map(|e| {
+            sign_auth_entry(&dummy_host, ledger_info, &test_signers, e.clone())
+                .to_xdr(Limits::none())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut resources = recording.resources.clone();
+    let recorded_instructions = resources.instructions as u64;
+    resources.instructions =
+        (resources.instructions as f64 * (1.0 + RECORDING_MODE_INSTRUCTIONS_RANGE)) as u32;
+
+    // The enforcing-mode run must only be given the entries in its footprint.
+    let footprint_keys: Vec<&LedgerKey> = resources
+        .footprint
+        .read_only
+        .iter()
+        .chain(resources.footprint.read_write.iter())
+        .collect();
+    let mut encoded_entries = vec![];
+    let mut encoded_ttl_entries = vec![];
+    for (entry, live_until) in ledger_entries {
+        let key = ledger_entry_to_ledger_key(entry, &Budget::default())?;
+        if footprint_keys.contains(&&key) {
+            encoded_entries.push(entry.to_xdr(Limits::none())?);
+            encoded_ttl_entries.push(encode_ttl_entry(entry, *live_until)?);
+        }
+    }
+
+    let budget = Budget::default();
+    budget.reset_cpu_limit(resources.instructions as u64)?;
+    let enforcing = invoke_host_function(
+        &budget,
+        false,
+        host_fn.to_xdr(Limits::none())?,
+        resources.to_xdr(Limits::none())?,
+        source_account.to_xdr(Limits::none())?,
+        auth_entries.into_iter(),
+        ledger_info.clone(),
+        encoded_entries.into_iter(),
+        encoded_ttl_entries.into_iter(),
+        prng_seed.to_vec(),
+        &mut vec![],
+    );
+    let (enforcing_result, enforcing_changes, enforcing_events) = match enforcing {
+        Ok(res) => (
+            res.encoded_invoke_result
+                .map(|v| ScVal::from_xdr(v, Limits::none()).unwrap()),
+            res.ledger_changes,
+            res.encoded_contract_events
+                .iter()
+                .map(|e| ContractEvent::from_xdr(e, Limits::none()).unwrap())
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => (Err(e), vec![], vec![]),
+    };
+    let enforcing_instructions = budget.get_cpu_insns_consumed()?;
+
+    let mut divergences = vec![];
+    match &enforcing_result {
+        Err(e) if recording.invoke_result.is_ok() && is_budget_exceeded(e) => {
+            divergences.push(Divergence::Budget {
+                recording: recorded_instructions,
+                enforcing: None,
+            });
+        }
+        enforcing_result => {
+            if !same_result(&recording.invoke_result, enforcing_result) {
+                divergences.push(Divergence::InvokeResult {
+                    recording: recording.invoke_result.clone(),
+                    enforcing: enforcing_result.clone(),
+                });
+            }
+        }
+    }
+    // Ledger changes and events are only produced by successful invocations,
+    // so there is nothing more to compare if either run failed.
+    if let (Ok(res), Ok(_)) = (&recording.invoke_result, &enforcing_result) {
+        let recording_changes: Vec<ComparableLedgerChange> = recording
+            .ledger_changes
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let enforcing_changes: Vec<ComparableLedgerChange> =
+            enforcing_changes.into_iter().map(Into::into).collect();
+        if recording_changes != enforcing_changes {
+            divergences.push(Divergence::LedgerChanges {
+                recording: recording_changes,
+                enforcing: enforcing_changes,
+            });
+        }
+
+        let mut enforcing_size = res.to_xdr(Limits::none())?.len();
+        for e in &enforcing_events {
+            enforcing_size += e.to_xdr(Limits::none())?.len();
+        }
+        if recording.contract_events_and_return_value_size != enforcing_size as u32 {
+            divergences.push(Divergence::EventsAndReturnValueSize {
+                recording: recording.contract_events_and_return_value_size,
+                enforcing: enforcing_size as u32,
+            });
+        }
+        if recording.contract_events != enforcing_events {
+            divergences.push(Divergence::Events {
+                recording: recording.contract_events,
+                enforcing: enforcing_events,
+            });
+        }
+
+        if !within_range(recorded_instructions, enforcing_instructions)
+            || !within_range(enforcing_instructions, recorded_instructions)
+        {
+            divergences.push(Divergence::Budget {
+                recording: recorded_instructions,
+                enforcing: Some(enforcing_instructions),
+            });
+        }
+    }
+
+    Ok(DifferentialOutcome {
+        invoke_result: recording.invoke_result,
+        divergences,
+    })
+}
@@ -0,0 +1,286 @@
+//! Golden-trace snapshot testing for contract invocations.
+//!
+//! This exposes the same observation machinery the host's own test suite uses
+//! to produce its `observations/<protocol>/*.json` files. A [GoldenTraceHost]
+//! hooks into the host's tracing system and records a summary of the host
+//! state (budget consumption and hashes of objects, storage, events, auth
+//! and VM state) at every frame push and pop and every host function call and
+//! return. When the test finishes, the recording is compared against a golden
+//! file and any difference fails the test with a readable diff.
+//!
+//! To (re)record golden files, run with the environment variable
+//! `UPDATE_OBSERVATIONS=1` set.
+//!
+//! Golden files are stored as JSON, so this requires the `json` feature along
+//! with `testutils`.
+
+use crate::{
+    host::trace::{TraceHook, TraceRecord},
+    Host, HostError,
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs::File,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// Name of the environment variable that switches golden traces (and the
+/// host's own observations) from checking to recording.
+pub const UPDATE_OBSERVATIONS_ENV_VAR: &str = "UPDATE_OBSERVATIONS";
+
+pub(crate) fn update_observations() -> bool {
+    env::var(UPDATE_OBSERVATIONS_ENV_VAR).is_ok()
+}
+
+/// An ordered set of observations of a traced host, keyed by a sequence
+/// number and the event that triggered each observation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Observations(pub(crate) BTreeMap<String, String>);
+
+// We use a pseudo-entry to track the last-written entry while building a map
+// to enable each non-begin-or-end stored line to be a diff from the last
+// line's full state, for greater compactness and legibility.
+const PREV_FULL: &str = "___PREV_FULL";
+
+fn diff_line(last: &str, new: &str) -> String {
+    last.split(',')
+        .zip(new.split(','))
+        .filter(|(a, b)| a != b)
+        .map(|(_, b)| b)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Observations {
+    pub(crate) fn load(path: &Path) -> Self {
+        let obs: BTreeMap<String, String> = if path.exists() {
+            println!("reading {}", path.display());
+            let file =
+                File::open(path).unwrap_or_else(|_| panic!("unable to open {}", path.display()));
+            serde_json::from_reader(file)
+                .unwrap_or_else(|_| panic!("failed to parse {}", path.display()))
+        } else {
+            BTreeMap::new()
+        };
+        Self(obs)
+    }
+
+    pub(crate) fn save(&self, path: &Path) {
+        println!("writing {}", path.display());
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .unwrap_or_else(|_| panic!("unable to create {}", dir.display()));
+        }
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("unable to create {}", path.display()));
+        serde_json::to_writer_pretty(file, &self.0)
+            .unwrap_or_else(|_| panic!("error writing {}", path.display()));
+    }
+
+    /// Appends the observation `tr`, clearing any previous observations when
+    /// it is a `Begin` event. Returns `true` when `tr` is an `End` event, at
+    /// which point the observations are complete.
+    pub(crate) fn record(&mut self, tr: TraceRecord) -> bool {
+        if tr.event.is_begin() {
+            self.0.clear();
+        }
+
+        let prev = self.0.remove(PREV_FULL).unwrap_or_default();
+
+        let key = format!("{:4.4} {}", self.0.len(), tr.event);
+        let value = format!("{}", tr.state);
+
+        self.0.insert(PREV_FULL.to_string(), value.clone());
+        if tr.event.is_begin() || tr.event.is_end() {
+            self.0.insert(key, value);
+        } else {
+            let diff = diff_line(&prev, &value);
+            self.0.insert(key, diff);
+        }
+
+        if tr.event.is_end() {
+            self.0.remove(PREV_FULL);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns a line-oriented diff between `old` and `new`, or `None` if they
+    /// are identical.
+    pub(crate) fn diff(old: &Observations, new: &Observations) -> Option<String> {
+        use std::fmt::Write;
+        if old == new {
+            return None;
+        }
+        let mut out = String::new();
+        let old_entries: Vec<_> = old.0.iter().collect();
+        let new_entries: Vec<_> = new.0.iter().collect();
+        for i in 0..old_entries.len().max(new_entries.len()) {
+            let (o, n) = (old_entries.get(i), new_entries.get(i));
+            if o == n {
+                continue;
+            }
+            let _ = writeln!(out, "@ observation {}", i);
+            if let Some((k, v)) = o {
+                let _ = writeln!(out, "- {:?}: {:?}", k, v);
+            }
+            if let Some((k, v)) = n {
+                let _ = writeln!(out, "+ {:?}: {:?}", k, v);
+            }
+        }
+        Some(out)
+    }
+}
+
+/// The difference between a recorded trace and its golden file, returned by
+/// [GoldenTraceHost::finish].
+#[derive(Debug, Clone)]
+pub struct GoldenTraceMismatch {
+    pub path: PathBuf,
+    pub diff: String,
+}
+
+impl Display for GoldenTraceMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "golden trace {} changed since last recording",
+            self.path.display()
+        )?;
+        writeln!(
+            f,
+            "note: if this is intentional, re-run with {}=1",
+            UPDATE_OBSERVATIONS_ENV_VAR
+        )?;
+        write!(f, "{}", self.diff)
+    }
+}
+
+impl std::error::Error for GoldenTraceMismatch {}
+
+/// Whether a [GoldenTraceHost] compares against or overwrites its golden file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoldenTraceMode {
+    Check,
+    Update,
+}
+
+impl GoldenTraceMode {
+    /// Returns [GoldenTraceMode::Update] if `UPDATE_OBSERVATIONS` is set in
+    /// the environment, otherwise [GoldenTraceMode::Check].
+    pub fn from_env() -> Self {
+        if update_observations() {
+            GoldenTraceMode::Update
+        } else {
+            GoldenTraceMode::Check
+        }
+    }
+}
+
+/// A [Host] wrapper that records a trace of everything run on the host between
+/// its construction and [GoldenTraceHost::finish] (or drop), and compares that
+/// trace against a golden file.
+///
+/// ```ignore
+/// let host = GoldenTraceHost::new(Host::test_host_with_recording_footprint(),
+///                                 "test_snapshots/swap.json");
+/// // ... register contracts and invoke them via `host` ...
+/// host.finish().unwrap();
+/// ```
+///
+/// If the wrapper is dropped without calling `finish`, it is finished on drop
+/// and panics on a mismatch (unless the thread is already panicking).
+pub struct GoldenTraceHost {
+    host: Host,
+    path: PathBuf,
+    mode: GoldenTraceMode,
+    observations: Rc<RefCell<Observations>>,
+    finished: bool,
+}
+
+impl GoldenTraceHost {
+    /// Starts tracing `host` against the golden file at `path`, in the mode
+    /// selected by [GoldenTraceMode::from_env].
+    pub fn new(host: Host, path: impl Into<PathBuf>) -> Self {
+        Self::new_with_mode(host, path, GoldenTraceMode::from_env())
+    }
+
+    pub fn new_with_mode(host: Host, path: impl Into<PathBuf>, mode: GoldenTraceMode) -> Self {
+        let observations = Rc::new(RefCell::new(Observations::default()));
+        host.set_trace_hook(Some(Self::make_obs_hook(observations.clone())))
+            .expect("installing host trace hook");
+        Self {
+            host,
+            path: path.into(),
+            mode,
+            observations,
+            finished: false,
+        }
+    }
+
+    fn make_obs_hook(observations: Rc<RefCell<Observations>>) -> TraceHook {
+        Rc::new(move |host, evt| {
+            let tr = TraceRecord::new(host, evt).expect("observing host");
+            observations.borrow_mut().record(tr);
+            Ok(())
+        })
+    }
+
+    /// Stops tracing and either compares the recorded trace against the golden
+    /// file or, in [GoldenTraceMode::Update], overwrites the golden file with
+    /// it. A missing golden file counts as an empty trace.
+    pub fn finish(mut self) -> Result<(), GoldenTraceMismatch> {
+        self.finish_internal()
+    }
+
+    fn finish_internal(&mut self) -> Result<(), GoldenTraceMismatch> {
+        self.finished = true;
+        self.host
+            .set_trace_hook(None)
+            .map_err(|e: HostError| GoldenTraceMismatch {
+                path: self.path.clone(),
+                diff: format!("failed to stop tracing: {:?}", e),
+            })?;
+        let new = self.observations.borrow();
+        match self.mode {
+            GoldenTraceMode::Update => {
+                new.save(&self.path);
+                Ok(())
+            }
+            GoldenTraceMode::Check => {
+                let old = Observations::load(&self.path);
+                match Observations::diff(&old, &new) {
+                    None => Ok(()),
+                    Some(diff) => Err(GoldenTraceMismatch {
+                        path: self.path.clone(),
+                        diff,
+                    }),
+                }
+            }
+        }
+    }
+}
+
+impl std::ops::Deref for GoldenTraceHost {
+    type Target = Host;
+
+    fn deref(&self) -> &Self::Target {
+        &self.host
+    }
+}
+
+impl Drop for GoldenTraceHost {
+    fn drop(&mut self) {
+        if self.finished || std::thread::panicking() {
+            return;
+        }
+        if let Err(mismatch) = self.finish_internal() {
+            panic!("{}", mismatch);
+        }
+    }
+}
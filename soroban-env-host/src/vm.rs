@@ -8,16 +8,24 @@
 //! The implementation of WASM types and the WASM bytecode interpreter come from
 //! the [wasmi](https://github.com/paritytech/wasmi) project.
 
+mod compatibility;
 mod dispatch;
 mod fuel_refillable;
 mod func_info;
+#[cfg(any(test, feature = "testutils"))]
+mod insn_profile;
+#[cfg(any(test, feature = "testutils"))]
+mod memory_snapshot;
 mod module_cache;
 mod parsed_module;
+mod wasm_backtrace;
 
 #[cfg(feature = "bench")]
 pub(crate) use dispatch::dummy0;
 #[cfg(test)]
 pub(crate) use dispatch::protocol_gated_dummy;
+#[cfg(test)]
+pub(crate) use wasm_backtrace::WasmSymbols;
 
 use crate::{
     budget::{get_wasmi_config, AsBudget, Budget},
@@ -29,13 +37,20 @@ use crate::{
     xdr::{ContractCostType, Hash, ScErrorCode, ScErrorType},
     ConversionError, Host, HostError, Symbol, SymbolStr, TryIntoVal, Val, WasmiMarshal,
 };
-use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc, sync::Arc};
 
 use fuel_refillable::FuelRefillable;
 use func_info::HOST_FUNCTIONS;
 
-pub use module_cache::ModuleCache;
+pub use compatibility::{ImportedHostFunction, ProtocolCompatibilityReport, NEXT_PROTOCOL};
+#[cfg(any(test, feature = "testutils"))]
+pub use insn_profile::{ContractInsnProfile, WasmInsnClass, WasmInsnHistogram, WasmInsnProfile};
+#[cfg(any(test, feature = "testutils"))]
+pub use memory_snapshot::VmMemorySnapshot;
+pub(crate) use module_cache::SharedModule;
+pub use module_cache::{ModuleCache, SharedModuleCache};
 pub use parsed_module::{ParsedModule, VersionedContractCodeCostInputs};
+pub use wasm_backtrace::{WasmBacktrace, WasmFrame};
 
 use wasmi::{Instance, Linker, Memory, Store, Value};
 
@@ -85,8 +100,7 @@ impl Drop for VmInstantiationTimer {
 /// will fail.
 pub struct Vm {
     pub(crate) contract_id: Hash,
-    #[allow(dead_code)]
-    pub(crate) module: Rc<ParsedModule>,
+    pub(crate) module: Arc<ParsedModule>,
     store: RefCell<Store<Host>>,
     instance: Instance,
     pub(crate) memory: Option<Memory>,
@@ -151,7 +165,7 @@ impl Vm {
     fn instantiate(
         host: &Host,
         contract_id: Hash,
-        parsed_module: Rc<ParsedModule>,
+        parsed_module: Arc<ParsedModule>,
         linker: &Linker<Host>,
     ) -> Result<Rc<Self>, HostError> {
         let _span = tracy_span!("Vm::instantiate");
@@ -250,7 +264,7 @@ impl Vm {
     pub fn from_parsed_module(
         host: &Host,
         contract_id: Hash,
-        parsed_module: Rc<ParsedModule>,
+        parsed_module: Arc<ParsedModule>,
     ) -> Result<Rc<Self>, HostError> {
         let _span = tracy_span!("Vm::from_parsed_module");
         VmInstantiationTimer::new(host.clone());
@@ -291,20 +305,32 @@ impl Vm {
             wasm,
             cost_inputs,
             ModuleParseCostMode::Normal,
+            None,
         )
     }
 
+    // `shared_module` is the module parsed in advance from `wasm`, taken from
+    // the [SharedModuleCache] along with its linker, to use instead of parsing
+    // `wasm` again.
     pub(crate) fn new_with_cost_inputs(
         host: &Host,
         contract_id: Hash,
         wasm: &[u8],
         cost_inputs: VersionedContractCodeCostInputs,
         cost_mode: ModuleParseCostMode,
+        shared_module: Option<SharedModule>,
     ) -> Result<Rc<Self>, HostError> {
         let _span = tracy_span!("Vm::new");
         VmInstantiationTimer::new(host.clone());
-        let parsed_module = Self::parse_module(host, wasm, cost_inputs, cost_mode)?;
-        let linker = parsed_module.make_linker(host)?;
+        let (shared_module, shared_linker) = shared_module.unzip();
+        let parsed_module = Self::parse_module(host, wasm, cost_inputs, cost_mode, shared_module)?;
+        // Host functions can't be defined on the engine of a shared module,
+        // which may be running on other threads, so the linker of the shared
+        // cache is used instead, charged the same way.
+        let linker = match shared_linker {
+            Some(linker) => parsed_module.with_import_symbols(host, |_| Ok(linker))?,
+            None => Arc::new(parsed_module.make_linker(host)?),
+        };
         Self::instantiate(host, contract_id, parsed_module, &linker)
     }
 
@@ -314,8 +340,21 @@ impl Vm {
         wasm: &[u8],
         cost_inputs: VersionedContractCodeCostInputs,
         _cost_mode: ModuleParseCostMode,
-    ) -> Result<Rc<ParsedModule>, HostError> {
-        ParsedModule::new_with_isolated_engine(host, wasm, cost_inputs)
+        shared_module: Option<Arc<ParsedModule>>,
+    ) -> Result<Arc<ParsedModule>, HostError> {
+        Self::parse_or_reuse_module(host, wasm, cost_inputs, shared_module)
+    }
+
+    fn parse_or_reuse_module(
+        host: &Host,
+        wasm: &[u8],
+        cost_inputs: VersionedContractCodeCostInputs,
+        shared_module: Option<Arc<ParsedModule>>,
+    ) -> Result<Arc<ParsedModule>, HostError> {
+        match shared_module {
+            Some(parsed_module) => parsed_module.reuse(host),
+            None => ParsedModule::new_with_isolated_engine(host, wasm, cost_inputs),
+        }
     }
 
     /// This method exists to support [crate::storage::FootprintMode::Recording]
@@ -346,7 +385,8 @@ impl Vm {
     ///
     /// We still charge the reparses to the shadow budget, to avoid a DoS risk,
     /// and we still charge the instantiations to the real budget, to behave the
-    /// same as if we had a cache.
+    /// same as if we had a cache. Modules taken from the [SharedModuleCache]
+    /// instead of being parsed are charged in exactly the same way.
     ///
     /// Finally, for those scratching their head about the overall structure:
     /// all of this happens as a result of the "module cache" not being
@@ -359,15 +399,16 @@ impl Vm {
         wasm: &[u8],
         cost_inputs: VersionedContractCodeCostInputs,
         cost_mode: ModuleParseCostMode,
-    ) -> Result<Rc<ParsedModule>, HostError> {
+        shared_module: Option<Arc<ParsedModule>>,
+    ) -> Result<Arc<ParsedModule>, HostError> {
         if cost_mode == ModuleParseCostMode::PossiblyDeferredIfRecording {
             if host.in_storage_recording_mode()? {
                 return host.budget_ref().with_observable_shadow_mode(|| {
-                    ParsedModule::new_with_isolated_engine(host, wasm, cost_inputs)
+                    Self::parse_or_reuse_module(host, wasm, cost_inputs, shared_module)
                 });
             }
         }
-        ParsedModule::new_with_isolated_engine(host, wasm, cost_inputs)
+        Self::parse_or_reuse_module(host, wasm, cost_inputs, shared_module)
     }
 
     pub(crate) fn get_memory(&self, host: &Host) -> Result<Memory, HostError> {
@@ -467,17 +508,36 @@ impl Vm {
                     if let Some(code) = trap.trap_code() {
                         let err = code.into();
                         let mut msg = Cow::Borrowed("VM call trapped");
+                        let mut backtrace = None;
                         host.with_debug_mode(|| {
-                            msg = Cow::Owned(format!("VM call trapped: {:?}", &code));
+                            let bt = host.wasm_backtrace()?;
+                            msg = Cow::Owned(format!("VM call trapped: {:?}, in {}", &code, bt));
+                            backtrace = Some(bt);
                             Ok(())
                         });
-                        return Err(host.error(err, &msg, &[func_sym.to_val()]));
+                        let mut he = host.error(err, &msg, &[func_sym.to_val()]);
+                        #[cfg(any(test, feature = "testutils"))]
+                        let _ = host.capture_failed_vm_memory(self, func_sym, true);
+                        if let Some(bt) = backtrace {
+                            he.set_wasm_backtrace_if_missing(bt);
+                        }
+                        return Err(he);
                     }
-                    if let Some(he) = trap.downcast::<HostError>() {
+                    if let Some(mut he) = trap.downcast::<HostError>() {
                         host.log_diagnostics(
                             "VM call trapped with HostError",
                             &[func_sym.to_val(), he.error.to_val()],
                         );
+                        // Errors escalated from host functions called by the
+                        // innermost VM already pass through here first, so the
+                        // backtrace is captured with the deepest frame still on
+                        // the context stack; outer VMs leave it untouched.
+                        host.with_debug_mode(|| {
+                            he.set_wasm_backtrace_if_missing(host.wasm_backtrace()?);
+                            Ok(())
+                        });
+                        #[cfg(any(test, feature = "testutils"))]
+                        let _ = host.capture_failed_vm_memory(self, func_sym, false);
                         return Err(he);
                     }
                     return Err(host.err(
@@ -493,6 +553,8 @@ impl Vm {
                         msg = Cow::Owned(format!("VM call failed: {:?}", &e));
                         Ok(())
                     });
+                    #[cfg(any(test, feature = "testutils"))]
+                    let _ = host.capture_failed_vm_memory(self, func_sym, true);
                     return Err(host.error(e.into(), &msg, &[func_sym.to_val()]));
                 }
             }
@@ -0,0 +1,187 @@
+//! This module provides a static protocol-compatibility report for a contract's
+//! host-function imports: which host functions it imports, the range of ledger
+//! protocols in which it can be linked, and which of its imports are gated
+//! beyond the contract's own declared protocol or are deprecated or removed in
+//! upcoming protocols.
+//!
+//! The report mirrors the link-time checks performed in [Vm::instantiate] but
+//! runs without a [Host](crate::Host), directly on a Wasm blob, so it is
+//! suitable for offline tooling. It is not metered.
+
+use super::{func_info::HOST_FUNCTIONS, Vm};
+use crate::{
+    meta,
+    xdr::{
+        Limited, ReadXdr, ScEnvMetaEntry, ScEnvMetaEntryInterfaceVersion, ScErrorCode, ScErrorType,
+    },
+    HostError, DEFAULT_XDR_RW_LIMITS,
+};
+use std::{io::Cursor, ops::RangeInclusive};
+
+/// The protocol that the `next` feature of this crate builds towards, i.e. the
+/// first protocol not yet released to the network when building without it.
+#[cfg(feature = "next")]
+pub const NEXT_PROTOCOL: u32 = meta::INTERFACE_VERSION.protocol;
+#[cfg(not(feature = "next"))]
+pub const NEXT_PROTOCOL: u32 = meta::INTERFACE_VERSION.protocol + 1;
+
+/// A host function imported by a contract, together with the range of
+/// protocols in which it is supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedHostFunction {
+    /// Wasm import module name, eg. `"v"`.
+    pub mod_str: &'static str,
+    /// Wasm import function name, eg. `"_"`.
+    pub fn_str: &'static str,
+    /// Descriptive name from `env.json`, eg. `"vec_new"`.
+    pub name: &'static str,
+    pub min_proto: Option<u32>,
+    pub max_proto: Option<u32>,
+}
+
+/// The result of [Vm::protocol_compatibility_report].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolCompatibilityReport {
+    /// The interface version declared in the contract's env meta section.
+    pub interface_version: ScEnvMetaEntryInterfaceVersion,
+    /// Every imported host function known to this host, in import order.
+    pub imports: Vec<ImportedHostFunction>,
+    /// Function imports that this host does not define at all; a contract with
+    /// any of these can not be linked in any protocol.
+    pub unknown_imports: Vec<(String, String)>,
+    /// The inclusive range of ledger protocols in which the contract can be
+    /// linked by this host, or `None` if there is no such protocol.
+    pub linkable_protocols: Option<RangeInclusive<u32>>,
+    /// Imports whose minimum protocol is later than the contract's declared
+    /// protocol. These prevent linking regardless of the ledger protocol.
+    pub gated_beyond_contract_protocol: Vec<ImportedHostFunction>,
+    /// Imports that have a maximum supported protocol, i.e. are scheduled to
+    /// be removed.
+    pub deprecated: Vec<ImportedHostFunction>,
+    /// Imports that are not supported anymore in [NEXT_PROTOCOL].
+    pub removed_in_next: Vec<ImportedHostFunction>,
+}
+
+impl ProtocolCompatibilityReport {
+    /// Returns `true` if the contract can be linked at ledger protocol `proto`.
+    pub fn is_linkable_at(&self, proto: u32) -> bool {
+        self.linkable_protocols
+            .as_ref()
+            .is_some_and(|r| r.contains(&proto))
+    }
+}
+
+fn invalid_input() -> HostError {
+    (ScErrorType::WasmVm, ScErrorCode::InvalidInput).into()
+}
+
+fn read_interface_version(data: &[u8]) -> Result<ScEnvMetaEntryInterfaceVersion, HostError> {
+    let mut limits = DEFAULT_XDR_RW_LIMITS;
+    limits.len = data.len();
+    let mut cursor = Limited::new(Cursor::new(data), limits);
+    match ScEnvMetaEntry::read_xdr_iter(&mut cursor).next() {
+        Some(Ok(ScEnvMetaEntry::ScEnvMetaKindInterfaceVersion(v))) => Ok(v),
+        _ => Err(invalid_input()),
+    }
+}
+
+impl Vm {
+    /// Produces a [ProtocolCompatibilityReport] for the provided Wasm blob,
+    /// based on the protocol ranges of the host functions known to this host.
+    ///
+    /// Fails with `(WasmVm, InvalidInput)` if the blob can not be parsed or
+    /// has no interface version in its env meta section.
+    pub fn protocol_compatibility_report(
+        wasm: &[u8],
+    ) -> Result<ProtocolCompatibilityReport, HostError> {
+        use wasmparser::{Parser, Payload, TypeRef};
+
+        let mut interface_version = None;
+        let mut imports = Vec::new();
+        let mut unknown_imports = Vec::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload.map_err(|_| invalid_input())? {
+                Payload::CustomSection(s) if s.name() == meta::ENV_META_V0_SECTION_NAME => {
+                    interface_version = Some(read_interface_version(s.data())?);
+                }
+                Payload::ImportSection(s) => {
+                    for import in s {
+                        let import = import.map_err(|_| invalid_input())?;
+                        if !matches!(import.ty, TypeRef::Func(_)) {
+                            continue;
+                        }
+                        match HOST_FUNCTIONS
+                            .iter()
+                            .find(|hf| hf.mod_str == import.module && hf.fn_str == import.name)
+                        {
+                            Some(hf) => imports.push(ImportedHostFunction {
+                                mod_str: hf.mod_str,
+                                fn_str: hf.fn_str,
+                                name: hf.name,
+                                min_proto: hf.min_proto,
+                                max_proto: hf.max_proto,
+                            }),
+                            None => unknown_imports
+                                .push((import.module.to_string(), import.name.to_string())),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        let interface_version = interface_version.ok_or_else(invalid_input)?;
+        let contract_proto = interface_version.protocol;
+
+        // These mirror the checks in `Vm::instantiate` and
+        // `ParsedModule::check_contract_interface_version`: the ledger
+        // protocol must be at least the contract protocol, at most the host
+        // protocol, and within the range of every imported host function,
+        // which in turn must include the contract protocol.
+        let gated_beyond_contract_protocol: Vec<_> = imports
+            .iter()
+            .filter(|hf| hf.min_proto.is_some_and(|min| min > contract_proto))
+            .cloned()
+            .collect();
+        let removed_before_contract_protocol = imports
+            .iter()
+            .any(|hf| hf.max_proto.is_some_and(|max| max < contract_proto));
+        let lo = imports
+            .iter()
+            .filter_map(|hf| hf.min_proto)
+            .fold(contract_proto, u32::max);
+        let hi = imports
+            .iter()
+            .filter_map(|hf| hf.max_proto)
+            .fold(meta::INTERFACE_VERSION.protocol, u32::min);
+        let linkable_protocols = if unknown_imports.is_empty()
+            && gated_beyond_contract_protocol.is_empty()
+            && !removed_before_contract_protocol
+            && lo <= hi
+        {
+            Some(lo..=hi)
+        } else {
+            None
+        };
+
+        let deprecated = imports
+            .iter()
+            .filter(|hf| hf.max_proto.is_some())
+            .cloned()
+            .collect();
+        let removed_in_next = imports
+            .iter()
+            .filter(|hf| hf.max_proto.is_some_and(|max| max < NEXT_PROTOCOL))
+            .cloned()
+            .collect();
+
+        Ok(ProtocolCompatibilityReport {
+            interface_version,
+            imports,
+            unknown_imports,
+            linkable_protocols,
+            gated_beyond_contract_protocol,
+            deprecated,
+            removed_in_next,
+        })
+    }
+}
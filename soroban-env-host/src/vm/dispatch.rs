@@ -4,9 +4,10 @@ use crate::{
     CheckedEnvArg, EnvBase, Host, HostError, VmCaller, VmCallerEnv,
 };
 use crate::{
-    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I256Object, I256Val,
-    I64Object, MapObject, StorageType, StringObject, Symbol, SymbolObject, TimepointObject,
-    U128Object, U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject, Void,
+    AddressObject, Bool, BytesObject, DurationObject, Error, I128Object, I128Val, I256Object,
+    I256Val, I64Object, MapObject, StorageType, StringObject, Symbol, SymbolObject,
+    TimepointObject, U128Object, U256Object, U256Val, U32Val, U64Object, U64Val, Val, VecObject,
+    Void,
 };
 use core::fmt::Debug;
 use soroban_env_common::{call_macro_with_all_host_functions, WasmiMarshal};
@@ -86,6 +87,9 @@ macro_rules! homogenize_tuple {
     ($u:ident, ($_a:expr, $_b:expr, $_c:expr, $_d:expr, $_e:expr)) => {
         &[&$u.0, &$u.1, &$u.2, &$u.3, &$u.4]
     };
+    ($u:ident, ($_a:expr, $_b:expr, $_c:expr, $_d:expr, $_e:expr, $_f:expr)) => {
+        &[&$u.0, &$u.1, &$u.2, &$u.3, &$u.4, &$u.5]
+    };
 }
 
 // Define a relative-to-absolute impl for any type that is (a) mentioned
@@ -114,6 +118,7 @@ impl_relative_object_conversion!(U128Object);
 impl_relative_object_conversion!(U256Object);
 
 impl_relative_object_conversion!(U64Val);
+impl_relative_object_conversion!(I128Val);
 impl_relative_object_conversion!(U256Val);
 impl_relative_object_conversion!(I256Val);
 
@@ -267,10 +272,15 @@ macro_rules! generate_dispatch_functions {
                         },
                         Err(hosterr) => {
                             // We make a new HostError here to capture the escalation event itself.
-                            let escalation: HostError =
+                            let mut escalation: HostError =
                                 host.error(hosterr.error,
                                            concat!("escalating error to VM trap from failed host function call: ",
                                                    stringify!($fn_id)), &[]);
+                            // Carry over any Wasm backtrace captured by a
+                            // nested VM (eg. a failed cross-contract call).
+                            if let Some(bt) = hosterr.wasm_backtrace() {
+                                escalation.set_wasm_backtrace_if_missing(bt.clone());
+                            }
                             let trap: Trap = escalation.into();
                             Err(trap)
                         }
@@ -36,6 +36,8 @@ pub(crate) trait FuelRefillable {
         let fuel = self.fuel_consumed()?;
         host.as_budget()
             .bulk_charge(ContractCostType::WasmInsnExec, fuel, None)?;
+        #[cfg(any(test, feature = "testutils"))]
+        host.add_fuel_to_wasm_insn_profile(fuel)?;
         self.reset_fuel()
     }
 }
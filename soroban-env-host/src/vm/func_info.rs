@@ -11,6 +11,9 @@ pub(crate) struct HostFuncInfo {
     /// as.
     pub(crate) fn_str: &'static str,
 
+    /// Descriptive name of the host function, as declared in `env.json`.
+    pub(crate) name: &'static str,
+
     /// Number of I64-typed wasm arguments the function takes.
     #[allow(dead_code)]
     pub(crate) arity: u32,
@@ -44,6 +47,7 @@ macro_rules! host_function_info_helper {
         HostFuncInfo {
             mod_str: $mod_str,
             fn_str: $fn_id,
+            name: stringify!($func_id),
             arity: fn_arity!($args),
             wrap: |linker| linker.func_wrap($mod_str, $fn_id, dispatch::$func_id),
             min_proto: Some($min_proto),
@@ -54,6 +58,7 @@ macro_rules! host_function_info_helper {
         HostFuncInfo {
             mod_str: $mod_str,
             fn_str: $fn_id,
+            name: stringify!($func_id),
             arity: fn_arity!($args),
             wrap: |linker| linker.func_wrap($mod_str, $fn_id, dispatch::$func_id),
             min_proto: Some($min_proto),
@@ -64,6 +69,7 @@ macro_rules! host_function_info_helper {
         HostFuncInfo {
             mod_str: $mod_str,
             fn_str: $fn_id,
+            name: stringify!($func_id),
             arity: fn_arity!($args),
             wrap: |linker| linker.func_wrap($mod_str, $fn_id, dispatch::$func_id),
             min_proto: None,
@@ -74,6 +80,7 @@ macro_rules! host_function_info_helper {
         HostFuncInfo {
             mod_str: $mod_str,
             fn_str: $fn_id,
+            name: stringify!($func_id),
             arity: fn_arity!($args),
             wrap: |linker| linker.func_wrap($mod_str, $fn_id, dispatch::$func_id),
             min_proto: None,
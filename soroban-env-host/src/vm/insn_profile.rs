@@ -0,0 +1,321 @@
+//! This module implements an optional profiling mode that breaks down the Wasm
+//! instructions of each contract by instruction class, along with the fuel
+//! its VMs consume.
+//!
+//! Wasmi charges all executed instructions to a single fuel counter, which the
+//! host converts into the single [ContractCostType::WasmInsnExec] cost type.
+//! Wasmi consumes that fuel per basic block internally and doesn't expose it,
+//! so the instructions executed of each class can't be counted without
+//! changing what is executed. Profiling mode instead attributes the fuel each
+//! contract's VMs return to the host to that contract, and combines it with a
+//! static pass over the contract's code that counts the instructions of each
+//! class and the fuel wasmi charges for them. See [ContractInsnProfile].
+//!
+//! The fuel is exact, but the per-class figures describe the code's static
+//! mix rather than its hot paths: they are not executed instruction counts.
+//! The static pass runs on the shadow budget, so enabling profiling leaves the
+//! budget untouched. The profile is reported separately from the budget, see
+//! [Budget::wasm_insn_profile].
+//!
+//! [ContractCostType::WasmInsnExec]: crate::xdr::ContractCostType::WasmInsnExec
+//! [Budget::wasm_insn_profile]: crate::budget::Budget::wasm_insn_profile
+
+use crate::{
+    budget::{load_calibrated_fuel_costs, AsBudget},
+    host::frame::Frame,
+    xdr::Hash,
+    Host, HostError,
+};
+use std::{collections::BTreeMap, fmt::Display};
+use wasmi::FuelCosts;
+use wasmparser::{Operator, Parser, Payload};
+
+/// A class of Wasm instructions, as counted by [WasmInsnHistogram].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WasmInsnClass {
+    /// `call` and `call_indirect`, including calls to host functions.
+    Call,
+    /// Memory loads and stores.
+    LoadStore,
+    /// Integer arithmetic, comparisons and conversions.
+    Arithmetic,
+    /// Branches, conditionals, returns and `unreachable`.
+    Branch,
+    /// `memory.grow`.
+    MemoryGrow,
+    /// `memory.copy`, `memory.fill`, `memory.init` and `data.drop`.
+    BulkMemory,
+    /// Everything else: block structure, locals, globals, constants and
+    /// stack manipulation.
+    Other,
+}
+
+impl WasmInsnClass {
+    pub const ALL: [WasmInsnClass; 7] = [
+        WasmInsnClass::Call,
+        WasmInsnClass::LoadStore,
+        WasmInsnClass::Arithmetic,
+        WasmInsnClass::Branch,
+        WasmInsnClass::MemoryGrow,
+        WasmInsnClass::BulkMemory,
+        WasmInsnClass::Other,
+    ];
+
+    // Classifies an instruction, along with the fuel wasmi charges for it
+    // ahead of executing it. This leaves out the fuel charged in proportion
+    // to the values moved by calls and branches and to the bytes processed by
+    // bulk memory operations, which depends on the inputs.
+    fn of_operator(op: &Operator, costs: &FuelCosts) -> (Self, u64) {
+        use Operator::*;
+        match op {
+            Call { .. } | CallIndirect { .. } => (WasmInsnClass::Call, costs.call),
+            I32Load { .. }
+            | I64Load { .. }
+            | F32Load { .. }
+            | F64Load { .. }
+            | I32Load8S { .. }
+            | I32Load8U { .. }
+            | I32Load16S { .. }
+            | I32Load16U { .. }
+            | I64Load8S { .. }
+            | I64Load8U { .. }
+            | I64Load16S { .. }
+            | I64Load16U { .. }
+            | I64Load32S { .. }
+            | I64Load32U { .. } => (WasmInsnClass::LoadStore, costs.load),
+            I32Store { .. }
+            | I64Store { .. }
+            | F32Store { .. }
+            | F64Store { .. }
+            | I32Store8 { .. }
+            | I32Store16 { .. }
+            | I64Store8 { .. }
+            | I64Store16 { .. }
+            | I64Store32 { .. } => (WasmInsnClass::LoadStore, costs.store),
+            Unreachable
+            | Br { .. }
+            | BrIf { .. }
+            | BrTable { .. }
+            | If { .. }
+            | Else
+            | Return
+            | Select
+            | TypedSelect { .. } => (WasmInsnClass::Branch, costs.base),
+            MemoryGrow { .. } => (WasmInsnClass::MemoryGrow, costs.entity),
+            MemoryCopy { .. } | MemoryFill { .. } | MemoryInit { .. } | DataDrop { .. } => {
+                (WasmInsnClass::BulkMemory, costs.entity)
+            }
+            GlobalGet { .. } | GlobalSet { .. } | MemorySize { .. } => {
+                (WasmInsnClass::Other, costs.entity)
+            }
+            Nop
+            | Block { .. }
+            | Loop { .. }
+            | End
+            | Drop
+            | LocalGet { .. }
+            | LocalSet { .. }
+            | LocalTee { .. }
+            | I32Const { .. }
+            | I64Const { .. }
+            | F32Const { .. }
+            | F64Const { .. } => (WasmInsnClass::Other, costs.base),
+            // Numeric instructions, which is all that's left in the subset of
+            // Wasm that the host accepts, aside from the reference types and
+            // table instructions classified as `Other` below.
+            I32Eqz | I32Eq | I32Ne | I32LtS | I32LtU | I32GtS | I32GtU | I32LeS | I32LeU
+            | I32GeS | I32GeU | I64Eqz | I64Eq | I64Ne | I64LtS | I64LtU | I64GtS | I64GtU
+            | I64LeS | I64LeU | I64GeS | I64GeU | I32Clz | I32Ctz | I32Popcnt | I32Add | I32Sub
+            | I32Mul | I32DivS | I32DivU | I32RemS | I32RemU | I32And | I32Or | I32Xor | I32Shl
+            | I32ShrS | I32ShrU | I32Rotl | I32Rotr | I64Clz | I64Ctz | I64Popcnt | I64Add
+            | I64Sub | I64Mul | I64DivS | I64DivU | I64RemS | I64RemU | I64And | I64Or | I64Xor
+            | I64Shl | I64ShrS | I64ShrU | I64Rotl | I64Rotr | I32WrapI64 | I64ExtendI32S
+            | I64ExtendI32U | I32Extend8S | I32Extend16S | I64Extend8S | I64Extend16S
+            | I64Extend32S => (WasmInsnClass::Arithmetic, costs.base),
+            _ => (WasmInsnClass::Other, costs.base),
+        }
+    }
+}
+
+/// A count of Wasm instructions (or of the fuel charged for them) of each
+/// [WasmInsnClass].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmInsnHistogram {
+    counts: [u64; WasmInsnClass::ALL.len()],
+}
+
+impl WasmInsnHistogram {
+    pub fn get(&self, class: WasmInsnClass) -> u64 {
+        self.counts[class as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().fold(0, |acc, c| acc.saturating_add(*c))
+    }
+
+    fn add(&mut self, class: WasmInsnClass, count: u64) {
+        let c = &mut self.counts[class as usize];
+        *c = c.saturating_add(count);
+    }
+}
+
+/// The instruction profile of a single contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractInsnProfile {
+    /// Wasmi fuel consumed by the contract's VMs, as charged to
+    /// [ContractCostType::WasmInsnExec]. Summed over all calls to the
+    /// contract.
+    ///
+    /// [ContractCostType::WasmInsnExec]: crate::xdr::ContractCostType::WasmInsnExec
+    pub fuel_consumed: u64,
+    /// Number of instructions of each class in the contract's code.
+    pub static_insns: WasmInsnHistogram,
+    /// Fuel charged for the instructions of each class in the contract's
+    /// code, were each of them executed once.
+    pub static_fuel: WasmInsnHistogram,
+}
+
+impl ContractInsnProfile {
+    /// Scales the static instruction mix of the contract to the fuel it
+    /// consumed, by splitting [Self::fuel_consumed] between the classes in
+    /// proportion to their [Self::static_fuel] and dividing each share by the
+    /// average fuel of an instruction of the class.
+    ///
+    /// This is a static estimate, not a count of executed instructions: it
+    /// assumes that every instruction in the code is executed equally often,
+    /// so eg. loads run in a loop are still attributed in proportion to the
+    /// code around them.
+    pub fn static_mix_scaled_to_fuel(&self) -> WasmInsnHistogram {
+        let mut hist = WasmInsnHistogram::default();
+        let total_fuel = self.static_fuel.total() as u128;
+        if total_fuel == 0 {
+            return hist;
+        }
+        for class in WasmInsnClass::ALL {
+            // fuel_consumed * (static_fuel / total_fuel) / (static_fuel / static_insns)
+            let count =
+                self.fuel_consumed as u128 * self.static_insns.get(class) as u128 / total_fuel;
+            hist.add(class, u64::try_from(count).unwrap_or(u64::MAX));
+        }
+        hist
+    }
+}
+
+/// The [ContractInsnProfile] of every contract executed while profiling.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmInsnProfile(pub BTreeMap<Hash, ContractInsnProfile>);
+
+impl Display for WasmInsnProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Wasm instruction mix by class (static estimate, not executed counts):"
+        )?;
+        for (contract_id, profile) in self.0.iter() {
+            write!(f, "Contract ")?;
+            for b in contract_id.0.iter() {
+                write!(f, "{:02x}", b)?;
+            }
+            writeln!(f, ", fuel consumed: {}", profile.fuel_consumed)?;
+            writeln!(
+                f,
+                "    {:<20}{:<15}{:<15}",
+                "Class", "Static", "Scaled to fuel"
+            )?;
+            let estimated = profile.static_mix_scaled_to_fuel();
+            for class in WasmInsnClass::ALL {
+                writeln!(
+                    f,
+                    "    {:<20}{:<15}{:<15}",
+                    format!("{:?}", class),
+                    profile.static_insns.get(class),
+                    estimated.get(class)
+                )?;
+            }
+            writeln!(
+                f,
+                "    {:<20}{:<15}{:<15}",
+                "Total",
+                profile.static_insns.total(),
+                estimated.total()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts the instructions of each class in the function bodies of `wasm`,
+/// and the fuel charged for them, see [ContractInsnProfile].
+pub(crate) fn static_insn_histograms(wasm: &[u8]) -> (WasmInsnHistogram, WasmInsnHistogram) {
+    let costs = load_calibrated_fuel_costs();
+    let mut insns = WasmInsnHistogram::default();
+    let mut fuel = WasmInsnHistogram::default();
+    for payload in Parser::new(0).parse_all(wasm) {
+        // The module has already been validated when it was uploaded, and
+        // profiling is best-effort anyway: ignore anything malformed.
+        let Ok(Payload::CodeSectionEntry(body)) = payload else {
+            continue;
+        };
+        let Ok(ops) = body.get_operators_reader() else {
+            continue;
+        };
+        for op in ops.into_iter().flatten() {
+            let (class, op_fuel) = WasmInsnClass::of_operator(&op, &costs);
+            insns.add(class, 1);
+            fuel.add(class, op_fuel);
+        }
+    }
+    (insns, fuel)
+}
+
+impl Host {
+    /// Enables or disables Wasm instruction profiling, see [WasmInsnProfile].
+    /// Only contracts instantiated while profiling is enabled are profiled.
+    pub fn enable_wasm_insn_profiling(&self, enabled: bool) -> Result<(), HostError> {
+        self.as_budget().enable_wasm_insn_profiling(enabled)
+    }
+
+    pub(crate) fn is_wasm_insn_profiling_enabled(&self) -> Result<bool, HostError> {
+        self.as_budget().is_wasm_insn_profiling_enabled()
+    }
+
+    /// Adds the static instruction counts of the contract code with hash
+    /// `wasm_hash` to the profile of contract `contract_id`, unless it has
+    /// them already. Runs on the shadow budget and ignores any failure.
+    pub(crate) fn add_static_insns_to_wasm_insn_profile(
+        &self,
+        contract_id: &Hash,
+        wasm_hash: &Hash,
+    ) -> Result<(), HostError> {
+        if !self.is_wasm_insn_profiling_enabled()?
+            || self.as_budget().has_static_wasm_insn_profile(contract_id)?
+        {
+            return Ok(());
+        }
+        self.as_budget().with_shadow_mode(|| {
+            let (code, _) = self.retrieve_wasm_from_storage(wasm_hash)?;
+            let (insns, fuel) = static_insn_histograms(code.as_slice());
+            self.as_budget()
+                .set_static_wasm_insn_profile(contract_id, insns, fuel)
+        });
+        Ok(())
+    }
+
+    /// Attributes `fuel` consumed by a VM to the contract of the VM frame at
+    /// the top of the context stack, if profiling is enabled. Not metered, as
+    /// profiling must not affect the budget.
+    pub(crate) fn add_fuel_to_wasm_insn_profile(&self, fuel: u64) -> Result<(), HostError> {
+        if !self.is_wasm_insn_profiling_enabled()? {
+            return Ok(());
+        }
+        let contract_id = self.with_current_frame_opt(|frame| match frame {
+            Some(Frame::ContractVM { vm, .. }) => Ok(Some(vm.contract_id.clone())),
+            _ => Ok(None),
+        })?;
+        if let Some(contract_id) = contract_id {
+            self.as_budget()
+                .add_fuel_to_wasm_insn_profile(&contract_id, fuel)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! This module lets tests and tooling inspect the linear memory of a contract
+//! VM after it failed. Normally the [Vm] of a failing contract, and with it its
+//! linear memory, is dropped as soon as the error propagates out of it, which
+//! makes memory-corruption bugs in contracts hard to diagnose.
+//!
+//! When enabled with [Host::enable_failed_vm_memory_capture], the host copies
+//! the linear memory and exported globals of the VM in which the most recent
+//! failure of a top-level contract invocation originated into a
+//! [VmMemorySnapshot], which can then be retrieved with
+//! [Host::take_failed_vm_memory].
+//!
+//! Capturing is unmetered and only available with the `testutils` feature.
+
+use super::Vm;
+use crate::{
+    host::error::TryBorrowOrErr, xdr::Hash, Host, HostError, Symbol, SymbolStr, TryIntoVal, Val,
+};
+use std::{fs::File, io::Write, path::Path};
+
+/// A copy of the linear memory and exported globals of a contract VM, taken at
+/// the point it failed.
+#[derive(Debug, Clone)]
+pub struct VmMemorySnapshot {
+    /// The contract that the VM was instantiated for.
+    pub contract_id: Hash,
+    /// The exported function whose invocation failed.
+    pub function: String,
+    /// The content of the VM's linear memory, empty if the module has none.
+    pub memory: Vec<u8>,
+    /// The names and values of the module's exported globals. Non-exported
+    /// globals (such as the Rust shadow-stack pointer) are not accessible.
+    pub globals: Vec<(String, wasmi::Value)>,
+}
+
+impl VmMemorySnapshot {
+    /// Returns the `len` bytes starting at `addr`, or `None` if the range is
+    /// out of bounds.
+    pub fn read_bytes(&self, addr: u32, len: u32) -> Option<&[u8]> {
+        let start = addr as usize;
+        let end = start.checked_add(len as usize)?;
+        self.memory.get(start..end)
+    }
+
+    /// Decodes the 8 little-endian bytes at `addr` as a [Val], the same way
+    /// the host reads `Val`s out of linear memory. Object handles in the
+    /// decoded value are relative to the failed VM and no longer resolvable.
+    pub fn read_val(&self, addr: u32) -> Option<Val> {
+        let bytes: [u8; 8] = self.read_bytes(addr, 8)?.try_into().ok()?;
+        Some(Val::from_payload(u64::from_le_bytes(bytes)))
+    }
+
+    /// Decodes `count` consecutive [Val]s starting at `addr`.
+    pub fn read_vals(&self, addr: u32, count: u32) -> Option<Vec<Val>> {
+        (0..count)
+            .map(|i| self.read_val(addr.checked_add(i.checked_mul(8)?)?))
+            .collect()
+    }
+
+    /// Returns the value of the exported global named `name`.
+    pub fn global(&self, name: &str) -> Option<&wasmi::Value> {
+        self.globals
+            .iter()
+            .find_map(|(n, v)| (n == name).then_some(v))
+    }
+
+    /// Writes the raw linear memory to the file at `path`.
+    pub fn dump_to_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        File::create(path)?.write_all(&self.memory)
+    }
+}
+
+impl Vm {
+    fn memory_snapshot(
+        &self,
+        host: &Host,
+        func_sym: &Symbol,
+    ) -> Result<VmMemorySnapshot, HostError> {
+        let func_ss: SymbolStr = func_sym.try_into_val(host)?;
+        let function: &str = func_ss.as_ref();
+        let store = self.store.try_borrow_or_err()?;
+        let memory = self
+            .memory
+            .map(|mem| mem.data(&*store).to_vec())
+            .unwrap_or_default();
+        let globals = self
+            .instance
+            .exports(&*store)
+            .filter_map(|export| {
+                let name = export.name().to_string();
+                export
+                    .into_global()
+                    .map(|global| (name, global.get(&*store)))
+            })
+            .collect();
+        Ok(VmMemorySnapshot {
+            contract_id: self.contract_id.clone(),
+            function: function.to_string(),
+            memory,
+            globals,
+        })
+    }
+}
+
+impl Host {
+    /// Enables or disables capturing the memory of failed contract VMs. See
+    /// [VmMemorySnapshot].
+    pub fn enable_failed_vm_memory_capture(&self, enabled: bool) -> Result<(), HostError> {
+        *self.try_borrow_capture_failed_vm_memory_mut()? = enabled;
+        Ok(())
+    }
+
+    /// Takes the snapshot of the VM that failed during the most recent
+    /// top-level contract invocation, if capturing is enabled and any VM
+    /// failed.
+    pub fn take_failed_vm_memory(&self) -> Result<Option<VmMemorySnapshot>, HostError> {
+        Ok(self.try_borrow_failed_vm_memory_mut()?.take())
+    }
+
+    pub(crate) fn clear_failed_vm_memory(&self) -> Result<(), HostError> {
+        *self.try_borrow_failed_vm_memory_mut()? = None;
+        Ok(())
+    }
+
+    /// Records the memory of `vm`, which just failed while running `func_sym`.
+    /// A VM that trapped on its own always replaces any earlier snapshot;
+    /// errors propagated out of a VM (from a host function or a nested VM)
+    /// only record it if nothing deeper was recorded yet.
+    pub(crate) fn capture_failed_vm_memory(
+        &self,
+        vm: &Vm,
+        func_sym: &Symbol,
+        replace: bool,
+    ) -> Result<(), HostError> {
+        if !*self.try_borrow_capture_failed_vm_memory()? {
+            return Ok(());
+        }
+        if !replace && self.try_borrow_failed_vm_memory()?.is_some() {
+            return Ok(());
+        }
+        let snapshot = vm.memory_snapshot(self, func_sym)?;
+        *self.try_borrow_failed_vm_memory_mut()? = Some(snapshot);
+        Ok(())
+    }
+}
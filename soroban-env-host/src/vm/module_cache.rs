@@ -3,38 +3,98 @@ use super::{
     parsed_module::{ParsedModule, VersionedContractCodeCostInputs},
 };
 use crate::{
-    budget::{get_wasmi_config, AsBudget},
+    budget::{get_fuel_costs, get_wasmi_config, same_fuel_costs, AsBudget},
     host::metered_clone::{MeteredClone, MeteredContainer},
-    xdr::{Hash, ScErrorCode, ScErrorType},
+    xdr::{
+        ContractCodeEntry, ContractCodeEntryExt, Hash, LedgerEntry, LedgerEntryData, ScErrorCode,
+        ScErrorType,
+    },
     Host, HostError, MeteredOrdMap,
 };
-use std::{collections::BTreeSet, rc::Rc};
-use wasmi::Engine;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+    sync::Arc,
+};
+use wasmi::{Engine, FuelCosts, Linker};
 
 /// A [ModuleCache] is a cache of a set of Wasm modules that have been parsed
 /// but not yet instantiated, along with a shared and reusable [Engine] storing
 /// their code. The cache must be populated eagerly with all the contracts in a
 /// single [Host]'s lifecycle (at least) added all at once, since each wasmi
 /// [Engine] is locked during execution and no new modules can be added to it.
+///
+/// When the [Host] has a [SharedModuleCache] that contains all of its stored
+/// contracts, the cache is built from its modules and [Engine] instead of
+/// parsing the contracts again.
 #[derive(Clone, Default)]
 pub struct ModuleCache {
     pub(crate) engine: Engine,
-    modules: MeteredOrdMap<Hash, Rc<ParsedModule>, Host>,
+    modules: MeteredOrdMap<Hash, Arc<ParsedModule>, Host>,
+    shared: Option<SharedModuleCache>,
 }
 
 impl ModuleCache {
     pub fn new(host: &Host) -> Result<Self, HostError> {
-        let config = get_wasmi_config(host.as_budget())?;
-        let engine = Engine::new(&config);
+        let contracts = Self::stored_contracts(host)?;
+        // All the modules of the cache have to share its engine, so the
+        // shared modules can only be used if none has to be parsed.
+        let shared = host
+            .try_borrow_shared_module_cache()?
+            .clone()
+            .filter(|shared| {
+                contracts
+                    .iter()
+                    .filter_map(|entry| code_entry(entry))
+                    .all(|code| shared.contains(code))
+            });
+        let engine = if let Some(shared) = &shared {
+            shared.engine.clone()
+        } else {
+            let config = get_wasmi_config(host.as_budget())?;
+            Engine::new(&config)
+        };
         let modules = MeteredOrdMap::new();
-        let mut cache = Self { engine, modules };
-        cache.add_stored_contracts(host)?;
+        let mut cache = Self {
+            engine,
+            modules,
+            shared,
+        };
+        cache.add_contracts(host, &contracts)?;
         Ok(cache)
     }
 
     pub fn add_stored_contracts(&mut self, host: &Host) -> Result<(), HostError> {
-        use crate::xdr::{ContractCodeEntry, ContractCodeEntryExt, LedgerEntryData, LedgerKey};
+        let contracts = Self::stored_contracts(host)?;
+        self.add_contracts(host, &contracts)
+    }
+
+    fn add_contracts(
+        &mut self,
+        host: &Host,
+        contracts: &[Rc<LedgerEntry>],
+    ) -> Result<(), HostError> {
+        for ContractCodeEntry { code, hash, ext } in
+            contracts.iter().filter_map(|entry| code_entry(entry))
+        {
+            let code_cost_inputs = match ext {
+                ContractCodeEntryExt::V0 => VersionedContractCodeCostInputs::V0 {
+                    wasm_bytes: code.len(),
+                },
+                ContractCodeEntryExt::V1(v1) => VersionedContractCodeCostInputs::V1(
+                    v1.cost_inputs.metered_clone(host.as_budget())?,
+                ),
+            };
+            self.parse_and_cache_module(host, hash, code, code_cost_inputs)?;
+        }
+        Ok(())
+    }
+
+    // Returns the contract code entries of the storage.
+    fn stored_contracts(host: &Host) -> Result<Vec<Rc<LedgerEntry>>, HostError> {
+        use crate::xdr::LedgerKey;
         let storage = host.try_borrow_storage()?;
+        let mut contracts = vec![];
         for (k, v) in storage.map.iter(host.as_budget())? {
             // In recording mode we build the module cache *after* the contract invocation has
             // finished. This means that if any new Wasm has been uploaded, then we will add it to
@@ -52,33 +112,11 @@ impl ModuleCache {
 
             if let LedgerKey::ContractCode(_) = &**k {
                 if let Some((e, _)) = v {
-                    if let LedgerEntryData::ContractCode(ContractCodeEntry { code, hash, ext }) =
-                        &e.data
-                    {
-                        // We allow empty contracts in testing mode; they exist
-                        // to exercise as much of the contract-code-storage
-                        // infrastructure as possible, while still redirecting
-                        // the actual execution into a `ContractFunctionSet`.
-                        // They should never be called, so we do not have to go
-                        // as far as making a fake `ParsedModule` for them.
-                        if cfg!(any(test, feature = "testutils")) && code.as_slice().is_empty() {
-                            continue;
-                        }
-
-                        let code_cost_inputs = match ext {
-                            ContractCodeEntryExt::V0 => VersionedContractCodeCostInputs::V0 {
-                                wasm_bytes: code.len(),
-                            },
-                            ContractCodeEntryExt::V1(v1) => VersionedContractCodeCostInputs::V1(
-                                v1.cost_inputs.metered_clone(host.as_budget())?,
-                            ),
-                        };
-                        self.parse_and_cache_module(host, hash, code, code_cost_inputs)?;
-                    }
+                    contracts.push(e.clone());
                 }
             }
         }
-        Ok(())
+        Ok(contracts)
     }
 
     pub fn parse_and_cache_module(
@@ -96,7 +134,23 @@ impl ModuleCache {
                 &[],
             ));
         }
-        let parsed_module = ParsedModule::new(host, &self.engine, &wasm, cost_inputs)?;
+        let parsed_module = if let Some(shared) = &self.shared {
+            match shared.get_parsed_module(contract_id, &cost_inputs) {
+                Some(parsed_module) => parsed_module.reuse(host)?,
+                // Parsing would add the module to the engine of the shared
+                // cache, which may be running on other threads.
+                None => {
+                    return Err(host.err(
+                        ScErrorType::Context,
+                        ScErrorCode::InternalError,
+                        "module is missing from the shared module cache",
+                        &[],
+                    ))
+                }
+            }
+        } else {
+            ParsedModule::new(host, &self.engine, &wasm, cost_inputs)?
+        };
         self.modules =
             self.modules
                 .insert(contract_id.metered_clone(host)?, parsed_module, host)?;
@@ -132,14 +186,22 @@ impl ModuleCache {
     }
 
     pub fn make_linker(&self, host: &Host) -> Result<wasmi::Linker<Host>, HostError> {
+        if let Some(shared) = &self.shared {
+            // Defining host functions would lock the engine of the shared
+            // cache, so its linker is used instead, charged the same way.
+            return self.with_import_symbols(host, |_| Ok((*shared.linker).clone()));
+        }
         self.with_import_symbols(host, |symbols| Host::make_linker(&self.engine, symbols))
     }
 
+    /// Returns the parsed module of the Wasm with the given hash. Modules are
+    /// returned in an [Arc] rather than an [Rc], since the same module can be
+    /// taken from a [SharedModuleCache] by hosts on different threads.
     pub fn get_module(
         &self,
         host: &Host,
         wasm_hash: &Hash,
-    ) -> Result<Option<Rc<ParsedModule>>, HostError> {
+    ) -> Result<Option<Arc<ParsedModule>>, HostError> {
         if let Some(m) = self.modules.get(wasm_hash, host)? {
             Ok(Some(m.clone()))
         } else {
@@ -147,3 +209,149 @@ impl ModuleCache {
         }
     }
 }
+
+// We allow empty contracts in testing mode; they exist to exercise as much of
+// the contract-code-storage infrastructure as possible, while still
+// redirecting the actual execution into a `ContractFunctionSet`. They should
+// never be called, so we do not have to go as far as making a fake
+// `ParsedModule` for them.
+fn is_empty_test_contract(code: &ContractCodeEntry) -> bool {
+    cfg!(any(test, feature = "testutils")) && code.code.as_slice().is_empty()
+}
+
+// Returns the contract code of the entry, unless it's an empty test contract.
+fn code_entry(entry: &LedgerEntry) -> Option<&ContractCodeEntry> {
+    match &entry.data {
+        LedgerEntryData::ContractCode(code) if !is_empty_test_contract(code) => Some(code),
+        _ => None,
+    }
+}
+
+// Returns the cost inputs of the contract code without metering, for the
+// lookups in the shared module cache that are not charged to any host.
+fn unmetered_cost_inputs(code: &ContractCodeEntry) -> VersionedContractCodeCostInputs {
+    match &code.ext {
+        ContractCodeEntryExt::V0 => VersionedContractCodeCostInputs::V0 {
+            wasm_bytes: code.code.len(),
+        },
+        ContractCodeEntryExt::V1(v1) => VersionedContractCodeCostInputs::V1(v1.cost_inputs.clone()),
+    }
+}
+
+/// A [SharedModuleCache] is an immutable set of Wasm modules parsed in
+/// advance, along with the [Engine] storing their code, that can be shared
+/// between [Host]s running on different threads (see
+/// [`Host::set_shared_module_cache`]) so that every host doesn't have to parse
+/// the same contracts again.
+///
+/// The hosts still charge their budgets for parsing every module they take
+/// from the shared cache, so the resources they consume are the same as if
+/// they had parsed the modules themselves. A module is only taken from the
+/// shared cache if its cost inputs are the same as the ones of the contract
+/// code stored in the host.
+///
+/// No modules can be added to the cache once it's built, and no host
+/// functions can be defined on its [Engine], since it may be running on other
+/// threads. The cache comes with a [Linker] defining all the host functions
+/// instead, that the hosts use for its modules.
+///
+/// The [Engine] meters the fuel of the modules it runs with the fuel costs of
+/// the budget the cache was built with, so the cache can only be given to
+/// hosts whose budgets have the same fuel costs.
+#[derive(Clone)]
+pub struct SharedModuleCache {
+    engine: Engine,
+    fuel_costs: FuelCosts,
+    modules: Arc<BTreeMap<Hash, Arc<ParsedModule>>>,
+    linker: Arc<Linker<Host>>,
+}
+
+/// A module of a [SharedModuleCache] along with the linker to instantiate it.
+pub(crate) type SharedModule = (Arc<ParsedModule>, Arc<Linker<Host>>);
+
+impl SharedModuleCache {
+    /// Parses the contract code entries into a new shared cache. The parsing
+    /// is charged to the budget of `host` and the contracts are checked
+    /// against its ledger protocol, which has to be the same for all the hosts
+    /// that use the cache, as do the fuel costs of its budget.
+    pub fn new<'a>(
+        host: &Host,
+        contracts: impl IntoIterator<Item = &'a ContractCodeEntry>,
+    ) -> Result<Self, HostError> {
+        let fuel_costs = get_fuel_costs(host.as_budget())?;
+        let config = get_wasmi_config(host.as_budget())?;
+        let engine = Engine::new(&config);
+        let mut modules = BTreeMap::new();
+        for code in contracts {
+            if is_empty_test_contract(code) {
+                continue;
+            }
+            let parsed_module = ParsedModule::new(
+                host,
+                &engine,
+                code.code.as_slice(),
+                unmetered_cost_inputs(code),
+            )?;
+            modules.insert(code.hash.clone(), parsed_module);
+        }
+        let symbols = HOST_FUNCTIONS
+            .iter()
+            .map(|hf| (hf.mod_str, hf.fn_str))
+            .collect();
+        let linker = Host::make_linker(&engine, &symbols)?;
+        Ok(Self {
+            engine,
+            fuel_costs,
+            modules: Arc::new(modules),
+            linker: Arc::new(linker),
+        })
+    }
+
+    /// Returns the number of modules in the cache.
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Returns whether the cache has no modules.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    // Returns an error if the budget of `host` doesn't have the fuel costs
+    // that the engine of the cache meters its modules with.
+    pub(crate) fn check_fuel_costs(&self, host: &Host) -> Result<(), HostError> {
+        if !same_fuel_costs(&self.fuel_costs, &get_fuel_costs(host.as_budget())?) {
+            return Err(host.err(
+                ScErrorType::Context,
+                ScErrorCode::InvalidInput,
+                "shared module cache was built with different fuel costs",
+                &[],
+            ));
+        }
+        Ok(())
+    }
+
+    fn get_parsed_module(
+        &self,
+        wasm_hash: &Hash,
+        cost_inputs: &VersionedContractCodeCostInputs,
+    ) -> Option<&Arc<ParsedModule>> {
+        self.modules
+            .get(wasm_hash)
+            .filter(|parsed_module| &parsed_module.cost_inputs == cost_inputs)
+    }
+
+    pub(crate) fn get(
+        &self,
+        wasm_hash: &Hash,
+        cost_inputs: &VersionedContractCodeCostInputs,
+    ) -> Option<SharedModule> {
+        self.get_parsed_module(wasm_hash, cost_inputs)
+            .map(|parsed_module| (parsed_module.clone(), self.linker.clone()))
+    }
+
+    fn contains(&self, code: &ContractCodeEntry) -> bool {
+        self.get_parsed_module(&code.hash, &unmetered_cost_inputs(code))
+            .is_some()
+    }
+}
@@ -12,9 +12,9 @@ use crate::{
 use wasmi::{Engine, Module};
 
 use super::Vm;
-use std::{collections::BTreeSet, io::Cursor, rc::Rc};
+use std::{collections::BTreeSet, io::Cursor, sync::Arc};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VersionedContractCodeCostInputs {
     V0 { wasm_bytes: usize },
     V1(crate::xdr::ContractCodeCostInputs),
@@ -144,21 +144,37 @@ pub struct ParsedModule {
 }
 
 impl ParsedModule {
+    /// Parses the Wasm blob into a module of `engine`, charging the parsing
+    /// to the budget of `host`. The module is returned in an [Arc] rather than
+    /// an [Rc](std::rc::Rc), so that a
+    /// [SharedModuleCache](crate::vm::SharedModuleCache) can share it with
+    /// hosts on different threads.
     pub fn new(
         host: &Host,
         engine: &Engine,
         wasm: &[u8],
         cost_inputs: VersionedContractCodeCostInputs,
-    ) -> Result<Rc<Self>, HostError> {
+    ) -> Result<Arc<Self>, HostError> {
         cost_inputs.charge_for_parsing(host)?;
         let (module, proto_version) = Self::parse_wasm(host, engine, wasm)?;
-        Ok(Rc::new(Self {
+        Ok(Arc::new(Self {
             module,
             proto_version,
             cost_inputs,
         }))
     }
 
+    /// Returns this already parsed module as if it was parsed again by
+    /// `host`: the parsing is charged to the budget of `host` and the module is
+    /// checked against its ledger protocol, exactly like in [`Self::new`], but
+    /// the Wasm isn't parsed again.
+    pub(crate) fn reuse(self: &Arc<Self>, host: &Host) -> Result<Arc<Self>, HostError> {
+        self.cost_inputs.charge_for_parsing(host)?;
+        Self::check_max_args(host, &self.module)?;
+        Self::check_meta_section(host, &self.module)?;
+        Ok(self.clone())
+    }
+
     pub fn with_import_symbols<T>(
         &self,
         host: &Host,
@@ -203,7 +219,7 @@ impl ParsedModule {
         host: &Host,
         wasm: &[u8],
         cost_inputs: VersionedContractCodeCostInputs,
-    ) -> Result<Rc<Self>, HostError> {
+    ) -> Result<Arc<Self>, HostError> {
         use crate::budget::AsBudget;
         let config = crate::vm::get_wasmi_config(host.as_budget())?;
         let engine = Engine::new(&config);
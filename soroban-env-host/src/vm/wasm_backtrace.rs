@@ -0,0 +1,349 @@
+//! This module provides symbolic "Wasm backtraces" for diagnostic purposes:
+//! when a contract traps, the host reconstructs the chain of contract calls
+//! active on its context stack and resolves each of them to a human-readable
+//! name using the `name` custom section of the contract's module and, if the
+//! module carries any, its DWARF debug info.
+//!
+//! These backtraces are scoped to contract calls, not to Wasm functions. The
+//! wasmi version the host runs on reports neither the function index nor the
+//! code offset at which a trap occurred, and offers no way to inspect its
+//! call stack from a trap or a host function call. So the frames inside a
+//! contract (eg. `core::panicking::panic`) can't be recovered: each frame
+//! names the exported function that was called on a contract, resolved
+//! through the name section to its function index and through DWARF to its
+//! qualified name and declaration site. Instrumenting modules to track their
+//! calls is not an option either, since debug mode must not change how much
+//! fuel a contract consumes.
+//!
+//! Demangling and DWARF symbolization are only available with the `backtrace`
+//! feature (and in tests); without it, frames carry raw name section names.
+//!
+//! None of this is observable outside of debug mode: backtraces are only ever
+//! computed inside [Host::with_debug_mode] and only ever stored in
+//! diagnostic events and [HostError] debug info.
+
+use super::ParsedModule;
+use crate::{host::frame::Frame, Host, HostError};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+};
+
+/// The name of the standard Wasm custom section carrying debug names.
+const NAME_SECTION_NAME: &str = "name";
+
+/// A single frame of a [WasmBacktrace], standing for a call to an exported
+/// contract function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFrame {
+    /// Hex-encoded contract ID of the contract the frame belongs to.
+    pub contract_id: String,
+    /// Index of the function in the module's function index space, if it
+    /// could be recovered from the module's `name` section.
+    pub func_index: Option<u32>,
+    /// Symbolized function name: the qualified name from DWARF debug info if
+    /// available, else the (demangled) `name` section name, else the export
+    /// name.
+    pub func_name: String,
+    /// Source file declaring the function, from DWARF debug info.
+    pub file: Option<String>,
+    /// Source line declaring the function, from DWARF debug info.
+    pub line: Option<u64>,
+}
+
+impl Display for WasmFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.func_name)?;
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, " ({}:{})", file, line),
+            (Some(file), None) => write!(f, " ({})", file),
+            (None, Some(line)) => write!(f, " (line {})", line),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A symbolic backtrace of the contract calls active at the time of an error,
+/// ordered outermost (first-called) first. It doesn't include the functions
+/// called within each contract, see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmBacktrace {
+    pub frames: Vec<WasmFrame>,
+}
+
+impl Display for WasmBacktrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, frame) in self.frames.iter().enumerate() {
+            if i != 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// A function described by a module's DWARF debug info.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DwarfFunction {
+    pub(crate) name: String,
+    pub(crate) file: Option<String>,
+    pub(crate) line: Option<u64>,
+}
+
+/// Function names recovered from a module's `name` custom section and DWARF
+/// debug info.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WasmSymbols {
+    func_names: BTreeMap<u32, String>,
+    // Keyed by linkage (mangled) name where DWARF provides one, and by plain
+    // name otherwise.
+    dwarf_funcs: BTreeMap<String, DwarfFunction>,
+}
+
+impl WasmSymbols {
+    /// Parses the function-names subsection of the provided `name` custom
+    /// section content. Malformed content is ignored rather than reported:
+    /// symbolization is strictly best-effort.
+    pub(crate) fn from_name_section(data: &[u8]) -> Self {
+        use wasmparser::{Name, NameSectionReader};
+        let mut func_names = BTreeMap::new();
+        for subsection in NameSectionReader::new(data, 0) {
+            let Ok(Name::Function(names)) = subsection else {
+                continue;
+            };
+            for naming in names.into_iter().flatten() {
+                func_names.insert(naming.index, naming.name.to_string());
+            }
+        }
+        Self {
+            func_names,
+            dwarf_funcs: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn from_module(module: &ParsedModule) -> Self {
+        let symbols = module
+            .custom_section(NAME_SECTION_NAME)
+            .map(Self::from_name_section)
+            .unwrap_or_default();
+        #[cfg(any(test, feature = "backtrace"))]
+        let symbols = Self {
+            dwarf_funcs: dwarf::functions(|name| module.custom_section(name)).unwrap_or_default(),
+            ..symbols
+        };
+        symbols
+    }
+
+    /// Returns the demangled name of the function at `index`, if named.
+    pub(crate) fn func_name(&self, index: u32) -> Option<String> {
+        self.func_names.get(&index).map(|n| demangle(n))
+    }
+
+    /// Looks up the index of the function with the given (raw) name. Exported
+    /// contract functions are `#[no_mangle]`, so their name section entry
+    /// matches the export name.
+    pub(crate) fn func_index(&self, name: &str) -> Option<u32> {
+        self.func_names
+            .iter()
+            .find_map(|(idx, n)| (n == name).then_some(*idx))
+    }
+
+    /// Looks up the DWARF description of the function with the given raw
+    /// (linkage or plain) name.
+    pub(crate) fn dwarf_function(&self, name: &str) -> Option<&DwarfFunction> {
+        self.dwarf_funcs.get(name)
+    }
+
+    /// Produces a frame for a call to the exported function `export`.
+    pub(crate) fn frame_for_export(&self, contract_id: String, export: &str) -> WasmFrame {
+        let func_index = self.func_index(export);
+        if let Some(df) = self.dwarf_function(export) {
+            return WasmFrame {
+                contract_id,
+                func_index,
+                func_name: df.name.clone(),
+                file: df.file.clone(),
+                line: df.line,
+            };
+        }
+        let func_name = func_index
+            .and_then(|idx| self.func_name(idx))
+            .unwrap_or_else(|| export.to_string());
+        WasmFrame {
+            contract_id,
+            func_index,
+            func_name,
+            file: None,
+            line: None,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "backtrace"))]
+fn demangle(name: &str) -> String {
+    // The alternate format omits the trailing hash of legacy Rust symbols.
+    format!("{:#}", rustc_demangle::demangle(name))
+}
+
+#[cfg(not(any(test, feature = "backtrace")))]
+fn demangle(name: &str) -> String {
+    name.to_string()
+}
+
+#[cfg(any(test, feature = "backtrace"))]
+mod dwarf {
+    use super::{demangle, DwarfFunction};
+    use gimli::{
+        AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, LittleEndian, SectionId,
+        Unit,
+    };
+    use std::collections::BTreeMap;
+
+    type R<'a> = EndianSlice<'a, LittleEndian>;
+
+    /// Collects the subprograms described by the DWARF sections returned by
+    /// `section`. Wasm carries each DWARF section as a custom section named
+    /// after it (`.debug_info` and so on).
+    pub(super) fn functions<'a>(
+        section: impl Fn(&str) -> Option<&'a [u8]>,
+    ) -> Result<BTreeMap<String, DwarfFunction>, gimli::Error> {
+        let mut funcs = BTreeMap::new();
+        let dwarf = Dwarf::load(|id: SectionId| -> Result<R<'a>, gimli::Error> {
+            Ok(EndianSlice::new(
+                section(id.name()).unwrap_or_default(),
+                LittleEndian,
+            ))
+        })?;
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            // Names of the enclosing scopes of the current entry, along with
+            // the depth at which each scope's children live.
+            let mut scopes: Vec<(isize, String)> = Vec::new();
+            let mut depth = 0;
+            let mut entries = unit.entries();
+            while let Some((delta, entry)) = entries.next_dfs()? {
+                depth += delta;
+                while scopes.last().is_some_and(|(d, _)| *d > depth) {
+                    scopes.pop();
+                }
+                let name = attr_string(&dwarf, &unit, entry, gimli::DW_AT_name)?;
+                match entry.tag() {
+                    gimli::DW_TAG_namespace
+                    | gimli::DW_TAG_structure_type
+                    | gimli::DW_TAG_enumeration_type
+                    | gimli::DW_TAG_union_type => {
+                        if let Some(name) = name {
+                            scopes.push((depth + 1, name));
+                        }
+                    }
+                    gimli::DW_TAG_subprogram => {
+                        let Some(name) = name else {
+                            continue;
+                        };
+                        let linkage_name =
+                            match attr_string(&dwarf, &unit, entry, gimli::DW_AT_linkage_name)? {
+                                Some(n) => Some(n),
+                                None => attr_string(
+                                    &dwarf,
+                                    &unit,
+                                    entry,
+                                    gimli::DW_AT_MIPS_linkage_name,
+                                )?,
+                            };
+                        let qualified_name = match &linkage_name {
+                            Some(n) => demangle(n),
+                            None => scopes
+                                .iter()
+                                .map(|(_, s)| s.as_str())
+                                .chain(std::iter::once(name.as_str()))
+                                .collect::<Vec<_>>()
+                                .join("::"),
+                        };
+                        let func = DwarfFunction {
+                            name: qualified_name,
+                            file: decl_file(&dwarf, &unit, entry)?,
+                            line: entry
+                                .attr_value(gimli::DW_AT_decl_line)?
+                                .and_then(|v| v.udata_value()),
+                        };
+                        // Linkage names are unique; plain names only stand in
+                        // for them when nothing else claimed the name first.
+                        if let Some(linkage_name) = linkage_name {
+                            funcs.insert(linkage_name, func.clone());
+                        }
+                        funcs.entry(name).or_insert(func);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(funcs)
+    }
+
+    fn attr_string(
+        dwarf: &Dwarf<R<'_>>,
+        unit: &Unit<R<'_>>,
+        entry: &DebuggingInformationEntry<R<'_>>,
+        name: gimli::DwAt,
+    ) -> Result<Option<String>, gimli::Error> {
+        let Some(value) = entry.attr_value(name)? else {
+            return Ok(None);
+        };
+        let s = dwarf.attr_string(unit, value)?;
+        Ok(Some(s.to_string_lossy().into_owned()))
+    }
+
+    fn decl_file(
+        dwarf: &Dwarf<R<'_>>,
+        unit: &Unit<R<'_>>,
+        entry: &DebuggingInformationEntry<R<'_>>,
+    ) -> Result<Option<String>, gimli::Error> {
+        let Some(AttributeValue::FileIndex(index)) = entry.attr_value(gimli::DW_AT_decl_file)?
+        else {
+            return Ok(None);
+        };
+        let Some(program) = &unit.line_program else {
+            return Ok(None);
+        };
+        let header = program.header();
+        let Some(file) = header.file(index) else {
+            return Ok(None);
+        };
+        let mut path = String::new();
+        if let Some(dir) = file.directory(header) {
+            let dir = dwarf.attr_string(unit, dir)?.to_string_lossy();
+            if !dir.is_empty() {
+                path.push_str(&dir);
+                path.push('/');
+            }
+        }
+        let name = dwarf.attr_string(unit, file.path_name())?;
+        path.push_str(&name.to_string_lossy());
+        Ok(Some(path))
+    }
+}
+
+impl Host {
+    /// Builds a [WasmBacktrace] out of the contract VM frames currently on the
+    /// context stack. Only meant to be called in debug mode.
+    pub(crate) fn wasm_backtrace(&self) -> Result<WasmBacktrace, HostError> {
+        let mut frames = Vec::new();
+        for ctx in self.try_borrow_context_stack()?.iter() {
+            if let Frame::ContractVM { vm, fn_name, .. } = &ctx.frame {
+                // Read the symbol directly rather than through the `Env`
+                // interface, so as not to show up in traces.
+                let fn_sym = self.scsymbol_from_symbol(*fn_name)?;
+                let fn_str = String::from_utf8_lossy(fn_sym.as_slice());
+                let symbols = WasmSymbols::from_module(&vm.module);
+                let contract_id = vm.contract_id.0.iter().fold(String::new(), |mut s, b| {
+                    let _ = write!(s, "{b:02x}");
+                    s
+                });
+                frames.push(symbols.frame_for_export(contract_id, &fn_str));
+            }
+        }
+        Ok(WasmBacktrace { frames })
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(feature = "next")]
+mod v23 {
+    use k256::ecdsa::Signature;
+    use soroban_env_common::EnvBase;
+    use soroban_env_host::{budget::AsBudget, Env, Host, HostError};
+
+    #[test]
+    fn wycheproof_test() -> Result<(), HostError> {
+        use wycheproof::ecdsa::{TestName::EcdsaSecp256k1Sha256, TestSet};
+        use wycheproof::TestResult;
+
+        let test_set = TestSet::load(EcdsaSecp256k1Sha256).unwrap();
+        let host = Host::test_host();
+        for test_group in test_set.test_groups {
+            let public_key = host.bytes_new_from_slice(&test_group.key.key).unwrap();
+            for test in test_group.tests {
+                // reset the budget for each test case so they don't interfere
+                host.as_budget().reset_default()?;
+                let msg = host.bytes_new_from_slice(test.msg.as_slice())?;
+                let msg_digest = host.compute_hash_sha256(msg)?;
+
+                let sig = match Signature::from_der(&test.sig) {
+                    Ok(s) => s,
+                    Err(_) => {
+                        // If the signature is not a valid DER-encoded one, we skip
+                        // it. Since our host function expects signatures to be
+                        // fixed-witdh bytes encoded, we don't have to validate it.
+                        assert_ne!(test.result, TestResult::Valid);
+                        continue;
+                    }
+                };
+                // Wycheproof tests do not enforce low s but we do, so we need to normalize
+                let sig = sig.normalize_s().unwrap_or(sig);
+                let signature = host.bytes_new_from_slice(&sig.to_bytes())?;
+
+                match host.verify_sig_ecdsa_secp256k1(public_key, msg_digest, signature) {
+                    Ok(_) => assert_eq!(test.result, TestResult::Valid),
+                    // we treat `TestResult::Acceptable` as invalid
+                    Err(_) => {
+                        assert_ne!(test.result, TestResult::Valid)
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn high_s_signature_is_rejected() -> Result<(), HostError> {
+        use wycheproof::ecdsa::{TestName::EcdsaSecp256k1Sha256, TestSet};
+        use wycheproof::TestResult;
+
+        let test_set = TestSet::load(EcdsaSecp256k1Sha256).unwrap();
+        let host = Host::test_host();
+        let test_group = &test_set.test_groups[0];
+        let public_key = host.bytes_new_from_slice(&test_group.key.key)?;
+        let test = test_group
+            .tests
+            .iter()
+            .find(|t| t.result == TestResult::Valid)
+            .unwrap();
+        let msg = host.bytes_new_from_slice(test.msg.as_slice())?;
+        let msg_digest = host.compute_hash_sha256(msg)?;
+        let sig = Signature::from_der(&test.sig).unwrap();
+        let low_s = sig.normalize_s().unwrap_or(sig);
+        let high_s = Signature::from_scalars(low_s.r(), -*low_s.s()).unwrap();
+
+        let signature = host.bytes_new_from_slice(&low_s.to_bytes())?;
+        assert!(host
+            .verify_sig_ecdsa_secp256k1(public_key, msg_digest, signature)
+            .is_ok());
+        let signature = host.bytes_new_from_slice(&high_s.to_bytes())?;
+        assert!(host
+            .verify_sig_ecdsa_secp256k1(public_key, msg_digest, signature)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn verification_is_charged_as_its_own_cost_type() -> Result<(), HostError> {
+        use soroban_env_host::xdr::ContractCostType;
+        use wycheproof::ecdsa::{TestName::EcdsaSecp256k1Sha256, TestSet};
+        use wycheproof::TestResult;
+
+        let test_set = TestSet::load(EcdsaSecp256k1Sha256).unwrap();
+        let host = Host::test_host();
+        let test_group = &test_set.test_groups[0];
+        let public_key = host.bytes_new_from_slice(&test_group.key.key)?;
+        let test = test_group
+            .tests
+            .iter()
+            .find(|t| t.result == TestResult::Valid)
+            .unwrap();
+        let msg = host.bytes_new_from_slice(test.msg.as_slice())?;
+        let msg_digest = host.compute_hash_sha256(msg)?;
+        let sig = Signature::from_der(&test.sig).unwrap();
+        let sig = sig.normalize_s().unwrap_or(sig);
+        let signature = host.bytes_new_from_slice(&sig.to_bytes())?;
+
+        host.as_budget().reset_default()?;
+        host.verify_sig_ecdsa_secp256k1(public_key, msg_digest, signature)?;
+        let budget = host.as_budget();
+        assert_eq!(
+            budget
+                .get_tracker(ContractCostType::VerifyEcdsaSecp256k1Sig)?
+                .iterations,
+            1
+        );
+        assert_eq!(
+            budget
+                .get_tracker(ContractCostType::RecoverEcdsaSecp256k1Key)?
+                .iterations,
+            0
+        );
+        Ok(())
+    }
+}
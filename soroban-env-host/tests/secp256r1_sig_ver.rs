@@ -231,3 +231,56 @@ mod v21 {
         Ok(())
     }
 }
+
+#[cfg(feature = "next")]
+mod v23 {
+    use p256::ecdsa::Signature;
+    use soroban_env_common::{EnvBase, U32Val};
+    use soroban_env_host::{budget::AsBudget, Env, Host, HostError};
+
+    // Recovers the key from each of wycheproof's secp256r1 signatures with
+    // every recovery id. The key of the test group must be recovered for one
+    // of them if, and only if, the signature is valid.
+    #[test]
+    fn wycheproof_recover_test() -> Result<(), HostError> {
+        use wycheproof::ecdsa::{TestName::EcdsaSecp256r1Sha256, TestSet};
+        use wycheproof::TestResult;
+
+        let test_set = TestSet::load(EcdsaSecp256r1Sha256).unwrap();
+        let host = Host::test_host();
+        for test_group in test_set.test_groups {
+            for test in test_group.tests {
+                let sig = match Signature::from_der(&test.sig) {
+                    Ok(s) => s,
+                    Err(_) => {
+                        assert_ne!(test.result, TestResult::Valid);
+                        continue;
+                    }
+                };
+                // Wycheproof tests do not enforce low s but we do, so we need to normalize
+                let sig = sig.normalize_s().unwrap_or(sig);
+                let mut recovered = false;
+                for recovery_id in 0..4 {
+                    // reset the budget for each recovery so they don't interfere
+                    host.as_budget().reset_default()?;
+                    let msg = host.bytes_new_from_slice(test.msg.as_slice())?;
+                    let msg_digest = host.compute_hash_sha256(msg)?;
+                    let signature = host.bytes_new_from_slice(&sig.to_bytes())?;
+                    let Ok(key) = host.recover_key_ecdsa_secp256r1(
+                        msg_digest,
+                        signature,
+                        U32Val::from(recovery_id),
+                    ) else {
+                        continue;
+                    };
+                    let mut buf = [0u8; 65];
+                    host.bytes_copy_to_slice(key, U32Val::from(0), &mut buf)?;
+                    recovered |= buf.as_slice() == test_group.key.key.as_slice();
+                }
+                // we treat `TestResult::Acceptable` as invalid
+                assert_eq!(recovered, test.result == TestResult::Valid);
+            }
+        }
+        Ok(())
+    }
+}
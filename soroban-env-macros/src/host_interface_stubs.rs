@@ -0,0 +1,210 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::fmt::Write;
+use syn::{Error, LitStr};
+
+use crate::{Function, Module, Root, LEDGER_PROTOCOL_VERSION};
+
+// This module generates the host interface described by env.json in two
+// non-Rust forms, for contracts written in other languages: a C/C++ header
+// (also usable from Zig via `@cImport`) and a list of WAT import declarations
+// (usable from hand-written WAT or AssemblyScript `@external` declarations).
+//
+// Both are emitted as string constants rather than files, so that they are
+// always in sync with the env.json the crate was built from; tooling can write
+// them out wherever it needs them.
+
+const GENERATED_NOTE: &str = "Generated from env.json by soroban-env-macros. Do not edit.";
+
+// The names of the Val-encoded types in env.json are also used (with a prefix)
+// for the C typedefs. The two raw integer types are the only non-Val types.
+fn c_type(ty: &str) -> String {
+    match ty {
+        "i64" => "int64_t".to_string(),
+        "u64" => "uint64_t".to_string(),
+        ty => format!("Soroban{ty}"),
+    }
+}
+
+fn protocol_condition(f: &Function) -> Option<String> {
+    match (f.min_supported_protocol, f.max_supported_protocol) {
+        (None, None) => None,
+        (Some(min), None) => Some(format!("SOROBAN_PROTOCOL >= {min}")),
+        (None, Some(max)) => Some(format!("SOROBAN_PROTOCOL <= {max}")),
+        (Some(min), Some(max)) => Some(format!(
+            "SOROBAN_PROTOCOL >= {min} && SOROBAN_PROTOCOL <= {max}"
+        )),
+    }
+}
+
+fn protocol_note(f: &Function) -> Option<String> {
+    match (f.min_supported_protocol, f.max_supported_protocol) {
+        (None, None) => None,
+        (Some(min), None) => Some(format!("supported from protocol {min}")),
+        (None, Some(max)) => Some(format!("supported up to protocol {max}")),
+        (Some(min), Some(max)) => Some(format!("supported from protocol {min} to {max}")),
+    }
+}
+
+fn c_header(root: &Root) -> String {
+    let mut types = root
+        .modules
+        .iter()
+        .flat_map(|m| m.functions.iter())
+        .flat_map(|f| f.args.iter().map(|a| &a.r#type).chain([&f.r#return]))
+        .filter(|ty| !matches!(ty.as_str(), "i64" | "u64" | "Val"))
+        .collect::<Vec<_>>();
+    types.sort();
+    types.dedup();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "/* {GENERATED_NOTE} */");
+    let _ = writeln!(out, "#ifndef SOROBAN_ENV_H");
+    let _ = writeln!(out, "#define SOROBAN_ENV_H");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#include <stdint.h>");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "/* Define SOROBAN_PROTOCOL to target an earlier protocol than the one the"
+    );
+    let _ = writeln!(out, " * header was generated for. */");
+    let _ = writeln!(out, "#ifndef SOROBAN_PROTOCOL");
+    let _ = writeln!(out, "#define SOROBAN_PROTOCOL {LEDGER_PROTOCOL_VERSION}");
+    let _ = writeln!(out, "#endif");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#if defined(__clang__)");
+    let _ = writeln!(
+        out,
+        "#define SOROBAN_IMPORT(mod, name) __attribute__((import_module(mod), import_name(name)))"
+    );
+    let _ = writeln!(out, "#else");
+    let _ = writeln!(out, "#define SOROBAN_IMPORT(mod, name)");
+    let _ = writeln!(out, "#endif");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#ifdef __cplusplus");
+    let _ = writeln!(out, "extern \"C\" {{");
+    let _ = writeln!(out, "#endif");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "/* Every value crossing the host interface is a 64-bit `Val`: a tagged"
+    );
+    let _ = writeln!(
+        out,
+        " * union whose low 8 bits identify its type. The named types below are"
+    );
+    let _ = writeln!(
+        out,
+        " * the subsets of `Val` that host functions accept or return. */"
+    );
+    let _ = writeln!(out, "typedef uint64_t SorobanVal;");
+    for ty in types {
+        let _ = writeln!(out, "typedef SorobanVal Soroban{ty};");
+    }
+    for m in root.modules.iter() {
+        c_module(&mut out, m);
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#ifdef __cplusplus");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out, "#endif");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#endif /* SOROBAN_ENV_H */");
+    out
+}
+
+fn c_module(out: &mut String, m: &Module) {
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "/* Module `{}`, imported as \"{}\". */",
+        m.name, m.export
+    );
+    for f in m.functions.iter() {
+        let _ = writeln!(out);
+        let mut comment = f.docs.clone().unwrap_or_default().replace("*/", "* /");
+        if let Some(note) = protocol_note(f) {
+            if !comment.is_empty() {
+                comment.push(' ');
+            }
+            comment.push_str(&format!("({note})"));
+        }
+        if !comment.is_empty() {
+            let _ = writeln!(out, "/* {comment} */");
+        }
+        let cond = protocol_condition(f);
+        if let Some(cond) = &cond {
+            let _ = writeln!(out, "#if {cond}");
+        }
+        let args = if f.args.is_empty() {
+            "void".to_string()
+        } else {
+            f.args
+                .iter()
+                .map(|a| format!("{} {}", c_type(&a.r#type), a.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let _ = writeln!(
+            out,
+            "SOROBAN_IMPORT(\"{}\", \"{}\") {} soroban_{}({});",
+            m.export,
+            f.export,
+            c_type(&f.r#return),
+            f.name,
+            args
+        );
+        if cond.is_some() {
+            let _ = writeln!(out, "#endif");
+        }
+    }
+}
+
+fn wat_imports(root: &Root) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, ";; {GENERATED_NOTE}");
+    let _ = writeln!(
+        out,
+        ";; Host function imports for protocol {LEDGER_PROTOCOL_VERSION}. Every parameter and"
+    );
+    let _ = writeln!(
+        out,
+        ";; result is a 64-bit `Val` (or a raw i64/u64 where the C header says so)."
+    );
+    for m in root.modules.iter() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, ";; module `{}`", m.name);
+        for f in m.functions.iter() {
+            if let Some(note) = protocol_note(f) {
+                let _ = writeln!(out, ";; {}: {note}", f.name);
+            }
+            let params = if f.args.is_empty() {
+                String::new()
+            } else {
+                format!(" (param{})", " i64".repeat(f.args.len()))
+            };
+            let _ = writeln!(
+                out,
+                "(import \"{}\" \"{}\" (func ${}{} (result i64)))",
+                m.export, f.export, f.name, params
+            );
+        }
+    }
+    out
+}
+
+pub fn generate(file_lit: LitStr) -> Result<TokenStream, Error> {
+    let root: Root = crate::load_env_file(file_lit)?;
+    let header = LitStr::new(&c_header(&root), proc_macro2::Span::call_site());
+    let wat = LitStr::new(&wat_imports(&root), proc_macro2::Span::call_site());
+    Ok(quote! {
+        /// A C/C++ header declaring every host function, with the Wasm
+        /// import module and name of each and preprocessor guards for
+        /// protocol-gated functions.
+        pub const C_HEADER: &str = #header;
+
+        /// WAT import declarations for every host function.
+        pub const WAT_IMPORTS: &str = #wat;
+    })
+}
@@ -1,4 +1,5 @@
 mod call_macro_with_all_host_functions;
+mod host_interface_stubs;
 mod path;
 mod synth_dispatch_host_fn_tests;
 mod synth_linear_memory_tests;
@@ -160,6 +161,15 @@ pub fn generate_call_macro_with_all_host_functions(input: TokenStream) -> TokenS
     }
 }
 
+#[proc_macro]
+pub fn generate_host_interface_stubs(input: TokenStream) -> TokenStream {
+    let file = parse_macro_input!(input as LitStr);
+    match host_interface_stubs::generate(file) {
+        Ok(t) => t.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[proc_macro]
 pub fn generate_synth_wasm_expr_type(input: TokenStream) -> TokenStream {
     let file = parse_macro_input!(input as LitStr);
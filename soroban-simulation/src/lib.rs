@@ -1,7 +1,9 @@
+pub mod parallel;
 pub mod simulation;
 pub use network_config::NetworkConfig;
 pub use snapshot_source::AutoRestoringSnapshotSource;
 pub use snapshot_source::SnapshotSourceWithArchive;
+pub use snapshot_source::{SyncEntryWithLiveUntil, SyncSnapshotSource, SyncSnapshotSourceAdapter};
 mod network_config;
 mod snapshot_source;
 
@@ -0,0 +1,565 @@
+//! Footprint-based conflict analysis for applying transactions in parallel.
+//!
+//! Two transactions conflict when one of them writes a ledger key that the
+//! other one reads or writes, according to their declared footprints.
+//! Transactions that don't conflict, directly or through other
+//! transactions, can be applied concurrently without changing the result of
+//! applying them sequentially.
+//!
+//! Transactions may extend the TTL of the entries in their read-only
+//! footprint. Such extensions don't make transactions conflict: TTLs can
+//! only be extended, so applying the extensions in any order results in the
+//! maximum of the extended live_until ledgers, which is what the changes of
+//! independently applied transactions are merged into.
+//!
+//! This also provides drivers for running independent invocations on
+//! multiple threads, each with its own host, that share a read-only
+//! `SyncSnapshotSource` and, optionally, an immutable `SharedModuleCache`.
+
+use crate::network_config::NetworkConfig;
+use crate::simulation::{
+    simulate_invoke_host_function_op_with_module_cache, InvokeHostFunctionSimulationResult,
+    SimulationAdjustmentConfig,
+};
+use crate::snapshot_source::{
+    SyncEntryWithLiveUntil, SyncSnapshotSource, SyncSnapshotSourceAdapter,
+};
+use anyhow::{bail, Context, Result};
+use soroban_env_host::{
+    budget::Budget,
+    storage::{EntryWithLiveUntil, SnapshotSource, Storage},
+    vm::SharedModuleCache,
+    xdr::{
+        AccountId, ContractCodeEntry, HostFunction, LedgerFootprint, LedgerKey,
+        SorobanAuthorizationEntry,
+    },
+    Host, HostError, LedgerInfo,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Ledger changes made by one or more transactions, keyed by the modified
+/// ledger key. `None` means that the entry has been removed.
+pub type LedgerChanges = BTreeMap<Rc<LedgerKey>, Option<EntryWithLiveUntil>>;
+
+/// `LedgerChanges` that can be sent between threads.
+type SyncLedgerChanges = Vec<(Arc<LedgerKey>, Option<SyncEntryWithLiveUntil>)>;
+
+#[derive(Default)]
+struct KeyAccesses {
+    readers: Vec<usize>,
+    writers: Vec<usize>,
+}
+
+/// Read/write conflict graph of a set of transactions, built from their
+/// footprints. Transactions are identified by their index in the set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConflictGraph {
+    conflicts: Vec<BTreeSet<usize>>,
+}
+
+impl ConflictGraph {
+    /// Builds the conflict graph of the transactions with the given
+    /// footprints. A key that is both in the read-only and read-write
+    /// footprint of a transaction is considered written. Read-only keys
+    /// don't cause conflicts, even when their TTL is extended.
+    pub fn new(footprints: &[LedgerFootprint]) -> Self {
+        let mut accesses: BTreeMap<&LedgerKey, KeyAccesses> = BTreeMap::new();
+        for (tx, footprint) in footprints.iter().enumerate() {
+            for key in footprint.read_write.iter() {
+                accesses.entry(key).or_default().writers.push(tx);
+            }
+            for key in footprint.read_only.iter() {
+                if !footprint.read_write.contains(key) {
+                    accesses.entry(key).or_default().readers.push(tx);
+                }
+            }
+        }
+        let mut conflicts = vec![BTreeSet::new(); footprints.len()];
+        for access in accesses.values() {
+            for (i, &writer) in access.writers.iter().enumerate() {
+                for &other in access.writers[i + 1..].iter().chain(access.readers.iter()) {
+                    if other != writer {
+                        conflicts[writer].insert(other);
+                        conflicts[other].insert(writer);
+                    }
+                }
+            }
+        }
+        Self { conflicts }
+    }
+
+    /// Returns the number of transactions in the graph.
+    pub fn tx_count(&self) -> usize {
+        self.conflicts.len()
+    }
+
+    /// Returns the transactions that conflict with `tx`.
+    pub fn conflicts(&self, tx: usize) -> &BTreeSet<usize> {
+        &self.conflicts[tx]
+    }
+
+    /// Partitions the transactions into clusters such that no two
+    /// transactions from different clusters conflict. The clusters are the
+    /// connected components of the graph: every cluster can be applied
+    /// independently of the others, as long as the transactions within it
+    /// are applied in order.
+    ///
+    /// Each cluster is sorted, and the clusters are sorted by their first
+    /// transaction.
+    pub fn clusters(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.tx_count()];
+        let mut clusters = vec![];
+        for start in 0..self.tx_count() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut cluster = vec![];
+            let mut pending = vec![start];
+            while let Some(tx) = pending.pop() {
+                cluster.push(tx);
+                for &other in self.conflicts[tx].iter() {
+                    if !visited[other] {
+                        visited[other] = true;
+                        pending.push(other);
+                    }
+                }
+            }
+            cluster.sort_unstable();
+            clusters.push(cluster);
+        }
+        clusters
+    }
+
+    /// Partitions the transactions into stages such that no two
+    /// transactions from the same stage conflict. Applying the stages in
+    /// order, with the transactions of each stage applied concurrently,
+    /// gives the same result as applying all the transactions in order:
+    /// every transaction is placed in the stage right after the last stage
+    /// that contains a conflicting transaction preceding it.
+    ///
+    /// Each stage is sorted.
+    pub fn stages(&self) -> Vec<Vec<usize>> {
+        let mut tx_stage = vec![0; self.tx_count()];
+        let mut stages: Vec<Vec<usize>> = vec![];
+        for tx in 0..self.tx_count() {
+            let stage = self.conflicts[tx]
+                .range(..tx)
+                .map(|&other| tx_stage[other] + 1)
+                .max()
+                .unwrap_or(0);
+            tx_stage[tx] = stage;
+            if stage == stages.len() {
+                stages.push(vec![]);
+            }
+            stages[stage].push(tx);
+        }
+        stages
+    }
+}
+
+/// `SnapshotSource` that returns the entries modified by the transactions
+/// applied so far on top of a base snapshot.
+struct OverlaySnapshotSource {
+    base: Rc<dyn SnapshotSource>,
+    changes: RefCell<LedgerChanges>,
+}
+
+impl SnapshotSource for OverlaySnapshotSource {
+    fn get(&self, key: &Rc<LedgerKey>) -> Result<Option<EntryWithLiveUntil>, HostError> {
+        if let Some(entry) = self.changes.borrow().get(key) {
+            return Ok(entry.clone());
+        }
+        self.base.get(key)
+    }
+}
+
+// Returns whether `after` is the same entry as `before` with an extended
+// TTL.
+fn is_ttl_extension(
+    before: &Option<EntryWithLiveUntil>,
+    after: &Option<EntryWithLiveUntil>,
+) -> bool {
+    match (before, after) {
+        (Some((before, Some(before_live_until))), Some((after, Some(after_live_until)))) => {
+            before == after && after_live_until >= before_live_until
+        }
+        _ => false,
+    }
+}
+
+// Checks that the transaction has only modified the keys in its read-write
+// footprint, or extended the TTL of the keys in its read-only footprint.
+// `snapshot` is the state the transaction has been applied to.
+fn check_changes_within_footprint(
+    tx: usize,
+    footprint: &LedgerFootprint,
+    snapshot: &dyn SnapshotSource,
+    changes: &LedgerChanges,
+) -> Result<()> {
+    for (key, entry) in changes.iter() {
+        if footprint.read_write.contains(key.as_ref()) {
+            continue;
+        }
+        if !footprint.read_only.contains(key.as_ref()) {
+            bail!("transaction {tx} modified key outside of its read-write footprint: {key:?}");
+        }
+        if !is_ttl_extension(&snapshot.get(key)?, entry) {
+            bail!(
+                "transaction {tx} modified read-only key other than by extending its TTL: {key:?}"
+            );
+        }
+    }
+    Ok(())
+}
+
+// Applies the transactions of the cluster in order on top of `snapshot` and
+// returns their combined changes.
+fn apply_cluster<F>(
+    snapshot: Rc<dyn SnapshotSource>,
+    footprints: &[LedgerFootprint],
+    cluster: &[usize],
+    apply_tx: &mut F,
+) -> Result<LedgerChanges>
+where
+    F: FnMut(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges>,
+{
+    let overlay = Rc::new(OverlaySnapshotSource {
+        base: snapshot,
+        changes: RefCell::new(LedgerChanges::new()),
+    });
+    for &tx in cluster {
+        let Some(footprint) = footprints.get(tx) else {
+            bail!("transaction {tx} doesn't have a footprint");
+        };
+        let changes = apply_tx(tx, overlay.clone())?;
+        check_changes_within_footprint(tx, footprint, overlay.as_ref(), &changes)?;
+        overlay.changes.borrow_mut().extend(changes);
+    }
+    Ok(overlay.changes.take())
+}
+
+/// Changes of independently applied clusters merged together.
+#[derive(Default)]
+struct MergedChanges {
+    changes: LedgerChanges,
+    // Keys of `changes` whose TTL has only been extended.
+    extended: BTreeSet<Rc<LedgerKey>>,
+}
+
+impl MergedChanges {
+    // Merges the changes of the cluster. A key may only be changed by more
+    // than one cluster if none of them writes it, in which case the changes
+    // are TTL extensions of the same entry and the maximum one is kept.
+    fn merge(
+        &mut self,
+        footprints: &[LedgerFootprint],
+        cluster: &[usize],
+        changes: LedgerChanges,
+    ) -> Result<()> {
+        let written: BTreeSet<&LedgerKey> = cluster
+            .iter()
+            .filter_map(|&tx| footprints.get(tx))
+            .flat_map(|footprint| footprint.read_write.iter())
+            .collect();
+        for (key, entry) in changes {
+            let extended = !written.contains(key.as_ref());
+            let Some(merged_entry) = self.changes.get_mut(&key) else {
+                if extended {
+                    self.extended.insert(key.clone());
+                }
+                self.changes.insert(key, entry);
+                continue;
+            };
+            if !extended || !self.extended.contains(&key) {
+                bail!("key modified by more than one cluster: {key:?}");
+            }
+            if let (Some((_, Some(live_until))), Some((_, Some(merged_live_until)))) =
+                (&entry, &*merged_entry)
+            {
+                if live_until > merged_live_until {
+                    *merged_entry = entry;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies every cluster of transactions to its own view of `snapshot`,
+/// and returns the merged changes of all the clusters.
+///
+/// The transactions within a cluster are applied in order by calling
+/// `apply_tx` with the transaction index and a snapshot that includes the
+/// changes of the preceding transactions of the cluster. `apply_tx` must
+/// return the changes made by the transaction, which have to be within its
+/// read-write footprint, except for TTL extensions of the entries in its
+/// read-only footprint. Fails if two clusters modify the same key, unless
+/// both of them only extend its TTL, in which case the maximum extension is
+/// kept.
+pub fn apply_clusters<F>(
+    snapshot: Rc<dyn SnapshotSource>,
+    footprints: &[LedgerFootprint],
+    clusters: &[Vec<usize>],
+    mut apply_tx: F,
+) -> Result<LedgerChanges>
+where
+    F: FnMut(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges>,
+{
+    let mut merged = MergedChanges::default();
+    for cluster in clusters {
+        let changes = apply_cluster(snapshot.clone(), footprints, cluster, &mut apply_tx)?;
+        merged.merge(footprints, cluster, changes)?;
+    }
+    Ok(merged.changes)
+}
+
+/// Same as [`apply_clusters`], but applies the clusters on up to
+/// `thread_count` threads, each with its own view of the shared `snapshot`.
+///
+/// `apply_tx` is called from multiple threads concurrently, and the changes
+/// are merged in the order of the clusters, so the result doesn't depend on
+/// the thread scheduling.
+pub fn apply_clusters_on_threads<F>(
+    snapshot: Arc<dyn SyncSnapshotSource>,
+    footprints: &[LedgerFootprint],
+    clusters: &[Vec<usize>],
+    thread_count: NonZeroUsize,
+    apply_tx: F,
+) -> Result<LedgerChanges>
+where
+    F: Fn(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges> + Sync,
+{
+    let cluster_changes = run_on_threads(clusters, thread_count, |cluster| {
+        let base = Rc::new(SyncSnapshotSourceAdapter::new(snapshot.clone()));
+        apply_cluster(base, footprints, cluster, &mut &apply_tx).map(to_sync_changes)
+    });
+    let mut merged = MergedChanges::default();
+    for (cluster, changes) in clusters.iter().zip(cluster_changes) {
+        merged.merge(footprints, cluster, from_sync_changes(changes?))?;
+    }
+    Ok(merged.changes)
+}
+
+fn to_sync_changes(changes: LedgerChanges) -> SyncLedgerChanges {
+    changes
+        .into_iter()
+        .map(|(key, entry)| {
+            (
+                Arc::new(Rc::unwrap_or_clone(key)),
+                entry.map(|(entry, live_until)| (Arc::new(Rc::unwrap_or_clone(entry)), live_until)),
+            )
+        })
+        .collect()
+}
+
+fn from_sync_changes(changes: SyncLedgerChanges) -> LedgerChanges {
+    changes
+        .into_iter()
+        .map(|(key, entry)| {
+            (
+                Rc::new(Arc::unwrap_or_clone(key)),
+                entry.map(|(entry, live_until)| (Rc::new(Arc::unwrap_or_clone(entry)), live_until)),
+            )
+        })
+        .collect()
+}
+
+/// Applies all the transactions in order on top of `snapshot`. See
+/// [`apply_clusters`] for the requirements on `apply_tx`.
+pub fn apply_sequentially<F>(
+    snapshot: Rc<dyn SnapshotSource>,
+    footprints: &[LedgerFootprint],
+    apply_tx: F,
+) -> Result<LedgerChanges>
+where
+    F: FnMut(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges>,
+{
+    let all_txs: Vec<usize> = (0..footprints.len()).collect();
+    apply_clusters(snapshot, footprints, &[all_txs], apply_tx)
+}
+
+/// Applies the transactions as independent clusters built from their
+/// footprints and verifies that the merged changes are the same as the
+/// changes of applying them sequentially. Returns the changes on success.
+///
+/// `apply_tx` is called twice for every transaction, so it must be
+/// deterministic. A mismatch means that some transaction has accessed a key
+/// outside of its declared footprint.
+pub fn apply_clusters_and_verify<F>(
+    snapshot: Rc<dyn SnapshotSource>,
+    footprints: &[LedgerFootprint],
+    mut apply_tx: F,
+) -> Result<LedgerChanges>
+where
+    F: FnMut(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges>,
+{
+    let clusters = ConflictGraph::new(footprints).clusters();
+    let parallel = apply_clusters(snapshot.clone(), footprints, &clusters, &mut apply_tx)?;
+    let sequential = apply_sequentially(snapshot, footprints, &mut apply_tx)?;
+    check_same_changes(&parallel, &sequential)?;
+    Ok(parallel)
+}
+
+/// Same as [`apply_clusters_and_verify`], but applies the clusters on up to
+/// `thread_count` threads with [`apply_clusters_on_threads`]. The sequential
+/// application that their merged changes are verified against runs on the
+/// calling thread.
+pub fn apply_clusters_on_threads_and_verify<F>(
+    snapshot: Arc<dyn SyncSnapshotSource>,
+    footprints: &[LedgerFootprint],
+    thread_count: NonZeroUsize,
+    apply_tx: F,
+) -> Result<LedgerChanges>
+where
+    F: Fn(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges> + Sync,
+{
+    let clusters = ConflictGraph::new(footprints).clusters();
+    let parallel = apply_clusters_on_threads(
+        snapshot.clone(),
+        footprints,
+        &clusters,
+        thread_count,
+        &apply_tx,
+    )?;
+    let sequential = apply_sequentially(
+        Rc::new(SyncSnapshotSourceAdapter::new(snapshot)),
+        footprints,
+        &apply_tx,
+    )?;
+    check_same_changes(&parallel, &sequential)?;
+    Ok(parallel)
+}
+
+fn check_same_changes(parallel: &LedgerChanges, sequential: &LedgerChanges) -> Result<()> {
+    if parallel != sequential {
+        let keys: BTreeSet<&Rc<LedgerKey>> = parallel.keys().chain(sequential.keys()).collect();
+        for key in keys {
+            if parallel.get(key) != sequential.get(key) {
+                bail!("clustered and sequential application differ for key {key:?}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Calls `f` for every input on a pool of up to `thread_count` threads and
+/// returns the outputs in the order of the inputs.
+///
+/// Hosts can't be shared between threads, so every call of `f` has to run
+/// its own host. Panics if any call of `f` panics.
+pub fn run_on_threads<I, O, F>(inputs: &[I], thread_count: NonZeroUsize, f: F) -> Vec<O>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&I) -> O + Sync,
+{
+    let next_input = AtomicUsize::new(0);
+    let mut outputs: Vec<Option<O>> = (0..inputs.len()).map(|_| None).collect();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..thread_count.get().min(inputs.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut worker_outputs = vec![];
+                    loop {
+                        let i = next_input.fetch_add(1, Ordering::Relaxed);
+                        let Some(input) = inputs.get(i) else {
+                            break;
+                        };
+                        worker_outputs.push((i, f(input)));
+                    }
+                    worker_outputs
+                })
+            })
+            .collect();
+        for worker in workers {
+            match worker.join() {
+                Ok(worker_outputs) => {
+                    for (i, output) in worker_outputs {
+                        outputs[i] = Some(output);
+                    }
+                }
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    });
+    outputs
+        .into_iter()
+        .map(|output| output.expect("every input must have been processed"))
+        .collect()
+}
+
+/// Input of a single simulation run by
+/// [`simulate_invoke_host_function_ops_on_threads`]. See
+/// [`simulate_invoke_host_function_op`] for the meaning of the fields.
+#[derive(Clone, Debug)]
+pub struct InvokeHostFunctionSimulationInput {
+    pub host_fn: HostFunction,
+    pub auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+    pub source_account: AccountId,
+    pub base_prng_seed: [u8; 32],
+}
+
+/// Parses the contract code entries into a `SharedModuleCache` for the
+/// simulations with the given network configuration and ledger, to be shared
+/// by [`simulate_invoke_host_function_ops_on_threads`].
+///
+/// The parsing isn't a part of any transaction, so it's not limited by the
+/// per-transaction network limits.
+pub fn build_shared_module_cache(
+    network_config: &NetworkConfig,
+    ledger_info: &LedgerInfo,
+    contracts: &[ContractCodeEntry],
+) -> Result<SharedModuleCache> {
+    let budget = Budget::try_from_configs(
+        u64::MAX,
+        u64::MAX,
+        network_config.cpu_cost_params.clone(),
+        network_config.memory_cost_params.clone(),
+    )
+    .context("cannot create budget from network configuration")?;
+    let host = Host::with_storage_and_budget(Storage::default(), budget);
+    host.set_ledger_info(ledger_info.clone())?;
+    Ok(SharedModuleCache::new(&host, contracts)?)
+}
+
+/// Simulates independent `InvokeHostFunctionOp` operations on up to
+/// `thread_count` threads, all against the same ledger state, and returns
+/// the results in the order of `inputs`.
+///
+/// Every simulation runs `simulate_invoke_host_function_op` with its own
+/// host, so the results are the same as when running the simulations one by
+/// one. When `module_cache` is provided, the hosts take the contracts it
+/// contains from it instead of parsing them again; the parsing is still
+/// charged to every host, so the simulated resources don't change.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_invoke_host_function_ops_on_threads(
+    snapshot_source: Arc<dyn SyncSnapshotSource>,
+    network_config: &NetworkConfig,
+    adjustment_config: &SimulationAdjustmentConfig,
+    ledger_info: &LedgerInfo,
+    inputs: &[InvokeHostFunctionSimulationInput],
+    enable_diagnostics: bool,
+    module_cache: Option<&SharedModuleCache>,
+    thread_count: NonZeroUsize,
+) -> Vec<Result<InvokeHostFunctionSimulationResult>> {
+    run_on_threads(inputs, thread_count, |input| {
+        simulate_invoke_host_function_op_with_module_cache(
+            Rc::new(SyncSnapshotSourceAdapter::new(snapshot_source.clone())),
+            network_config,
+            adjustment_config,
+            ledger_info,
+            input.host_fn.clone(),
+            input.auth_entries.clone(),
+            &input.source_account,
+            input.base_prng_seed,
+            enable_diagnostics,
+            module_cache.cloned(),
+        )
+    })
+}
@@ -8,9 +8,10 @@ use crate::snapshot_source::{
 };
 use anyhow::Result;
 use soroban_env_host::{
-    e2e_invoke::invoke_host_function_in_recording_mode,
+    e2e_invoke::invoke_host_function_in_recording_mode_with_module_cache,
     e2e_invoke::LedgerEntryChange,
     storage::SnapshotSource,
+    vm::SharedModuleCache,
     xdr::{
         AccountId, ContractEvent, DiagnosticEvent, HostFunction, InvokeHostFunctionOp, LedgerKey,
         OperationBody, ScVal, SorobanAuthorizationEntry, SorobanResources, SorobanTransactionData,
@@ -126,11 +127,42 @@ pub fn simulate_invoke_host_function_op(
     source_account: &AccountId,
     base_prng_seed: [u8; 32],
     enable_diagnostics: bool,
+) -> Result<InvokeHostFunctionSimulationResult> {
+    simulate_invoke_host_function_op_with_module_cache(
+        snapshot_source,
+        network_config,
+        adjustment_config,
+        ledger_info,
+        host_fn,
+        auth_entries,
+        source_account,
+        base_prng_seed,
+        enable_diagnostics,
+        None,
+    )
+}
+
+/// Same as `simulate_invoke_host_function_op`, but takes the parsed
+/// contracts from `module_cache` instead of parsing them, if provided. The
+/// parsing is still accounted for in the simulated resources, so the result
+/// is the same as without the cache.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_invoke_host_function_op_with_module_cache(
+    snapshot_source: Rc<dyn SnapshotSource>,
+    network_config: &NetworkConfig,
+    adjustment_config: &SimulationAdjustmentConfig,
+    ledger_info: &LedgerInfo,
+    host_fn: HostFunction,
+    auth_entries: Option<Vec<SorobanAuthorizationEntry>>,
+    source_account: &AccountId,
+    base_prng_seed: [u8; 32],
+    enable_diagnostics: bool,
+    module_cache: Option<SharedModuleCache>,
 ) -> Result<InvokeHostFunctionSimulationResult> {
     let snapshot_source = Rc::new(SimulationSnapshotSource::new_from_rc(snapshot_source));
     let budget = network_config.create_budget()?;
     let mut diagnostic_events = vec![];
-    let recording_result = invoke_host_function_in_recording_mode(
+    let recording_result = invoke_host_function_in_recording_mode_with_module_cache(
         &budget,
         enable_diagnostics,
         &host_fn,
@@ -140,6 +172,7 @@ pub fn simulate_invoke_host_function_op(
         snapshot_source.clone(),
         base_prng_seed,
         &mut diagnostic_events,
+        module_cache,
     );
     let invoke_result = match &recording_result {
         Ok(r) => r.invoke_result.clone(),
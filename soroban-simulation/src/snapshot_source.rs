@@ -11,12 +11,13 @@ use soroban_env_host::xdr::{
 use soroban_env_host::{
     ledger_info::get_key_durability,
     storage::{EntryWithLiveUntil, SnapshotSource},
-    xdr::{ContractDataDurability, LedgerKey, ScErrorCode, ScErrorType},
+    xdr::{ContractDataDurability, LedgerEntry, LedgerKey, ScErrorCode, ScErrorType},
     HostError, LedgerInfo,
 };
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
 use std::rc::Rc;
+use std::sync::Arc;
 
 /// Read-only ledger snapshot accessor that also has access
 /// to archived entries.
@@ -121,6 +122,50 @@ impl<T: SnapshotSourceWithArchive> SnapshotSource for AutoRestoringSnapshotSourc
     }
 }
 
+/// Ledger entry with its live_until ledger, as returned by
+/// `SyncSnapshotSource`.
+pub type SyncEntryWithLiveUntil = (Arc<LedgerEntry>, Option<u32>);
+
+/// Read-only ledger snapshot accessor that can be shared between threads.
+///
+/// `SnapshotSource` returns `Rc`-based entries, so it can only be used by
+/// hosts running on a single thread. This returns `Arc`-based entries instead,
+/// and can be turned into a `SnapshotSource` for every host with
+/// `SyncSnapshotSourceAdapter`.
+pub trait SyncSnapshotSource: Send + Sync {
+    /// Returns the ledger entry for the key and its live_until ledger if entry
+    /// exists, or `None` otherwise. Like `SnapshotSource`, this must only
+    /// return live entries.
+    fn get(
+        &self,
+        key: &LedgerKey,
+    ) -> std::result::Result<Option<SyncEntryWithLiveUntil>, HostError>;
+}
+
+/// The `SnapshotSource` implementation backed by a `SyncSnapshotSource`
+/// shared between threads.
+///
+/// The entries are cloned into `Rc`s when they are accessed, which the host
+/// does at most once per key and invocation.
+pub struct SyncSnapshotSourceAdapter {
+    snapshot_source: Arc<dyn SyncSnapshotSource>,
+}
+
+impl SyncSnapshotSourceAdapter {
+    pub fn new(snapshot_source: Arc<dyn SyncSnapshotSource>) -> Self {
+        Self { snapshot_source }
+    }
+}
+
+impl SnapshotSource for SyncSnapshotSourceAdapter {
+    fn get(&self, key: &Rc<LedgerKey>) -> Result<Option<EntryWithLiveUntil>, HostError> {
+        Ok(self
+            .snapshot_source
+            .get(key)?
+            .map(|(entry, live_until)| (Rc::new((*entry).clone()), live_until)))
+    }
+}
+
 #[derive(Default)]
 struct LedgerEntryUpdater {
     updated_entries_cache: BTreeMap<Rc<LedgerKey>, Option<EntryWithLiveUntil>>,
@@ -1,3 +1,4 @@
 mod network_config;
+mod parallel;
 mod simulation;
 mod snapshot_source;
@@ -0,0 +1,668 @@
+use crate::parallel::{
+    apply_clusters, apply_clusters_and_verify, apply_clusters_on_threads,
+    apply_clusters_on_threads_and_verify, apply_sequentially, run_on_threads, ConflictGraph,
+    LedgerChanges,
+};
+use crate::testutils::{ledger_entry_to_ledger_key, MockSnapshotSource, MockSyncSnapshotSource};
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use sha2::{Digest, Sha256};
+use soroban_env_host::budget::Budget;
+use soroban_env_host::e2e_invoke::invoke_host_function;
+use soroban_env_host::e2e_testutils::{
+    default_ledger_info, get_account_id, ledger_entry, CreateContractData, DEFAULT_LEDGER_SEQ,
+};
+use soroban_env_host::storage::{EntryWithLiveUntil, SnapshotSource};
+use soroban_env_host::xdr::{
+    ContractDataDurability, ContractDataEntry, ExtensionPoint, Hash, HostFunction,
+    InvokeContractArgs, LedgerEntry, LedgerEntryData, LedgerFootprint, LedgerKey,
+    LedgerKeyContractData, Limits, ReadXdr, ScAddress, ScBytes, ScVal, SorobanResources, TtlEntry,
+    WriteXdr,
+};
+use soroban_test_wasms::CONTRACT_STORAGE;
+use std::collections::BTreeSet;
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::Arc;
+
+const LEDGER_SEQ: u32 = 100;
+const LIVE_UNTIL: u32 = 1000;
+
+fn counter_entry(id: u8, value: u32) -> LedgerEntry {
+    ledger_entry(LedgerEntryData::ContractData(ContractDataEntry {
+        ext: ExtensionPoint::V0,
+        contract: ScAddress::Contract(Hash([0; 32])),
+        key: ScVal::Bytes(ScBytes([id].try_into().unwrap())),
+        durability: ContractDataDurability::Persistent,
+        val: ScVal::U32(value),
+    }))
+}
+
+fn counter_key(id: u8) -> LedgerKey {
+    ledger_entry_to_ledger_key(&counter_entry(id, 0)).unwrap()
+}
+
+fn counter_value(snapshot: &dyn SnapshotSource, id: u8) -> u32 {
+    match snapshot.get(&Rc::new(counter_key(id))).unwrap() {
+        Some((entry, _)) => match &entry.data {
+            LedgerEntryData::ContractData(ContractDataEntry {
+                val: ScVal::U32(v), ..
+            }) => *v,
+            _ => panic!("unexpected entry {entry:?}"),
+        },
+        None => 0,
+    }
+}
+
+fn footprint(read_only: &[u8], read_write: &[u8]) -> LedgerFootprint {
+    LedgerFootprint {
+        read_only: read_only
+            .iter()
+            .map(|id| counter_key(*id))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap(),
+        read_write: read_write
+            .iter()
+            .map(|id| counter_key(*id))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap(),
+    }
+}
+
+fn snapshot(values: &[(u8, u32)]) -> Rc<dyn SnapshotSource> {
+    Rc::new(
+        MockSnapshotSource::from_entries(
+            values
+                .iter()
+                .map(|(id, v)| (counter_entry(*id, *v), Some(LIVE_UNTIL)))
+                .collect(),
+            LEDGER_SEQ,
+        )
+        .unwrap(),
+    )
+}
+
+// Reads the counters in `reads` and writes their sum plus `tx + 1` to every
+// counter in `writes`.
+fn apply_counters_tx(
+    tx: usize,
+    snapshot: &dyn SnapshotSource,
+    reads: &[u8],
+    writes: &[u8],
+) -> LedgerChanges {
+    let sum: u32 = reads.iter().map(|id| counter_value(snapshot, *id)).sum();
+    writes
+        .iter()
+        .map(|id| {
+            let entry = counter_entry(*id, sum + tx as u32 + 1);
+            (
+                Rc::new(counter_key(*id)),
+                Some((Rc::new(entry), Some(LIVE_UNTIL))),
+            )
+        })
+        .collect()
+}
+
+// Applies a transaction that accesses exactly its footprint.
+fn apply_footprint_tx(
+    footprints: &[(Vec<u8>, Vec<u8>)],
+) -> impl FnMut(usize, Rc<dyn SnapshotSource>) -> Result<LedgerChanges> + '_ {
+    move |tx, snapshot| {
+        let (read_only, read_write) = &footprints[tx];
+        let reads: Vec<u8> = read_only.iter().chain(read_write.iter()).copied().collect();
+        Ok(apply_counters_tx(tx, snapshot.as_ref(), &reads, read_write))
+    }
+}
+
+// Extends the TTL of the counter to `live_until` if it's currently lower.
+fn extend_counter_ttl(
+    snapshot: &dyn SnapshotSource,
+    id: u8,
+    live_until: u32,
+) -> Option<(Rc<LedgerKey>, Option<EntryWithLiveUntil>)> {
+    let key = Rc::new(counter_key(id));
+    match snapshot.get(&key).unwrap() {
+        Some((entry, Some(current))) if current < live_until => {
+            Some((key, Some((entry, Some(live_until)))))
+        }
+        _ => None,
+    }
+}
+
+fn to_footprints(footprints: &[(Vec<u8>, Vec<u8>)]) -> Vec<LedgerFootprint> {
+    footprints
+        .iter()
+        .map(|(ro, rw)| footprint(ro, rw))
+        .collect()
+}
+
+#[test]
+fn test_conflict_graph() {
+    let footprints = [
+        footprint(&[1], &[2]),
+        // Only shares a read-only key with tx 0.
+        footprint(&[1], &[3]),
+        // Reads the key written by tx 0.
+        footprint(&[2], &[4]),
+        // Writes the key written by tx 1.
+        footprint(&[], &[3]),
+        footprint(&[5], &[]),
+        // Writes the key read by txs 0 and 1 and reads the key written by
+        // tx 2.
+        footprint(&[4], &[1]),
+    ];
+    let graph = ConflictGraph::new(&footprints);
+    assert_eq!(graph.tx_count(), 6);
+    let expected_conflicts: [BTreeSet<usize>; 6] = [
+        [2, 5].into(),
+        [3, 5].into(),
+        [0, 5].into(),
+        [1].into(),
+        [].into(),
+        [0, 1, 2].into(),
+    ];
+    for (tx, expected) in expected_conflicts.iter().enumerate() {
+        assert_eq!(graph.conflicts(tx), expected);
+    }
+    assert_eq!(graph.clusters(), vec![vec![0, 1, 2, 3, 5], vec![4]]);
+    assert_eq!(graph.stages(), vec![vec![0, 1, 4], vec![2, 3], vec![5]]);
+
+    let graph = ConflictGraph::new(&footprints[..5]);
+    assert_eq!(graph.clusters(), vec![vec![0, 2], vec![1, 3], vec![4]]);
+    assert_eq!(graph.stages(), vec![vec![0, 1, 4], vec![2, 3]]);
+}
+
+#[test]
+fn test_conflict_graph_key_in_both_footprints() {
+    let footprints = [
+        footprint(&[1], &[1]),
+        footprint(&[1], &[]),
+        footprint(&[2], &[]),
+        footprint(&[2], &[]),
+    ];
+    let graph = ConflictGraph::new(&footprints);
+    assert_eq!(graph.conflicts(0), &BTreeSet::from([1]));
+    assert_eq!(graph.conflicts(2), &BTreeSet::new());
+    assert_eq!(graph.clusters(), vec![vec![0, 1], vec![2], vec![3]]);
+    assert_eq!(graph.stages(), vec![vec![0, 2, 3], vec![1]]);
+
+    let graph = ConflictGraph::new(&[]);
+    assert_eq!(graph.clusters(), Vec::<Vec<usize>>::new());
+    assert_eq!(graph.stages(), Vec::<Vec<usize>>::new());
+}
+
+#[test]
+fn test_apply_clusters_matches_sequential() {
+    let snapshot = snapshot(&[(1, 10), (2, 20), (3, 30), (5, 50)]);
+    let tx_footprints = [
+        (vec![1], vec![2]),
+        (vec![1], vec![3]),
+        (vec![2], vec![4]),
+        (vec![], vec![3]),
+        (vec![5], vec![]),
+        (vec![4], vec![1, 6]),
+        (vec![], vec![7]),
+    ];
+    let footprints = to_footprints(&tx_footprints);
+
+    let changes = apply_clusters_and_verify(
+        snapshot.clone(),
+        &footprints,
+        apply_footprint_tx(&tx_footprints),
+    )
+    .unwrap();
+    let sequential =
+        apply_sequentially(snapshot, &footprints, apply_footprint_tx(&tx_footprints)).unwrap();
+    assert_eq!(changes, sequential);
+
+    let values: Vec<(u8, u32)> = [1, 2, 3, 4, 6, 7]
+        .into_iter()
+        .map(|id| {
+            let (entry, _) = changes[&counter_key(id)].clone().unwrap();
+            let value = match &entry.data {
+                LedgerEntryData::ContractData(ContractDataEntry {
+                    val: ScVal::U32(v), ..
+                }) => *v,
+                _ => panic!("unexpected entry {entry:?}"),
+            };
+            (id, value)
+        })
+        .collect();
+    // tx 0: 2 = 10 + 20 + 1 = 31
+    // tx 1: 3 = 10 + 30 + 2 = 42
+    // tx 2: 4 = 31 + 0 + 3 = 34
+    // tx 3: 3 = 42 + 4 = 46
+    // tx 5: 1, 6 = 34 + 10 + 0 + 6 = 50
+    // tx 6: 7 = 0 + 7 = 7
+    assert_eq!(
+        values,
+        vec![(1, 50), (2, 31), (3, 46), (4, 34), (6, 50), (7, 7)]
+    );
+}
+
+#[test]
+fn test_apply_clusters_detects_undeclared_read() {
+    let snapshot = snapshot(&[(1, 10), (2, 20)]);
+    let footprints = [footprint(&[], &[1]), footprint(&[], &[2])];
+    // tx 1 also reads key 1, which is written by tx 0, without declaring it.
+    let apply_tx = |tx: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        Ok(match tx {
+            0 => apply_counters_tx(tx, snapshot.as_ref(), &[1], &[1]),
+            _ => apply_counters_tx(tx, snapshot.as_ref(), &[1, 2], &[2]),
+        })
+    };
+    assert_eq!(
+        ConflictGraph::new(&footprints).clusters(),
+        vec![vec![0], vec![1]]
+    );
+    let err = apply_clusters_and_verify(snapshot, &footprints, apply_tx).unwrap_err();
+    assert!(err.to_string().contains("differ"));
+
+    let sync_snapshot = Arc::new(
+        MockSyncSnapshotSource::from_entries(vec![
+            (counter_entry(1, 10), Some(LIVE_UNTIL)),
+            (counter_entry(2, 20), Some(LIVE_UNTIL)),
+        ])
+        .unwrap(),
+    );
+    let err = apply_clusters_on_threads_and_verify(
+        sync_snapshot,
+        &footprints,
+        NonZeroUsize::new(2).unwrap(),
+        apply_tx,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("differ"));
+}
+
+#[test]
+fn test_apply_clusters_rejects_invalid_changes() {
+    let snapshot = snapshot(&[(1, 10)]);
+    let footprints = [footprint(&[1], &[2]), footprint(&[], &[3])];
+
+    // tx 0 writes a key outside of its footprint.
+    let apply_tx = |tx: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        Ok(apply_counters_tx(tx, snapshot.as_ref(), &[1], &[4]))
+    };
+    let err = apply_sequentially(snapshot.clone(), &footprints, apply_tx).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("outside of its read-write footprint"));
+
+    // The clusters are not independent.
+    let tx_footprints = [(vec![1], vec![2]), (vec![], vec![2])];
+    let footprints = to_footprints(&tx_footprints);
+    let apply_tx = apply_footprint_tx(&tx_footprints);
+    let err = apply_clusters(snapshot, &footprints, &[vec![0], vec![1]], apply_tx).unwrap_err();
+    assert!(err.to_string().contains("more than one cluster"));
+}
+
+#[test]
+fn test_apply_clusters_merges_ttl_extensions() {
+    let snapshot = snapshot(&[(1, 10), (2, 20)]);
+    let footprints = [
+        footprint(&[1], &[2]),
+        footprint(&[1], &[3]),
+        footprint(&[1, 2], &[]),
+    ];
+    // Every transaction extends the TTL of key 1 and tx 2 also extends the
+    // TTL of key 2, which is written by tx 0.
+    let apply_tx = |tx: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        let (read_only, read_write) = match tx {
+            0 => (vec![1], vec![2]),
+            1 => (vec![1], vec![3]),
+            _ => (vec![1, 2], vec![]),
+        };
+        let mut changes = apply_counters_tx(tx, snapshot.as_ref(), &read_only, &read_write);
+        let live_until = [LIVE_UNTIL + 20, LIVE_UNTIL + 30, LIVE_UNTIL + 10][tx];
+        changes.extend(
+            read_only
+                .iter()
+                .filter_map(|id| extend_counter_ttl(snapshot.as_ref(), *id, live_until)),
+        );
+        Ok(changes)
+    };
+    assert_eq!(
+        ConflictGraph::new(&footprints).clusters(),
+        vec![vec![0, 2], vec![1]]
+    );
+    let changes = apply_clusters_and_verify(snapshot.clone(), &footprints, apply_tx).unwrap();
+    let live_until = |id: u8| changes[&counter_key(id)].as_ref().unwrap().1;
+    assert_eq!(live_until(1), Some(LIVE_UNTIL + 30));
+    assert_eq!(live_until(2), Some(LIVE_UNTIL + 10));
+    assert_eq!(counter_value(snapshot.as_ref(), 1), 10);
+    assert_eq!(
+        changes[&counter_key(1)].as_ref().unwrap().0.as_ref(),
+        &counter_entry(1, 10)
+    );
+
+    let sync_snapshot = Arc::new(
+        MockSyncSnapshotSource::from_entries(vec![
+            (counter_entry(1, 10), Some(LIVE_UNTIL)),
+            (counter_entry(2, 20), Some(LIVE_UNTIL)),
+        ])
+        .unwrap(),
+    );
+    let threaded = apply_clusters_on_threads(
+        sync_snapshot,
+        &footprints,
+        &[vec![0, 2], vec![1]],
+        NonZeroUsize::new(2).unwrap(),
+        apply_tx,
+    )
+    .unwrap();
+    assert_eq!(threaded, changes);
+}
+
+#[test]
+fn test_apply_clusters_rejects_invalid_read_only_changes() {
+    let snapshot = snapshot(&[(1, 10)]);
+    let footprints = [footprint(&[1], &[])];
+
+    // The TTL of a read-only key can't be reduced.
+    let apply_tx = |_: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        let key = Rc::new(counter_key(1));
+        let (entry, _) = snapshot.get(&key).unwrap().unwrap();
+        Ok([(key, Some((entry, Some(LIVE_UNTIL - 1))))].into())
+    };
+    let err = apply_sequentially(snapshot.clone(), &footprints, apply_tx).unwrap_err();
+    assert!(err.to_string().contains("other than by extending its TTL"));
+
+    // A read-only entry can't be modified, even if its TTL is extended.
+    let apply_tx = |_: usize, _: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        Ok([(
+            Rc::new(counter_key(1)),
+            Some((Rc::new(counter_entry(1, 11)), Some(LIVE_UNTIL + 1))),
+        )]
+        .into())
+    };
+    let err = apply_sequentially(snapshot.clone(), &footprints, apply_tx).unwrap_err();
+    assert!(err.to_string().contains("other than by extending its TTL"));
+
+    // A key written by one cluster can't be extended by another one.
+    let footprints = [footprint(&[], &[1]), footprint(&[1], &[])];
+    let apply_tx = |tx: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        Ok(
+            extend_counter_ttl(snapshot.as_ref(), 1, LIVE_UNTIL + tx as u32 + 1)
+                .into_iter()
+                .collect(),
+        )
+    };
+    let err = apply_clusters(snapshot, &footprints, &[vec![0], vec![1]], apply_tx).unwrap_err();
+    assert!(err.to_string().contains("more than one cluster"));
+}
+
+#[test]
+fn test_apply_clusters_on_threads_matches_sequential() {
+    let values = [(1, 10), (2, 20), (3, 30), (5, 50)];
+    let tx_footprints = [
+        (vec![1], vec![2]),
+        (vec![1], vec![3]),
+        (vec![2], vec![4]),
+        (vec![], vec![3]),
+        (vec![5], vec![]),
+        (vec![], vec![7]),
+    ];
+    let footprints = to_footprints(&tx_footprints);
+    let apply_tx = |tx: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        let (read_only, read_write) = &tx_footprints[tx];
+        let reads: Vec<u8> = read_only.iter().chain(read_write.iter()).copied().collect();
+        Ok(apply_counters_tx(tx, snapshot.as_ref(), &reads, read_write))
+    };
+    let sequential = apply_sequentially(snapshot(&values), &footprints, apply_tx).unwrap();
+
+    let sync_snapshot = Arc::new(
+        MockSyncSnapshotSource::from_entries(
+            values
+                .iter()
+                .map(|(id, v)| (counter_entry(*id, *v), Some(LIVE_UNTIL)))
+                .collect(),
+        )
+        .unwrap(),
+    );
+    let clusters = ConflictGraph::new(&footprints).clusters();
+    assert_eq!(clusters.len(), 4);
+    for thread_count in [1, 3] {
+        let threaded = apply_clusters_on_threads(
+            sync_snapshot.clone(),
+            &footprints,
+            &clusters,
+            NonZeroUsize::new(thread_count).unwrap(),
+            apply_tx,
+        )
+        .unwrap();
+        assert_eq!(threaded, sequential);
+    }
+
+    // Errors are reported for the first failing cluster.
+    let err = apply_clusters_on_threads(
+        sync_snapshot,
+        &footprints,
+        &clusters,
+        NonZeroUsize::new(2).unwrap(),
+        |tx: usize, _: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+            anyhow::bail!("tx {tx} failed")
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "tx 0 failed");
+}
+
+#[test]
+fn test_run_on_threads_preserves_input_order() {
+    let inputs: Vec<u64> = (0..100).collect();
+    for thread_count in [1, 4, 200] {
+        let outputs = run_on_threads(&inputs, NonZeroUsize::new(thread_count).unwrap(), |i| i * i);
+        assert_eq!(outputs, inputs.iter().map(|i| i * i).collect::<Vec<_>>());
+    }
+    let outputs: Vec<u64> = run_on_threads(&[], NonZeroUsize::new(2).unwrap(), |i: &u64| *i);
+    assert!(outputs.is_empty());
+}
+
+fn storage_key(contract: &ScAddress, key: &str) -> LedgerKey {
+    LedgerKey::ContractData(LedgerKeyContractData {
+        contract: contract.clone(),
+        key: ScVal::Symbol(key.try_into().unwrap()),
+        durability: ContractDataDurability::Persistent,
+    })
+}
+
+fn storage_entry(contract: &ScAddress, key: &str, val: u64) -> LedgerEntry {
+    ledger_entry(LedgerEntryData::ContractData(ContractDataEntry {
+        ext: ExtensionPoint::V0,
+        contract: contract.clone(),
+        key: ScVal::Symbol(key.try_into().unwrap()),
+        durability: ContractDataDurability::Persistent,
+        val: ScVal::U64(val),
+    }))
+}
+
+fn invoke_storage_fn(contract: &ScAddress, function: &str, args: Vec<ScVal>) -> HostFunction {
+    HostFunction::InvokeContract(InvokeContractArgs {
+        contract_address: contract.clone(),
+        function_name: function.try_into().unwrap(),
+        args: args.try_into().unwrap(),
+    })
+}
+
+// Applies the host function with `e2e_invoke::invoke_host_function`, passing
+// it the entries of the footprint from `snapshot`, and converts its ledger
+// changes to `LedgerChanges`.
+fn apply_e2e_tx(
+    host_fn: &HostFunction,
+    footprint: &LedgerFootprint,
+    snapshot: &dyn SnapshotSource,
+) -> Result<LedgerChanges> {
+    let limits = Limits::none();
+    let mut encoded_entries = vec![];
+    let mut encoded_ttl_entries = vec![];
+    for key in footprint
+        .read_only
+        .iter()
+        .chain(footprint.read_write.iter())
+    {
+        let Some((entry, live_until)) = snapshot.get(&Rc::new(key.clone()))? else {
+            continue;
+        };
+        encoded_entries.push(entry.to_xdr(limits.clone())?);
+        encoded_ttl_entries.push(match live_until {
+            Some(live_until_ledger_seq) => TtlEntry {
+                key_hash: Hash(Sha256::digest(key.to_xdr(limits.clone())?).into()),
+                live_until_ledger_seq,
+            }
+            .to_xdr(limits.clone())?,
+            None => vec![],
+        });
+    }
+    let resources = SorobanResources {
+        footprint: footprint.clone(),
+        instructions: 10_000_000,
+        read_bytes: 100_000,
+        write_bytes: 10_000,
+    };
+    let res = invoke_host_function(
+        &Budget::default(),
+        false,
+        host_fn.to_xdr(limits.clone())?,
+        resources.to_xdr(limits.clone())?,
+        get_account_id([0; 32]).to_xdr(limits.clone())?,
+        Vec::<Vec<u8>>::new().into_iter(),
+        default_ledger_info(),
+        encoded_entries.into_iter(),
+        encoded_ttl_entries.into_iter(),
+        vec![0; 32],
+        &mut vec![],
+    )?;
+    res.encoded_invoke_result?;
+
+    let mut changes = LedgerChanges::new();
+    for change in res.ledger_changes {
+        let key = Rc::new(LedgerKey::from_xdr(&change.encoded_key, limits.clone())?);
+        let live_until = change.ttl_change.as_ref().map(|c| c.new_live_until_ledger);
+        if change.read_only {
+            // Read-only entries can only have their TTL extended.
+            let Some(ttl_change) = &change.ttl_change else {
+                continue;
+            };
+            if ttl_change.new_live_until_ledger > ttl_change.old_live_until_ledger {
+                let (entry, _) = snapshot.get(&key)?.unwrap();
+                changes.insert(key, Some((entry, live_until)));
+            }
+        } else {
+            let entry = change
+                .encoded_new_value
+                .map(|v| LedgerEntry::from_xdr(v, limits.clone()))
+                .transpose()?;
+            changes.insert(key, entry.map(|e| (Rc::new(e), live_until)));
+        }
+    }
+    Ok(changes)
+}
+
+#[test]
+fn test_apply_e2e_transactions_on_threads_matches_sequential() {
+    let contract_a = CreateContractData::new([1; 32], CONTRACT_STORAGE);
+    let contract_b = CreateContractData::new([2; 32], CONTRACT_STORAGE);
+    let (a, b) = (&contract_a.contract_address, &contract_b.contract_address);
+    let live_until = DEFAULT_LEDGER_SEQ + 1000;
+    let snapshot = Arc::new(
+        MockSyncSnapshotSource::from_entries(vec![
+            (contract_a.wasm_entry.clone(), Some(live_until)),
+            (contract_a.contract_entry.clone(), Some(live_until)),
+            (contract_b.contract_entry.clone(), Some(live_until)),
+            (storage_entry(a, "c", 5), Some(live_until)),
+        ])
+        .unwrap(),
+    );
+    let footprint = |contract: &CreateContractData,
+                     read_only: &[LedgerKey],
+                     read_write: &[LedgerKey]| LedgerFootprint {
+        read_only: [contract.wasm_key.clone(), contract.contract_key.clone()]
+            .into_iter()
+            .chain(read_only.iter().cloned())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap(),
+        read_write: read_write.to_vec().try_into().unwrap(),
+    };
+    let put = |contract: &ScAddress, key: &str, val: u64| {
+        invoke_storage_fn(
+            contract,
+            "put_persistent",
+            vec![ScVal::Symbol(key.try_into().unwrap()), ScVal::U64(val)],
+        )
+    };
+    // Using `extend_to` as the threshold makes the second extension apply
+    // after the first one when the transactions are applied sequentially.
+    let extend = |contract: &ScAddress, key: &str, extend_to: u32| {
+        invoke_storage_fn(
+            contract,
+            "extend_persistent",
+            vec![
+                ScVal::Symbol(key.try_into().unwrap()),
+                ScVal::U32(extend_to),
+                ScVal::U32(extend_to),
+            ],
+        )
+    };
+    let txs = [
+        (
+            put(a, "a", 1),
+            footprint(&contract_a, &[], &[storage_key(a, "a")]),
+        ),
+        (
+            put(b, "b", 3),
+            footprint(&contract_b, &[], &[storage_key(b, "b")]),
+        ),
+        // Overwrites the value written by tx 0.
+        (
+            put(a, "a", 2),
+            footprint(&contract_a, &[], &[storage_key(a, "a")]),
+        ),
+        // Both extend the TTL of an entry in their read-only footprint.
+        (
+            extend(a, "c", 20_000),
+            footprint(&contract_a, &[storage_key(a, "c")], &[]),
+        ),
+        (
+            extend(a, "c", 30_000),
+            footprint(&contract_a, &[storage_key(a, "c")], &[]),
+        ),
+    ];
+    let footprints: Vec<LedgerFootprint> = txs.iter().map(|(_, fp)| fp.clone()).collect();
+    assert_eq!(
+        ConflictGraph::new(&footprints).clusters(),
+        vec![vec![0, 2], vec![1], vec![3], vec![4]]
+    );
+    let apply_tx = |tx: usize, snapshot: Rc<dyn SnapshotSource>| -> Result<LedgerChanges> {
+        let (host_fn, footprint) = &txs[tx];
+        apply_e2e_tx(host_fn, footprint, snapshot.as_ref())
+    };
+
+    for thread_count in [1, 3] {
+        let changes = apply_clusters_on_threads_and_verify(
+            snapshot.clone(),
+            &footprints,
+            NonZeroUsize::new(thread_count).unwrap(),
+            apply_tx,
+        )
+        .unwrap();
+        let entry = |key: LedgerKey| changes[&key].as_ref().map(|(e, l)| ((**e).clone(), *l));
+        let new_live_until = DEFAULT_LEDGER_SEQ + 100_000 - 1;
+        assert_eq!(
+            entry(storage_key(a, "a")),
+            Some((storage_entry(a, "a", 2), Some(new_live_until)))
+        );
+        assert_eq!(
+            entry(storage_key(b, "b")),
+            Some((storage_entry(b, "b", 3), Some(new_live_until)))
+        );
+        assert_eq!(
+            entry(storage_key(a, "c")),
+            Some((storage_entry(a, "c", 5), Some(DEFAULT_LEDGER_SEQ + 30_000)))
+        );
+        assert_eq!(changes.len(), 3);
+    }
+}
@@ -1,9 +1,15 @@
+use crate::parallel::{
+    build_shared_module_cache, simulate_invoke_host_function_ops_on_threads,
+    InvokeHostFunctionSimulationInput,
+};
 use crate::simulation::{
     simulate_extend_ttl_op, simulate_invoke_host_function_op, simulate_restore_op,
     ExtendTtlOpSimulationResult, LedgerEntryDiff, RestoreOpSimulationResult,
     SimulationAdjustmentConfig, SimulationAdjustmentFactor,
 };
-use crate::testutils::{ledger_entry_to_ledger_key, temp_entry, MockSnapshotSource};
+use crate::testutils::{
+    ledger_entry_to_ledger_key, temp_entry, MockSnapshotSource, MockSyncSnapshotSource,
+};
 use crate::NetworkConfig;
 use pretty_assertions::assert_eq;
 use soroban_env_host::e2e_testutils::{
@@ -25,7 +31,9 @@ use soroban_env_host::xdr::{
 };
 use soroban_env_host::HostError;
 use soroban_test_wasms::{ADD_I32, AUTH_TEST_CONTRACT, TRY_CALL_SAC};
+use std::num::NonZeroUsize;
 use std::rc::Rc;
+use std::sync::Arc;
 use tap::prelude::*;
 
 fn default_network_config() -> NetworkConfig {
@@ -203,6 +211,126 @@ fn test_simulate_upload_wasm() {
     );
 }
 
+#[test]
+fn test_simulate_invoke_host_function_ops_on_threads() {
+    let source_account = get_account_id([123; 32]);
+    let ledger_info = default_ledger_info();
+    let network_config = default_network_config();
+    let entries = vec![(
+        wasm_entry(TRY_CALL_SAC),
+        Some(ledger_info.sequence_number + 100),
+    )];
+    let snapshot_source = Rc::new(
+        MockSnapshotSource::from_entries(entries.clone(), ledger_info.sequence_number).unwrap(),
+    );
+    let sync_snapshot_source = Arc::new(MockSyncSnapshotSource::from_entries(entries).unwrap());
+
+    let inputs: Vec<InvokeHostFunctionSimulationInput> =
+        [ADD_I32, AUTH_TEST_CONTRACT, TRY_CALL_SAC]
+            .into_iter()
+            .cycle()
+            .take(7)
+            .enumerate()
+            .map(|(i, wasm)| InvokeHostFunctionSimulationInput {
+                host_fn: upload_wasm_host_fn(wasm),
+                auth_entries: None,
+                source_account: source_account.clone(),
+                base_prng_seed: [i as u8; 32],
+            })
+            .collect();
+    let results = simulate_invoke_host_function_ops_on_threads(
+        sync_snapshot_source,
+        &network_config,
+        &test_adjustment_config(),
+        &ledger_info,
+        &inputs,
+        true,
+        None,
+        NonZeroUsize::new(3).unwrap(),
+    );
+    assert_eq!(results.len(), inputs.len());
+    for (input, res) in inputs.iter().zip(results) {
+        let res = res.unwrap();
+        let expected = simulate_invoke_host_function_op(
+            snapshot_source.clone(),
+            &network_config,
+            &test_adjustment_config(),
+            &ledger_info,
+            input.host_fn.clone(),
+            None,
+            &source_account,
+            input.base_prng_seed,
+            true,
+        )
+        .unwrap();
+        assert_eq!(res.invoke_result.unwrap(), expected.invoke_result.unwrap());
+        assert_eq!(res.transaction_data, expected.transaction_data);
+        assert_eq!(res.simulated_instructions, expected.simulated_instructions);
+        assert_eq!(res.simulated_memory, expected.simulated_memory);
+        assert_eq!(res.modified_entries, expected.modified_entries);
+    }
+}
+
+#[test]
+fn test_simulate_invoke_host_function_ops_on_threads_with_module_cache() {
+    let source_account = get_account_id([123; 32]);
+    let ledger_info = default_ledger_info();
+    let network_config = default_network_config();
+    let contract = CreateContractData::new([1; 32], ADD_I32);
+    let entries = vec![
+        (
+            contract.wasm_entry.clone(),
+            Some(ledger_info.sequence_number + 100),
+        ),
+        (
+            contract.contract_entry.clone(),
+            Some(ledger_info.sequence_number + 100),
+        ),
+    ];
+    let sync_snapshot_source = Arc::new(MockSyncSnapshotSource::from_entries(entries).unwrap());
+    let LedgerEntryData::ContractCode(code) = &contract.wasm_entry.data else {
+        panic!("not a contract code entry");
+    };
+    let module_cache =
+        build_shared_module_cache(&network_config, &ledger_info, &[code.clone()]).unwrap();
+    assert_eq!(module_cache.len(), 1);
+
+    let inputs: Vec<InvokeHostFunctionSimulationInput> = (0..5)
+        .map(|i| InvokeHostFunctionSimulationInput {
+            host_fn: HostFunction::InvokeContract(InvokeContractArgs {
+                contract_address: contract.contract_address.clone(),
+                function_name: "add".try_into().unwrap(),
+                args: vec![ScVal::I32(i), ScVal::I32(10)].try_into().unwrap(),
+            }),
+            auth_entries: None,
+            source_account: source_account.clone(),
+            base_prng_seed: [i as u8; 32],
+        })
+        .collect();
+    let simulate = |module_cache| {
+        simulate_invoke_host_function_ops_on_threads(
+            sync_snapshot_source.clone(),
+            &network_config,
+            &test_adjustment_config(),
+            &ledger_info,
+            &inputs,
+            false,
+            module_cache,
+            NonZeroUsize::new(2).unwrap(),
+        )
+    };
+    let results = simulate(Some(&module_cache));
+    let expected_results = simulate(None);
+    for (i, (res, expected)) in results.into_iter().zip(expected_results).enumerate() {
+        let res = res.unwrap();
+        let expected = expected.unwrap();
+        assert_eq!(res.invoke_result.unwrap(), ScVal::I32(i as i32 + 10));
+        assert_eq!(res.transaction_data, expected.transaction_data);
+        assert_eq!(res.simulated_instructions, expected.simulated_instructions);
+        assert_eq!(res.simulated_memory, expected.simulated_memory);
+    }
+}
+
 #[test]
 fn test_simulation_returns_insufficient_budget_error() {
     let source_account = get_account_id([123; 32]);
@@ -1,4 +1,6 @@
-use crate::snapshot_source::SnapshotSourceWithArchive;
+use crate::snapshot_source::{
+    SnapshotSourceWithArchive, SyncEntryWithLiveUntil, SyncSnapshotSource,
+};
 use anyhow::{bail, Result};
 use soroban_env_host::{
     e2e_testutils::ledger_entry,
@@ -13,6 +15,7 @@ use soroban_env_host::{
 };
 use std::collections::BTreeMap;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct MockSnapshotSource {
     map: BTreeMap<Rc<LedgerKey>, EntryWithLiveUntil>,
@@ -36,6 +39,30 @@ impl MockSnapshotSource {
     }
 }
 
+pub struct MockSyncSnapshotSource {
+    map: BTreeMap<LedgerKey, SyncEntryWithLiveUntil>,
+}
+
+impl MockSyncSnapshotSource {
+    pub fn from_entries(entries: Vec<(LedgerEntry, Option<u32>)>) -> Result<Self> {
+        let mut map = BTreeMap::<LedgerKey, SyncEntryWithLiveUntil>::new();
+        for (e, maybe_ttl) in entries {
+            let key = ledger_entry_to_ledger_key(&e)?;
+            map.insert(key, (Arc::new(e), maybe_ttl));
+        }
+        Ok(Self { map })
+    }
+}
+
+impl SyncSnapshotSource for MockSyncSnapshotSource {
+    fn get(
+        &self,
+        key: &LedgerKey,
+    ) -> std::result::Result<Option<SyncEntryWithLiveUntil>, HostError> {
+        Ok(self.map.get(key).cloned())
+    }
+}
+
 pub fn ledger_entry_to_ledger_key(entry: &LedgerEntry) -> Result<LedgerKey> {
     match &entry.data {
         LedgerEntryData::Account(a) => Ok(LedgerKey::Account(LedgerKeyAccount {
@@ -0,0 +1,199 @@
+//! This module renders wasm modules (typically ones emitted by [`ModEmitter`],
+//! or generated by a fuzzer) in forms that are easier to debug than the raw
+//! binary:
+//!
+//!   - [`annotated_wat`] prints the module as WAT, with every host-function
+//!     import and call labelled by its `env.json` module and function name and
+//!     every `i64.const` that is a plausible [`Val`] decoded into its tag and
+//!     body.
+//!
+//!   - [`disassemble`] produces a [`Disassembly`], a structured listing of the
+//!     module's imports, exports and function bodies carrying the same
+//!     annotations, for tools that want to inspect a module programmatically.
+
+use crate::{host_funcs::host_function_name, ModEmitter};
+use soroban_env_common::{Tag, Val};
+use wasmparser::{ExternalKind, Operator, Parser, Payload, TypeRef};
+
+/// A function imported by a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasmImport {
+    /// The function index of the import.
+    pub index: u32,
+    /// The import module name, e.g. `"x"`.
+    pub module: String,
+    /// The import function name, e.g. `"_"`.
+    pub name: String,
+    /// The `env.json` module and function name of the host function the
+    /// import refers to, if it refers to one.
+    pub host_function: Option<(&'static str, &'static str)>,
+}
+
+/// A single instruction in a function body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasmInsn {
+    /// The offset of the instruction in the module.
+    pub offset: usize,
+    /// The instruction, as printed by its `Debug` representation.
+    pub op: String,
+    /// A note on the instruction: the host function called, or the decoded
+    /// [`Val`] of an `i64.const`.
+    pub note: Option<String>,
+}
+
+/// A function defined by a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasmFunc {
+    /// The function index, counting imported functions first.
+    pub index: u32,
+    /// The names the function is exported under, if any.
+    pub exports: Vec<String>,
+    pub insns: Vec<DisasmInsn>,
+}
+
+/// A structured listing of a wasm module's functions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Disassembly {
+    pub imports: Vec<DisasmImport>,
+    pub funcs: Vec<DisasmFunc>,
+}
+
+impl Disassembly {
+    fn call_note(&self, function_index: u32) -> Option<String> {
+        self.imports
+            .iter()
+            .find(|imp| imp.index == function_index)
+            .map(import_label)
+    }
+}
+
+fn import_label(imp: &DisasmImport) -> String {
+    match imp.host_function {
+        Some((mod_name, fn_name)) => format!("{mod_name}.{fn_name}"),
+        None => format!("unknown host function {:?} {:?}", imp.module, imp.name),
+    }
+}
+
+/// Decodes `payload` as a [`Val`], returning a description of its tag, body
+/// and value, or `None` if its tag is not a valid one.
+pub fn describe_val(payload: i64) -> Option<String> {
+    let val = Val::from_payload(payload as u64);
+    match val.get_tag() {
+        Tag::Bad
+        | Tag::SmallCodeUpperBound
+        | Tag::ObjectCodeLowerBound
+        | Tag::ObjectCodeUpperBound => None,
+        tag => Some(format!(
+            "Val tag={:?} body={:#x}: {:?}",
+            tag,
+            (payload as u64) >> 8,
+            val
+        )),
+    }
+}
+
+/// Produces a [`Disassembly`] of the wasm module `wasm`.
+pub fn disassemble(wasm: &[u8]) -> Result<Disassembly, wasmparser::BinaryReaderError> {
+    let mut dis = Disassembly::default();
+    let mut exports: Vec<(u32, String)> = Vec::new();
+    let mut n_funcs = 0;
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if let TypeRef::Func(_) = import.ty {
+                        dis.imports.push(DisasmImport {
+                            index: n_funcs,
+                            module: import.module.to_string(),
+                            name: import.name.to_string(),
+                            host_function: host_function_name(import.module, import.name),
+                        });
+                        n_funcs += 1;
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if let ExternalKind::Func = export.kind {
+                        exports.push((export.index, export.name.to_string()));
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut insns = Vec::new();
+                for op in body.get_operators_reader()?.into_iter_with_offsets() {
+                    let (op, offset) = op?;
+                    let note = match op {
+                        Operator::Call { function_index } => dis.call_note(function_index),
+                        Operator::I64Const { value } => describe_val(value),
+                        _ => None,
+                    };
+                    insns.push(DisasmInsn {
+                        offset,
+                        op: format!("{op:?}"),
+                        note,
+                    });
+                }
+                dis.funcs.push(DisasmFunc {
+                    index: n_funcs,
+                    exports: Vec::new(),
+                    insns,
+                });
+                n_funcs += 1;
+            }
+            _ => (),
+        }
+    }
+    for (index, name) in exports {
+        if let Some(func) = dis.funcs.iter_mut().find(|f| f.index == index) {
+            func.exports.push(name);
+        }
+    }
+    Ok(dis)
+}
+
+/// Prints the wasm module `wasm` as WAT, labelling host-function imports and
+/// calls with their `env.json` names and decoding `i64.const` operands that
+/// are plausible [`Val`]s.
+pub fn annotated_wat(wasm: &[u8]) -> Result<String, wasmparser::BinaryReaderError> {
+    let dis = disassemble(wasm)?;
+    let printed = match wasmprinter::print_bytes(wasm) {
+        Ok(printed) => printed,
+        // The module parsed above, so this is essentially unreachable, but we
+        // can still fall back to the structured listing.
+        Err(e) => return Ok(format!(";; wasmprinter failed: {e}\n{dis:#?}")),
+    };
+    let mut imports = dis.imports.iter();
+    let mut out = String::with_capacity(printed.len() * 2);
+    for line in printed.lines() {
+        out.push_str(line);
+        let insn = line.trim_start();
+        let note = if insn.starts_with("(import ") && insn.contains("(func ") {
+            imports.next().map(import_label)
+        } else if let Some(idx) = insn.strip_prefix("call ") {
+            idx.parse().ok().and_then(|idx| dis.call_note(idx))
+        } else if let Some(value) = insn.strip_prefix("i64.const ") {
+            value.parse().ok().and_then(describe_val)
+        } else {
+            None
+        };
+        if let Some(note) = note {
+            out.push_str(" ;; ");
+            out.push_str(&note);
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+impl ModEmitter {
+    /// Finish emitting code like [`ModEmitter::finish`], but return the
+    /// module rendered by [`annotated_wat`] along with its binary form.
+    pub fn finish_with_wat(self) -> (Vec<u8>, String) {
+        let bytes = self.finish();
+        let wat = annotated_wat(&bytes).expect("validated module must disassemble");
+        (bytes, wat)
+    }
+}
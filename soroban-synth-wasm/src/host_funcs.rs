@@ -68,3 +68,47 @@ macro_rules! generate_call_emitter_functions {
 
 // Here we invoke the x-macro passing generate_call_emitter_functions as its callback macro.
 call_macro_with_all_host_functions! { generate_call_emitter_functions }
+
+macro_rules! generate_host_function_names {
+    {
+        $(
+            $(#[$mod_attr:meta])*
+            mod $mod_name:ident $mod_str:literal
+            {
+                $(
+                    $(#[$fn_attr:meta])*
+                    { $fn_str:literal, $($min_proto:literal)?, $($max_proto:literal)?, fn $fn_id:ident ($($arg:ident:$type:ty),*) -> $ret:ty }
+                )*
+            }
+        )*
+    }
+
+    =>
+
+    {
+        // This macro expands to a single table mapping the (module, function)
+        // import names of every host function to its `env.json` module and
+        // function names, used to label imports in disassembly.
+        pub(crate) const HOST_FUNCTION_NAMES: &[(&str, &str, &str, &str)] = &[
+        $(
+            $(
+                ($mod_str, $fn_str, stringify!($mod_name), stringify!($fn_id)),
+            )*
+        )*
+        ];
+    };
+}
+
+call_macro_with_all_host_functions! { generate_host_function_names }
+
+/// Returns the `env.json` module and function name of the host function
+/// imported as `fname` from `module`, if there is one.
+pub(crate) fn host_function_name(
+    module: &str,
+    fname: &str,
+) -> Option<(&'static str, &'static str)> {
+    HOST_FUNCTION_NAMES
+        .iter()
+        .find(|(m, f, _, _)| *m == module && *f == fname)
+        .map(|(_, _, mod_name, fn_name)| (*mod_name, *fn_name))
+}
@@ -8,13 +8,20 @@
 //!   - [`FuncEmitter`] which provides helper methods for encoding calls to
 //!     the soroban host functions, as defined in [`soroban_env_common::Env`],
 //!     as well as working with the [`soroban_env_common::Val`] type.
+//!
+//! It also provides [`annotated_wat`] and [`disassemble`] for inspecting
+//! emitted (or fuzzer-generated) modules when debugging.
 
+mod disasm;
 mod func_emitter;
 mod host_funcs;
 mod mod_emitter;
 #[cfg(test)]
 mod test;
 
+pub use disasm::{
+    annotated_wat, describe_val, disassemble, DisasmFunc, DisasmImport, DisasmInsn, Disassembly,
+};
 pub use func_emitter::{FuncEmitter, LocalRef, Operand};
 pub use mod_emitter::{Arity, FuncRef, GlobalRef, ModEmitter, TypeRef};
 
@@ -26,3 +33,7 @@ pub use mod_emitter::{Arity, FuncRef, GlobalRef, ModEmitter, TypeRef};
 mod expr;
 #[cfg(feature = "testutils")]
 pub use expr::{Emit, Expr};
+#[cfg(feature = "testutils")]
+mod typed_contract;
+#[cfg(feature = "testutils")]
+pub use typed_contract::{contract_arg_types, ContractArg, Kind, Stmt, TypedContract};
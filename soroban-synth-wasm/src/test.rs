@@ -1,4 +1,4 @@
-use crate::{Arity, GlobalRef, ModEmitter, Operand};
+use crate::{disassemble, Arity, GlobalRef, ModEmitter, Operand};
 use expect_test::expect;
 use soroban_env_common::{Symbol, Tag, Val};
 use wasmprinter::print_bytes;
@@ -323,3 +323,130 @@ fn br_table() {
         )"#]];
     expected.assert_eq(&printed);
 }
+
+#[test]
+fn annotated_wat_labels_host_calls_and_vals() {
+    let mut fe = ModEmitter::default().func(Arity(0), 0);
+    fe.map_new();
+    fe.map_put(
+        Operand::StackTop,
+        Val::from_u32(1).to_val(),
+        Val::from_u32(123).to_val(),
+    );
+    let (bytes, wat) = fe.finish_and_export("test").finish_with_wat();
+
+    assert!(wat.contains(r#"(import "m" "_" (func (;0;) (type 0))) ;; map.map_new"#));
+    assert!(wat.contains(r#"(import "m" "0" (func (;1;) (type 1))) ;; map.map_put"#));
+    assert!(wat.contains("call 0 ;; map.map_new\n"));
+    assert!(wat.contains("call 1 ;; map.map_put\n"));
+    assert!(wat.contains("i64.const 528280977412 ;; Val tag=U32Val body=0x7b000000: U32(123)\n"));
+    // Constants outside function bodies are not annotated.
+    assert!(wat.contains("(global (;0;) (mut i64) i64.const 42)\n"));
+
+    let dis = disassemble(&bytes).unwrap();
+    assert_eq!(dis.imports.len(), 2);
+    assert_eq!(dis.imports[1].host_function, Some(("map", "map_put")));
+    assert_eq!(dis.funcs.len(), 1);
+    assert_eq!(dis.funcs[0].index, 2);
+    assert_eq!(dis.funcs[0].exports, vec!["test".to_string()]);
+    let notes: Vec<_> = dis.funcs[0]
+        .insns
+        .iter()
+        .filter_map(|i| i.note.as_deref())
+        .collect();
+    assert_eq!(
+        notes,
+        vec![
+            "map.map_new",
+            "Val tag=U32Val body=0x1000000: U32(1)",
+            "Val tag=U32Val body=0x7b000000: U32(123)",
+            "map.map_put",
+        ]
+    );
+}
+
+#[cfg(feature = "testutils")]
+#[test]
+fn typed_contract_only_uses_valid_operands() {
+    use crate::{ContractArg, Stmt, TypedContract};
+    use soroban_env_common::StorageType;
+
+    let args = [
+        ContractArg::DataKey {
+            storage_type: StorageType::Persistent,
+            read_only: true,
+        },
+        ContractArg::DataKey {
+            storage_type: StorageType::Temporary,
+            read_only: false,
+        },
+        ContractArg::Callee { arity: 2 },
+        ContractArg::Signer,
+    ];
+    for seed in 0..200u32 {
+        let data: Vec<u8> = (0..512u32)
+            .map(|i| (i.wrapping_mul(seed.wrapping_add(7)) >> 3) as u8)
+            .collect();
+        let mut u = arbitrary::Unstructured::new(&data);
+        let contract = TypedContract::generate(&mut u, &args).unwrap();
+        for stmt in contract.stmts.iter() {
+            match stmt {
+                // Slot 0 is the read-only key, slots 2 and 3 the callee.
+                Stmt::PutData(k, ..) | Stmt::DelData(k, _) => assert_eq!(*k, 1),
+                Stmt::TryCall(addr, func, call_args) => {
+                    assert_eq!((*addr, *func, call_args.len()), (2, 3, 2))
+                }
+                Stmt::RequireAuth(a) => assert_eq!(*a, 4),
+                _ => (),
+            }
+        }
+        // Emitting validates the module.
+        let wasm = contract.as_single_function_wasm_module("test");
+        let mut called = std::collections::BTreeMap::new();
+        for insn in disassemble(&wasm).unwrap().funcs[0].insns.iter() {
+            // Every note that isn't a decoded constant labels a host call.
+            if let Some(note) = insn.note.as_deref().filter(|n| !n.starts_with("Val ")) {
+                let (_, fn_name) = note.split_once('.').unwrap();
+                *called.entry(fn_name.to_string()).or_insert(0usize) += 1;
+            }
+        }
+        let expected: std::collections::BTreeMap<_, _> = contract
+            .static_host_function_calls()
+            .into_iter()
+            .map(|(f, n)| (f.to_string(), n))
+            .collect();
+        assert_eq!(called, expected);
+    }
+}
+
+#[cfg(feature = "testutils")]
+#[test]
+fn typed_contract_reports_data_keys_read_before_written() {
+    use crate::{ContractArg, Stmt, TypedContract};
+    use soroban_env_common::StorageType;
+
+    let key = |read_only| ContractArg::DataKey {
+        storage_type: StorageType::Persistent,
+        read_only,
+    };
+    let p = StorageType::Persistent;
+    // The keys are in slots 0, 3 and 4; the callee takes slots 1 and 2.
+    let contract = TypedContract {
+        args: vec![
+            key(true),
+            ContractArg::Callee { arity: 0 },
+            key(false),
+            key(false),
+        ],
+        stmts: vec![
+            Stmt::PutData(3, 0, p),
+            Stmt::GetData(3, p),
+            Stmt::GetData(0, p),
+            Stmt::HasData(4, p),
+        ],
+    };
+    assert_eq!(
+        contract.data_keys_read_before_written(),
+        vec![true, false, false]
+    );
+}
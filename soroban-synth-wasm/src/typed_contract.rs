@@ -0,0 +1,676 @@
+//! This module contains a type-aware generator of whole contract functions,
+//! for fuzzing deeper host logic than the expression trees in [`crate::Expr`]
+//! tend to reach.
+//!
+//! [`crate::Expr`] trees are typed only by the `env.json` signatures of the
+//! host functions they call: any `Val` argument can be fed any expression, any
+//! `MapObject` can be passed to `map_get` with any key, and so on. Most such
+//! programs therefore fail on their first host call with a tag or
+//! missing-value error.
+//!
+//! A [`TypedContract`] is instead generated as a straight-line sequence of
+//! [`Stmt`]s, each of which stores its result in a fresh local. The generator
+//! tracks the [`Kind`] of every local (including facts such as the length of
+//! a vector or the keys stored in a map) and only generates statements whose
+//! arguments are known to be acceptable, for example:
+//!
+//!   - `vec_get` only with an index below the vector's known length, and
+//!     `map_get` only with a key that was put into the map,
+//!
+//!   - storage accesses only with the contract-data keys the harness set up,
+//!     writes only to keys that are writable in the footprint, and reads only
+//!     of keys currently present,
+//!
+//!   - `require_auth` only on addresses the harness can sign for, and
+//!   - cross-contract calls only with the arity the callee expects.
+//!
+//! The harness describes what it passes to the contract with a list of
+//! [`ContractArg`]s, and [`TypedContract::host_functions`] reports which host
+//! functions a generated contract calls.
+
+use crate::{Arity, FuncEmitter, LocalRef, ModEmitter, Operand};
+use arbitrary::{Result, Unstructured};
+use soroban_env_common::{StorageType, Symbol, U32Val, Val};
+use std::collections::BTreeMap;
+
+/// The maximum number of statements in a generated contract.
+const MAX_STMTS: usize = 64;
+
+/// Small symbols the generator uses as constants, e.g. as map keys.
+const SYMBOLS: &[&str] = &["a", "balance", "owner", "nonce", "admin", "data"];
+
+/// Describes one argument the harness passes to a generated contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContractArg {
+    /// A `U32Val` that is the key of a contract data entry of the given
+    /// storage type. The entry can be written unless `read_only` is set. The
+    /// contract may read the entry before writing it, in which case it must
+    /// exist when the contract is invoked (see
+    /// [TypedContract::data_keys_read_before_written]).
+    DataKey {
+        storage_type: StorageType,
+        read_only: bool,
+    },
+    /// Two arguments: an `AddressObject` of a contract to call and a `Symbol`
+    /// naming a function it exports that takes `arity` arguments.
+    Callee { arity: u32 },
+    /// An `AddressObject` of an account the harness will sign for.
+    Signer,
+    /// A `BytesObject`.
+    Bytes,
+    /// A `Val` of unknown type.
+    Val,
+}
+
+impl ContractArg {
+    fn wasm_arg_types(&self) -> &'static [&'static str] {
+        match self {
+            ContractArg::DataKey { .. } => &["U32Val"],
+            ContractArg::Callee { .. } => &["AddressObject", "Symbol"],
+            ContractArg::Signer => &["AddressObject"],
+            ContractArg::Bytes => &["BytesObject"],
+            ContractArg::Val => &["Val"],
+        }
+    }
+}
+
+/// Returns the `env.json` types of the wasm parameters of a contract taking
+/// `args`, in order.
+pub fn contract_arg_types(args: &[ContractArg]) -> Vec<&'static str> {
+    args.iter()
+        .flat_map(|a| a.wasm_arg_types().iter().copied())
+        .collect()
+}
+
+/// The type of a local, along with what the generator knows about its value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Val,
+    Void,
+    Bool,
+    U32,
+    Symbol,
+    U64Object,
+    I64Object,
+    Bytes,
+    /// A vector of known length.
+    Vec(u32),
+    /// A map containing (at least) the keys held in the given slots.
+    Map(Vec<usize>),
+    Address,
+}
+
+/// A single statement of a generated contract. Operands are slot numbers:
+/// the contract's wasm parameters come first, followed by one slot for the
+/// result of each preceding statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stmt {
+    U32Const(u32),
+    SymbolConst(usize),
+    U64New(u64),
+    I64New(i64),
+    VecNew,
+    VecPushBack(usize, usize),
+    VecLen(usize),
+    VecGet(usize, u32),
+    VecFront(usize),
+    MapNew,
+    MapPut(usize, usize, usize),
+    MapGet(usize, usize),
+    MapHas(usize, usize),
+    MapLen(usize),
+    BytesNew,
+    BytesPush(usize, u8),
+    BytesLen(usize),
+    BytesAppend(usize, usize),
+    Sha256(usize),
+    Keccak256(usize),
+    HasData(usize, StorageType),
+    GetData(usize, StorageType),
+    PutData(usize, usize, StorageType),
+    DelData(usize, StorageType),
+    RequireAuth(usize),
+    /// Calls the function named by the second slot on the contract in the
+    /// first slot, with the values in the remaining slots as arguments.
+    TryCall(usize, usize, Vec<usize>),
+    LedgerSequence,
+    LedgerTimestamp,
+    CurrentContractAddress,
+}
+
+impl Stmt {
+    /// The host functions the code emitted for this statement calls (each
+    /// at least once).
+    pub fn host_functions(&self) -> &'static [&'static str] {
+        match self {
+            Stmt::U32Const(_) | Stmt::SymbolConst(_) => &[],
+            Stmt::U64New(_) => &["obj_from_u64"],
+            Stmt::I64New(_) => &["obj_from_i64"],
+            Stmt::VecNew => &["vec_new"],
+            Stmt::VecPushBack(..) => &["vec_push_back"],
+            Stmt::VecLen(_) => &["vec_len"],
+            Stmt::VecGet(..) => &["vec_get"],
+            Stmt::VecFront(_) => &["vec_front"],
+            Stmt::MapNew => &["map_new"],
+            Stmt::MapPut(..) => &["map_put"],
+            Stmt::MapGet(..) => &["map_get"],
+            Stmt::MapHas(..) => &["map_has"],
+            Stmt::MapLen(_) => &["map_len"],
+            Stmt::BytesNew => &["bytes_new"],
+            Stmt::BytesPush(..) => &["bytes_push"],
+            Stmt::BytesLen(_) => &["bytes_len"],
+            Stmt::BytesAppend(..) => &["bytes_append"],
+            Stmt::Sha256(_) => &["compute_hash_sha256"],
+            Stmt::Keccak256(_) => &["compute_hash_keccak256"],
+            Stmt::HasData(..) => &["has_contract_data"],
+            Stmt::GetData(..) => &["get_contract_data"],
+            Stmt::PutData(..) => &["put_contract_data"],
+            Stmt::DelData(..) => &["del_contract_data"],
+            Stmt::RequireAuth(_) => &["require_auth"],
+            Stmt::TryCall(_, _, args) if args.is_empty() => &["vec_new", "try_call"],
+            Stmt::TryCall(..) => &["vec_new", "vec_push_back", "try_call"],
+            Stmt::LedgerSequence => &["get_ledger_sequence"],
+            Stmt::LedgerTimestamp => &["get_ledger_timestamp"],
+            Stmt::CurrentContractAddress => &["get_current_contract_address"],
+        }
+    }
+}
+
+struct DataKey {
+    slot: usize,
+    storage_type: StorageType,
+    read_only: bool,
+    present: bool,
+}
+
+// The generator's view of the contract so far: the kind of every slot and the
+// state of the storage and auth resources the harness provided.
+#[derive(Default)]
+struct GenState {
+    slots: Vec<Kind>,
+    data_keys: Vec<DataKey>,
+    signers: Vec<usize>,
+    callees: Vec<(usize, usize, u32)>,
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    U32Const,
+    SymbolConst,
+    U64New,
+    I64New,
+    VecNew,
+    VecPushBack,
+    VecLen,
+    VecGet,
+    VecFront,
+    MapNew,
+    MapPut,
+    MapGet,
+    MapHas,
+    MapLen,
+    BytesNew,
+    BytesPush,
+    BytesLen,
+    BytesAppend,
+    Sha256,
+    Keccak256,
+    HasData,
+    GetData,
+    PutData,
+    DelData,
+    RequireAuth,
+    TryCall,
+    LedgerSequence,
+    LedgerTimestamp,
+    CurrentContractAddress,
+}
+
+const ALL_OPS: &[Op] = &[
+    Op::U32Const,
+    Op::SymbolConst,
+    Op::U64New,
+    Op::I64New,
+    Op::VecNew,
+    Op::VecPushBack,
+    Op::VecLen,
+    Op::VecGet,
+    Op::VecFront,
+    Op::MapNew,
+    Op::MapPut,
+    Op::MapGet,
+    Op::MapHas,
+    Op::MapLen,
+    Op::BytesNew,
+    Op::BytesPush,
+    Op::BytesLen,
+    Op::BytesAppend,
+    Op::Sha256,
+    Op::Keccak256,
+    Op::HasData,
+    Op::GetData,
+    Op::PutData,
+    Op::DelData,
+    Op::RequireAuth,
+    Op::TryCall,
+    Op::LedgerSequence,
+    Op::LedgerTimestamp,
+    Op::CurrentContractAddress,
+];
+
+impl GenState {
+    fn slots_where(&self, pred: impl Fn(&Kind) -> bool) -> Vec<usize> {
+        (0..self.slots.len())
+            .filter(|i| pred(&self.slots[*i]))
+            .collect()
+    }
+
+    fn vecs(&self, min_len: u32) -> Vec<usize> {
+        self.slots_where(|k| matches!(k, Kind::Vec(n) if *n >= min_len))
+    }
+
+    fn maps(&self, nonempty: bool) -> Vec<usize> {
+        self.slots_where(|k| matches!(k, Kind::Map(keys) if !nonempty || !keys.is_empty()))
+    }
+
+    fn bytes(&self) -> Vec<usize> {
+        self.slots_where(|k| *k == Kind::Bytes)
+    }
+
+    fn data_keys(&self, pred: impl Fn(&DataKey) -> bool) -> Vec<usize> {
+        (0..self.data_keys.len())
+            .filter(|i| pred(&self.data_keys[*i]))
+            .collect()
+    }
+
+    fn applicable(&self, op: Op) -> bool {
+        match op {
+            Op::VecPushBack | Op::VecLen => !self.vecs(0).is_empty(),
+            Op::VecGet | Op::VecFront => !self.vecs(1).is_empty(),
+            Op::MapPut | Op::MapHas | Op::MapLen => !self.maps(false).is_empty(),
+            Op::MapGet => !self.maps(true).is_empty(),
+            Op::BytesPush | Op::BytesLen | Op::BytesAppend | Op::Sha256 | Op::Keccak256 => {
+                !self.bytes().is_empty()
+            }
+            Op::HasData => !self.data_keys.is_empty(),
+            Op::GetData => !self.data_keys(|k| k.present).is_empty(),
+            Op::PutData => !self.data_keys(|k| !k.read_only).is_empty(),
+            Op::DelData => !self.data_keys(|k| !k.read_only && k.present).is_empty(),
+            Op::RequireAuth => !self.signers.is_empty(),
+            Op::TryCall => !self.callees.is_empty(),
+            _ => true,
+        }
+    }
+
+    // Picks an operand of any kind. Every kind is a valid `Val`.
+    fn any(&self, u: &mut Unstructured<'_>) -> Result<usize> {
+        u.choose_index(self.slots.len())
+    }
+
+    fn generate_stmt(&mut self, u: &mut Unstructured<'_>, op: Op) -> Result<(Stmt, Kind)> {
+        Ok(match op {
+            Op::U32Const => (Stmt::U32Const(u.arbitrary()?), Kind::U32),
+            Op::SymbolConst => (
+                Stmt::SymbolConst(u.choose_index(SYMBOLS.len())?),
+                Kind::Symbol,
+            ),
+            Op::U64New => (Stmt::U64New(u.arbitrary()?), Kind::U64Object),
+            Op::I64New => (Stmt::I64New(u.arbitrary()?), Kind::I64Object),
+            Op::VecNew => (Stmt::VecNew, Kind::Vec(0)),
+            Op::VecPushBack => {
+                let v = *u.choose(&self.vecs(0))?;
+                let x = self.any(u)?;
+                let Kind::Vec(n) = self.slots[v] else {
+                    unreachable!()
+                };
+                (Stmt::VecPushBack(v, x), Kind::Vec(n + 1))
+            }
+            Op::VecLen => (Stmt::VecLen(*u.choose(&self.vecs(0))?), Kind::U32),
+            Op::VecGet => {
+                let v = *u.choose(&self.vecs(1))?;
+                let Kind::Vec(n) = self.slots[v] else {
+                    unreachable!()
+                };
+                (Stmt::VecGet(v, u.int_in_range(0..=n - 1)?), Kind::Val)
+            }
+            Op::VecFront => (Stmt::VecFront(*u.choose(&self.vecs(1))?), Kind::Val),
+            Op::MapNew => (Stmt::MapNew, Kind::Map(Vec::new())),
+            Op::MapPut => {
+                let m = *u.choose(&self.maps(false))?;
+                let k = self.any(u)?;
+                let v = self.any(u)?;
+                let Kind::Map(mut keys) = self.slots[m].clone() else {
+                    unreachable!()
+                };
+                keys.push(k);
+                (Stmt::MapPut(m, k, v), Kind::Map(keys))
+            }
+            Op::MapGet => {
+                let m = *u.choose(&self.maps(true))?;
+                let Kind::Map(keys) = &self.slots[m] else {
+                    unreachable!()
+                };
+                (Stmt::MapGet(m, *u.choose(keys)?), Kind::Val)
+            }
+            Op::MapHas => {
+                let m = *u.choose(&self.maps(false))?;
+                (Stmt::MapHas(m, self.any(u)?), Kind::Bool)
+            }
+            Op::MapLen => (Stmt::MapLen(*u.choose(&self.maps(false))?), Kind::U32),
+            Op::BytesNew => (Stmt::BytesNew, Kind::Bytes),
+            Op::BytesPush => (
+                Stmt::BytesPush(*u.choose(&self.bytes())?, u.arbitrary()?),
+                Kind::Bytes,
+            ),
+            Op::BytesLen => (Stmt::BytesLen(*u.choose(&self.bytes())?), Kind::U32),
+            Op::BytesAppend => {
+                let bytes = self.bytes();
+                (
+                    Stmt::BytesAppend(*u.choose(&bytes)?, *u.choose(&bytes)?),
+                    Kind::Bytes,
+                )
+            }
+            Op::Sha256 => (Stmt::Sha256(*u.choose(&self.bytes())?), Kind::Bytes),
+            Op::Keccak256 => (Stmt::Keccak256(*u.choose(&self.bytes())?), Kind::Bytes),
+            Op::HasData => {
+                let k = &self.data_keys[u.choose_index(self.data_keys.len())?];
+                (Stmt::HasData(k.slot, k.storage_type), Kind::Bool)
+            }
+            Op::GetData => {
+                let k = &self.data_keys[*u.choose(&self.data_keys(|k| k.present))?];
+                (Stmt::GetData(k.slot, k.storage_type), Kind::Val)
+            }
+            Op::PutData => {
+                let i = *u.choose(&self.data_keys(|k| !k.read_only))?;
+                let v = self.any(u)?;
+                let k = &mut self.data_keys[i];
+                k.present = true;
+                (Stmt::PutData(k.slot, v, k.storage_type), Kind::Void)
+            }
+            Op::DelData => {
+                let i = *u.choose(&self.data_keys(|k| !k.read_only && k.present))?;
+                let k = &mut self.data_keys[i];
+                k.present = false;
+                (Stmt::DelData(k.slot, k.storage_type), Kind::Void)
+            }
+            Op::RequireAuth => (Stmt::RequireAuth(*u.choose(&self.signers)?), Kind::Void),
+            Op::TryCall => {
+                let (addr, func, arity) = *u.choose(&self.callees)?;
+                let args = (0..arity)
+                    .map(|_| self.any(u))
+                    .collect::<Result<Vec<_>>>()?;
+                (Stmt::TryCall(addr, func, args), Kind::Val)
+            }
+            Op::LedgerSequence => (Stmt::LedgerSequence, Kind::U32),
+            Op::LedgerTimestamp => (Stmt::LedgerTimestamp, Kind::Val),
+            Op::CurrentContractAddress => (Stmt::CurrentContractAddress, Kind::Address),
+        })
+    }
+}
+
+/// A generated contract function: the arguments it takes and the statements
+/// of its body. It returns the result of its last statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedContract {
+    pub args: Vec<ContractArg>,
+    pub stmts: Vec<Stmt>,
+}
+
+impl TypedContract {
+    /// Generates a contract taking `args`, drawing choices from `u`.
+    pub fn generate(u: &mut Unstructured<'_>, args: &[ContractArg]) -> Result<Self> {
+        let mut st = GenState::default();
+        for arg in args {
+            let slot = st.slots.len();
+            match arg {
+                ContractArg::DataKey {
+                    storage_type,
+                    read_only,
+                } => {
+                    st.slots.push(Kind::U32);
+                    st.data_keys.push(DataKey {
+                        slot,
+                        storage_type: *storage_type,
+                        read_only: *read_only,
+                        present: true,
+                    });
+                }
+                ContractArg::Callee { arity } => {
+                    st.slots.push(Kind::Address);
+                    st.slots.push(Kind::Symbol);
+                    st.callees.push((slot, slot + 1, *arity));
+                }
+                ContractArg::Signer => {
+                    st.slots.push(Kind::Address);
+                    st.signers.push(slot);
+                }
+                ContractArg::Bytes => st.slots.push(Kind::Bytes),
+                ContractArg::Val => st.slots.push(Kind::Val),
+            }
+        }
+        let n_stmts = u.int_in_range(0..=MAX_STMTS)?;
+        let mut stmts = Vec::with_capacity(n_stmts);
+        for _ in 0..n_stmts {
+            if u.is_empty() {
+                break;
+            }
+            let ops: Vec<Op> = ALL_OPS
+                .iter()
+                .copied()
+                .filter(|op| st.applicable(*op))
+                .collect();
+            let op = *u.choose(&ops)?;
+            let (stmt, kind) = st.generate_stmt(u, op)?;
+            stmts.push(stmt);
+            st.slots.push(kind);
+        }
+        Ok(TypedContract {
+            args: args.to_vec(),
+            stmts,
+        })
+    }
+
+    /// Counts the call sites of each host function in the contract's code.
+    /// These are static occurrences: a run may reach fewer of them (if an
+    /// earlier call traps) but never more, since the code has no loops.
+    pub fn static_host_function_calls(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for stmt in self.stmts.iter() {
+            for hf in stmt.host_functions() {
+                *counts.entry(*hf).or_default() += 1;
+            }
+            // A call pushes each of its arguments onto the argument vector.
+            if let Stmt::TryCall(_, _, args) = stmt {
+                if !args.is_empty() {
+                    *counts.entry("vec_push_back").or_default() += args.len() - 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Returns, for each `DataKey` argument in order, whether the contract
+    /// gets or deletes the entry before it puts it, so the entry has to exist
+    /// for the contract to get past that statement.
+    pub fn data_keys_read_before_written(&self) -> Vec<bool> {
+        let mut slot = 0;
+        let mut keys = Vec::new();
+        for arg in self.args.iter() {
+            if let ContractArg::DataKey { .. } = arg {
+                keys.push(slot);
+            }
+            slot += arg.wasm_arg_types().len();
+        }
+        let mut read = vec![false; keys.len()];
+        let mut written = vec![false; keys.len()];
+        for stmt in self.stmts.iter() {
+            let (k, is_write) = match stmt {
+                Stmt::GetData(k, _) | Stmt::DelData(k, _) => (k, false),
+                Stmt::PutData(k, _, _) => (k, true),
+                _ => continue,
+            };
+            if let Some(i) = keys.iter().position(|slot| slot == k) {
+                if is_write {
+                    written[i] = true;
+                } else if !written[i] {
+                    read[i] = true;
+                }
+            }
+        }
+        read
+    }
+
+    fn emit_stmt(&self, fe: &mut FuncEmitter, slots: &mut Vec<LocalRef>, stmt: &Stmt) {
+        let s = |i: &usize| Operand::Local(slots[*i]);
+        let ty = match stmt {
+            Stmt::U32Const(x) => {
+                fe.push(U32Val::from(*x));
+                "U32Val"
+            }
+            Stmt::SymbolConst(i) => {
+                fe.push(Symbol::try_from_small_str(SYMBOLS[*i]).unwrap());
+                "Symbol"
+            }
+            Stmt::U64New(x) => {
+                fe.obj_from_u64(*x);
+                "U64Object"
+            }
+            Stmt::I64New(x) => {
+                fe.obj_from_i64(*x);
+                "I64Object"
+            }
+            Stmt::VecNew => {
+                fe.vec_new();
+                "VecObject"
+            }
+            Stmt::VecPushBack(v, x) => {
+                fe.vec_push_back(s(v), s(x));
+                "VecObject"
+            }
+            Stmt::VecLen(v) => {
+                fe.vec_len(s(v));
+                "U32Val"
+            }
+            Stmt::VecGet(v, i) => {
+                fe.vec_get(s(v), U32Val::from(*i));
+                "Val"
+            }
+            Stmt::VecFront(v) => {
+                fe.vec_front(s(v));
+                "Val"
+            }
+            Stmt::MapNew => {
+                fe.map_new();
+                "MapObject"
+            }
+            Stmt::MapPut(m, k, v) => {
+                fe.map_put(s(m), s(k), s(v));
+                "MapObject"
+            }
+            Stmt::MapGet(m, k) => {
+                fe.map_get(s(m), s(k));
+                "Val"
+            }
+            Stmt::MapHas(m, k) => {
+                fe.map_has(s(m), s(k));
+                "Bool"
+            }
+            Stmt::MapLen(m) => {
+                fe.map_len(s(m));
+                "U32Val"
+            }
+            Stmt::BytesNew => {
+                fe.bytes_new();
+                "BytesObject"
+            }
+            Stmt::BytesPush(b, x) => {
+                fe.bytes_push(s(b), U32Val::from(*x as u32));
+                "BytesObject"
+            }
+            Stmt::BytesLen(b) => {
+                fe.bytes_len(s(b));
+                "U32Val"
+            }
+            Stmt::BytesAppend(b1, b2) => {
+                fe.bytes_append(s(b1), s(b2));
+                "BytesObject"
+            }
+            Stmt::Sha256(b) => {
+                fe.compute_hash_sha256(s(b));
+                "BytesObject"
+            }
+            Stmt::Keccak256(b) => {
+                fe.compute_hash_keccak256(s(b));
+                "BytesObject"
+            }
+            Stmt::HasData(k, t) => {
+                fe.has_contract_data(s(k), *t);
+                "Bool"
+            }
+            Stmt::GetData(k, t) => {
+                fe.get_contract_data(s(k), *t);
+                "Val"
+            }
+            Stmt::PutData(k, v, t) => {
+                fe.put_contract_data(s(k), s(v), *t);
+                "Void"
+            }
+            Stmt::DelData(k, t) => {
+                fe.del_contract_data(s(k), *t);
+                "Void"
+            }
+            Stmt::RequireAuth(a) => {
+                fe.require_auth(s(a));
+                "Void"
+            }
+            Stmt::TryCall(addr, func, args) => {
+                // The argument vector needs a local of its own, because only
+                // the first argument of a host call can come from the stack.
+                fe.vec_new();
+                for arg in args {
+                    fe.vec_push_back(Operand::StackTop, s(arg));
+                }
+                let args = fe.alloc_and_store_local("VecObject");
+                fe.try_call(s(addr), s(func), args);
+                "Val"
+            }
+            Stmt::LedgerSequence => {
+                fe.get_ledger_sequence();
+                "U32Val"
+            }
+            Stmt::LedgerTimestamp => {
+                fe.get_ledger_timestamp();
+                "Val"
+            }
+            Stmt::CurrentContractAddress => {
+                fe.get_current_contract_address();
+                "AddressObject"
+            }
+        };
+        slots.push(fe.alloc_and_store_local(ty));
+    }
+
+    /// Emits a wasm module exporting the contract as a function named `name`.
+    pub fn as_single_function_wasm_module(&self, name: &str) -> Vec<u8> {
+        let arg_types = contract_arg_types(&self.args);
+        let n_call_args = self
+            .stmts
+            .iter()
+            .filter(|s| matches!(s, Stmt::TryCall(..)))
+            .count();
+        let mut fe = ModEmitter::default_with_test_protocol().func(
+            Arity(arg_types.len() as u32),
+            (self.stmts.len() + n_call_args) as u32,
+        );
+        let mut slots: Vec<LocalRef> = arg_types.iter().map(|&ty| fe.alloc_arg(ty)).collect();
+        for stmt in self.stmts.iter() {
+            self.emit_stmt(&mut fe, &mut slots, stmt);
+        }
+        if self.stmts.is_empty() {
+            fe.push(Val::VOID);
+        } else {
+            fe.push(slots[slots.len() - 1]);
+        }
+        fe.finish_and_export(name).finish()
+    }
+}